@@ -95,9 +95,17 @@ const initializeApp = async () => {
         const callbacks = {
             // File operations
             loadBeatmapFromDialog: DirectoryScanner.loadBeatmapFromDialog,
+            initDroppedFileImport: DirectoryScanner.initDroppedFileImport,
+            initDeepLinks: DirectoryScanner.initDeepLinks,
+            initMapWindow: DirectoryScanner.initMapWindow,
+            openMapWindow: DirectoryScanner.openMapWindow,
             loadBeatmapsByMapper: DirectoryScanner.loadBeatmapsByMapper,
             loadBeatmapsFromFolder: DirectoryScanner.loadBeatmapsFromFolder,
             refreshLastDirectory: DirectoryScanner.refreshLastDirectory,
+            loadLibraryRoots: DirectoryScanner.loadLibraryRoots,
+            addLibraryRootFromDialog: DirectoryScanner.addLibraryRootFromDialog,
+            removeLibraryRoot: DirectoryScanner.removeLibraryRoot,
+            scanLibraryRoots: DirectoryScanner.scanLibraryRoots,
 
             // UI rendering
             renderFromState: StateRenderer.renderFromState,
@@ -10,26 +10,29 @@
 // Imports
 // ============================================
 
-import { SETTINGS_STORAGE_KEY } from '../config/Constants.js';
+import { SETTINGS_STORAGE_KEY, MAP_WINDOW_LABEL, STORAGE_KEY, AUDIO_ANALYSIS_STATE_KEY, STAR_RATING_STATE_KEY } from '../config/Constants.js';
 import { generateUserId, generateApiKey, computeProgress } from '../utils/Helpers.js';
 import { showNotification } from '../components/NotificationSystem.js';
 import { closeDialogWithAnimation } from '../ui/DialogManager.js';
 import { createDropdownMenu } from '../ui/DropdownMenu.js';
-import { updateSortUI, updateSRRangeUI, setupSRRangeResizeObserver, updateVersionLabels } from '../ui/SettingsUI.js';
+import { updateSortUI, updateSRRangeUI, setupSRRangeResizeObserver, updateVersionLabels, renderLibraryRoots } from '../ui/SettingsUI.js';
 import { renderFromState, updateTabCounts, updateListItemElement, setLoading, updateProgress, updateEmptyState, clearFilterCache } from '../ui/StateRenderer.js';
 import { initEventDelegation } from '../interaction/EventDelegation.js';
+import { getItemsToRender, scrollToItem } from '../ui/VirtualList.js';
 import { initSearch, clearSearch } from '../interaction/SearchHandler.js';
 import { AudioController } from '../services/AudioController.js';
 import * as CollectionsManager from '../services/CollectionsManager.js';
 import { openLazerSessionForAction } from '../services/LazerEditSession.js';
 import { initMapPreview, openMapPreview } from '../services/MapPreview.js';
-import { initScanEventListeners, startStreamingScan } from '../services/ScanManager.js';
+import { initScanEventListeners, startStreamingScan, handleScanBatch, STREAMING_SCAN_OPTIONS } from '../services/ScanManager.js';
 import * as Persistence from '../state/Persistence.js';
+import { updateItemNotes } from '../itemProcessing/ItemUpdater.js';
 import * as BackgroundProcessor from '../services/BackgroundProcessor.js';
 import { isStarRatingMissing } from '../utils/Validation.js';
 import { processMapperInput } from '../parsers/GuestDifficultyFilter.js';
 import { checkForUpdatesAndUpdateIndicator } from './UpdateChecker.js';
-import { triggerManualSync } from '../services/EmbedSync.js';
+import { triggerManualSync, configureEmbedAutoSync } from '../services/EmbedSync.js';
+import { tauriEvents } from '../bridge/Tauri.js';
 import * as Store from '../state/Store.js';
 
 // ============================================
@@ -218,6 +221,26 @@ const setClientDirectory = (dir, client = getActiveOsuClient()) => {
     }
 };
 
+/**
+ * Fill in the active client's folder from an auto-detected osu! install
+ * @returns {Promise<string|null>} The detected directory, if any
+ */
+const applyDetectedClientDirectory = async () => {
+    try {
+        const detected = await window.beatmapApi.detectOsuSongsFolder();
+        const dir = getActiveOsuClient() === CLIENT_LAZER ? detected?.lazerData : detected?.stableSongs;
+        if (dir) {
+            setClientDirectory(dir);
+            Persistence.persistSettings();
+            applySettings();
+        }
+        return dir || null;
+    } catch (error) {
+        console.warn('Failed to detect osu! folders:', error);
+        return null;
+    }
+};
+
 const updateClientUiCopy = (options = {}) => {
     const { highlightSelection = true } = options;
     const client = getActiveOsuClient();
@@ -254,13 +277,35 @@ const updateClientUiCopy = (options = {}) => {
 // ============================================
 
 /**
- * Load and migrate settings from localStorage
+ * Read settings from the native store, seeding it from localStorage the first time
+ * @param {string|null} localRaw - Settings JSON from localStorage
+ * @returns {Promise<Object|null>} Native settings, or null when the store is unavailable
+ */
+const loadNativeSettings = async (localRaw) => {
+    if (!window.appInfo?.getSettings) return null;
+    try {
+        const stored = await window.appInfo.getSettings();
+        if (stored) return stored;
+        if (localRaw) {
+            // localStorage settings are unversioned (schema 0); the backend migrates them on import
+            return await window.appInfo.setSettings(JSON.parse(localRaw), true, 0);
+        }
+    } catch (error) {
+        console.warn('Native settings store unavailable, using localStorage:', error);
+    }
+    return null;
+};
+
+/**
+ * Load and migrate settings from the native store, falling back to localStorage
  * Handles migration from old settings format to new format
  */
-export const loadSettings = () => {
+export const loadSettings = async () => {
     const isCurrentUserIdFormat = (value) => /^msu[a-z0-9]{6}$/i.test(String(value || '').trim());
 
-    const raw = localStorage.getItem(SETTINGS_STORAGE_KEY);
+    const localRaw = localStorage.getItem(SETTINGS_STORAGE_KEY);
+    const nativeSettings = await loadNativeSettings(localRaw);
+    const raw = nativeSettings ? JSON.stringify(nativeSettings) : localRaw;
     if (raw) {
         try {
             const parsed = JSON.parse(raw);
@@ -334,6 +379,9 @@ export const loadSettings = () => {
             console.error('Failed to load settings:', e);
         }
     }
+    if (await Persistence.loadSecretSettings()) {
+        Persistence.persistSettings();
+    }
     // Generate or migrate userId if missing/legacy
     if (!isCurrentUserIdFormat(Store.settings.userId)) {
         Store.updateSettings({ userId: generateUserId() });
@@ -469,6 +517,12 @@ export const applySettings = () => {
     const groupMapsBySongEl = document.querySelector('#groupMapsBySong');
     if (groupMapsBySongEl) groupMapsBySongEl.checked = !!Store.settings.groupMapsBySong;
 
+    const preferUnicodeMetadataEl = document.querySelector('#preferUnicodeMetadata');
+    if (preferUnicodeMetadataEl) preferUnicodeMetadataEl.checked = Store.settings.preferUnicodeMetadata !== false;
+
+    const minimizeToTrayEl = document.querySelector('#minimizeToTray');
+    if (minimizeToTrayEl) minimizeToTrayEl.checked = !!Store.settings.minimizeToTray;
+
     if (collectionsImportToggle) collectionsImportToggle.checked = !!Store.settings.collectionsImportEnabled;
     if (collectionsTargetSetting) collectionsTargetSetting.hidden = !Store.settings.collectionsImportEnabled;
     if (collectionsTargetDropdown) {
@@ -578,6 +632,110 @@ export const initImportButtons = (callbacks = {}) => {
     }
 };
 
+// ============================================
+// Initialize Tray Actions
+// ============================================
+
+/** @type {string|null} Map last opened in the preview, for the tray's "Open last map" */
+let lastPreviewedItemId = null;
+
+/**
+ * Handle actions picked from the system tray menu
+ * @param {Object} callbacks - App callbacks
+ * @param {Function} callbacks.refreshLastDirectory - Rescan the library
+ */
+const initTrayActions = (callbacks = {}) => {
+    if (!tauriEvents?.listen) return;
+
+    tauriEvents.listen('tray-action', ({ action, paused }) => {
+        if (action === 'rescan-library' && callbacks.refreshLastDirectory) {
+            callbacks.refreshLastDirectory(callbacks);
+        } else if (action === 'open-last-map') {
+            const fallback = Store.beatmapItems.reduce(
+                (latest, item) => (!latest || (item.dateModified || 0) > (latest.dateModified || 0) ? item : latest),
+                null
+            );
+            const itemId = Store.beatmapItems.some((item) => item.id === lastPreviewedItemId)
+                ? lastPreviewedItemId
+                : fallback?.id;
+            if (itemId) {
+                lastPreviewedItemId = itemId;
+                openMapPreview(itemId);
+            }
+        } else if (action === 'pause-watcher') {
+            CollectionsManager.setCollectionModeAutoRefreshPaused(paused);
+            showNotification(paused ? 'Watcher paused' : 'Watcher resumed',
+                paused ? 'Collections will not refresh in the background.' : 'Collections refresh in the background again.');
+        }
+    }).catch((error) => console.warn('[mosu] Tray actions unavailable:', error));
+};
+
+/**
+ * Append editor timestamps captured with the global shortcut to the notes of the map being edited
+ */
+const initEditorTimestampCapture = () => {
+    if (!tauriEvents?.listen) return;
+
+    tauriEvents.listen('editor-timestamp-captured', ({ filePath, timestamp, error }) => {
+        if (error) {
+            showNotification('Timestamp Not Logged', error, 'error');
+            return;
+        }
+        const item = Store.beatmapItems.find((entry) => entry.filePath === filePath);
+        if (!item) {
+            showNotification('Timestamp Not Logged', 'The map being edited is not in your list.', 'error');
+            return;
+        }
+
+        const line = `${timestamp.text.trim()} - `;
+        const notes = item.notes ? `${item.notes.replace(/\s+$/, '')}\n${line}` : line;
+        updateItemNotes(item.id, notes, {
+            onUpdated: () => {
+                const textarea = document.querySelector(`#listContainer [data-item-id="${item.id}"] .notes-textarea`);
+                if (textarea) textarea.value = notes;
+                showNotification('Timestamp Logged', `${line}${item.artist} - ${item.title} [${item.version}]`);
+            }
+        });
+    }).catch((error) => console.warn('[mosu] Timestamp capture unavailable:', error));
+};
+
+/**
+ * Scroll a list item into view, expanding its song group when maps are grouped
+ * @param {string} itemId - Item ID
+ */
+const focusListItem = (itemId) => {
+    const chip = document.querySelector(`#listContainer .group-row-version-chip[data-item-id="${itemId}"]`);
+    if (chip) {
+        chip.click();
+        return;
+    }
+    const index = getItemsToRender().findIndex((item) => item.id === itemId);
+    if (index !== -1) {
+        scrollToItem(index);
+    }
+};
+
+/**
+ * Follow the map open in osu! stable and focus it in the list whenever osu! switches maps
+ */
+const initNowPlaying = () => {
+    if (!tauriEvents?.listen) return;
+
+    const follow = ({ filePath } = {}) => {
+        if (!filePath || Store.settings.followNowPlaying === false) return;
+        const item = Store.beatmapItems.find((entry) => entry.filePath === filePath);
+        if (item) {
+            lastPreviewedItemId = item.id;
+            focusListItem(item.id);
+        }
+    };
+
+    tauriEvents.listen('osu-now-playing', follow)
+        .then(() => window.beatmapApi?.getNowPlaying?.())
+        .then(follow)
+        .catch((error) => console.warn('[mosu] Now playing unavailable:', error));
+};
+
 // ============================================
 // Initialize Toolbar
 // ============================================
@@ -1010,6 +1168,55 @@ export const initSettingsPanel = (callbacks = {}) => {
         }
     });
 
+    // Library roots
+    const libraryRootsList = document.querySelector('#libraryRootsList');
+    const addLibraryRootBtn = document.querySelector('#addLibraryRootBtn');
+    const scanLibraryRootsBtn = document.querySelector('#scanLibraryRootsBtn');
+    const rootCallbacks = { ...callbacks, showNotification };
+    const reportRootError = (error) => {
+        showNotification('Library Folders', String(error?.message || error), 'error');
+    };
+
+    if (callbacks.loadLibraryRoots) {
+        callbacks.loadLibraryRoots().then((roots) => {
+            renderLibraryRoots(roots);
+            // Source badges only show once there is more than one root
+            if (roots.length > 1) renderFromState();
+        }).catch(reportRootError);
+    }
+    if (addLibraryRootBtn && callbacks.addLibraryRootFromDialog) {
+        addLibraryRootBtn.addEventListener('click', async () => {
+            try {
+                const dir = await callbacks.addLibraryRootFromDialog();
+                if (!dir) return;
+                renderLibraryRoots(Store.libraryRoots);
+                await callbacks.scanLibraryRoots?.([dir], rootCallbacks);
+            } catch (error) {
+                reportRootError(error);
+            }
+        });
+    }
+    if (scanLibraryRootsBtn && callbacks.scanLibraryRoots) {
+        scanLibraryRootsBtn.addEventListener('click', () => callbacks.scanLibraryRoots(null, rootCallbacks));
+    }
+    if (libraryRootsList) {
+        libraryRootsList.addEventListener('click', async (e) => {
+            const button = e.target.closest('[data-root-action]');
+            const rootPath = button?.closest('.library-root-row')?.dataset.rootPath;
+            if (!rootPath) return;
+            try {
+                if (button.dataset.rootAction === 'rescan') {
+                    await callbacks.scanLibraryRoots?.([rootPath], rootCallbacks);
+                } else if (button.dataset.rootAction === 'remove') {
+                    await callbacks.removeLibraryRoot?.(rootPath, rootCallbacks);
+                    renderLibraryRoots(Store.libraryRoots);
+                }
+            } catch (error) {
+                reportRootError(error);
+            }
+        });
+    }
+
     // Songs directory selection
     if (selectSongsDirBtn) {
         selectSongsDirBtn.addEventListener('click', async () => {
@@ -1111,7 +1318,7 @@ export const initSettingsPanel = (callbacks = {}) => {
     }
 
     // Generic Setting Toggles
-    ['autoRescan', 'ignoreStartAndBreaks', 'ignoreGuestDifficulties'].forEach(id => {
+    ['autoRescan', 'ignoreStartAndBreaks', 'ignoreGuestDifficulties', 'minimizeToTray'].forEach(id => {
         const el = document.getElementById(id);
         if (el) {
             el.addEventListener('change', (e) => {
@@ -1179,6 +1386,16 @@ export const initSettingsPanel = (callbacks = {}) => {
         });
     }
 
+    // Unicode Title Display Toggle
+    const preferUnicodeMetadataEl = document.getElementById('preferUnicodeMetadata');
+    if (preferUnicodeMetadataEl) {
+        preferUnicodeMetadataEl.addEventListener('change', (e) => {
+            Store.updateSettings({ preferUnicodeMetadata: e.target.checked });
+            Persistence.persistSettings();
+            renderFromState();
+        });
+    }
+
     // Mode filter dropdown
     if (modeFilterDropdown) {
         if (modeFilterDropdownMenu) modeFilterDropdownMenu.destroy();
@@ -1338,7 +1555,7 @@ export const initSettingsPanel = (callbacks = {}) => {
                         }
                     });
 
-                    await window.beatmapApi.scanDirectoryOsuFiles(targetDir, mapper, knownFiles, getActiveOsuClient());
+                    await window.beatmapApi.scanDirectoryOsuFiles(targetDir, mapper, knownFiles, getActiveOsuClient(), STREAMING_SCAN_OPTIONS, handleScanBatch);
                     await scanDone;
                 } catch (err) {
                     console.error('Mapper rescan failed:', err);
@@ -2028,26 +2245,28 @@ export const init = async (callbacks = {}) => {
     initMapPreview();
 
     // Load settings
-    loadSettings();
-
-    // Initialize scan event listeners
-    await initScanEventListeners({
-        scheduleAudioAnalysis: BackgroundProcessor.scheduleAudioAnalysis,
-        scheduleStarRatingCalculation: BackgroundProcessor.scheduleStarRatingCalculation,
-        updateProgress,
-        setLoading,
-        updateTabCounts,
-        renderFromState,
-        saveToStorage: () => Persistence.saveToStorage({ showNotification }),
-        processBackgroundQueues: () => callbacks.processBackgroundQueues(callbacks),
-        onScanComplete: async () => {
-            await CollectionsManager.syncImportedCollection(callbacks, {
-                forceCollectionsRefresh: true,
-                reapply: true,
-                silent: true,
-            });
-        }
-    });
+    await loadSettings();
+
+    // Scan events are broadcast to every window, so comparison windows don't listen
+    if (!MAP_WINDOW_LABEL) {
+        await initScanEventListeners({
+            scheduleAudioAnalysis: BackgroundProcessor.scheduleAudioAnalysis,
+            scheduleStarRatingCalculation: BackgroundProcessor.scheduleStarRatingCalculation,
+            updateProgress,
+            setLoading,
+            updateTabCounts,
+            renderFromState,
+            saveToStorage: () => Persistence.saveToStorage({ showNotification }),
+            processBackgroundQueues: () => callbacks.processBackgroundQueues(callbacks),
+            onScanComplete: async () => {
+                await CollectionsManager.syncImportedCollection(callbacks, {
+                    forceCollectionsRefresh: true,
+                    reapply: true,
+                    silent: true,
+                });
+            }
+        });
+    }
 
     // Load from storage
     await Persistence.loadFromStorage({
@@ -2066,6 +2285,9 @@ export const init = async (callbacks = {}) => {
         processBackgroundQueues: () => callbacks.processBackgroundQueues(callbacks)
     });
 
+    configureEmbedAutoSync({ persistSettings: () => Persistence.persistSettings() })
+        .catch(err => console.error('Failed to start embed auto-sync:', err));
+
     if (normalizeCollectionSyncState()) {
         updateTabCounts();
     }
@@ -2155,8 +2377,16 @@ export const init = async (callbacks = {}) => {
             }
         },
         openMapPreview: (itemId) => {
+            lastPreviewedItemId = itemId;
             openMapPreview(itemId);
         },
+        openMapWindow: async (path) => {
+            try {
+                await callbacks.openMapWindow?.(path);
+            } catch (error) {
+                showNotification('Map Window', String(error?.message || error), 'error');
+            }
+        },
         addToCollection: async (itemId) => {
             await CollectionsManager.promptAddBeatmapToCollection(itemId, callbacks);
         }
@@ -2194,8 +2424,19 @@ export const init = async (callbacks = {}) => {
         onResize: () => updateSRRangeUI(Store.srFilter, null, { rerenderList: false })
     });
 
-    // Initialize import buttons
+    // Tray actions, timestamp capture, now playing and mosu:// links go to the main window, not to beatmap comparison windows
+    if (!MAP_WINDOW_LABEL) {
+        initTrayActions(callbacks);
+        initEditorTimestampCapture();
+        initNowPlaying();
+    }
+
+    // Initialize import buttons, file drops and mosu:// links
     initImportButtons(callbacks);
+    callbacks.initDroppedFileImport?.({ ...callbacks, showNotification });
+    if (!MAP_WINDOW_LABEL) {
+        callbacks.initDeepLinks?.({ ...callbacks, showNotification });
+    }
     CollectionsManager.syncCollectionImportOption(callbacks);
     CollectionsManager.initCollectionModeAutoRefresh(callbacks);
 
@@ -2230,36 +2471,11 @@ export const init = async (callbacks = {}) => {
         silent: true,
     });
 
-    // Run first-run wizard if needed
-    await initFirstRunWizard(callbacks);
-
-    // Check if we need to prompt for missing settings
-    if (!Store.settings.rescanMapperName || !Store.settings.songsDir) {
-        if (!Store.settings.rescanMapperName && Store.settings.initialImportChoice !== 'all') {
-            await promptForMapperName();
-        }
-        if (!Store.settings.songsDir && !suppressStartupSongsDirPrompt && window.beatmapApi?.selectDirectory) {
-            await promptForSongsDir();
-        }
-
-        // First-run fallback: ensure an initial scan happens once setup data is complete
-        const shouldDoInitialScan =
-            Store.settings.initialSetupDone &&
-            Store.settings.songsDir &&
-            Store.beatmapItems.length === 0 &&
-            (
-                Store.settings.initialImportChoice === 'all' ||
-                (Store.settings.initialImportChoice === 'mapper' && !!Store.settings.rescanMapperName)
-            );
-
-        if (shouldDoInitialScan && callbacks.refreshLastDirectory) {
-            await callbacks.refreshLastDirectory(callbacks);
-        }
-    }
-
-    // Auto rescan if enabled
-    if (Store.settings.autoRescan && Store.settings.songsDir && callbacks.refreshLastDirectory) {
-        await callbacks.refreshLastDirectory(callbacks);
+    if (MAP_WINDOW_LABEL) {
+        // Comparison windows skip setup and rescans and only show the map they were opened for
+        await callbacks.initMapWindow?.({ ...callbacks, showNotification });
+    } else {
+        await runStartupScans(callbacks);
     }
 
     // Check for updates in the background
@@ -2290,6 +2506,13 @@ export const init = async (callbacks = {}) => {
             clearTimeout(Store.saveTimer);
             Store.updateState('saveTimer', null);
         }
+        if (MAP_WINDOW_LABEL) {
+            // Per-window state dies with the window instead of piling up in localStorage
+            localStorage.removeItem(STORAGE_KEY);
+            localStorage.removeItem(AUDIO_ANALYSIS_STATE_KEY);
+            localStorage.removeItem(STAR_RATING_STATE_KEY);
+            return;
+        }
         Persistence.saveToStorage({ showNotification });
         Persistence.persistAudioAnalysisState();
         Persistence.persistStarRatingState();
@@ -2300,6 +2523,47 @@ export const init = async (callbacks = {}) => {
 // Helper Functions
 // ============================================
 
+/**
+ * Run the first-run wizard, prompt for missing settings and do the initial or automatic rescan
+ * @param {Object} callbacks - Callback functions
+ */
+async function runStartupScans(callbacks) {
+    // Run first-run wizard if needed
+    await initFirstRunWizard(callbacks);
+
+    // Check if we need to prompt for missing settings
+    if (!Store.settings.rescanMapperName || !Store.settings.songsDir) {
+        if (!Store.settings.rescanMapperName && Store.settings.initialImportChoice !== 'all') {
+            await promptForMapperName();
+        }
+        if (!Store.settings.songsDir && window.beatmapApi?.detectOsuSongsFolder) {
+            await applyDetectedClientDirectory();
+        }
+        if (!Store.settings.songsDir && !suppressStartupSongsDirPrompt && window.beatmapApi?.selectDirectory) {
+            await promptForSongsDir();
+        }
+
+        // First-run fallback: ensure an initial scan happens once setup data is complete
+        const shouldDoInitialScan =
+            Store.settings.initialSetupDone &&
+            Store.settings.songsDir &&
+            Store.beatmapItems.length === 0 &&
+            (
+                Store.settings.initialImportChoice === 'all' ||
+                (Store.settings.initialImportChoice === 'mapper' && !!Store.settings.rescanMapperName)
+            );
+
+        if (shouldDoInitialScan && callbacks.refreshLastDirectory) {
+            await callbacks.refreshLastDirectory(callbacks);
+        }
+    }
+
+    // Auto rescan if enabled
+    if (Store.settings.autoRescan && Store.settings.songsDir && callbacks.refreshLastDirectory) {
+        await callbacks.refreshLastDirectory(callbacks);
+    }
+}
+
 /**
  * Prompt user for mapper name
  */
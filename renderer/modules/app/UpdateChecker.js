@@ -4,7 +4,7 @@
  */
 
 import { showNotification } from '../components/NotificationSystem.js';
-import { appInfo } from '../bridge/Tauri.js';
+import { appInfo, tauriEvents } from '../bridge/Tauri.js';
 
 /** @type {string} GitHub API URL for latest release */
 const GITHUB_API_URL = 'https://api.github.com/repos/fax1015/mosu/releases/latest';
@@ -151,8 +151,17 @@ export function showUpdateNotification(version, downloadUrl, currentVersion = ''
 
     closeBtn.addEventListener('click', closeHandler);
 
-    // Download button handler
-    downloadBtn.addEventListener('click', () => {
+    // Download button handler: install in-app when the backend updater is available,
+    // otherwise fall back to the release page
+    downloadBtn.addEventListener('click', async () => {
+        clearTimeout(timeoutId);
+        if (appInfo?.downloadUpdate) {
+            downloadBtn.disabled = true;
+            const installed = await installUpdate((percent) => {
+                downloadBtn.textContent = percent === null ? 'Downloading…' : `Downloading… ${percent}%`;
+            });
+            if (installed) return;
+        }
         if (appInfo?.openExternalUrl) {
             appInfo.openExternalUrl(downloadUrl);
         } else {
@@ -177,6 +186,32 @@ export function showUpdateNotification(version, downloadUrl, currentVersion = ''
     closeBtn.addEventListener('click', () => clearTimeout(timeoutId), { once: true });
 }
 
+/**
+ * Download the latest release through the backend updater, then install it and restart
+ * @param {(percent: number|null) => void} [onProgress] - Receives download progress, or null when the size is unknown
+ * @returns {Promise<boolean>} False if the download or install failed
+ */
+export async function installUpdate(onProgress) {
+    let unlisten = null;
+    try {
+        if (onProgress && tauriEvents?.listen) {
+            unlisten = await tauriEvents.listen('update-download-progress', (payload) => {
+                const total = payload?.totalBytes;
+                onProgress(total ? Math.round((payload.downloadedBytes / total) * 100) : null);
+            });
+        }
+        await appInfo.downloadUpdate();
+        await appInfo.applyUpdate();
+        return true;
+    } catch (error) {
+        console.error('[UpdateChecker] Failed to install update:', error);
+        showNotification('Update Failed', String(error?.message || error), 'error');
+        return false;
+    } finally {
+        if (typeof unlisten === 'function') unlisten();
+    }
+}
+
 /**
  * Compare two semantic versions
  * @param {string} v1 - First version string (e.g., "1.2.3")
@@ -21,14 +21,100 @@ export const beatmapApi = window.beatmapApi || {
     addToLazerCollection: (dataRoot, collectionName, beatmapHash) => { throw new Error('Tauri not available'); },
     prepareLazerMapSession: (filePath, dataRoot) => { throw new Error('Tauri not available'); },
     commitLazerMapSession: (sessionDir) => { throw new Error('Tauri not available'); },
-    scanDirectoryOsuFiles: (dirPath, mapperName, knownFiles, clientType) => { throw new Error('Tauri not available'); },
+    scanDirectoryOsuFiles: (dirPath, mapperName, knownFiles, clientType, options, onBatch) => { throw new Error('Tauri not available'); },
+    rescanChanged: (dirPath, mapperName, clientType, options, onBatch) => { throw new Error('Tauri not available'); },
     listDirectoryOsuFiles: (dirPath, mapperName, clientType) => { throw new Error('Tauri not available'); },
     selectDirectory: (title) => { throw new Error('Tauri not available'); },
     showItemInFolder: (filePath) => { throw new Error('Tauri not available'); },
     openInTextEditor: (filePath) => { throw new Error('Tauri not available'); },
     convertFileSrc: (filePath) => { throw new Error('Tauri not available'); },
+    fetchBinaryTimeline: (id) => { throw new Error('Tauri not available'); },
     getAudioDuration: (filePath, fileNameHint) => { throw new Error('Tauri not available'); },
-    calculateStarRating: (filePath) => { throw new Error('Tauri not available'); },
+    calculateStarRating: (filePath, options) => { throw new Error('Tauri not available'); },
+    checkSnapping: (filePath) => { throw new Error('Tauri not available'); },
+    backfillBeatmapIds: (filePaths, apiKey, writeBack) => { throw new Error('Tauri not available'); },
+    lintBeatmap: (filePath, options) => { throw new Error('Tauri not available'); },
+    scanFileList: (paths, mapperName, knownFiles, options) => { throw new Error('Tauri not available'); },
+    writeOsuMetadata: (filePath, changes, applyToSet) => { throw new Error('Tauri not available'); },
+    compareMaps: (pathA, pathB, bucketMs) => { throw new Error('Tauri not available'); },
+    mergeGuestDiff: (osuOrOsz, targetFolder, overwrite) => { throw new Error('Tauri not available'); },
+    writeOsuBookmarks: (filePath, bookmarks, backup) => { throw new Error('Tauri not available'); },
+    applyOffset: (filePath, offsetMs, backup) => { throw new Error('Tauri not available'); },
+    setPreviewTime: (filePath, timeMs, applyToSet) => { throw new Error('Tauri not available'); },
+    getDifficultySettings: (filePath, mods) => { throw new Error('Tauri not available'); },
+    queryLibrary: (filter, sortKey, descending) => { throw new Error('Tauri not available'); },
+    compareBeatmaps: (pathA, pathB) => { throw new Error('Tauri not available'); },
+    snapshotBeatmap: (path) => { throw new Error('Tauri not available'); },
+    listSnapshots: (path) => { throw new Error('Tauri not available'); },
+    restoreSnapshot: (path, id) => { throw new Error('Tauri not available'); },
+    exportScanResults: (format, destination, fields) => { throw new Error('Tauri not available'); },
+    aggregateMapperStats: (directory, mapper, clientType, apiKey) => { throw new Error('Tauri not available'); },
+    detectOsuSongsFolder: () => { throw new Error('Tauri not available'); },
+    listLibraryRoots: () => { throw new Error('Tauri not available'); },
+    addLibraryRoot: (path, clientType) => { throw new Error('Tauri not available'); },
+    removeLibraryRoot: (path) => { throw new Error('Tauri not available'); },
+    scanLibraryRoots: (roots, mapperName, knownFiles, options) => { throw new Error('Tauri not available'); },
+    exportMappool: (slots, format, destination, bundlePath) => { throw new Error('Tauri not available'); },
+    importOsz: () => { throw new Error('Tauri not available'); },
+    describeDeepLink: (url) => { throw new Error('Tauri not available'); },
+    openDeepLink: (url, confirmed) => { throw new Error('Tauri not available'); },
+    takeLaunchDeepLinks: () => { throw new Error('Tauri not available'); },
+    probeAudio: (filePath, fileNameHint) => { throw new Error('Tauri not available'); },
+    probeVideo: (filePath) => { throw new Error('Tauri not available'); },
+    renderPlayfieldSnapshot: (filePath, timeMs, options) => { throw new Error('Tauri not available'); },
+    getSliderPaths: (filePath, timeMs) => { throw new Error('Tauri not available'); },
+    analyzeMania: (filePath) => { throw new Error('Tauri not available'); },
+    analyzeTaiko: (filePath) => { throw new Error('Tauri not available'); },
+    analyzeCatch: (filePath) => { throw new Error('Tauri not available'); },
+    simulateScore: (filePath, mods) => { throw new Error('Tauri not available'); },
+    getLocalScores: (beatmapMd5) => { throw new Error('Tauri not available'); },
+    getRecentPlays: (userId, apiKey, mode, limit) => { throw new Error('Tauri not available'); },
+    getLibraryPage: (offset, limit, sortKey, descending, filter) => { throw new Error('Tauri not available'); },
+    sortLibrary: (key, dir) => { throw new Error('Tauri not available'); },
+    getLibraryStats: () => { throw new Error('Tauri not available'); },
+    searchLibrary: (query, limit) => { throw new Error('Tauri not available'); },
+    getCachedStarRating: (filePath, mods) => { throw new Error('Tauri not available'); },
+    enqueueJob: (kind) => { throw new Error('Tauri not available'); },
+    cancelJob: (jobId) => { throw new Error('Tauri not available'); },
+    applyModsToDifficulty: (filePath, mods) => { throw new Error('Tauri not available'); },
+    openInOsuEditor: (filePath, timeMs, clientType) => { throw new Error('Tauri not available'); },
+    parseEditorTimestamp: (text) => { throw new Error('Tauri not available'); },
+    formatEditorTimestamp: (timeMs, objectIndices, copy) => { throw new Error('Tauri not available'); },
+    exportModPost: (issues, format) => { throw new Error('Tauri not available'); },
+    suggestBreaks: (filePath, minLengthMs) => { throw new Error('Tauri not available'); },
+    analyzeSpread: (folder) => { throw new Error('Tauri not available'); },
+    getDensityHistogram: (filePath, bucketMs, levels) => { throw new Error('Tauri not available'); },
+    computeMappingProgress: (filePath, audioDuration) => { throw new Error('Tauri not available'); },
+    getMapWorkStats: (path) => { throw new Error('Tauri not available'); },
+    listBackups: (path) => { throw new Error('Tauri not available'); },
+    restoreBackup: (path, id) => { throw new Error('Tauri not available'); },
+    trashFiles: (paths) => { throw new Error('Tauri not available'); },
+    undoTrash: () => { throw new Error('Tauri not available'); },
+    exportOsz: (folder, destination, options) => { throw new Error('Tauri not available'); },
+    analyzeAudioQuality: (filePath, fileNameHint) => { throw new Error('Tauri not available'); },
+    checkOnsetAlignment: (filePath, audioPath) => { throw new Error('Tauri not available'); },
+    renderClickTrack: (filePath, destination, durationMs) => { throw new Error('Tauri not available'); },
+    exportAudioClip: (filePath, startMs, endMs, destination, format) => { throw new Error('Tauri not available'); },
+    renderRatePreview: (filePath, startMs, endMs, rate, preservePitch) => { throw new Error('Tauri not available'); },
+    checkMetadataConsistency: (folder) => { throw new Error('Tauri not available'); },
+    getStackedObjects: (filePath) => { throw new Error('Tauri not available'); },
+    checkReadability: (filePath, options) => { throw new Error('Tauri not available'); },
+    analyzeDistanceSnap: (filePath, tolerance) => { throw new Error('Tauri not available'); },
+    calculateDifficultyAttributes: (filePath, mods) => { throw new Error('Tauri not available'); },
+    calculateStarRatingProgression: (filePath, stepObjects, mods) => { throw new Error('Tauri not available'); },
+    renderProgressImage: (filePath, width, height, outputPath) => { throw new Error('Tauri not available'); },
+    classifyBeatmap: (filePath, apiKey) => { throw new Error('Tauri not available'); },
+    listPlaylists: () => { throw new Error('Tauri not available'); },
+    createPlaylist: (name) => { throw new Error('Tauri not available'); },
+    deletePlaylist: (name) => { throw new Error('Tauri not available'); },
+    addToPlaylist: (name, filePaths) => { throw new Error('Tauri not available'); },
+    removeFromPlaylist: (name, filePaths) => { throw new Error('Tauri not available'); },
+    exportPlaylistAsCollection: (name, collectionDbPath, collectionName) => { throw new Error('Tauri not available'); },
+    getStaleRatings: () => { throw new Error('Tauri not available'); },
+    recalculateStaleRatings: (filePaths) => { throw new Error('Tauri not available'); },
+    openMapWindow: (filePath) => { throw new Error('Tauri not available'); },
+    getMapWindowContext: () => { throw new Error('Tauri not available'); },
+    getNowPlaying: () => { throw new Error('Tauri not available'); },
 };
 
 /**
@@ -39,6 +125,15 @@ export const appInfo = window.appInfo || {
     checkForUpdates: () => { throw new Error('Tauri not available'); },
     openExternalUrl: (url) => { throw new Error('Tauri not available'); },
     getOsuUserData: (urlOrId) => { throw new Error('Tauri not available'); },
+    createSupportBundle: (path) => { throw new Error('Tauri not available'); },
+    getSettings: () => { throw new Error('Tauri not available'); },
+    setSettings: (settings, replace, schemaVersion) => { throw new Error('Tauri not available'); },
+    storeSecret: (name, value) => { throw new Error('Tauri not available'); },
+    getSecret: (name) => { throw new Error('Tauri not available'); },
+    downloadUpdate: () => { throw new Error('Tauri not available'); },
+    applyUpdate: () => { throw new Error('Tauri not available'); },
+    resolveOsuUsers: (queries) => { throw new Error('Tauri not available'); },
+    getOsuUserProfile: (id) => { throw new Error('Tauri not available'); },
 };
 
 /**
@@ -53,4 +148,9 @@ export const tauriEvents = window.tauriEvents || {
  */
 export const embedSyncApi = window.embedSyncApi || {
     sync: (url, apiKey, data) => { throw new Error('Tauri not available'); },
+    buildProgressEmbed: (map, stats) => { throw new Error('Tauri not available'); },
+    postProgressEmbed: (webhookUrl, map, stats) => { throw new Error('Tauri not available'); },
+    startAutoSync: (url, apiKey, intervalSecs, payload, watched) => { throw new Error('Tauri not available'); },
+    updateAutoSyncPayload: (payload, watched) => { throw new Error('Tauri not available'); },
+    stopAutoSync: () => { throw new Error('Tauri not available'); },
 };
@@ -1,5 +1,11 @@
-export const STORAGE_KEY = 'beatmapItemsV1';
+// Label of the beatmap comparison window this page runs in (`?mapWindow=`), empty in the main window
+export const MAP_WINDOW_LABEL = new URLSearchParams(window.location.search).get('mapWindow') || '';
+
+// Comparison windows keep their own item lists so they never overwrite the main library
+const windowScoped = (key) => (MAP_WINDOW_LABEL ? `${key}:${MAP_WINDOW_LABEL}` : key);
+
+export const STORAGE_KEY = windowScoped('beatmapItemsV1');
 export const SETTINGS_STORAGE_KEY = 'mapTrackerSettingsV1';
-export const AUDIO_ANALYSIS_STATE_KEY = 'audioAnalysisStateV1';
-export const STAR_RATING_STATE_KEY = 'starRatingStateV1';
+export const AUDIO_ANALYSIS_STATE_KEY = windowScoped('audioAnalysisStateV1');
+export const STAR_RATING_STATE_KEY = windowScoped('starRatingStateV1');
 export const STORAGE_VERSION = 1;
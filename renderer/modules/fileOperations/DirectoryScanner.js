@@ -3,21 +3,25 @@
  * Extracted from renderer.js (lines 3998-4047, 4232-4347)
  */
 
-import { beatmapApi } from '../bridge/Tauri.js';
+import { beatmapApi, tauriEvents } from '../bridge/Tauri.js';
 import {
     settings,
     setBeatmapItems,
-    beatmapItems
+    beatmapItems,
+    libraryRoots,
+    setLibraryRoots
 } from '../state/Store.js';
 import { scheduleSave, persistSettings } from '../state/Persistence.js';
-import { startStreamingScan } from '../services/ScanManager.js';
+import { startStreamingScan, handleScanBatch, STREAMING_SCAN_OPTIONS } from '../services/ScanManager.js';
 import {
     queueMissingAudioAnalysisFromItems,
     queueMissingStarRatingFromItems
 } from '../services/BackgroundProcessor.js';
 import { buildItemFromContent } from '../itemProcessing/ItemBuilder.js';
 import { getEffectiveMapperName, processMapperInput } from '../parsers/GuestDifficultyFilter.js';
-import { closeDialogWithAnimation as defaultCloseDialogWithAnimation } from '../ui/DialogManager.js';
+import { closeDialogWithAnimation as defaultCloseDialogWithAnimation, showConfirmDialog } from '../ui/DialogManager.js';
+import { escapeHtml } from '../utils/Helpers.js';
+import { MAP_WINDOW_LABEL } from '../config/Constants.js';
 
 // ============================================
 // State
@@ -28,6 +32,21 @@ let lastScannedDirectory = localStorage.getItem('lastScannedDirectory') || null;
 
 const getActiveOsuClient = () => settings?.osuClient === 'lazer' ? 'lazer' : 'stable';
 
+/**
+ * Known files (path -> mtime) the backend may skip re-reading during a rescan
+ * @returns {Object<string, number>} Known files
+ */
+const buildKnownFiles = () => {
+    const knownFiles = {};
+    beatmapItems.forEach(item => {
+        const hasKnownHash = getActiveOsuClient() === 'lazer' || !!String(item.beatmapHash || '').trim();
+        if (item.filePath && hasKnownHash) {
+            knownFiles[item.filePath] = item.dateModified;
+        }
+    });
+    return knownFiles;
+};
+
 // ============================================
 // Directory Getters/Setters
 // ============================================
@@ -80,6 +99,16 @@ export async function loadBeatmapFromDialog(callbacks) {
         return;
     }
 
+    const result = await beatmapApi.openOsuFile();
+    await addOsuFilePayloads(result?.files, callbacks);
+}
+
+/**
+ * Build list items from `.osu` file payloads (as returned by `openOsuFile`/`importOsz`) and add them
+ * @param {Array<Object>|undefined} files - Osu file payloads
+ * @param {Object} callbacks - Same callbacks as loadBeatmapFromDialog
+ */
+async function addOsuFilePayloads(files, callbacks) {
     const {
         setLoading,
         updateEmptyState,
@@ -90,8 +119,7 @@ export async function loadBeatmapFromDialog(callbacks) {
 
     let didSetLoading = false;
     try {
-        const result = await beatmapApi.openOsuFile();
-        if (!result || !result.files || !result.files.length) {
+        if (!files || !files.length) {
             if (updateEmptyState) {
                 updateEmptyState();
             }
@@ -104,7 +132,7 @@ export async function loadBeatmapFromDialog(callbacks) {
         didSetLoading = true;
 
         const items = [];
-        for (const file of result.files) {
+        for (const file of files) {
             if (!file?.content) {
                 continue;
             }
@@ -153,6 +181,134 @@ export async function loadBeatmapFromDialog(callbacks) {
     }
 }
 
+/**
+ * Listen for files dropped on the window. The backend reads dropped .osu files, extracts .osz
+ * archives and parses .osr replays, so drops end up exactly like the file dialogs.
+ * @param {Object} callbacks - Same callbacks as loadBeatmapFromDialog, plus showNotification
+ * @returns {Promise<Function|null>} Unlisten function
+ */
+export async function initDroppedFileImport(callbacks = {}) {
+    if (!tauriEvents?.listen) {
+        return null;
+    }
+
+    const eventPrefix = MAP_WINDOW_LABEL ? `map-window:${MAP_WINDOW_LABEL}:` : '';
+    return tauriEvents.listen(`${eventPrefix}files-dropped`, (payload) => addIngestedFiles(payload, callbacks));
+}
+
+/**
+ * Load the beatmap a comparison window was opened for
+ * @param {Object} callbacks - Same callbacks as initDroppedFileImport
+ */
+export async function initMapWindow(callbacks = {}) {
+    if (!MAP_WINDOW_LABEL || !beatmapApi?.getMapWindowContext) {
+        return;
+    }
+
+    try {
+        const context = await beatmapApi.getMapWindowContext();
+        if (context?.osu) {
+            await addIngestedFiles({ osu: { files: [context.osu] } }, callbacks);
+        }
+    } catch (error) {
+        callbacks.showNotification?.('Map Window', String(error?.message || error), 'error');
+    }
+}
+
+/**
+ * Open a beatmap in its own window, e.g. to compare two difficulties side by side
+ * @param {string} filePath - Path of the .osu file
+ */
+export async function openMapWindow(filePath) {
+    if (!filePath || !beatmapApi?.openMapWindow) {
+        return null;
+    }
+    return beatmapApi.openMapWindow(filePath);
+}
+
+/**
+ * Ask before a link downloads a beatmap set or extracts an archive
+ * @param {Object} action - `{ kind, path, beatmapSetId, beatmapId }` from describeDeepLink
+ * @returns {Promise<boolean>} Whether the link may go ahead
+ */
+async function confirmDeepLinkAction(action) {
+    if (action.kind === 'open') {
+        return true;
+    }
+    const target = action.kind === 'extract'
+        ? `extract <code>${escapeHtml(action.path)}</code> into your Songs folder`
+        : action.beatmapSetId
+            ? `download beatmap set ${escapeHtml(action.beatmapSetId)}`
+            : `download the set of beatmap ${escapeHtml(action.beatmapId)}`;
+    return showConfirmDialog({
+        title: action.kind === 'extract' ? 'Extract Beatmap Archive?' : 'Download Beatmap?',
+        message: `A link wants mosu to ${target}. Only continue if you opened this link yourself.`,
+        confirmText: action.kind === 'extract' ? 'Extract' : 'Download'
+    });
+}
+
+/**
+ * Open `mosu://` links, both the ones the app was launched with and those forwarded later.
+ * Links that download or extract anything are confirmed first.
+ * @param {Object} callbacks - Same callbacks as initDroppedFileImport
+ * @returns {Promise<Function|null>} Unlisten function
+ */
+export async function initDeepLinks(callbacks = {}) {
+    if (!tauriEvents?.listen || !beatmapApi?.openDeepLink || !beatmapApi?.describeDeepLink) {
+        return null;
+    }
+
+    const openLink = async (url) => {
+        try {
+            const action = await beatmapApi.describeDeepLink(url);
+            if (!(await confirmDeepLinkAction(action))) {
+                return;
+            }
+            await addIngestedFiles(await beatmapApi.openDeepLink(url, action.kind !== 'open'), callbacks);
+        } catch (error) {
+            callbacks.showNotification?.('Link Failed', String(error?.message || error), 'error');
+        }
+    };
+
+    const unlisten = await tauriEvents.listen('deep-link', openLink);
+    for (const url of await beatmapApi.takeLaunchDeepLinks()) {
+        await openLink(url);
+    }
+    return unlisten;
+}
+
+/**
+ * Add the beatmaps from a backend file ingest (drops and deep links) and report replays/errors
+ * @param {Object} payload - `{ osu, imports, replays, errors }`
+ * @param {Object} callbacks - Same callbacks as initDroppedFileImport
+ */
+async function addIngestedFiles(payload, callbacks) {
+    const files = [
+        ...(payload?.osu?.files || []),
+        ...(payload?.imports || []).flatMap((archive) => archive.files || [])
+    ];
+    if (files.length) {
+        await addOsuFilePayloads(files, callbacks);
+    }
+
+    const { showNotification } = callbacks;
+    if (!showNotification) {
+        return;
+    }
+    for (const replay of payload?.replays || []) {
+        const map = beatmapItems.find((item) => item.beatmapHash === replay.beatmapHash);
+        const mapLabel = map ? `${map.artist} - ${map.title} [${map.version}]` : 'a map not in your library';
+        const mods = replay.modsAcronym && replay.modsAcronym !== 'NM' ? ` +${replay.modsAcronym}` : '';
+        showNotification(
+            'Replay',
+            `${replay.playerName} on ${mapLabel}: ${(replay.accuracy * 100).toFixed(2)}%${mods}, ${replay.maxCombo}x`
+        );
+    }
+    for (const failure of payload?.errors || []) {
+        showNotification('Import Failed', `${failure.filePath}: ${failure.error}`, 'error');
+    }
+}
+
 /**
  * Refresh the last scanned directory
  * @param {Object} callbacks - Callback functions
@@ -180,18 +336,11 @@ export async function refreshLastDirectory(callbacks = {}) {
     try {
         const mapperName = (getEffectiveMapperName() || '').trim() || null;
 
-        // Build knownFiles cache (path -> mtime)
-        const knownFiles = {};
-        beatmapItems.forEach(item => {
-            const hasKnownHash = getActiveOsuClient() === 'lazer' || !!String(item.beatmapHash || '').trim();
-            if (item.filePath && hasKnownHash) {
-                knownFiles[item.filePath] = item.dateModified;
-            }
-        });
+        const knownFiles = buildKnownFiles();
 
-        // Start streaming scan — results arrive via scan-batch events
+        // Start streaming scan — results arrive in order over the onBatch channel
         const scanDone = startStreamingScan('directory', { callbacks });
-        await beatmapApi.scanDirectoryOsuFiles(targetDir, mapperName, knownFiles, getActiveOsuClient());
+        await beatmapApi.scanDirectoryOsuFiles(targetDir, mapperName, knownFiles, getActiveOsuClient(), STREAMING_SCAN_OPTIONS, handleScanBatch);
         await scanDone;
 
         // Success animation
@@ -367,3 +516,85 @@ export async function loadBeatmapsFromFolder(callbacks = {}) {
 
     await scanDone;
 }
+
+// ============================================
+// Library Roots
+// ============================================
+
+/**
+ * Reload the registered library roots
+ * @returns {Promise<Array<Object>>} Registered roots
+ */
+export async function loadLibraryRoots() {
+    if (!beatmapApi?.listLibraryRoots) {
+        return [];
+    }
+    setLibraryRoots(await beatmapApi.listLibraryRoots());
+    return libraryRoots;
+}
+
+/**
+ * Ask for a folder and register it as a library root for the active client
+ * @returns {Promise<string|null>} The added folder, or null if the picker was cancelled
+ */
+export async function addLibraryRootFromDialog() {
+    if (!beatmapApi?.selectDirectory || !beatmapApi?.addLibraryRoot) {
+        return null;
+    }
+    const dir = await beatmapApi.selectDirectory('Add library folder');
+    if (!dir) {
+        return null;
+    }
+    setLibraryRoots(await beatmapApi.addLibraryRoot(dir, getActiveOsuClient()));
+    return dir;
+}
+
+/**
+ * Unregister a library root and drop its maps from the list
+ * @param {string} rootPath - Root to remove
+ * @param {Object} callbacks - Callback functions
+ * @param {Function} callbacks.updateTabCounts - Update tab counts
+ * @param {Function} callbacks.renderFromState - Render from current state
+ */
+export async function removeLibraryRoot(rootPath, callbacks = {}) {
+    if (!beatmapApi?.removeLibraryRoot) {
+        return;
+    }
+    setLibraryRoots(await beatmapApi.removeLibraryRoot(rootPath));
+
+    const normalizedRoot = rootPath.toLowerCase().replace(/\\/g, '/').replace(/\/?$/, '/');
+    setBeatmapItems(beatmapItems.filter(item => {
+        const itemPath = (item.filePath || '').toLowerCase().replace(/\\/g, '/');
+        return !itemPath.startsWith(normalizedRoot);
+    }));
+    if (callbacks.updateTabCounts) {
+        callbacks.updateTabCounts();
+    }
+    if (callbacks.renderFromState) {
+        callbacks.renderFromState();
+    }
+    scheduleSave();
+}
+
+/**
+ * Scan the registered library roots one after another and merge their maps into the list
+ * @param {Array<string>|null} roots - Roots to rescan, or null for all of them
+ * @param {Object} callbacks - Same callbacks as refreshLastDirectory
+ */
+export async function scanLibraryRoots(roots, callbacks = {}) {
+    if (!beatmapApi?.scanLibraryRoots) {
+        return;
+    }
+
+    try {
+        const mapperName = (getEffectiveMapperName() || '').trim() || null;
+        const scanDone = startStreamingScan('roots', { callbacks });
+        await beatmapApi.scanLibraryRoots(roots, mapperName, buildKnownFiles(), STREAMING_SCAN_OPTIONS);
+        await scanDone;
+    } catch (error) {
+        console.error('Library scan failed:', error);
+        if (callbacks.showNotification) {
+            callbacks.showNotification('Scan Failed', error.message || 'Failed to scan library folders.', 'error');
+        }
+    }
+}
@@ -36,6 +36,7 @@ let boundClickHandler = null;
  * @param {Function} callbacks.showItemInFolder - Show item in folder
  * @param {Function} callbacks.openInTextEditor - Open item in text editor
  * @param {Function} callbacks.openMapPreview - Open map preview popup
+ * @param {Function} callbacks.openMapWindow - Open a beatmap in its own window
  * @param {Function} callbacks.addToCollection - Add beatmap to an osu! collection
  */
 export const handleActionClick = (action, itemId, target, callbacks) => {
@@ -90,6 +91,14 @@ export const handleActionClick = (action, itemId, target, callbacks) => {
             }
             break;
 
+        case 'open-map-window': {
+            const path = target.dataset.path;
+            if (path && callbacks.openMapWindow) {
+                callbacks.openMapWindow(path);
+            }
+            break;
+        }
+
         case 'add-to-collection':
             if (itemId && callbacks.addToCollection) {
                 callbacks.addToCollection(itemId);
@@ -257,6 +266,7 @@ export const unbindGlobalEvents = () => {
  * @param {Function} callbacks.showItemInFolder - Show item in folder
  * @param {Function} callbacks.openInTextEditor - Open item in text editor
  * @param {Function} callbacks.openMapPreview - Open map preview popup
+ * @param {Function} callbacks.openMapWindow - Open a beatmap in its own window
  * @param {Function} callbacks.addToCollection - Add beatmap to an osu! collection
  * @param {Function} callbacks.playAudio - Play audio for item
  * @param {string} callbacks.viewMode - Current view mode
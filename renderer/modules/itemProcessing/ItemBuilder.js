@@ -110,11 +110,12 @@ export async function buildItemFromContent(filePath, content, stat, existing, be
  * @param {Array<number>} file.bookmarks - Bookmarks
  * @param {string} file.filePath - File path
  * @param {Object} file.stat - File stats
+ * @param {string} [file.libraryRoot] - Library root the file was scanned from
  * @param {Object|null} existing - Existing item data
  * @returns {Object} Processed item
  */
 export function processWorkerResult(file, existing) {
-    const { metadata, hitStarts, hitEnds, breakPeriods, bookmarks, filePath, stat, beatmapHash } = file || {};
+    const { metadata, hitStarts, hitEnds, breakPeriods, bookmarks, filePath, stat, beatmapHash, libraryRoot } = file || {};
     const isLazerClient = getActiveOsuClient() === 'lazer';
 
     let coverUrl = '';
@@ -182,6 +183,7 @@ export function processWorkerResult(file, existing) {
         dateModified: stat?.mtimeMs ?? 0,
         id: existing?.id ?? createItemId(filePath),
         filePath,
+        libraryRoot: libraryRoot || existing?.libraryRoot || '',
         beatmapHash: deriveBeatmapHash(filePath, beatmapHash, isLazerClient, existing),
         starRating: isValidStarRating(metadata?.starRating) ? metadata.starRating : null,
     };
@@ -247,11 +249,17 @@ export function buildItemFromCache(cached, settingsObj = settings) {
         artistUnicode: cached.artistUnicode || cached.artist || 'Unknown Artist',
         creator: cached.creator || 'Unknown Creator',
         version: cached.version,
+        source: cached.source || '',
+        tags: cached.tags || '',
+        guestMapper: cached.guestMapper || '',
+        libraryRoot: cached.libraryRoot || '',
         beatmapSetID: cached.beatmapSetID || '-1',
         mode: Number.isFinite(cached.mode) ? Math.min(Math.max(cached.mode, 0), 3) : 0,
         audio: cached.audio || '',
         audioFileName: cached.audioFileName || '',
         background: cached.background || '',
+        video: cached.video || '',
+        backgroundInfo: cached.backgroundInfo || null,
         beatmapHash: cached.beatmapHash || (
             isLazerClient
                 ? String(cached.filePath || '').replace(/\\/g, '/').split('/').pop()?.toLowerCase() || ''
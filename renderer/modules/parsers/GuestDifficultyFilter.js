@@ -57,6 +57,13 @@ export const isGuestDifficultyItem = (item, mapperNeedles) => {
 
     const creator = String(item?.creator || '').toLowerCase();
     const version = String(item?.version || '').toLowerCase();
+    const guestMapper = String(item?.guestMapper || '').toLowerCase();
+
+    // Prefer the guest mapper detected during the scan; it also catches tagged guests without a possessive name
+    if (guestMapper) {
+        return mapperNeedles.some(mapper => creator.includes(mapper))
+            && !mapperNeedles.some(mapper => guestMapper.includes(mapper));
+    }
 
     return mapperNeedles.some(mapper => {
         if (!creator.includes(mapper)) return false;
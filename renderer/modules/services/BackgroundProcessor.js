@@ -29,7 +29,8 @@ import {
     STORAGE_KEY,
     SETTINGS_STORAGE_KEY,
     AUDIO_ANALYSIS_STATE_KEY,
-    STAR_RATING_STATE_KEY
+    STAR_RATING_STATE_KEY,
+    MAP_WINDOW_LABEL
 } from '../config/Constants.js';
 
 // ============================================
@@ -577,6 +578,8 @@ export const processStarRatingQueue = async (callbacks = {}) => {
  * @param {Object} [callbacks] - Optional callbacks passed to individual processors
  */
 export const processBackgroundQueues = (callbacks = {}) => {
+    // Comparison windows only read results; the main window does the analysis
+    if (MAP_WINDOW_LABEL) return;
     processStarRatingQueue(callbacks);
     processAudioQueue(callbacks);
 };
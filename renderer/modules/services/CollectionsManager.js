@@ -20,6 +20,7 @@ let collectionModeAutoRefreshCallbacks = {};
 let collectionModeAutoRefreshTimer = null;
 let collectionModeAutoRefreshBound = false;
 let collectionModeAutoRefreshInFlight = false;
+let collectionModeAutoRefreshPaused = false;
 
 const getActiveOsuClient = () => Store.settings.osuClient === CLIENT_LAZER ? CLIENT_LAZER : CLIENT_STABLE;
 
@@ -586,6 +587,7 @@ export const initCollectionModeAutoRefresh = (callbacks = {}) => {
 
     if (!collectionModeAutoRefreshBound) {
         const refreshOnResume = () => {
+            if (collectionModeAutoRefreshPaused) return;
             void checkForCollectionModeUpdates(collectionModeAutoRefreshCallbacks, {
                 silent: true,
             });
@@ -607,12 +609,21 @@ export const initCollectionModeAutoRefresh = (callbacks = {}) => {
     }
 
     collectionModeAutoRefreshTimer = window.setInterval(() => {
+        if (collectionModeAutoRefreshPaused) return;
         void checkForCollectionModeUpdates(collectionModeAutoRefreshCallbacks, {
             silent: true,
         });
     }, COLLECTION_MODE_REFRESH_INTERVAL_MS);
 };
 
+/**
+ * Pause or resume the background collection watcher (the tray's "Pause watcher")
+ * @param {boolean} paused
+ */
+export const setCollectionModeAutoRefreshPaused = (paused) => {
+    collectionModeAutoRefreshPaused = !!paused;
+};
+
 export const promptAddBeatmapToCollection = async (itemId, callbacks = {}) => {
     if (!Store.settings.collectionsImportEnabled) {
         return;
@@ -3,7 +3,7 @@
  * Extracted from renderer.js (lines 2829-3032)
  */
 
-import { embedSyncApi } from '../bridge/Tauri.js';
+import { beatmapApi, embedSyncApi } from '../bridge/Tauri.js';
 import {
     beatmapItems,
     todoIds,
@@ -13,6 +13,7 @@ import {
 } from '../state/Store.js';
 import { showNotification } from '../components/NotificationSystem.js';
 import { generateApiKey } from '../utils/Helpers.js';
+import { MAP_WINDOW_LABEL } from '../config/Constants.js';
 
 // ============================================
 // Constants
@@ -31,6 +32,12 @@ let embedSyncTimer = null;
 /** @type {number} */
 let lastEmbedSyncTime = 0;
 
+/** @type {boolean} */
+let autoSyncRunning = false;
+
+/** @type {Function|null} */
+let unlistenSyncResult = null;
+
 // ============================================
 // Payload Building
 // ============================================
@@ -247,6 +254,8 @@ export const performEmbedSync = async (callbacks = {}) => {
  * @param {Object} [callbacks] - Optional callbacks passed to performEmbedSync
  */
 export const scheduleEmbedSync = (callbacks = {}) => {
+    refreshAutoSyncPayload();
+
     if (embedSyncTimer) {
         clearTimeout(embedSyncTimer);
     }
@@ -270,6 +279,70 @@ export const triggerManualSync = async (callbacks = {}) => {
     await performEmbedSync(callbacks);
 };
 
+// ============================================
+// Auto Sync
+// ============================================
+
+/**
+ * Watched maps for the background scheduler: to-do items with a file on disk
+ * @returns {Array<{id: string, filePath: string}>}
+ */
+const getWatchedMaps = () => todoIds
+    .map(id => beatmapItems.find(item => item.id === id))
+    .filter(item => item && item.filePath)
+    .map(item => ({ id: item.id, filePath: item.filePath }));
+
+/**
+ * Start, restart or stop the background auto-sync according to settings.embedAutoSyncMinutes
+ * @param {Object} [callbacks] - Optional callbacks
+ * @param {Function} callbacks.persistSettings - Function to persist settings
+ */
+export const configureEmbedAutoSync = async (callbacks = {}) => {
+    // The scheduler is global, so only the main window may start or replace it
+    if (MAP_WINDOW_LABEL) return;
+    const minutes = Number(settings.embedAutoSyncMinutes) || 0;
+    if (minutes <= 0 || !settings.embedApiKey) {
+        if (autoSyncRunning) {
+            autoSyncRunning = false;
+            await embedSyncApi.stopAutoSync();
+        }
+        return;
+    }
+
+    if (!unlistenSyncResult && beatmapApi?.listen) {
+        unlistenSyncResult = await beatmapApi.listen('sync-result', (result) => {
+            if (result.skipped) return;
+            if (result.success) {
+                updateSettings({ embedLastSynced: result.syncedAtMs });
+                if (callbacks.persistSettings) {
+                    callbacks.persistSettings();
+                }
+                updateEmbedSyncStatus('synced');
+            } else {
+                updateEmbedSyncStatus('error', result.error || 'Sync Failed');
+            }
+        });
+    }
+
+    await embedSyncApi.startAutoSync(
+        `${settings.embedSyncUrl}/api/sync`,
+        settings.embedApiKey,
+        Math.round(minutes * 60),
+        buildEmbedPayload(),
+        getWatchedMaps()
+    );
+    autoSyncRunning = true;
+};
+
+/**
+ * Hand the latest payload to the running auto-sync scheduler
+ */
+export const refreshAutoSyncPayload = () => {
+    if (!autoSyncRunning) return;
+    embedSyncApi.updateAutoSyncPayload(buildEmbedPayload(), getWatchedMaps())
+        .catch(err => console.error('Failed to update auto-sync payload:', err));
+};
+
 /**
  * Check if sync is possible (has userId and API key)
  * @returns {boolean} Whether sync can be performed
@@ -310,6 +383,8 @@ export default {
     scheduleEmbedSync,
     updateEmbedSyncStatus,
     triggerManualSync,
+    configureEmbedAutoSync,
+    refreshAutoSyncPayload,
     canSync,
     cancelScheduledSync,
     getSyncStatus,
@@ -6,7 +6,7 @@ import { beatmapApi } from '../bridge/Tauri.js';
 import * as Store from '../state/Store.js';
 import { parseMapPreviewData } from '../parsers/BeatmapParser.js';
 import { showNotification } from '../components/NotificationSystem.js';
-import { resolveItemAssetPath } from '../utils/Helpers.js';
+import { resolveItemAssetPath, formatSongName } from '../utils/Helpers.js';
 import { getAudioSourceUrl } from '../utils/AudioAssetLoader.js';
 import { renderTimeline } from './TimelineRenderer.js';
 
@@ -2778,7 +2778,7 @@ export const openMapPreview = async (itemId) => {
 
         state.popup.hidden = false;
         state.popup.classList.add('is-open');
-        state.title.textContent = `${item.artistUnicode || item.artist || 'Unknown Artist'} - ${item.titleUnicode || item.title || 'Unknown Title'}`;
+        state.title.textContent = formatSongName(item, Store.settings.preferUnicodeMetadata !== false);
         state.version.textContent = item.version || '';
         state.version.title = item.version || '';
         updateStatsLabel();
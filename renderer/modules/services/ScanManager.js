@@ -3,7 +3,7 @@
  * Extracted from renderer.js (lines 4049-4231)
  */
 
-import { tauriEvents } from '../bridge/Tauri.js';
+import { tauriEvents, beatmapApi } from '../bridge/Tauri.js';
 import {
     beatmapItems,
     updateState,
@@ -11,6 +11,9 @@ import {
 } from '../state/Store.js';
 import { isStarRatingMissing } from '../utils/Validation.js';
 import { processWorkerResult } from '../itemProcessing/ItemBuilder.js';
+import { showNotification } from '../components/NotificationSystem.js';
+import { base64ToBytes, decodeMessagePack } from '../utils/MessagePack.js';
+import { parseHitObjects } from '../parsers/BeatmapParser.js';
 
 // ============================================
 // Scan State
@@ -21,23 +24,42 @@ import { processWorkerResult } from '../itemProcessing/ItemBuilder.js';
  * @property {string} directory - Scanned directory path
  * @property {Map<string, Object>} existingMap - Map of existing items by file path
  * @property {Array<Object>} items - Collected items from scan
+ * @property {Set<string>} removedPaths - Cached paths the backend reported as deleted
+ * @property {Array<Object>} errors - Per-file errors reported via scan-errors
+ * @property {number} expiredTimelines - Maps whose binary timeline expired and were re-read from disk
  * @property {number} processed - Number of files processed
  * @property {number} totalFiles - Total files to process
+ * @property {Promise<void>} batchQueue - Keeps batches in arrival order while timelines are fetched
  * @property {Function} resolveComplete - Promise resolve callback
  */
 
+/**
+ * Options for scanDirectoryOsuFiles; hit times of large maps come back as binary timelines.
+ * compactEvents (MessagePack batches) stays off until it's shown to beat plain JSON.
+ */
+export const STREAMING_SCAN_OPTIONS = { binaryTimeline: true, compactEvents: false };
+
 /** @type {StreamingScanState|null} */
 let streamingScanState = null;
 
 /** @type {Function|null} */
 let scanBatchUnlisten = null;
 
+/** @type {Function|null} Processes one scan batch, whether it came as an event or over a channel */
+let scanBatchHandler = null;
+
 /** @type {Function|null} */
 let scanCompleteUnlisten = null;
 
 /** @type {Function|null} */
 let scanStatusUnlisten = null;
 
+/** @type {Function|null} */
+let scanRemovedUnlisten = null;
+
+/** @type {Function|null} */
+let scanErrorsUnlisten = null;
+
 /** @type {boolean} */
 let isScanningActive = false;
 
@@ -58,6 +80,63 @@ const formatStatusLabel = ({ stage, current = 0, total = 0, discoveredFiles } =
 // Event Listeners
 // ============================================
 
+/**
+ * Describe scan error counts, e.g. "3 permission denied, 1 timeout"
+ * @param {Object<string, number>} byKind - Error counts keyed by kind
+ * @returns {string} Human-readable summary
+ */
+const formatErrorKinds = (byKind = {}) => Object.entries(byKind)
+    .map(([kind, count]) => `${count} ${kind.replace(/-/g, ' ')}`)
+    .join(', ');
+
+/**
+ * Unpack a scan-batch payload the backend sent as MessagePack (compactEvents)
+ * @param {Object} payload - Raw scan-batch payload
+ * @returns {Object} Payload in the plain JSON shape
+ */
+const unpackScanBatch = (payload) => {
+    if (payload?.encoding !== 'msgpack') return payload;
+    return decodeMessagePack(base64ToBytes(payload.data));
+};
+
+/**
+ * Fetch hit times the backend sent as binary timelines and put them back on the scan results
+ * @param {Array<Object>} files - Files from a scan-batch payload
+ * @param {StreamingScanState} state - Scan the batch belongs to
+ * @returns {Promise<void>}
+ */
+const hydrateBinaryTimelines = async (files, state) => {
+    const pending = files.filter(file => file.timeline && !file.hitStarts);
+    if (pending.length === 0) return;
+
+    const blobIds = [...new Set(pending.map(file => file.timeline.id))];
+    const blobs = new Map(await Promise.all(blobIds.map(async (id) => {
+        try {
+            return [id, await beatmapApi.fetchBinaryTimeline(id)];
+        } catch (err) {
+            console.error(`Binary timeline ${id} expired before it was read:`, err);
+            return [id, null];
+        }
+    })));
+
+    await Promise.all(pending.map(async (file) => {
+        const values = blobs.get(file.timeline.id);
+        if (values) {
+            // Views into the batch blob; hit times are only ever indexed, never copied
+            const { offset, objectCount } = file.timeline;
+            file.hitStarts = values.subarray(offset + 1, offset + 1 + objectCount);
+            file.hitEnds = values.subarray(offset + 1 + objectCount, offset + 1 + 2 * objectCount);
+            return;
+        }
+        state.expiredTimelines += 1;
+        try {
+            Object.assign(file, parseHitObjects(await beatmapApi.readOsuFile(file.filePath)));
+        } catch (err) {
+            console.error(`Failed to re-read hit times for ${file.filePath}:`, err);
+        }
+    }));
+};
+
 /**
  * Initialize scan event listeners
  * @param {Object} [callbacks] - Optional callbacks
@@ -85,6 +164,12 @@ export const initScanEventListeners = async (callbacks = {}) => {
     if (scanStatusUnlisten) {
         await scanStatusUnlisten();
     }
+    if (scanRemovedUnlisten) {
+        await scanRemovedUnlisten();
+    }
+    if (scanErrorsUnlisten) {
+        await scanErrorsUnlisten();
+    }
 
     scanStatusUnlisten = await tauriEvents.listen('scan-status', (payload) => {
         if (!streamingScanState) return;
@@ -104,53 +189,118 @@ export const initScanEventListeners = async (callbacks = {}) => {
         }
     });
 
-    scanBatchUnlisten = await tauriEvents.listen('scan-batch', (payload) => {
+    scanRemovedUnlisten = await tauriEvents.listen('scan-removed', (payload) => {
         if (!streamingScanState) return;
 
-        const { files, directory, totalFiles } = payload;
+        for (const filePath of payload.filePaths || []) {
+            streamingScanState.removedPaths.add(filePath);
+        }
+    });
 
-        if (directory) {
-            streamingScanState.directory = directory;
+    scanErrorsUnlisten = await tauriEvents.listen('scan-errors', (payload) => {
+        if (!streamingScanState) return;
+
+        for (const error of payload.errors || []) {
+            console.warn(`Skipped beatmap (${error.kind}): ${error.filePath} - ${error.message}`);
+            streamingScanState.errors.push(error);
         }
-        if (totalFiles) {
-            streamingScanState.totalFiles = totalFiles;
+    });
+
+    scanBatchHandler = (event) => {
+        const state = streamingScanState;
+        if (!state) return Promise.resolve();
+
+        let payload;
+        try {
+            payload = unpackScanBatch(event);
+        } catch (err) {
+            console.error('Failed to decode scan batch:', err);
+            return Promise.resolve();
         }
 
-        for (const file of files) {
-            const existing = streamingScanState.existingMap.get(file.filePath);
+        state.batchQueue = state.batchQueue
+            .then(() => hydrateBinaryTimelines(payload.files || [], state))
+            .then(() => {
+                if (streamingScanState !== state) return;
 
-            if (file.unchanged && existing) {
-                streamingScanState.items.push(existing);
-                if (existing.audio && typeof existing.durationMs !== 'number') {
-                    if (callbacks.scheduleAudioAnalysis) {
-                        callbacks.scheduleAudioAnalysis(existing.id);
-                    }
+                const { files, directory, totalFiles } = payload;
+
+                if (directory) {
+                    streamingScanState.directory = directory;
+                }
+                if (totalFiles) {
+                    streamingScanState.totalFiles = totalFiles;
                 }
-                if (isStarRatingMissing(existing.starRating)) {
-                    if (callbacks.scheduleStarRatingCalculation) {
-                        callbacks.scheduleStarRatingCalculation(existing.id);
+
+                for (const file of files) {
+                    if (file.removed) {
+                        streamingScanState.removedPaths.add(file.filePath);
+                        continue;
+                    }
+
+                    const existing = streamingScanState.existingMap.get(file.filePath);
+
+                    if (file.unchanged && existing) {
+                        streamingScanState.items.push(existing);
+                        if (existing.audio && typeof existing.durationMs !== 'number') {
+                            if (callbacks.scheduleAudioAnalysis) {
+                                callbacks.scheduleAudioAnalysis(existing.id);
+                            }
+                        }
+                        if (isStarRatingMissing(existing.starRating)) {
+                            if (callbacks.scheduleStarRatingCalculation) {
+                                callbacks.scheduleStarRatingCalculation(existing.id);
+                            }
+                        }
+                    } else {
+                        try {
+                            const item = processWorkerResult(file, existing);
+                            streamingScanState.items.push(item);
+                        } catch (err) {
+                            console.error(`Failed to process beatmap: ${file.filePath}`, err);
+                        }
                     }
                 }
-            } else {
-                try {
-                    const item = processWorkerResult(file, existing);
-                    streamingScanState.items.push(item);
-                } catch (err) {
-                    console.error(`Failed to process beatmap: ${file.filePath}`, err);
+
+                streamingScanState.processed += files.length;
+                if (callbacks.updateProgress) {
+                    callbacks.updateProgress(streamingScanState.processed, streamingScanState.totalFiles);
                 }
-            }
-        }
+            });
+        return state.batchQueue;
+    };
 
-        streamingScanState.processed += files.length;
-        if (callbacks.updateProgress) {
-            callbacks.updateProgress(streamingScanState.processed, streamingScanState.totalFiles);
-        }
+    scanBatchUnlisten = await tauriEvents.listen('scan-batch', (event) => {
+        scanBatchHandler(event);
     });
 
-    scanCompleteUnlisten = await tauriEvents.listen('scan-complete', (payload) => {
+    scanCompleteUnlisten = await tauriEvents.listen('scan-complete', async (payload) => {
         if (!streamingScanState) return;
 
-        const { directory, totalFiles } = payload;
+        // Let batches still fetching their timelines land first
+        const state = streamingScanState;
+        await state.batchQueue;
+        if (streamingScanState !== state) return;
+
+        const { directory, totalFiles, errors } = payload;
+
+        if (state.expiredTimelines > 0) {
+            const expired = state.expiredTimelines;
+            showNotification(
+                'Scan Fell Behind',
+                `Hit times for ${expired} large map${expired !== 1 ? 's' : ''} expired before they were read and were re-read from disk.`,
+                'error'
+            );
+        }
+
+        if (errors?.total > 0) {
+            const skipped = errors.total;
+            showNotification(
+                'Some Beatmaps Were Skipped',
+                `${skipped} beatmap${skipped !== 1 ? 's' : ''} could not be read (${formatErrorKinds(errors.byKind)}).`,
+                'info'
+            );
+        }
 
         if (directory) {
             streamingScanState.directory = directory;
@@ -160,8 +310,9 @@ export const initScanEventListeners = async (callbacks = {}) => {
 
         const items = streamingScanState.items;
 
-        // Update beatmapItems based on directory (matching original renderer.js logic)
-        if (streamingScanState.directory) {
+        // Update beatmapItems based on directory (matching original renderer.js logic).
+        // A multi-root scan covers several folders, so its results are always merged.
+        if (streamingScanState.directory && streamingScanState.mode !== 'roots') {
             const normalizedDir = streamingScanState.directory.toLowerCase().replace(/\\/g, '/');
             const endWithSlash = normalizedDir.endsWith('/') ? normalizedDir : normalizedDir + '/';
             const newPaths = new Set(items.map(i => i.filePath));
@@ -185,6 +336,12 @@ export const initScanEventListeners = async (callbacks = {}) => {
             setBeatmapItems([...keptItems, ...items]);
         }
 
+        // Drop maps that vanished from disk since the last scan
+        const { removedPaths } = streamingScanState;
+        if (removedPaths.size > 0) {
+            setBeatmapItems(beatmapItems.filter(i => !removedPaths.has(i.filePath)));
+        }
+
         // Call callbacks
         if (callbacks.updateTabCounts) {
             callbacks.updateTabCounts();
@@ -248,11 +405,16 @@ export const startStreamingScan = (mode = 'directory', options = {}) => {
 
     return new Promise((resolve) => {
         streamingScanState = {
+            mode,
             directory: '',
             existingMap,
             items: [],
+            removedPaths: new Set(),
+            errors: [],
+            expiredTimelines: 0,
             processed: 0,
             totalFiles: 0,
+            batchQueue: Promise.resolve(),
             resolveComplete: resolve,
         };
         isScanningActive = true;
@@ -379,6 +541,15 @@ export const isScanning = () => {
     return isScanningActive || streamingScanState !== null;
 };
 
+/**
+ * Handle a scan batch delivered over a scan command's onBatch channel
+ * @param {Object} batch - scan-batch payload
+ * @returns {Promise<void>} Settles once the batch has been processed
+ */
+export const handleScanBatch = (batch) => {
+    return scanBatchHandler ? scanBatchHandler(batch) : Promise.resolve();
+};
+
 /**
  * Cancel the current scan
  */
@@ -425,11 +596,20 @@ export const cleanupScanListeners = async () => {
         await scanStatusUnlisten();
         scanStatusUnlisten = null;
     }
+    if (scanRemovedUnlisten) {
+        await scanRemovedUnlisten();
+        scanRemovedUnlisten = null;
+    }
+    if (scanErrorsUnlisten) {
+        await scanErrorsUnlisten();
+        scanErrorsUnlisten = null;
+    }
 };
 
 export default {
     initScanEventListeners,
     startStreamingScan,
+    handleScanBatch,
     loadBeatmapsFromResult,
     isScanning,
     cancelScan,
@@ -9,7 +9,8 @@ import {
     SETTINGS_STORAGE_KEY,
     AUDIO_ANALYSIS_STATE_KEY,
     STAR_RATING_STATE_KEY,
-    STORAGE_VERSION
+    STORAGE_VERSION,
+    MAP_WINDOW_LABEL
 } from '../config/Constants.js';
 
 import {
@@ -44,7 +45,8 @@ import {
     setAudioAnalysisQueue,
     setAudioAnalysisTotal,
     setStarRatingQueue,
-    setStarRatingTotal
+    setStarRatingTotal,
+    updateSettings
 } from './Store.js';
 
 // ============================================
@@ -147,11 +149,17 @@ export function saveToStorage({ showNotification } = {}) {
             artistUnicode: item.artistUnicode,
             creator: item.creator,
             version: item.version,
+            source: item.source || '',
+            tags: item.tags || '',
+            guestMapper: item.guestMapper || '',
+            libraryRoot: item.libraryRoot || '',
             beatmapSetID: item.beatmapSetID,
             mode: Number.isFinite(item.mode) ? Math.min(Math.max(item.mode, 0), 3) : 0,
             starRating: isValidStarRating(item.starRating) ? item.starRating : null,
             audio: item.audio || '',
             audioFileName: item.audioFileName || '',
+            video: item.video || '',
+            backgroundInfo: item.backgroundInfo || null,
             beatmapHash: item.beatmapHash || '',
             deadline: (typeof item.deadline === 'number' || item.deadline === null) ? item.deadline : null,
             targetStarRating: (typeof item.targetStarRating === 'number' || item.targetStarRating === null) ? item.targetStarRating : null,
@@ -362,12 +370,84 @@ export async function loadFromStorage(callbacks = {}) {
 // ============================================
 
 /**
- * Persist settings to localStorage
+ * Settings kept in the OS keychain instead of plaintext storage, mapped to their secret names
+ * @type {Object<string, string>}
+ */
+export const SECRET_SETTINGS = {
+    embedApiKey: 'embed-api-key',
+};
+
+/**
+ * Secret values known to be in the keychain, so unchanged ones aren't rewritten on every save
+ * @type {Object<string, string>}
+ */
+const storedSecrets = {};
+
+/**
+ * Copy settings without keychain-backed values, writing those to the keychain instead
+ * @returns {Object} Settings safe to store in plaintext
+ */
+function splitSecretSettings() {
+    if (!window.appInfo?.storeSecret) return settings;
+
+    const plain = { ...settings };
+    for (const [key, name] of Object.entries(SECRET_SETTINGS)) {
+        const value = plain[key];
+        delete plain[key];
+        // An empty value would delete the keychain entry, e.g. after a failed read at startup
+        if (!value || storedSecrets[key] === value) continue;
+        storedSecrets[key] = value;
+        window.appInfo.storeSecret(name, value).catch((error) => {
+            if (storedSecrets[key] === value) delete storedSecrets[key];
+            console.warn(`Failed to save ${key} to the keychain:`, error);
+        });
+    }
+    return plain;
+}
+
+/**
+ * Persist settings to localStorage and the native settings store
  */
 export function persistSettings() {
+    // Comparison windows share the main window's settings and must not write them back
+    if (MAP_WINDOW_LABEL) return;
+    const plain = splitSecretSettings();
     try {
-        localStorage.setItem(SETTINGS_STORAGE_KEY, JSON.stringify(settings));
+        localStorage.setItem(SETTINGS_STORAGE_KEY, JSON.stringify(plain));
     } catch (e) { /* storage full */ }
+    // The native store survives webview data being cleared; localStorage stays as a fast fallback
+    if (window.appInfo?.setSettings) {
+        window.appInfo.setSettings(plain, true).catch((error) => {
+            console.warn('Failed to save settings to the native store:', error);
+        });
+    }
+}
+
+/**
+ * Fill keychain-backed settings into the store. Plaintext values left over from older
+ * versions win and are moved to the keychain on the next save.
+ * @returns {Promise<boolean>} True if a plaintext secret still needs moving
+ */
+export async function loadSecretSettings() {
+    if (!window.appInfo?.getSecret) return false;
+
+    let hasPlaintextSecrets = false;
+    for (const [key, name] of Object.entries(SECRET_SETTINGS)) {
+        if (settings[key]) {
+            hasPlaintextSecrets = true;
+            continue;
+        }
+        try {
+            const value = await window.appInfo.getSecret(name);
+            if (value) {
+                storedSecrets[key] = value;
+                updateSettings({ [key]: value });
+            }
+        } catch (error) {
+            console.warn(`Failed to read ${key} from the keychain:`, error);
+        }
+    }
+    return hasPlaintextSecrets;
 }
 
 // ============================================
@@ -31,6 +31,9 @@ export let srFilter = { min: 0, max: 10 };
 /** @type {'all'|'standard'|'taiko'|'catch'|'mania'} Mode filter */
 export let modeFilter = 'all';
 
+/** @type {Array<{path: string, client: string, addedAtMs: number}>} Registered library roots */
+export let libraryRoots = [];
+
 // ============================================
 // UI State
 // ============================================
@@ -105,6 +108,14 @@ export let settings = {
     collectionModeSavedViewMode: null,
     ignoreStartAndBreaks: false,
     ignoreGuestDifficulties: false,
+    // Keep rolling backups of maps whenever a rescan sees them change
+    autoBackup: false,
+    backupRetention: 20,
+    // Global shortcut that logs the editor timestamp on the clipboard as a note, e.g.
+    // 'CommandOrControl+Alt+N' (opt-in, '' = off)
+    timestampShortcut: '',
+    // Focus the map osu! stable has open whenever it changes
+    followNowPlaying: true,
     volume: 0.5,
     listItemHeight: 170,
     // First-run setup state
@@ -121,7 +132,15 @@ export let settings = {
     embedShowCompletedList: true,
     embedShowProgressStats: true,
     embedLastSynced: null,
-    groupMapsBySong: true
+    // Background auto-sync interval in minutes (0 = off)
+    embedAutoSyncMinutes: 0,
+    groupMapsBySong: true,
+    preferUnicodeMetadata: true,
+    minimizeToTray: false,
+    // 'system' | 'none' | proxy URL used for all network requests
+    httpProxy: 'system',
+    // When true, every network request fails fast with an "offline" error
+    offlineMode: false
 };
 
 // ============================================
@@ -356,3 +375,11 @@ export function setStarRatingTotal(total) {
 export function setSettings(newSettings) {
     settings = newSettings;
 }
+
+/**
+ * Set the registered library roots
+ * @param {Array<Object>} roots - Roots as returned by listLibraryRoots
+ */
+export function setLibraryRoots(roots) {
+    libraryRoots = Array.isArray(roots) ? roots : [];
+}
@@ -3,7 +3,7 @@
  * Extracted from renderer.js (lines 2037-2336)
  */
 
-import { normalizeMetadata } from '../utils/Helpers.js';
+import { normalizeMetadata, formatSongName } from '../utils/Helpers.js';
 import { scheduleCoverLoad } from '../services/CoverLoader.js';
 import { queueTimelineBatchRender } from '../services/TimelineRenderer.js';
 import * as Store from '../state/Store.js';
@@ -117,7 +117,7 @@ export const buildGroupHeaderRow = (group, groupIndex, callbacks) => {
     const imgWrap = document.createElement('div');
     imgWrap.classList.add('group-row-cover');
     const img = document.createElement('img');
-    img.alt = `${normalized.artistUnicode} - ${normalized.titleUnicode}`;
+    img.alt = formatSongName(normalized, Store.settings.preferUnicodeMetadata !== false);
     img.loading = 'lazy';
     img.decoding = 'async';
 
@@ -157,7 +157,7 @@ export const buildGroupHeaderRow = (group, groupIndex, callbacks) => {
 
     const titleEl = document.createElement('h3');
     titleEl.classList.add('group-row-title');
-    titleEl.textContent = `${normalized.artistUnicode} - ${normalized.titleUnicode}`;
+    titleEl.textContent = formatSongName(normalized, Store.settings.preferUnicodeMetadata !== false);
     info.appendChild(titleEl);
 
     const countEl = document.createElement('span');
@@ -326,6 +326,7 @@ export const buildGroupHeaderRow = (group, groupIndex, callbacks) => {
     items.forEach(item => {
         const chip = document.createElement('span');
         chip.classList.add('group-row-version-chip');
+        chip.dataset.itemId = item.id;
         chip.textContent = item.version || 'Unknown';
         chip.title = item.version || 'Unknown';
         chip.addEventListener('click', (e) => {
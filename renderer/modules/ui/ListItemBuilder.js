@@ -3,13 +3,13 @@
  * Extracted from renderer.js (lines 1322-1900)
  */
 
-import { getStarRatingColor, formatDuration, normalizeMetadata, formatProgressLabel } from '../utils/Helpers.js';
+import { getStarRatingColor, formatDuration, normalizeMetadata, formatProgressLabel, formatSongName } from '../utils/Helpers.js';
 import { isValidStarRating } from '../utils/Validation.js';
 import { AudioController } from '../services/AudioController.js';
 import { scheduleCoverLoad } from '../services/CoverLoader.js';
 import { applyTimelineToBox } from '../services/TimelineRenderer.js';
 import { beatmapApi } from '../bridge/Tauri.js';
-import { settings } from '../state/Store.js';
+import { settings, libraryRoots } from '../state/Store.js';
 
 // ============================================
 // Constants
@@ -68,7 +68,7 @@ export const buildListItem = (metadata, index, callbacks) => {
     image.classList.add('list-img');
 
     const img = document.createElement('img');
-    img.alt = `${normalized.artistUnicode} - ${normalized.titleUnicode}`;
+    img.alt = formatSongName(normalized, settings.preferUnicodeMetadata !== false);
     img.loading = 'lazy';
     img.decoding = 'async';
 
@@ -103,7 +103,7 @@ export const buildListItem = (metadata, index, callbacks) => {
 
     const title = document.createElement('h3');
     title.classList.add('list-title');
-    title.textContent = `${normalized.artistUnicode} - ${normalized.titleUnicode}`;
+    title.textContent = formatSongName(normalized, settings.preferUnicodeMetadata !== false);
 
     const meta = document.createElement('div');
     meta.classList.add('list-meta');
@@ -175,6 +175,15 @@ export const buildListItem = (metadata, index, callbacks) => {
     meta.appendChild(creatorTag);
     meta.appendChild(versionTag);
 
+    // Only worth a badge once the library spans more than one folder
+    if (metadata.libraryRoot && libraryRoots.length > 1) {
+        const sourceTag = document.createElement('span');
+        sourceTag.classList.add('meta-tag', 'meta-tag--source');
+        sourceTag.textContent = metadata.libraryRoot.split(/[\\/]/).filter(Boolean).pop() || metadata.libraryRoot;
+        sourceTag.dataset.tooltip = metadata.libraryRoot;
+        meta.appendChild(sourceTag);
+    }
+
     // Target star rating tag (always create, but hide if no value)
     const starTag = document.createElement('span');
     starTag.classList.add('meta-tag', 'meta-tag--star-rating', 'meta-tag--target-sr', 'meta-tag--target-sr-cover');
@@ -266,6 +275,27 @@ export const buildListItem = (metadata, index, callbacks) => {
     previewIcon.appendChild(previewPath);
     mapPreviewLink.appendChild(previewIcon);
 
+    const mapWindowLink = document.createElement('button');
+    mapWindowLink.type = 'button';
+    mapWindowLink.classList.add('beatmap-link');
+    if (normalized.filePath) {
+        mapWindowLink.dataset.tooltip = 'Open in new window';
+        mapWindowLink.dataset.action = 'open-map-window';
+        mapWindowLink.dataset.itemId = normalized.id;
+        mapWindowLink.dataset.path = normalized.filePath;
+    } else {
+        mapWindowLink.dataset.tooltip = 'Map path unavailable';
+        mapWindowLink.classList.add('beatmap-link--disabled');
+    }
+
+    const windowIcon = document.createElementNS('http://www.w3.org/2000/svg', 'svg');
+    windowIcon.setAttribute('viewBox', '0 0 512 512');
+    windowIcon.classList.add('beatmap-link-icon');
+    const windowPath = document.createElementNS('http://www.w3.org/2000/svg', 'path');
+    windowPath.setAttribute('d', 'M64 32C28.7 32 0 60.7 0 96V416c0 35.3 28.7 64 64 64H448c35.3 0 64-28.7 64-64V96c0-35.3-28.7-64-64-64H64zM48 160H464V416c0 8.8-7.2 16-16 16H64c-8.8 0-16-7.2-16-16V160z');
+    windowIcon.appendChild(windowPath);
+    mapWindowLink.appendChild(windowIcon);
+
     const editorLink = document.createElement('button');
     editorLink.type = 'button';
     editorLink.classList.add('beatmap-link');
@@ -291,6 +321,7 @@ export const buildListItem = (metadata, index, callbacks) => {
     actionLinks.classList.add('list-action-links');
     actionLinks.appendChild(beatmapLink);
     actionLinks.appendChild(mapPreviewLink);
+    actionLinks.appendChild(mapWindowLink);
     actionLinks.appendChild(folderLink);
     actionLinks.appendChild(editorLink);
 
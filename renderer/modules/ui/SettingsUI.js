@@ -3,7 +3,7 @@
  * Extracted from renderer.js (lines 3654-3812, 4451-4552)
  */
 
-import { getStarRatingColor } from '../utils/Helpers.js';
+import { getStarRatingColor, escapeHtml } from '../utils/Helpers.js';
 import { appInfo } from '../bridge/Tauri.js';
 
 const getActiveOsuClient = (settings) => settings?.osuClient === 'lazer' ? 'lazer' : 'stable';
@@ -414,6 +414,41 @@ export const updateSettingsUI = (settings, options = {}) => {
 
     const groupMapsBySongEl = document.querySelector('#groupMapsBySong');
     if (groupMapsBySongEl) groupMapsBySongEl.checked = !!settings.groupMapsBySong;
+
+    const preferUnicodeMetadataEl = document.querySelector('#preferUnicodeMetadata');
+    if (preferUnicodeMetadataEl) preferUnicodeMetadataEl.checked = settings.preferUnicodeMetadata !== false;
+
+    const minimizeToTrayEl = document.querySelector('#minimizeToTray');
+    if (minimizeToTrayEl) minimizeToTrayEl.checked = !!settings.minimizeToTray;
+};
+
+// ============================================
+// Library Roots UI
+// ============================================
+
+/**
+ * Render the registered library roots with their rescan/remove actions
+ * @param {Array<Object>} roots - Roots as returned by listLibraryRoots
+ */
+export const renderLibraryRoots = (roots) => {
+    const list = document.querySelector('#libraryRootsList');
+    const scanAllBtn = document.querySelector('#scanLibraryRootsBtn');
+    if (!list) return;
+
+    if (scanAllBtn) scanAllBtn.disabled = !roots.length;
+    if (!roots.length) {
+        list.innerHTML = '<p class="settings-description">No extra folders added.</p>';
+        return;
+    }
+
+    list.innerHTML = roots.map((root) => `
+        <div class="library-root-row" data-root-path="${escapeHtml(root.path)}">
+            <span class="library-root-path" data-tooltip="${escapeHtml(root.path)}">${escapeHtml(root.path)}</span>
+            <span class="meta-tag meta-tag--source">${root.client === 'lazer' ? 'Lazer' : 'Stable'}</span>
+            <button type="button" class="secondary-button" data-root-action="rescan">Rescan</button>
+            <button type="button" class="secondary-button danger" data-root-action="remove">Remove</button>
+        </div>
+    `).join('');
 };
 
 // ============================================
@@ -1,3 +1,16 @@
+/**
+ * Escape text for use inside HTML markup
+ * @param {string} text - Untrusted text
+ * @returns {string} Escaped text
+ */
+export const escapeHtml = (text) => String(text ?? '').replace(/[&<>"']/g, (char) => ({
+    '&': '&amp;',
+    '<': '&lt;',
+    '>': '&gt;',
+    '"': '&quot;',
+    "'": '&#39;'
+})[char]);
+
 /**
  * Get color for a star rating value
  * @param {number} rating - Star rating value
@@ -259,6 +272,18 @@ export const computeProgress = (ranges, settings = { ignoreStartAndBreaks: false
     return clampProgressValue(populated);
 };
 
+/**
+ * Format "Artist - Title" for display, honouring the unicode preference
+ * @param {Object} item - Beatmap item or normalized metadata
+ * @param {boolean} [preferUnicode=true] - Show TitleUnicode/ArtistUnicode when present
+ * @returns {string} Display name
+ */
+export const formatSongName = (item, preferUnicode = true) => {
+    const artist = (preferUnicode && item?.artistUnicode) || item?.artist || 'Unknown Artist';
+    const title = (preferUnicode && item?.titleUnicode) || item?.title || 'Unknown Title';
+    return `${artist} - ${title}`;
+};
+
 /**
  * Normalize metadata object with default values
  * @param {Object} metadata - Raw metadata object
@@ -271,6 +296,9 @@ export const normalizeMetadata = (metadata) => ({
     artistUnicode: metadata?.artistUnicode || metadata?.artist || 'Unknown Artist',
     creator: metadata?.creator || 'Unknown Creator',
     version: metadata?.version || 'Unknown Version',
+    source: metadata?.source || '',
+    tags: metadata?.tags || '',
+    guestMapper: metadata?.guestMapper || '',
     beatmapSetID: metadata?.beatmapSetID ?? 'Unknown',
     coverUrl: metadata?.coverUrl || '',
     coverPath: metadata?.coverPath || '',
@@ -279,6 +307,8 @@ export const normalizeMetadata = (metadata) => ({
     durationMs: metadata?.durationMs ?? null,
     audio: metadata?.audio || '',
     audioFileName: metadata?.audioFileName || '',
+    video: metadata?.video || '',
+    backgroundInfo: metadata?.backgroundInfo || null,
     beatmapHash: metadata?.beatmapHash || '',
     previewTime: metadata?.previewTime ?? -1,
     dateAdded: metadata?.dateAdded ?? 0,
@@ -0,0 +1,101 @@
+/**
+ * MessagePack.js - Minimal MessagePack decoder for compact backend events
+ * Covers what rmp-serde emits: nil, booleans, ints, floats, strings, binary, arrays and maps.
+ */
+
+const textDecoder = new TextDecoder();
+
+/**
+ * Decode a base64 string into bytes
+ * @param {string} base64 - Base64-encoded data
+ * @returns {Uint8Array} Decoded bytes
+ */
+export const base64ToBytes = (base64) => {
+    const binary = atob(base64);
+    const bytes = new Uint8Array(binary.length);
+    for (let i = 0; i < binary.length; i++) {
+        bytes[i] = binary.charCodeAt(i);
+    }
+    return bytes;
+};
+
+/**
+ * Decode a single MessagePack value
+ * @param {Uint8Array} bytes - Encoded data
+ * @returns {*} Decoded value; maps become plain objects
+ */
+export const decodeMessagePack = (bytes) => {
+    const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+    let offset = 0;
+
+    const readString = (length) => {
+        const value = textDecoder.decode(bytes.subarray(offset, offset + length));
+        offset += length;
+        return value;
+    };
+    const readBinary = (length) => {
+        const value = bytes.slice(offset, offset + length);
+        offset += length;
+        return value;
+    };
+    const readArray = (length) => {
+        const value = new Array(length);
+        for (let i = 0; i < length; i++) {
+            value[i] = read();
+        }
+        return value;
+    };
+    const readMap = (length) => {
+        const value = {};
+        for (let i = 0; i < length; i++) {
+            const key = read();
+            value[key] = read();
+        }
+        return value;
+    };
+    const take = (size, getter) => {
+        const value = getter(offset);
+        offset += size;
+        return value;
+    };
+
+    function read() {
+        const type = bytes[offset++];
+
+        if (type <= 0x7f) return type;
+        if (type >= 0xe0) return type - 0x100;
+        if ((type & 0xf0) === 0x80) return readMap(type & 0x0f);
+        if ((type & 0xf0) === 0x90) return readArray(type & 0x0f);
+        if ((type & 0xe0) === 0xa0) return readString(type & 0x1f);
+
+        switch (type) {
+            case 0xc0: return null;
+            case 0xc2: return false;
+            case 0xc3: return true;
+            case 0xc4: return readBinary(take(1, o => view.getUint8(o)));
+            case 0xc5: return readBinary(take(2, o => view.getUint16(o)));
+            case 0xc6: return readBinary(take(4, o => view.getUint32(o)));
+            case 0xca: return take(4, o => view.getFloat32(o));
+            case 0xcb: return take(8, o => view.getFloat64(o));
+            case 0xcc: return take(1, o => view.getUint8(o));
+            case 0xcd: return take(2, o => view.getUint16(o));
+            case 0xce: return take(4, o => view.getUint32(o));
+            case 0xcf: return Number(take(8, o => view.getBigUint64(o)));
+            case 0xd0: return take(1, o => view.getInt8(o));
+            case 0xd1: return take(2, o => view.getInt16(o));
+            case 0xd2: return take(4, o => view.getInt32(o));
+            case 0xd3: return Number(take(8, o => view.getBigInt64(o)));
+            case 0xd9: return readString(take(1, o => view.getUint8(o)));
+            case 0xda: return readString(take(2, o => view.getUint16(o)));
+            case 0xdb: return readString(take(4, o => view.getUint32(o)));
+            case 0xdc: return readArray(take(2, o => view.getUint16(o)));
+            case 0xdd: return readArray(take(4, o => view.getUint32(o)));
+            case 0xde: return readMap(take(2, o => view.getUint16(o)));
+            case 0xdf: return readMap(take(4, o => view.getUint32(o)));
+            default:
+                throw new Error(`Unsupported MessagePack type 0x${type.toString(16)} at ${offset - 1}`);
+        }
+    }
+
+    return read();
+};
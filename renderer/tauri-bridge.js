@@ -11,14 +11,40 @@
     return tauri.core.invoke(command, args || {});
   };
 
-  const convertFileSrc = (filePath) => {
+  const convertFileSrc = (filePath, protocol = 'asset') => {
     const tauri = window.__TAURI__;
     if (tauri && tauri.core && typeof tauri.core.convertFileSrc === 'function') {
-      return tauri.core.convertFileSrc(filePath);
+      return tauri.core.convertFileSrc(filePath, protocol);
     }
     // Fallback: construct asset URL manually
     const encoded = encodeURIComponent(filePath);
-    return `http://asset.localhost/${encoded}`;
+    return `http://${protocol}.localhost/${encoded}`;
+  };
+
+  // Scan batches for one caller, in order. Each is acknowledged once the handler settles, so the
+  // backend holds off while the renderer catches up.
+  const createBatchChannel = (onBatch) => {
+    const Channel = window.__TAURI__?.core?.Channel;
+    if (typeof onBatch !== 'function' || !Channel) return null;
+    const channel = new Channel();
+    channel.onmessage = async (batch) => {
+      try {
+        await onBatch(batch);
+      } finally {
+        invoke('ack_scan_batch', { channelId: channel.id }).catch(() => {});
+      }
+    };
+    return channel;
+  };
+
+  // One blob per scan batch; each map's timeline offset points at its i32 object count, followed by
+  // the starts, then the ends (all little-endian i32)
+  const fetchBinaryTimeline = async (id) => {
+    const response = await fetch(convertFileSrc(id, 'mosu-timeline'));
+    if (!response.ok) {
+      throw new Error(`Timeline ${id} is no longer available`);
+    }
+    return new Int32Array(await response.arrayBuffer());
   };
 
   const toBinary = (value) => {
@@ -71,16 +97,110 @@
       invoke('add_to_lazer_collection', { dataRoot, collectionName, beatmapHash }),
     prepareLazerMapSession: (filePath, dataRoot) => invoke('prepare_lazer_map_session', { filePath, dataRoot }),
     commitLazerMapSession: (sessionDir) => invoke('commit_lazer_map_session', { sessionDir }),
-    scanDirectoryOsuFiles: (dirPath, mapperName, knownFiles, clientType) =>
-      invoke('scan_directory_osu_files', { dirPath, mapperName, knownFiles, clientType }),
+    scanDirectoryOsuFiles: (dirPath, mapperName, knownFiles, clientType, options, onBatch) =>
+      invoke('scan_directory_osu_files', { dirPath, mapperName, knownFiles, clientType, options, onBatch: createBatchChannel(onBatch) }),
+    rescanChanged: (dirPath, mapperName, clientType, options, onBatch) =>
+      invoke('rescan_changed', { dirPath, mapperName, clientType, options, onBatch: createBatchChannel(onBatch) }),
     listDirectoryOsuFiles: (dirPath, mapperName, clientType) =>
       invoke('list_directory_osu_files', { dirPath, mapperName, clientType }),
     selectDirectory: (title) => invoke('select_directory', { title }),
     showItemInFolder: (filePath) => invoke('show_item_in_folder', { filePath }),
     openInTextEditor: (filePath) => invoke('open_in_text_editor', { filePath }),
     convertFileSrc: (filePath) => convertFileSrc(filePath),
+    fetchBinaryTimeline: (id) => fetchBinaryTimeline(id),
     getAudioDuration: (filePath, fileNameHint) => invoke('get_audio_duration', { filePath, fileNameHint }),
-    calculateStarRating: (filePath) => invoke('calculate_star_rating', { filePath }),
+    calculateStarRating: (filePath, options) => invoke('calculate_star_rating', { filePath, options }),
+    checkSnapping: (filePath) => invoke('check_snapping', { filePath }),
+    backfillBeatmapIds: (filePaths, apiKey, writeBack) =>
+      invoke('backfill_beatmap_ids', { filePaths, apiKey, writeBack }),
+    lintBeatmap: (filePath, options) => invoke('lint_beatmap', { filePath, options }),
+    scanFileList: (paths, mapperName, knownFiles, options) =>
+      invoke('scan_file_list', { paths, mapperName, knownFiles, options }),
+    writeOsuMetadata: (filePath, changes, applyToSet) =>
+      invoke('write_osu_metadata', { filePath, changes, applyToSet }),
+    compareMaps: (pathA, pathB, bucketMs) => invoke('compare_maps', { pathA, pathB, bucketMs }),
+    mergeGuestDiff: (osuOrOsz, targetFolder, overwrite) =>
+      invoke('merge_guest_diff', { osuOrOsz, targetFolder, overwrite }),
+    writeOsuBookmarks: (filePath, bookmarks, backup) =>
+      invoke('write_osu_bookmarks', { filePath, bookmarks, backup }),
+    applyOffset: (filePath, offsetMs, backup) => invoke('apply_offset', { filePath, offsetMs, backup: !!backup }),
+    setPreviewTime: (filePath, timeMs, applyToSet) =>
+      invoke('set_preview_time', { filePath, timeMs, applyToSet }),
+    getDifficultySettings: (filePath, mods) => invoke('get_difficulty_settings', { filePath, mods }),
+    queryLibrary: (filter, sortKey, descending) => invoke('query_library', { filter, sortKey, descending }),
+    compareBeatmaps: (pathA, pathB) => invoke('compare_beatmaps', { pathA, pathB }),
+    snapshotBeatmap: (path) => invoke('snapshot_beatmap', { path }),
+    listSnapshots: (path) => invoke('list_snapshots', { path }),
+    restoreSnapshot: (path, id) => invoke('restore_snapshot', { path, id }),
+    exportScanResults: (format, destination, fields) => invoke('export_scan_results', { format, destination, fields }),
+    aggregateMapperStats: (directory, mapper, clientType, apiKey) => invoke('aggregate_mapper_stats', { directory, mapper, clientType, apiKey }),
+    detectOsuSongsFolder: () => invoke('detect_osu_songs_folder'),
+    listLibraryRoots: () => invoke('list_library_roots'),
+    addLibraryRoot: (path, clientType) => invoke('add_library_root', { path, clientType }),
+    removeLibraryRoot: (path) => invoke('remove_library_root', { path }),
+    scanLibraryRoots: (roots, mapperName, knownFiles, options) => invoke('scan_library_roots', { roots, mapperName, knownFiles, options }),
+    exportMappool: (slots, format, destination, bundlePath) => invoke('export_mappool', { slots, format, destination, bundlePath }),
+    importOsz: () => invoke('import_osz'),
+    describeDeepLink: (url) => invoke('describe_deep_link', { url }),
+    openDeepLink: (url, confirmed) => invoke('open_deep_link', { url, confirmed }),
+    takeLaunchDeepLinks: () => invoke('take_launch_deep_links'),
+    probeAudio: (filePath, fileNameHint) => invoke('probe_audio', { filePath, fileNameHint }),
+    probeVideo: (filePath) => invoke('probe_video', { filePath }),
+    renderPlayfieldSnapshot: (filePath, timeMs, options) => invoke('render_playfield_snapshot', { filePath, timeMs, options }),
+    getSliderPaths: (filePath, timeMs) => invoke('get_slider_paths', { filePath, timeMs }),
+    analyzeMania: (filePath) => invoke('analyze_mania', { filePath }),
+    analyzeTaiko: (filePath) => invoke('analyze_taiko', { filePath }),
+    analyzeCatch: (filePath) => invoke('analyze_catch', { filePath }),
+    simulateScore: (filePath, mods) => invoke('simulate_score', { filePath, mods }),
+    getLocalScores: (beatmapMd5) => invoke('get_local_scores', { beatmapMd5 }),
+    getRecentPlays: (userId, apiKey, mode, limit) => invoke('get_recent_plays', { userId, apiKey, mode, limit }),
+    getLibraryPage: (offset, limit, sortKey, descending, filter) => invoke('get_library_page', { offset, limit, sortKey, descending, filter }),
+    sortLibrary: (key, dir) => invoke('sort_library', { key, dir }),
+    getLibraryStats: () => invoke('get_library_stats'),
+    searchLibrary: (query, limit) => invoke('search_library', { query, limit }),
+    getCachedStarRating: (filePath, mods) => invoke('get_cached_star_rating', { filePath, mods }),
+    enqueueJob: (kind) => invoke('enqueue_job', { kind }),
+    cancelJob: (jobId) => invoke('cancel_job', { jobId }),
+    applyModsToDifficulty: (filePath, mods) => invoke('apply_mods_to_difficulty', { filePath, mods }),
+    openInOsuEditor: (filePath, timeMs, clientType) => invoke('open_in_osu_editor', { filePath, timeMs, clientType }),
+    parseEditorTimestamp: (text) => invoke('parse_editor_timestamp', { text }),
+    formatEditorTimestamp: (timeMs, objectIndices, copy) => invoke('format_editor_timestamp', { timeMs, objectIndices, copy }),
+    exportModPost: (issues, format) => invoke('export_mod_post', { issues, format }),
+    suggestBreaks: (filePath, minLengthMs) => invoke('suggest_breaks', { filePath, minLengthMs }),
+    analyzeSpread: (folder) => invoke('analyze_spread', { folder }),
+    getDensityHistogram: (filePath, bucketMs, levels) => invoke('get_density_histogram', { filePath, bucketMs, levels }),
+    computeMappingProgress: (filePath, audioDuration) => invoke('compute_mapping_progress', { filePath, audioDuration }),
+    getMapWorkStats: (path) => invoke('get_map_work_stats', { path }),
+    listBackups: (path) => invoke('list_backups', { path }),
+    restoreBackup: (path, id) => invoke('restore_backup', { path, id }),
+    trashFiles: (paths) => invoke('trash_files', { paths }),
+    undoTrash: () => invoke('undo_trash'),
+    exportOsz: (folder, destination, options) => invoke('export_osz', { folder, destination, options }),
+    analyzeAudioQuality: (filePath, fileNameHint) => invoke('analyze_audio_quality', { filePath, fileNameHint }),
+    checkOnsetAlignment: (filePath, audioPath) => invoke('check_onset_alignment', { filePath, audioPath }),
+    renderClickTrack: (filePath, destination, durationMs) => invoke('render_click_track', { filePath, destination, durationMs }),
+    exportAudioClip: (filePath, startMs, endMs, destination, format) => invoke('export_audio_clip', { filePath, startMs, endMs, destination, format }),
+    renderRatePreview: (filePath, startMs, endMs, rate, preservePitch) => invoke('render_rate_preview', { filePath, startMs, endMs, rate, preservePitch }),
+    checkMetadataConsistency: (folder) => invoke('check_metadata_consistency', { folder }),
+    getStackedObjects: (filePath) => invoke('get_stacked_objects', { filePath }),
+    checkReadability: (filePath, options) => invoke('check_readability', { filePath, options }),
+    analyzeDistanceSnap: (filePath, tolerance) => invoke('analyze_distance_snap', { filePath, tolerance }),
+    calculateDifficultyAttributes: (filePath, mods) => invoke('calculate_difficulty_attributes', { filePath, mods }),
+    calculateStarRatingProgression: (filePath, stepObjects, mods) => invoke('calculate_star_rating_progression', { filePath, stepObjects, mods }),
+    renderProgressImage: (filePath, width, height, outputPath) => invoke('render_progress_image', { filePath, width, height, outputPath }),
+    classifyBeatmap: (filePath, apiKey) => invoke('classify_beatmap', { filePath, apiKey }),
+    listPlaylists: () => invoke('list_playlists'),
+    createPlaylist: (name) => invoke('create_playlist', { name }),
+    deletePlaylist: (name) => invoke('delete_playlist', { name }),
+    addToPlaylist: (name, filePaths) => invoke('add_to_playlist', { name, filePaths }),
+    removeFromPlaylist: (name, filePaths) => invoke('remove_from_playlist', { name, filePaths }),
+    exportPlaylistAsCollection: (name, collectionDbPath, collectionName) =>
+      invoke('export_playlist_as_collection', { name, collectionDbPath, collectionName }),
+    getStaleRatings: () => invoke('get_stale_ratings'),
+    recalculateStaleRatings: (filePaths) => invoke('recalculate_stale_ratings', { filePaths }),
+    openMapWindow: (filePath) => invoke('open_map_window', { filePath }),
+    getMapWindowContext: () => invoke('get_map_window_context'),
+    getNowPlaying: () => invoke('get_now_playing'),
   };
 
   window.appInfo = window.appInfo || {
@@ -88,9 +208,23 @@
     checkForUpdates: () => invoke('check_for_updates'),
     openExternalUrl: (url) => invoke('open_external_url', { url }),
     getOsuUserData: (urlOrId) => invoke('get_osu_user_data', { urlOrId }),
+    createSupportBundle: (path) => invoke('create_support_bundle', { path }),
+    getSettings: () => invoke('get_settings'),
+    setSettings: (settings, replace, schemaVersion) => invoke('set_settings', { settings, replace, schemaVersion }),
+    storeSecret: (name, value) => invoke('store_secret', { name, value }),
+    getSecret: (name) => invoke('get_secret', { name }),
+    downloadUpdate: () => invoke('download_update'),
+    applyUpdate: () => invoke('apply_update'),
+    resolveOsuUsers: (queries) => invoke('resolve_osu_users', { queries }),
+    getOsuUserProfile: (id) => invoke('get_osu_user_profile', { id }),
   };
 
   window.embedSyncApi = window.embedSyncApi || {
     sync: (url, apiKey, data) => invoke('embed_sync', { url, apiKey, data }),
+    buildProgressEmbed: (map, stats) => invoke('build_progress_embed', { map, stats }),
+    postProgressEmbed: (webhookUrl, map, stats) => invoke('post_progress_embed', { webhookUrl, map, stats }),
+    startAutoSync: (url, apiKey, intervalSecs, payload, watched) => invoke('start_auto_sync', { url, apiKey, intervalSecs, payload, watched }),
+    updateAutoSyncPayload: (payload, watched) => invoke('update_auto_sync_payload', { payload, watched }),
+    stopAutoSync: () => invoke('stop_auto_sync'),
   };
 })();
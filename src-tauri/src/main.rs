@@ -5,14 +5,16 @@ use lofty::file::FileType;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::io::{BufReader, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::sync::{Arc, Mutex, OnceLock};
-use std::time::{Duration, UNIX_EPOCH};
-use tauri::Emitter;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
 use walkdir::WalkDir;
 use rosu_pp::{Beatmap, Difficulty};
 
@@ -30,30 +32,221 @@ struct OsuFilePayload {
     stat: FileStatPayload,
     #[serde(skip_serializing_if = "Option::is_none")]
     beatmap_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decode_warnings: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct OpenOsuFilePayload {
     files: Vec<OsuFilePayload>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct OszImportPayload {
+    archive_path: String,
+    folder_path: String,
+    files: Vec<OsuFilePayload>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct OsuScoreCounts {
+    count_300: u16,
+    count_100: u16,
+    count_50: u16,
+    count_geki: u16,
+    count_katu: u16,
+    count_miss: u16,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ReplayInfoPayload {
+    file_path: String,
+    mode: u8,
+    game_version: i32,
+    beatmap_hash: String,
+    player_name: String,
+    replay_hash: String,
+    #[serde(flatten)]
+    counts: OsuScoreCounts,
+    accuracy: f64,
+    score: i32,
+    max_combo: u16,
+    perfect: bool,
+    mods: u32,
+    mods_acronym: String,
+    played_at_ms: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DroppedFileError {
+    file_path: String,
+    error: String,
+}
+
 #[derive(Debug, Serialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
+struct FilesDroppedEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    osu: Option<OpenOsuFilePayload>,
+    imports: Vec<OszImportPayload>,
+    replays: Vec<ReplayInfoPayload>,
+    errors: Vec<DroppedFileError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
 struct ParsedMetadata {
     title: String,
+    title_unicode: String,
     artist: String,
+    artist_unicode: String,
     creator: String,
     version: String,
+    source: String,
+    tags: String,
+    /// Mapper of this difficulty when it's a guest difficulty in someone else's set.
+    guest_mapper: String,
+    /// Probable language (`japanese`, `korean`, ...) from Tags and the unicode artist/title; empty when unsure.
+    language: String,
+    /// Probable osu! genre (`anime`, `electronic`, ...) from Source and Tags; empty when unsure.
+    genre: String,
     mode: i32,
     audio: String,
     background: String,
+    /// Storyboard video from the `[Events]` `Video` line, relative to the set folder.
+    video: String,
     resolved_audio_path: String,
     resolved_background_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background_info: Option<BackgroundImageInfo>,
+    /// Custom combo colours in `ComboN` order; empty when the map uses the skin's.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    combo_colours: Vec<ComboColour>,
     #[serde(rename = "beatmapSetID")]
     beatmap_set_id: String,
     preview_time: i32,
     star_rating: f64,
+    difficulty: DifficultySettings,
+    general: GeneralSettings,
+}
+
+/// `[Difficulty]` values as written in the file. AR falls back to OD for old maps that predate it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct DifficultySettings {
+    cs: f64,
+    ar: f64,
+    od: f64,
+    hp: f64,
+    slider_multiplier: f64,
+    slider_tick_rate: f64,
+}
+
+impl Default for DifficultySettings {
+    fn default() -> Self {
+        Self {
+            cs: 5.0,
+            ar: 5.0,
+            od: 5.0,
+            hp: 5.0,
+            // Same fallback the parser uses for an unreadable SliderMultiplier.
+            slider_multiplier: 1.0,
+            slider_tick_rate: 1.0,
+        }
+    }
+}
+
+/// Remaining `[General]` keys, with osu!'s defaults for the ones a map leaves out.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct GeneralSettings {
+    /// 0 off, 1 normal, 2 half speed, 3 double speed.
+    countdown: i32,
+    letterbox_in_breaks: bool,
+    widescreen_storyboard: bool,
+    sample_set: String,
+    stack_leniency: f64,
+    epilepsy_warning: bool,
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        Self {
+            countdown: 1,
+            letterbox_in_breaks: false,
+            widescreen_storyboard: false,
+            sample_set: "Normal".to_string(),
+            stack_leniency: 0.7,
+            epilepsy_warning: false,
+        }
+    }
+}
+
+const MOD_EASY: u32 = 1 << 1;
+const MOD_HIDDEN: u32 = 1 << 3;
+const MOD_HARD_ROCK: u32 = 1 << 4;
+const MOD_DOUBLE_TIME: u32 = 1 << 6;
+const MOD_HALF_TIME: u32 = 1 << 8;
+const MOD_NIGHTCORE: u32 = 1 << 9;
+const MOD_FLASHLIGHT: u32 = 1 << 10;
+
+fn mods_clock_rate(mods: u32) -> f64 {
+    if mods & (MOD_DOUBLE_TIME | MOD_NIGHTCORE) != 0 {
+        1.5
+    } else if mods & MOD_HALF_TIME != 0 {
+        0.75
+    } else {
+        1.0
+    }
+}
+
+fn approach_rate_to_ms(ar: f64) -> f64 {
+    if ar < 5.0 {
+        1800.0 - 120.0 * ar
+    } else {
+        1200.0 - 150.0 * (ar - 5.0)
+    }
+}
+
+fn approach_ms_to_rate(ms: f64) -> f64 {
+    if ms > 1200.0 {
+        (1800.0 - ms) / 120.0
+    } else {
+        5.0 + (1200.0 - ms) / 150.0
+    }
+}
+
+impl DifficultySettings {
+    /// Values as the player experiences them with `mods` (legacy bitflags) applied.
+    /// EZ/HR scale the raw values first; DT/NC/HT then rescale AR and OD through their
+    /// osu!standard millisecond windows.
+    fn with_mods(self, mods: u32) -> Self {
+        let mut adjusted = self;
+        if mods & MOD_HARD_ROCK != 0 {
+            adjusted.cs = (adjusted.cs * 1.3).min(10.0);
+            adjusted.ar = (adjusted.ar * 1.4).min(10.0);
+            adjusted.od = (adjusted.od * 1.4).min(10.0);
+            adjusted.hp = (adjusted.hp * 1.4).min(10.0);
+        } else if mods & MOD_EASY != 0 {
+            adjusted.cs *= 0.5;
+            adjusted.ar *= 0.5;
+            adjusted.od *= 0.5;
+            adjusted.hp *= 0.5;
+        }
+
+        let clock_rate = mods_clock_rate(mods);
+        if clock_rate != 1.0 {
+            adjusted.ar = approach_ms_to_rate(approach_rate_to_ms(adjusted.ar) / clock_rate);
+            let od_window = (80.0 - 6.0 * adjusted.od) / clock_rate;
+            adjusted.od = (80.0 - od_window) / 6.0;
+        }
+        adjusted
+    }
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -63,6 +256,222 @@ struct TimeRange {
     end: i32,
 }
 
+/// Effective slider velocity from a given time onward.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SvChange {
+    time: i32,
+    /// Raw beat length of the green line; `None` when a red line resets SV to 1.0x.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_beat_length: Option<f64>,
+    multiplier: f64,
+    effective_sv: f64,
+    px_per_beat: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SnapClassification {
+    time: i32,
+    /// Coarsest divisor the object sits on, `None` when it is unsnapped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    divisor: Option<u32>,
+    nearest_divisor: u32,
+    offset_ms: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SnapDivisorCount {
+    divisor: u32,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnappingReportPayload {
+    file_path: String,
+    total_objects: usize,
+    divisor_counts: Vec<SnapDivisorCount>,
+    objects: Vec<SnapClassification>,
+    unsnapped: Vec<SnapClassification>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct BpmSummary {
+    dominant: f64,
+    min: f64,
+    max: f64,
+    variable: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CompareStatRow {
+    key: String,
+    a: f64,
+    b: f64,
+    delta: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TimingAlignment {
+    matching_red_lines: usize,
+    only_in_a: Vec<i32>,
+    only_in_b: Vec<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_offset_delta_ms: Option<f64>,
+    bpm_matches: bool,
+    aligned: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MapComparisonPayload {
+    path_a: String,
+    path_b: String,
+    metadata_a: ParsedMetadata,
+    metadata_b: ParsedMetadata,
+    stats: Vec<CompareStatRow>,
+    bucket_ms: i32,
+    density_a: Vec<u32>,
+    density_b: Vec<u32>,
+    strain_a: Vec<f64>,
+    strain_b: Vec<f64>,
+    timing: TimingAlignment,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FieldChange {
+    section: String,
+    key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum DiffChangeKind {
+    Added,
+    Removed,
+    Modified,
+    Moved,
+    Retimed,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TimingPointChange {
+    kind: DiffChangeKind,
+    time: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HitObjectSnapshot {
+    time: i32,
+    x: f64,
+    y: f64,
+    object_type: i32,
+    line: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HitObjectChange {
+    kind: DiffChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    before: Option<HitObjectSnapshot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<HitObjectSnapshot>,
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct BeatmapDiffSummary {
+    fields_changed: usize,
+    timing_points_changed: usize,
+    objects_added: usize,
+    objects_removed: usize,
+    objects_moved: usize,
+    objects_retimed: usize,
+    objects_modified: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BeatmapDiffPayload {
+    path_a: String,
+    path_b: String,
+    metadata_changes: Vec<FieldChange>,
+    timing_changes: Vec<TimingPointChange>,
+    hit_object_changes: Vec<HitObjectChange>,
+    summary: BeatmapDiffSummary,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum LintSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct LintIssue {
+    rule: String,
+    severity: LintSeverity,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct LintOptions {
+    disabled_rules: Vec<String>,
+    min_spinner_length_ms: i32,
+    max_audio_bitrate_kbps: u32,
+    max_background_width: u32,
+    max_background_height: u32,
+    min_break_length_ms: i32,
+    max_drain_without_break_ms: i32,
+}
+
+/// Gap osu! leaves between the last object and the start of a break.
+const BREAK_START_MIN_GAP_MS: i32 = 200;
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            disabled_rules: Vec::new(),
+            min_spinner_length_ms: 1000,
+            max_audio_bitrate_kbps: 192,
+            max_background_width: RANKED_BACKGROUND_MAX_WIDTH,
+            max_background_height: RANKED_BACKGROUND_MAX_HEIGHT,
+            min_break_length_ms: 650,
+            max_drain_without_break_ms: 60_000,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LintReportPayload {
+    file_path: String,
+    issues: Vec<LintIssue>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct ScanFilePayload {
@@ -82,6 +491,113 @@ struct ScanFilePayload {
     break_periods: Option<Vec<TimeRange>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     bookmarks: Option<Vec<i32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sv_changes: Option<Vec<SvChange>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bpm: Option<BpmSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    removed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decode_warnings: Option<Vec<String>>,
+    /// Scanned directory the file was found under, for source badges in a multi-root library.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    library_root: Option<String>,
+    /// Set instead of `hit_starts`/`hit_ends` when the scan asked for `binaryTimeline`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeline: Option<BinaryTimelineRef>,
+}
+
+/// Unfetched binary timelines are dropped after this, so an abandoned scan can't pin memory.
+const TIMELINE_BLOB_TTL: Duration = Duration::from_secs(120);
+const TIMELINE_PROTOCOL: &str = "mosu-timeline";
+/// Smaller maps keep their hit times inline; the extra fetch only pays off for big ones.
+const BINARY_TIMELINE_MIN_OBJECTS: usize = 2_000;
+
+/// Where a scan result's hit times went when the scan asked for `binaryTimeline`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BinaryTimelineRef {
+    /// Fetch from `mosu-timeline://localhost/<id>`; one blob holds every large map in a batch.
+    id: String,
+    /// Index of this map's i32 LE object count in the blob, followed by that many starts and ends.
+    offset: usize,
+    object_count: usize,
+    length_ms: i32,
+}
+
+static NEXT_TIMELINE_ID: AtomicUsize = AtomicUsize::new(1);
+static TIMELINE_BLOBS: Mutex<BTreeMap<String, (Instant, Vec<u8>)>> = Mutex::new(BTreeMap::new());
+
+/// Move the hit times of a batch's large maps into one binary blob served over `TIMELINE_PROTOCOL`,
+/// leaving references behind.
+fn stash_binary_timelines(batch: &mut [ScanFilePayload]) {
+    let id = NEXT_TIMELINE_ID.fetch_add(1, Ordering::Relaxed).to_string();
+    let mut bytes = Vec::new();
+    for file in batch.iter_mut() {
+        if file.hit_starts.as_ref().is_none_or(|starts| starts.len() < BINARY_TIMELINE_MIN_OBJECTS) {
+            continue;
+        }
+        let (Some(starts), Some(ends)) = (file.hit_starts.take(), file.hit_ends.take()) else {
+            continue;
+        };
+        let offset = bytes.len() / 4;
+        bytes.reserve(4 + (starts.len() + ends.len()) * 4);
+        bytes.extend_from_slice(&(starts.len() as i32).to_le_bytes());
+        for time in starts.iter().chain(&ends) {
+            bytes.extend_from_slice(&time.to_le_bytes());
+        }
+        file.timeline = Some(BinaryTimelineRef {
+            id: id.clone(),
+            offset,
+            object_count: starts.len(),
+            length_ms: ends.iter().max().copied().unwrap_or(0),
+        });
+    }
+    if bytes.is_empty() {
+        return;
+    }
+
+    let mut blobs = TIMELINE_BLOBS.lock().unwrap();
+    blobs.retain(|_, (stored_at, _)| stored_at.elapsed() < TIMELINE_BLOB_TTL);
+    blobs.insert(id, (Instant::now(), bytes));
+}
+
+/// Serve each stashed timeline once.
+fn timeline_protocol_response(path: &str) -> tauri::http::Response<Vec<u8>> {
+    let blob = TIMELINE_BLOBS.lock().unwrap().remove(path.trim_start_matches('/'));
+    let (status, body) = match blob {
+        Some((_, bytes)) => (200, bytes),
+        None => (404, Vec::new()),
+    };
+    tauri::http::Response::builder()
+        .status(status)
+        .header("Content-Type", "application/octet-stream")
+        .header("Access-Control-Allow-Origin", "*")
+        .body(body)
+        .unwrap()
+}
+
+impl ScanFilePayload {
+    /// Marker for a previously scanned beatmap that is no longer on disk.
+    fn removed(file_path: String) -> Self {
+        Self {
+            file_path,
+            stat: FileStatPayload { mtime_ms: 0.0 },
+            beatmap_hash: None,
+            unchanged: None,
+            metadata: None,
+            hit_starts: None,
+            hit_ends: None,
+            break_periods: None,
+            bookmarks: None,
+            sv_changes: None,
+            bpm: None,
+            removed: Some(true),
+            decode_warnings: None,
+            library_root: None,
+            timeline: None,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -116,6 +632,25 @@ struct UpdateInfoPayload {
     error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DownloadedUpdatePayload {
+    version: String,
+    asset_name: String,
+    file_path: String,
+    sha256: String,
+    size_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct UpdateDownloadProgressEvent {
+    asset_name: String,
+    downloaded_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_bytes: Option<u64>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct EmbedSyncPayload {
@@ -128,11 +663,152 @@ struct EmbedSyncPayload {
     error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BeatmapIdBackfillEntry {
+    file_path: String,
+    beatmap_hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beatmap_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beatmap_set_id: Option<i64>,
+    written: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    backup_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BeatmapIdBackfillPayload {
+    entries: Vec<BeatmapIdBackfillEntry>,
+    matched: usize,
+    skipped: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BeatmapIdBackfillProgressEvent {
+    file_path: String,
+    current: usize,
+    total: usize,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OsuMetadataChanges {
+    title: Option<String>,
+    artist: Option<String>,
+    creator: Option<String>,
+    version: Option<String>,
+    tags: Option<String>,
+    source: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct OsuFileWriteResult {
+    file_path: String,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    backup_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BeatmapSnapshotInfo {
+    id: String,
+    file_path: String,
+    created_at_ms: u64,
+    content_hash: String,
+    size_bytes: u64,
+}
+
+impl OsuFileWriteResult {
+    fn from_result(file_path: &Path, result: Result<Option<PathBuf>, String>) -> Self {
+        let file_path = file_path.to_string_lossy().to_string();
+        match result {
+            Ok(backup_path) => Self {
+                file_path,
+                success: true,
+                backup_path: backup_path.map(|path| path.to_string_lossy().to_string()),
+                error: None,
+            },
+            Err(error) => Self {
+                file_path,
+                success: false,
+                backup_path: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct MetadataMismatch {
+    field: String,
+    guest_value: String,
+    set_value: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct GuestDiffMergeEntry {
+    source_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    destination_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio_filename_fixed_from: Option<String>,
+    audio_missing: bool,
+    background_missing: bool,
+    metadata_mismatches: Vec<MetadataMismatch>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
+struct GuestDiffMergePayload {
+    target_folder: String,
+    diffs: Vec<GuestDiffMergeEntry>,
+    copied_assets: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 struct OsuUserData {
     id: String,
     names: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    avatar_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    country_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    country_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ranked_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    loved_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pending_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    graveyard_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    guest_count: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TimingPoint {
+    time: f64,
+    beat_length: f64,
+    /// Beats per measure; only meaningful on uninherited points.
+    meter: u32,
+    uninherited: bool,
+    kiai: bool,
 }
 
 #[derive(Debug)]
@@ -142,6 +818,11 @@ struct ParsedOsu {
     hit_ends: Vec<i32>,
     break_periods: Vec<TimeRange>,
     bookmarks: Vec<i32>,
+    sv_changes: Vec<SvChange>,
+    timing_points: Vec<TimingPoint>,
+    hit_types: Vec<i32>,
+    /// Sprites and animations in the difficulty's own `[Events]`; a set-wide .osb isn't counted.
+    storyboard_objects: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -380,10 +1061,15 @@ fn read_osu_string<R: Read>(reader: &mut R) -> Result<Option<String>, String> {
         0x00 => Ok(None),
         0x0b => {
             let length = read_uleb128(reader)?;
-            let mut bytes = vec![0_u8; length];
+            // The length comes from the file, so read up to it rather than allocating it up front.
+            let mut bytes = Vec::new();
             reader
-                .read_exact(&mut bytes)
+                .take(length as u64)
+                .read_to_end(&mut bytes)
                 .map_err(|err| err.to_string())?;
+            if bytes.len() != length {
+                return Err("osu string is truncated".to_string());
+            }
             String::from_utf8(bytes)
                 .map(Some)
                 .map_err(|err| err.to_string())
@@ -484,10 +1170,207 @@ fn get_mtime_ms(path: &Path) -> Result<f64, String> {
     Ok(duration.as_secs_f64() * 1000.0)
 }
 
-fn get_mime_type(path: &Path) -> &'static str {
-    match path
-        .extension()
-        .and_then(|ext| ext.to_str())
+fn unix_time_ms() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0))
+        .as_millis()
+}
+
+/// Copy `path` next to itself as `<name>.<unix ms>.bak` before it gets rewritten.
+fn create_timestamped_backup(path: &Path) -> Result<PathBuf, String> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| "invalid file path".to_string())?;
+    let backup_path = path.with_file_name(format!("{file_name}.{}.bak", unix_time_ms()));
+    fs::copy(path, &backup_path).map_err(|err| format!("failed to create backup: {err}"))?;
+    Ok(backup_path)
+}
+
+/// Write through a sibling temp file and rename it into place so a crash never leaves a half-written map.
+fn write_file_atomically(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| "invalid file path".to_string())?;
+    let temp_path = path.with_file_name(format!("{file_name}.mosu-tmp"));
+    fs::write(&temp_path, bytes).map_err(|err| err.to_string())?;
+    fs::rename(&temp_path, path).map_err(|err| {
+        let _ = fs::remove_file(&temp_path);
+        if is_locked_io_error(&err) {
+            "file_locked".to_string()
+        } else {
+            err.to_string()
+        }
+    })
+}
+
+/// Read pixel dimensions from a PNG/JPEG/GIF/BMP/WebP header without decoding the image.
+fn read_image_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let file = fs::File::open(path).ok()?;
+    let mut reader = BufReader::with_capacity(8192, file);
+    let mut header = Vec::with_capacity(32);
+    reader.by_ref().take(30).read_to_end(&mut header).ok()?;
+
+    let be_u32 = |at: usize| -> Option<u32> {
+        header.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let le_u16 = |at: usize| -> Option<u32> {
+        header.get(at..at + 2).map(|b| u32::from(u16::from_le_bytes([b[0], b[1]])))
+    };
+
+    if header.starts_with(&[0x89, b'P', b'N', b'G']) {
+        return Some((be_u32(16)?, be_u32(20)?));
+    }
+    if header.starts_with(b"GIF8") {
+        return Some((le_u16(6)?, le_u16(8)?));
+    }
+    if header.starts_with(b"BM") {
+        let width = header.get(18..22).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))?;
+        let height = header.get(22..26).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))?;
+        return Some((width.unsigned_abs(), height.unsigned_abs()));
+    }
+    if header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP".as_slice()) {
+        return match header.get(12..16)? {
+            b"VP8X" => {
+                let b = header.get(24..30)?;
+                let width = u32::from_le_bytes([b[0], b[1], b[2], 0]) + 1;
+                let height = u32::from_le_bytes([b[3], b[4], b[5], 0]) + 1;
+                Some((width, height))
+            }
+            b"VP8 " => Some((le_u16(26)? & 0x3fff, le_u16(28)? & 0x3fff)),
+            b"VP8L" => {
+                let b = header.get(21..25)?;
+                let bits = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            _ => None,
+        };
+    }
+    if !header.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+
+    // JPEG: walk segments from the start until a start-of-frame marker
+    let file = fs::File::open(path).ok()?;
+    let mut reader = BufReader::with_capacity(8192, file);
+    let mut byte = [0_u8; 1];
+    reader.read_exact(&mut [0_u8; 2]).ok()?;
+    loop {
+        reader.read_exact(&mut byte).ok()?;
+        if byte[0] != 0xff {
+            return None;
+        }
+        let mut marker = 0xff;
+        while marker == 0xff {
+            reader.read_exact(&mut byte).ok()?;
+            marker = byte[0];
+        }
+        if marker == 0x01 || (0xd0..=0xd8).contains(&marker) {
+            continue;
+        }
+        let mut length = [0_u8; 2];
+        reader.read_exact(&mut length).ok()?;
+        let length = u16::from_be_bytes(length);
+        if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let mut frame = [0_u8; 5];
+            reader.read_exact(&mut frame).ok()?;
+            let height = u32::from(u16::from_be_bytes([frame[1], frame[2]]));
+            let width = u32::from(u16::from_be_bytes([frame[3], frame[4]]));
+            return Some((width, height));
+        }
+        let skip = u64::from(length.checked_sub(2)?);
+        std::io::copy(&mut reader.by_ref().take(skip), &mut std::io::sink()).ok()?;
+    }
+}
+
+const RANKED_BACKGROUND_MAX_WIDTH: u32 = 2560;
+const RANKED_BACKGROUND_MAX_HEIGHT: u32 = 1440;
+const RANKED_BACKGROUND_MIN_WIDTH: u32 = 1280;
+const RANKED_BACKGROUND_MIN_HEIGHT: u32 = 720;
+const RANKED_BACKGROUND_MAX_BYTES: u64 = 2_500_000;
+
+/// Background image header info, read during scanning so the UI can crop by aspect ratio.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct BackgroundImageInfo {
+    width: u32,
+    height: u32,
+    size_bytes: u64,
+    /// Ranking criteria problems: `too-large`, `too-small` or `file-too-large`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    flags: Vec<String>,
+}
+
+fn read_background_info(path: &Path) -> Option<BackgroundImageInfo> {
+    let size_bytes = fs::metadata(path).ok()?.len();
+    let (width, height) = read_image_dimensions(path)?;
+    let mut flags = Vec::new();
+    if width > RANKED_BACKGROUND_MAX_WIDTH || height > RANKED_BACKGROUND_MAX_HEIGHT {
+        flags.push("too-large".to_string());
+    }
+    if width < RANKED_BACKGROUND_MIN_WIDTH || height < RANKED_BACKGROUND_MIN_HEIGHT {
+        flags.push("too-small".to_string());
+    }
+    if size_bytes > RANKED_BACKGROUND_MAX_BYTES {
+        flags.push("file-too-large".to_string());
+    }
+    Some(BackgroundImageInfo {
+        width,
+        height,
+        size_bytes,
+        flags,
+    })
+}
+
+/// With the default background dim the playfield is close to black, so combo colours below this
+/// HSP luminosity are hard to see; during kiai the flashes make ones above the bright limit glare.
+const COMBO_COLOUR_MIN_LUMINOSITY: f64 = 43.0;
+const COMBO_COLOUR_MAX_KIAI_LUMINOSITY: f64 = 250.0;
+
+/// A `ComboN` entry from `[Colours]`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+struct ComboColour {
+    /// The N in `ComboN`.
+    index: u32,
+    r: u8,
+    g: u8,
+    b: u8,
+    /// HSP perceived brightness, 0-255.
+    luminosity: f64,
+    /// Contrast problems: `too-dark`, or `too-bright-in-kiai` when the map has kiai time.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    flags: Vec<String>,
+}
+
+impl ComboColour {
+    /// Parse a `ComboN : r,g,b` line from `[Colours]`.
+    fn parse(key: &str, value: &str) -> Option<Self> {
+        let prefix = key.get(..5).filter(|prefix| eq_ascii_ci(prefix, "Combo"))?;
+        let index = key[prefix.len()..].parse::<u32>().ok()?;
+        let mut channels = value.split(',').map(|channel| channel.trim().parse::<u8>().ok());
+        let (Some(Some(r)), Some(Some(g)), Some(Some(b))) = (channels.next(), channels.next(), channels.next()) else {
+            return None;
+        };
+        let luminosity = (0.299 * f64::from(r).powi(2) + 0.587 * f64::from(g).powi(2) + 0.114 * f64::from(b).powi(2)).sqrt();
+        Some(Self { index, r, g, b, luminosity, flags: Vec::new() })
+    }
+
+    fn check_contrast(&mut self, has_kiai: bool) {
+        if self.luminosity < COMBO_COLOUR_MIN_LUMINOSITY {
+            self.flags.push("too-dark".to_string());
+        } else if has_kiai && self.luminosity > COMBO_COLOUR_MAX_KIAI_LUMINOSITY {
+            self.flags.push("too-bright-in-kiai".to_string());
+        }
+    }
+}
+
+fn get_mime_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
         .unwrap_or_default()
         .to_ascii_lowercase()
         .as_str()
@@ -549,12 +1432,20 @@ fn audio_mime_type_from_hint(file_name_hint: Option<&str>) -> Option<&'static st
 }
 
 fn normalize_metadata(mut metadata: ParsedMetadata) -> ParsedMetadata {
+    metadata.guest_mapper =
+        detect_guest_mapper(&metadata.creator, &metadata.version, &metadata.tags).unwrap_or_default();
     if metadata.title.is_empty() {
         metadata.title = "Unknown Title".to_string();
     }
     if metadata.artist.is_empty() {
         metadata.artist = "Unknown Artist".to_string();
     }
+    if metadata.title_unicode.is_empty() {
+        metadata.title_unicode = metadata.title.clone();
+    }
+    if metadata.artist_unicode.is_empty() {
+        metadata.artist_unicode = metadata.artist.clone();
+    }
     if metadata.creator.is_empty() {
         metadata.creator = "Unknown Creator".to_string();
     }
@@ -565,6 +1456,8 @@ fn normalize_metadata(mut metadata: ParsedMetadata) -> ParsedMetadata {
         metadata.beatmap_set_id = "Unknown".to_string();
     }
     metadata.mode = metadata.mode.clamp(0, 3);
+    metadata.language = guess_language(&metadata).to_string();
+    metadata.genre = guess_genre(&metadata.source, &metadata.tags).to_string();
     metadata
 }
 
@@ -868,6 +1761,7 @@ enum OsuSection {
     Difficulty,
     Events,
     TimingPoints,
+    Colours,
     HitObjects,
     Other,
 }
@@ -881,12 +1775,42 @@ impl OsuSection {
         else if eq_ascii_ci(header, "Difficulty") { OsuSection::Difficulty }
         else if eq_ascii_ci(header, "Events") { OsuSection::Events }
         else if eq_ascii_ci(header, "TimingPoints") { OsuSection::TimingPoints }
+        else if eq_ascii_ci(header, "Colours") { OsuSection::Colours }
         else if eq_ascii_ci(header, "HitObjects") { OsuSection::HitObjects }
         else { OsuSection::Other }
     }
 }
 
+/// Beat length and green-line SV multiplier in effect at `time`.
+fn timing_at(timing_points: &[TimingPoint], time: f64) -> (f64, f64) {
+    let mut beat_length = 60000.0 / 120.0;
+    let mut sv = 1.0;
+    for point in timing_points {
+        if point.time > time {
+            break;
+        }
+        if point.uninherited {
+            beat_length = point.beat_length;
+            sv = 1.0;
+        } else if point.beat_length < 0.0 {
+            sv = -100.0 / point.beat_length;
+        }
+    }
+    (beat_length, sv)
+}
+
+/// How long a slider lasts, given the `timing_at` its head and the map's SliderMultiplier.
+fn slider_duration_ms(pixel_length: f64, slides: f64, slider_multiplier: f64, (beat_length, sv): (f64, f64)) -> f64 {
+    pixel_length / (slider_multiplier * 100.0 * sv) * beat_length * slides
+}
+
 fn parse_osu_content(content: &str) -> ParsedOsu {
+    parse_osu_content_at(content, ScanDetailLevel::Full)
+}
+
+/// `Metadata` stops at `[HitObjects]` and only reads `[TimingPoints]` for kiai, leaving hit
+/// objects and timing points empty; `Timeline` skips building the SV series.
+fn parse_osu_content_at(content: &str, detail: ScanDetailLevel) -> ParsedOsu {
     const SLIDER_GAP_FILL_BEATS: f64 = 2.0;
     let mut metadata = ParsedMetadata {
         preview_time: -1,
@@ -896,13 +1820,16 @@ fn parse_osu_content(content: &str) -> ParsedOsu {
 
     let mut section = OsuSection::None;
     let mut slider_multiplier = 1.0_f64;
-    let mut timing_points: Vec<(i32, f64, bool)> = Vec::with_capacity(64);
+    let mut approach_rate: Option<f64> = None;
+    let mut timing_points: Vec<TimingPoint> = Vec::with_capacity(64);
     let mut hit_starts: Vec<i32> = Vec::with_capacity(512);
     let mut hit_ends: Vec<i32> = Vec::with_capacity(512);
     let mut hit_types: Vec<i32> = Vec::with_capacity(512);
     let mut hit_gap_thresholds: Vec<i32> = Vec::with_capacity(512);
     let mut break_periods: Vec<TimeRange> = Vec::with_capacity(8);
     let mut bookmarks: Vec<i32> = Vec::with_capacity(32);
+    let mut storyboard_objects = 0_usize;
+    let mut has_kiai = false;
 
     for line in content.lines() {
         let trimmed = line.trim();
@@ -920,6 +1847,10 @@ fn parse_osu_content(content: &str) -> ParsedOsu {
         // Section header
         if bytes[0] == b'[' && bytes[bytes.len() - 1] == b']' {
             section = OsuSection::from_header(&trimmed[1..trimmed.len() - 1]);
+            // `[Colours]` usually follows `[TimingPoints]`, so only the hit objects are skipped.
+            if detail == ScanDetailLevel::Metadata && section == OsuSection::HitObjects {
+                break;
+            }
             continue;
         }
 
@@ -930,12 +1861,20 @@ fn parse_osu_content(content: &str) -> ParsedOsu {
                     let value = value.trim();
                     if eq_ascii_ci(key, "Title") {
                         metadata.title = value.to_string();
+                    } else if eq_ascii_ci(key, "TitleUnicode") {
+                        metadata.title_unicode = value.to_string();
                     } else if eq_ascii_ci(key, "Artist") {
                         metadata.artist = value.to_string();
+                    } else if eq_ascii_ci(key, "ArtistUnicode") {
+                        metadata.artist_unicode = value.to_string();
                     } else if eq_ascii_ci(key, "Creator") {
                         metadata.creator = value.to_string();
                     } else if eq_ascii_ci(key, "Version") {
                         metadata.version = value.to_string();
+                    } else if eq_ascii_ci(key, "Source") {
+                        metadata.source = value.to_string();
+                    } else if eq_ascii_ci(key, "Tags") {
+                        metadata.tags = value.to_string();
                     } else if eq_ascii_ci(key, "BeatmapSetID") {
                         if let Ok(id) = value.parse::<i32>() {
                             if id > 0 {
@@ -963,27 +1902,69 @@ fn parse_osu_content(content: &str) -> ParsedOsu {
                         if let Ok(v) = value.parse::<i32>() {
                             metadata.mode = v;
                         }
+                    } else {
+                        let general = &mut metadata.general;
+                        if eq_ascii_ci(key, "Countdown") {
+                            general.countdown = value.parse().unwrap_or(general.countdown);
+                        } else if eq_ascii_ci(key, "LetterboxInBreaks") {
+                            general.letterbox_in_breaks = value == "1";
+                        } else if eq_ascii_ci(key, "WidescreenStoryboard") {
+                            general.widescreen_storyboard = value == "1";
+                        } else if eq_ascii_ci(key, "SampleSet") {
+                            general.sample_set = value.to_string();
+                        } else if eq_ascii_ci(key, "StackLeniency") {
+                            general.stack_leniency = value.parse().unwrap_or(general.stack_leniency);
+                        } else if eq_ascii_ci(key, "EpilepsyWarning") {
+                            general.epilepsy_warning = value == "1";
+                        }
                     }
                 }
             }
             OsuSection::Difficulty => {
                 if let Some((key, value)) = trimmed.split_once(':') {
-                    if eq_ascii_ci(key.trim(), "SliderMultiplier") {
-                        slider_multiplier = value.trim().parse::<f64>().unwrap_or(1.0);
+                    let key = key.trim();
+                    let value = value.trim().parse::<f64>().ok();
+                    let difficulty = &mut metadata.difficulty;
+                    if eq_ascii_ci(key, "SliderMultiplier") {
+                        slider_multiplier = value.unwrap_or(1.0);
+                        difficulty.slider_multiplier = slider_multiplier;
+                    } else if let Some(value) = value {
+                        if eq_ascii_ci(key, "CircleSize") {
+                            difficulty.cs = value;
+                        } else if eq_ascii_ci(key, "ApproachRate") {
+                            approach_rate = Some(value);
+                        } else if eq_ascii_ci(key, "OverallDifficulty") {
+                            difficulty.od = value;
+                        } else if eq_ascii_ci(key, "HPDrainRate") {
+                            difficulty.hp = value;
+                        } else if eq_ascii_ci(key, "SliderTickRate") {
+                            difficulty.slider_tick_rate = value;
+                        }
                     }
                 }
             }
             OsuSection::TimingPoints => {
                 let field_count = csv_field_count(trimmed);
                 if field_count >= 2 {
-                    let time = csv_field(trimmed, 0).unwrap_or("0").trim().parse::<i32>().unwrap_or(0);
+                    let time = csv_field(trimmed, 0).unwrap_or("0").trim().parse::<f64>().unwrap_or(0.0);
                     let beat_length = csv_field(trimmed, 1).unwrap_or("500").trim().parse::<f64>().unwrap_or(500.0);
+                    let meter = csv_field(trimmed, 2)
+                        .and_then(|v| v.trim().parse::<u32>().ok())
+                        .filter(|meter| *meter > 0)
+                        .unwrap_or(4);
                     let uninherited = if field_count >= 7 {
                         csv_field(trimmed, 6).map(|v| v.trim() == "1").unwrap_or(true)
                     } else {
                         true
                     };
-                    timing_points.push((time, beat_length, uninherited));
+                    let kiai = field_count >= 8
+                        && csv_field(trimmed, 7)
+                            .and_then(|v| v.trim().parse::<i32>().ok())
+                            .is_some_and(|effects| effects & 1 != 0);
+                    has_kiai |= kiai;
+                    if detail != ScanDetailLevel::Metadata {
+                        timing_points.push(TimingPoint { time, beat_length, meter, uninherited, kiai });
+                    }
                 }
             }
             OsuSection::Events => {
@@ -1003,6 +1984,23 @@ fn parse_osu_content(content: &str) -> ParsedOsu {
                             metadata.background = candidate.to_string();
                         }
                     }
+                    if matches!(f0, "4" | "6") || eq_ascii_ci(f0, "Sprite") || eq_ascii_ci(f0, "Animation") {
+                        storyboard_objects += 1;
+                    }
+                    if (f0 == "1" || eq_ascii_ci(f0, "Video")) && metadata.video.is_empty() {
+                        let candidate = csv_field(trimmed, 2).unwrap_or("").trim().trim_matches('"');
+                        if !candidate.is_empty() {
+                            metadata.video = candidate.to_string();
+                        }
+                    }
+                }
+            }
+            OsuSection::Colours => {
+                if let Some(colour) = trimmed
+                    .split_once(':')
+                    .and_then(|(key, value)| ComboColour::parse(key.trim(), value))
+                {
+                    metadata.combo_colours.push(colour);
                 }
             }
             OsuSection::Editor => {
@@ -1023,20 +2021,8 @@ fn parse_osu_content(content: &str) -> ParsedOsu {
                 let start_time = csv_field(trimmed, 2).unwrap_or("0").trim().parse::<i32>().unwrap_or(0);
                 let obj_type = csv_field(trimmed, 3).unwrap_or("0").trim().parse::<i32>().unwrap_or(0);
                 let mut end_time = start_time;
-                let mut active_beat = 60000.0 / 120.0;
-                let mut active_sv = 1.0;
-
-                for &(tp_time, beat_length, uninherited) in &timing_points {
-                    if tp_time > start_time {
-                        break;
-                    }
-                    if uninherited {
-                        active_beat = beat_length;
-                        active_sv = 1.0;
-                    } else if beat_length < 0.0 {
-                        active_sv = -100.0 / beat_length;
-                    }
-                }
+                let timing = timing_at(&timing_points, f64::from(start_time));
+                let active_beat = timing.0;
 
                 if obj_type & 2 != 0 {
                     // Slider
@@ -1044,8 +2030,7 @@ fn parse_osu_content(content: &str) -> ParsedOsu {
                         let slides = csv_field(trimmed, 6).unwrap_or("1").trim().parse::<f64>().unwrap_or(1.0);
                         let length = csv_field(trimmed, 7).unwrap_or("0").trim().parse::<f64>().unwrap_or(0.0);
 
-                        let duration =
-                            (length / (slider_multiplier * 100.0 * active_sv)) * active_beat * slides;
+                        let duration = slider_duration_ms(length, slides, slider_multiplier, timing);
                         end_time = start_time + duration.max(0.0).floor() as i32;
                     }
                 } else if obj_type & 8 != 0 {
@@ -1089,1453 +2074,14652 @@ fn parse_osu_content(content: &str) -> ParsedOsu {
         }
     }
 
+    let sv_changes = if detail == ScanDetailLevel::Full {
+        build_sv_changes(&timing_points, slider_multiplier)
+    } else {
+        Vec::new()
+    };
+    metadata.difficulty.ar = approach_rate.unwrap_or(metadata.difficulty.od);
+    metadata.combo_colours.sort_by_key(|colour| colour.index);
+    for colour in &mut metadata.combo_colours {
+        colour.check_contrast(has_kiai);
+    }
+
     ParsedOsu {
         metadata: normalize_metadata(metadata),
         hit_starts,
         hit_ends,
         break_periods,
         bookmarks,
+        sv_changes,
+        timing_points,
+        hit_types,
+        storyboard_objects,
     }
 }
 
-fn parse_header_creator_and_version(content: &str) -> (String, String) {
-    let mut in_metadata = false;
-    let mut creator = String::new();
-    let mut version = String::new();
-
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        let bytes = trimmed.as_bytes();
-        if bytes.len() >= 2 && bytes[0] == b'/' && bytes[1] == b'/' {
-            continue;
-        }
-        if bytes[0] == b'[' && bytes[bytes.len() - 1] == b']' {
-            let header = &trimmed[1..trimmed.len() - 1];
-            in_metadata = eq_ascii_ci(header, "Metadata");
-            // If we already found both values and left metadata, stop
-            if !in_metadata && !creator.is_empty() && !version.is_empty() {
-                break;
-            }
+/// Collapse timing points into the effective SV series (SliderMultiplier x green-line multiplier).
+/// Red lines reset the multiplier to 1.0x unless a green line shares the same tick.
+fn build_sv_changes(timing_points: &[TimingPoint], slider_multiplier: f64) -> Vec<SvChange> {
+    let mut changes: Vec<SvChange> = Vec::with_capacity(timing_points.len());
+
+    for point in timing_points {
+        let (raw_beat_length, multiplier) = if point.uninherited {
+            (None, 1.0)
+        } else if point.beat_length < 0.0 {
+            // osu! clamps green lines to the 0.1x-10x range
+            (Some(point.beat_length), (-100.0 / point.beat_length).clamp(0.1, 10.0))
+        } else {
             continue;
-        }
-        if in_metadata {
-            if let Some((key, value)) = trimmed.split_once(':') {
-                let key = key.trim();
-                let value = value.trim();
-                if eq_ascii_ci(key, "Creator") {
-                    creator = value.to_string();
-                } else if eq_ascii_ci(key, "Version") {
-                    version = value.to_string();
-                }
-                if !creator.is_empty() && !version.is_empty() {
-                    break;
+        };
+
+        let change = SvChange {
+            time: point.time.floor() as i32,
+            raw_beat_length,
+            multiplier,
+            effective_sv: slider_multiplier * multiplier,
+            px_per_beat: slider_multiplier * 100.0 * multiplier,
+        };
+
+        match changes.last_mut() {
+            Some(last) if last.time == change.time => {
+                if last.raw_beat_length.is_none() || change.raw_beat_length.is_some() {
+                    *last = change;
                 }
             }
+            _ => changes.push(change),
         }
     }
 
-    (creator, version)
+    changes
 }
 
-/// Discover osu beatmap files and their mtimes using WalkDir metadata.
-/// Stable scans use the .osu extension; lazer scans sniff beatmap text files in the hashed store.
-fn find_osu_files_with_mtime(
-    root: &Path,
-    client: OsuClient,
-    status: Option<(&tauri::Window, &str)>,
-) -> Vec<(String, f64)> {
-    match client {
-        OsuClient::Stable => {
-            let mut results = Vec::with_capacity(4096);
-            for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
-                if !entry.file_type().is_file() {
-                    continue;
-                }
-
-                let path = entry.path();
-                if !path
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| ext.eq_ignore_ascii_case("osu"))
-                    .unwrap_or(false)
-                {
-                    continue;
-                }
-
-                let metadata = match entry.metadata() {
-                    Ok(metadata) => metadata,
-                    Err(_) => continue,
-                };
-
-                let mtime_ms = metadata
-                    .modified()
-                    .ok()
-                    .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() * 1000.0)
-                    .unwrap_or(0.0);
-                results.push((path.to_string_lossy().to_string(), mtime_ms));
-            }
-            results
+/// Decode a .osu that isn't guaranteed to be UTF-8. A BOM wins; otherwise valid UTF-8 is taken
+/// as-is, then Shift_JIS (old Japanese maps), then Windows-1252, which accepts any byte.
+/// Warnings describe any fallback so the UI can flag the file instead of silently mangling it.
+fn decode_osu_bytes(bytes: &[u8]) -> (std::borrow::Cow<'_, str>, Vec<String>) {
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    let mut warnings = Vec::new();
+    if let Some((encoding, bom_length)) = encoding_rs::Encoding::for_bom(bytes) {
+        let (content, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+        if encoding != encoding_rs::UTF_8 {
+            warnings.push(format!("Decoded as {} (byte order mark)", encoding.name()));
         }
-        OsuClient::Lazer => {
-            let mut candidates = Vec::with_capacity(8192);
-            for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
-                if !entry.file_type().is_file() {
-                    continue;
-                }
+        if had_errors {
+            warnings.push("Invalid byte sequences were replaced".to_string());
+        }
+        return (content, warnings);
+    }
 
-                let metadata = match entry.metadata() {
-                    Ok(metadata) => metadata,
-                    Err(_) => continue,
-                };
-                let mtime_ms = metadata
-                    .modified()
-                    .ok()
-                    .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() * 1000.0)
-                    .unwrap_or(0.0);
+    if let Ok(content) = std::str::from_utf8(bytes) {
+        return (std::borrow::Cow::Borrowed(content), warnings);
+    }
 
-                candidates.push((
-                    entry.path().to_string_lossy().to_string(),
-                    metadata.len(),
-                    mtime_ms,
-                ));
-            }
+    if let Some(content) = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(bytes) {
+        warnings.push("File is not UTF-8; decoded as Shift_JIS".to_string());
+        return (content, warnings);
+    }
 
-            let total_candidates = candidates.len();
-            if let Some((window, dir_path)) = status {
-                emit_scan_status(window, dir_path, "discovering", 0, total_candidates, Some(0));
-            }
+    let (content, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+    warnings.push("File is not UTF-8; decoded as Windows-1252".to_string());
+    (content, warnings)
+}
 
-            let mut results = Vec::with_capacity((total_candidates / 8).max(256));
-            let mut discovered = 0_usize;
+fn load_parsed_osu(file_path: &str) -> Result<ParsedOsu, String> {
+    load_osu_with_content(file_path).map(|(_, parsed)| parsed)
+}
 
-            for (index, (file_path, file_size, mtime_ms)) in candidates.into_iter().enumerate() {
-                if is_probable_lazer_osu_file(Path::new(&file_path), file_size) {
-                    discovered += 1;
-                    results.push((file_path, mtime_ms));
-                }
+/// Decoded file text alongside the parsed map, for analyses that need the raw sections.
+fn load_osu_with_content(file_path: &str) -> Result<(String, ParsedOsu), String> {
+    let bytes = fs::read(file_path).map_err(|err| err.to_string())?;
+    let content = decode_osu_bytes(&bytes).0.into_owned();
+    let parsed = parse_osu_content(&content);
+    Ok((content, parsed))
+}
 
-                let current = index + 1;
-                if let Some((window, dir_path)) = status {
-                    if should_emit_scan_status(current, total_candidates) {
-                        emit_scan_status(
-                            window,
-                            dir_path,
-                            "discovering",
-                            current,
-                            total_candidates,
-                            Some(discovered),
-                        );
-                    }
-                }
-            }
+const SNAP_DIVISORS: [u32; 8] = [1, 2, 3, 4, 6, 8, 12, 16];
+const UNSNAPPED_TOLERANCE_MS: f64 = 1.0;
 
-            results
+/// Find the red line governing `time`. Objects before the first red line use the first one, like osu! does.
+fn active_uninherited_point(timing_points: &[TimingPoint], time: f64) -> Option<&TimingPoint> {
+    let mut active = None;
+    for point in timing_points
+        .iter()
+        .filter(|point| point.uninherited && point.beat_length > 0.0)
+    {
+        if active.is_some() && point.time > time {
+            break;
         }
+        active = Some(point);
     }
+    active
 }
 
-fn count_matching_entries(
-    osu_entries: &[(String, f64)],
-    mappers: &[String],
-    status: Option<(&tauri::Window, &str)>,
-) -> usize {
-    if mappers.is_empty() {
-        return osu_entries.len();
+fn classify_snap(time: i32, red_line: &TimingPoint) -> SnapClassification {
+    let offset = f64::from(time) - red_line.time;
+    let mut nearest_divisor = SNAP_DIVISORS[0];
+    let mut nearest_error = f64::INFINITY;
+
+    for divisor in SNAP_DIVISORS {
+        let tick = red_line.beat_length / f64::from(divisor);
+        let error = offset - (offset / tick).round() * tick;
+        if error.abs() <= UNSNAPPED_TOLERANCE_MS {
+            return SnapClassification {
+                time,
+                divisor: Some(divisor),
+                nearest_divisor: divisor,
+                offset_ms: error,
+            };
+        }
+        if error.abs() < nearest_error.abs() {
+            nearest_error = error;
+            nearest_divisor = divisor;
+        }
     }
 
-    let total = osu_entries.len();
-    if let Some((window, dir_path)) = status {
-        emit_scan_status(window, dir_path, "filtering", 0, total, None);
+    SnapClassification {
+        time,
+        divisor: None,
+        nearest_divisor,
+        offset_ms: nearest_error,
     }
+}
 
-    let mut matched = 0_usize;
-    for (index, (path, _)) in osu_entries.iter().enumerate() {
-        if file_matches_mapper(path, mappers) {
-            matched += 1;
-        }
+fn build_snapping_report(file_path: String, parsed: &ParsedOsu) -> Result<SnappingReportPayload, String> {
+    if active_uninherited_point(&parsed.timing_points, 0.0).is_none() {
+        return Err("beatmap has no uninherited timing points".to_string());
+    }
 
-        let current = index + 1;
-        if let Some((window, dir_path)) = status {
-            if should_emit_scan_status(current, total) {
-                emit_scan_status(window, dir_path, "filtering", current, total, None);
-            }
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    let mut objects = Vec::with_capacity(parsed.hit_starts.len());
+    let mut unsnapped = Vec::new();
+
+    for &time in &parsed.hit_starts {
+        let Some(red_line) = active_uninherited_point(&parsed.timing_points, f64::from(time)) else {
+            continue;
+        };
+        let classification = classify_snap(time, red_line);
+        match classification.divisor {
+            Some(divisor) => *counts.entry(divisor).or_insert(0) += 1,
+            None => unsnapped.push(classification.clone()),
         }
+        objects.push(classification);
     }
 
-    matched
-}
+    let divisor_counts = SNAP_DIVISORS
+        .iter()
+        .map(|&divisor| SnapDivisorCount {
+            divisor,
+            count: counts.get(&divisor).copied().unwrap_or(0),
+        })
+        .collect();
 
-/// Process a single .osu file. `mtime_ms` is pre-fetched from WalkDir.
-fn scan_single_osu_file(
-    file_path: &str,
-    mtime_ms: f64,
-    known: &HashMap<String, f64>,
-    mappers: &[String],
-    lazer_resolver: Option<&LazerResolvedAssets>,
-) -> Option<ScanFilePayload> {
-    let has_mapper = !mappers.is_empty();
+    Ok(SnappingReportPayload {
+        file_path,
+        total_objects: objects.len(),
+        divisor_counts,
+        objects,
+        unsnapped,
+    })
+}
 
-    // Fast path: check cache by mtime
-    if let Some(cached_mtime) = known.get(file_path) {
-        if (cached_mtime - mtime_ms).abs() < 0.5 {
-            if has_mapper {
-                // Only read first 8KB header for mapper filter on cached files
-                let path = Path::new(file_path);
-                if let Ok(file) = fs::File::open(path) {
-                    let mut reader = BufReader::with_capacity(8192, file);
-                    let mut buf = Vec::with_capacity(8192);
-                    let _ = reader.by_ref().take(8192).read_to_end(&mut buf);
-                    let header = String::from_utf8_lossy(&buf);
-                    let (creator, version) = parse_header_creator_and_version(&header);
-                    let creator_lower = creator.to_ascii_lowercase();
-                    let version_lower = version.to_ascii_lowercase();
-                    if !mappers.iter().any(|m| creator_lower.contains(m) || version_lower.contains(m)) {
-                        return None;
-                    }
-                } else {
-                    return None;
-                }
-            }
+/// (circles, sliders, spinners, mania holds)
+fn count_object_kinds(hit_types: &[i32]) -> (usize, usize, usize, usize) {
+    hit_types.iter().fold((0, 0, 0, 0), |(circles, sliders, spinners, holds), obj_type| {
+        if obj_type & 2 != 0 {
+            (circles, sliders + 1, spinners, holds)
+        } else if obj_type & 8 != 0 {
+            (circles, sliders, spinners + 1, holds)
+        } else if obj_type & 128 != 0 {
+            (circles, sliders, spinners, holds + 1)
+        } else {
+            (circles + 1, sliders, spinners, holds)
+        }
+    })
+}
 
-            return Some(ScanFilePayload {
-                file_path: file_path.to_string(),
-                stat: FileStatPayload { mtime_ms },
-                beatmap_hash: beatmap_hash_from_lazer_path(file_path),
-                unchanged: Some(true),
-                metadata: None,
-                hit_starts: None,
-                hit_ends: None,
-                break_periods: None,
-                bookmarks: None,
-            });
+/// Dominant BPM is the one covering the most time up to the last object, like osu! song select.
+fn compute_bpm_summary(timing_points: &[TimingPoint], last_object_time: i32) -> BpmSummary {
+    let red_lines: Vec<&TimingPoint> = timing_points
+        .iter()
+        .filter(|point| point.uninherited && point.beat_length > 0.0)
+        .collect();
+    if red_lines.is_empty() {
+        return BpmSummary::default();
+    }
+
+    let end_time = f64::from(last_object_time);
+    let mut durations: Vec<(f64, f64)> = Vec::new();
+    let mut min_bpm = f64::INFINITY;
+    let mut max_bpm = 0.0_f64;
+
+    for (index, point) in red_lines.iter().enumerate() {
+        if index > 0 && point.time > end_time {
+            break;
+        }
+        let bpm = 60000.0 / point.beat_length;
+        min_bpm = min_bpm.min(bpm);
+        max_bpm = max_bpm.max(bpm);
+
+        let start = if index == 0 { f64::NEG_INFINITY } else { point.time };
+        let end = red_lines.get(index + 1).map_or(end_time, |next| next.time.min(end_time));
+        let duration = if start.is_finite() { (end - start).max(0.0) } else { end.max(0.0) };
+        match durations.iter_mut().find(|(existing, _)| (existing - bpm).abs() < 1e-3) {
+            Some((_, total)) => *total += duration,
+            None => durations.push((bpm, duration)),
         }
     }
 
-    // Full parse path: read entire file with buffered I/O
-    let path = Path::new(file_path);
-    let file = fs::File::open(path).ok()?;
-    let mut reader = BufReader::with_capacity(32768, file);
-    let mut bytes = Vec::with_capacity(32768);
-    reader.read_to_end(&mut bytes).ok()?;
-    let content = String::from_utf8_lossy(&bytes);
+    let dominant = durations
+        .iter()
+        .fold(None::<(f64, f64)>, |best, &(bpm, duration)| match best {
+            Some((_, best_duration)) if best_duration >= duration => best,
+            _ => Some((bpm, duration)),
+        })
+        .map_or(0.0, |(bpm, _)| bpm);
 
-    let mut parsed = parse_osu_content(&content);
-    let beatmap_hash = match lazer_resolver {
-        Some(_) => beatmap_hash_from_lazer_path(file_path),
-        None => Some(compute_osu_md5_hex(&bytes)),
+    BpmSummary {
+        dominant,
+        min: min_bpm,
+        max: max_bpm,
+        variable: max_bpm - min_bpm > 0.01,
+    }
+}
+
+/// Playable length minus breaks, in ms.
+fn compute_drain_time_ms(parsed: &ParsedOsu) -> i32 {
+    drain_time_ms(&parsed.hit_starts, &parsed.hit_ends, &parsed.break_periods)
+}
+
+/// `compute_drain_time_ms` for hit times that are already split out, like a scan result's.
+fn drain_time_ms(hit_starts: &[i32], hit_ends: &[i32], break_periods: &[TimeRange]) -> i32 {
+    let (Some(first), Some(last)) = (hit_starts.first(), hit_ends.iter().max()) else {
+        return 0;
     };
+    let breaks: i32 = break_periods.iter().map(|range| range.end - range.start).sum();
+    (last - first - breaks).max(0)
+}
 
-    if let (Some(resolver), Some(beatmap_hash)) =
-        (lazer_resolver, beatmap_hash.as_deref())
-    {
-        if let Some((audio_path, bg_path)) = resolver.map.get(beatmap_hash) {
-            if let Some(audio) = audio_path {
-                parsed.metadata.resolved_audio_path = audio.clone();
-            }
-            if let Some(bg) = bg_path {
-                parsed.metadata.resolved_background_path = bg.clone();
-            }
+fn build_density_histogram(hit_starts: &[i32], bucket_ms: i32, bucket_count: usize) -> Vec<u32> {
+    let mut buckets = vec![0_u32; bucket_count];
+    for &time in hit_starts {
+        if let Some(slot) = buckets.get_mut((time.max(0) / bucket_ms) as usize) {
+            *slot += 1;
         }
     }
+    buckets
+}
 
-    if has_mapper {
-        let creator = parsed.metadata.creator.to_ascii_lowercase();
-        let version = parsed.metadata.version.to_ascii_lowercase();
-        if !mappers.iter().any(|m| creator.contains(m) || version.contains(m)) {
-            return None;
+/// Density-based strain curve: every object adds 1, decaying to 15% per second, peak kept per bucket.
+fn build_strain_series(hit_starts: &[i32], bucket_ms: i32, bucket_count: usize) -> Vec<f64> {
+    const DECAY_PER_SECOND: f64 = 0.15;
+    let mut series = vec![0.0_f64; bucket_count];
+    let mut strain = 0.0_f64;
+    let mut previous: Option<i32> = None;
+
+    for &time in hit_starts {
+        if let Some(previous) = previous {
+            strain *= DECAY_PER_SECOND.powf(f64::from((time - previous).max(0)) / 1000.0);
+        }
+        strain += 1.0;
+        previous = Some(time);
+        if let Some(slot) = series.get_mut((time.max(0) / bucket_ms) as usize) {
+            *slot = slot.max(strain);
         }
     }
 
-    Some(ScanFilePayload {
-        file_path: file_path.to_string(),
-        stat: FileStatPayload { mtime_ms },
-        beatmap_hash,
-        unchanged: None,
-        metadata: Some(parsed.metadata),
-        hit_starts: Some(parsed.hit_starts),
-        hit_ends: Some(parsed.hit_ends),
-        break_periods: Some(parsed.break_periods),
-        bookmarks: Some(parsed.bookmarks),
-    })
-}
-
-#[derive(Debug, Serialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct ScanBatchEvent {
-    files: Vec<ScanFilePayload>,
-    directory: String,
-    batch_index: usize,
-    total_files: usize,
+    series
 }
 
-#[derive(Debug, Serialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct ScanCompleteEvent {
-    directory: String,
-    total_files: usize,
+fn compute_star_rating(bytes: &[u8], mods: u32) -> Option<f64> {
+    let map = Beatmap::from_bytes(bytes).ok()?;
+    let stars = Difficulty::new().mods(mods).calculate(&map).stars();
+    if stars.is_finite() && stars >= 0.0 {
+        Some(stars)
+    } else {
+        None
+    }
 }
 
-#[derive(Debug, Serialize, Clone)]
+/// Star rating of a map in every mode it can be played in. Only osu!standard maps convert,
+/// so the other modes stay empty for taiko/catch/mania maps.
+#[derive(Debug, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct ScanStatusEvent {
-    directory: String,
-    stage: String,
-    current: usize,
-    total: usize,
+struct ConvertStarRatings {
+    stars: f64,
+    mode: i32,
     #[serde(skip_serializing_if = "Option::is_none")]
-    discovered_files: Option<usize>,
+    osu: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    taiko: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    catch: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mania: Option<f64>,
 }
 
-#[inline]
-fn should_emit_scan_status(current: usize, total: usize) -> bool {
-    current == total || current == 1 || current % 250 == 0
+fn compute_convert_star_ratings(bytes: &[u8]) -> Option<ConvertStarRatings> {
+    use rosu_pp::model::mode::{ConvertStatus, GameMode};
+
+    let map = Beatmap::from_bytes(bytes).ok()?;
+    let rating_in = |mode: GameMode| -> Option<f64> {
+        let mut converted = map.clone();
+        if converted.convert_in_place(mode) == ConvertStatus::Incompatible {
+            return None;
+        }
+        let stars = Difficulty::new().calculate(&converted).stars();
+        (stars.is_finite() && stars >= 0.0).then_some(stars)
+    };
+
+    let mut ratings = ConvertStarRatings {
+        osu: rating_in(GameMode::Osu),
+        taiko: rating_in(GameMode::Taiko),
+        catch: rating_in(GameMode::Catch),
+        mania: rating_in(GameMode::Mania),
+        mode: map.mode as i32,
+        ..ConvertStarRatings::default()
+    };
+    ratings.stars = match map.mode {
+        GameMode::Osu => ratings.osu,
+        GameMode::Taiko => ratings.taiko,
+        GameMode::Catch => ratings.catch,
+        GameMode::Mania => ratings.mania,
+    }?;
+    Some(ratings)
 }
 
-fn emit_scan_status(
-    window: &tauri::Window,
-    dir_path: &str,
-    stage: &str,
-    current: usize,
-    total: usize,
-    discovered_files: Option<usize>,
-) {
-    let _ = window.emit("scan-status", ScanStatusEvent {
-        directory: dir_path.to_string(),
-        stage: stage.to_string(),
-        current,
-        total,
-        discovered_files,
-    });
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct StarRatingOptions {
+    include_converts: bool,
+    /// Mod bitmask; the library only records the no-mod rating.
+    mods: u32,
 }
 
-/// Quick header-only check to see if a file matches the mapper filter.
-/// Returns true if the file should be included (matches mapper or no mapper filter).
-fn file_matches_mapper(file_path: &str, mappers: &[String]) -> bool {
-    let path = Path::new(file_path);
-    if let Ok(file) = fs::File::open(path) {
-        let mut reader = BufReader::with_capacity(8192, file);
-        let mut buf = Vec::with_capacity(8192);
-        let _ = reader.by_ref().take(8192).read_to_end(&mut buf);
-        let header = String::from_utf8_lossy(&buf);
-        let (creator, version) = parse_header_creator_and_version(&header);
-        let creator_lower = creator.to_ascii_lowercase();
-        let version_lower = version.to_ascii_lowercase();
-        return mappers.iter().any(|m| creator_lower.contains(m) || version_lower.contains(m));
-    }
-    false
+/// A plain number unless converts were asked for, so existing callers keep working.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum StarRatingResult {
+    Stars(f64),
+    WithConverts(ConvertStarRatings),
 }
 
-fn scan_directory_streaming(
-    dir_path: &str,
-    mapper_name: Option<String>,
-    known_files: Option<HashMap<String, f64>>,
-    client: OsuClient,
-    window: &tauri::Window,
-) {
-    let root = resolve_scan_root(dir_path, client);
-    if !root.exists() || !root.is_dir() {
-        let _ = window.emit("scan-complete", ScanCompleteEvent {
-            directory: dir_path.to_string(),
-            total_files: 0,
-        });
-        return;
-    }
+fn compare_timing(a: &[TimingPoint], b: &[TimingPoint]) -> TimingAlignment {
+    let red_a: Vec<&TimingPoint> = a.iter().filter(|point| point.uninherited).collect();
+    let red_b: Vec<&TimingPoint> = b.iter().filter(|point| point.uninherited).collect();
+    let same_line = |x: &TimingPoint, y: &TimingPoint| {
+        (x.time - y.time).abs() <= 1.0 && (x.beat_length - y.beat_length).abs() < 1e-6
+    };
 
-    // Phase 1: Discover all .osu files with their mtimes in one WalkDir pass
-    let osu_entries = find_osu_files_with_mtime(&root, client, Some((window, dir_path)));
-    if osu_entries.is_empty() {
-        let _ = window.emit("scan-complete", ScanCompleteEvent {
-            directory: dir_path.to_string(),
-            total_files: 0,
-        });
-        return;
+    let only_in_a: Vec<i32> = red_a
+        .iter()
+        .filter(|x| !red_b.iter().any(|y| same_line(x, y)))
+        .map(|x| x.time.floor() as i32)
+        .collect();
+    let only_in_b: Vec<i32> = red_b
+        .iter()
+        .filter(|y| !red_a.iter().any(|x| same_line(x, y)))
+        .map(|y| y.time.floor() as i32)
+        .collect();
+    let matching_red_lines = red_a.len() - only_in_a.len();
+    let first_offset_delta_ms = red_a
+        .first()
+        .zip(red_b.first())
+        .map(|(x, y)| y.time - x.time);
+    let bpm_matches = red_a.len() == red_b.len()
+        && red_a
+            .iter()
+            .zip(&red_b)
+            .all(|(x, y)| (x.beat_length - y.beat_length).abs() < 1e-6);
+
+    TimingAlignment {
+        matching_red_lines,
+        aligned: only_in_a.is_empty() && only_in_b.is_empty(),
+        only_in_a,
+        only_in_b,
+        first_offset_delta_ms,
+        bpm_matches,
     }
+}
 
-    let known = Arc::new(known_files.unwrap_or_default());
-    let mappers_raw = mapper_name.unwrap_or_default();
-    let mappers: Arc<Vec<String>> = Arc::new(
-        mappers_raw
-            .split(',')
-            .map(|s| s.trim().to_ascii_lowercase())
-            .filter(|s| !s.is_empty())
-            .collect()
-    );
-    let has_mapper = !mappers.is_empty();
-    let lazer_resolver = if client == OsuClient::Lazer {
-        emit_scan_status(window, dir_path, "resolving-media", 0, 0, None);
-        match get_lazer_resolver(dir_path) {
-            Ok(resolver) => resolver,
-            Err(err) => {
-                eprintln!("failed to resolve lazer media from {dir_path}: {err}");
-                None
-            }
-        }
-    } else {
-        None
+fn build_map_comparison(path_a: String, path_b: String, bucket_ms: i32) -> Result<MapComparisonPayload, String> {
+    let bytes_a = fs::read(&path_a).map_err(|err| format!("{path_a}: {err}"))?;
+    let bytes_b = fs::read(&path_b).map_err(|err| format!("{path_b}: {err}"))?;
+    let parsed_a = parse_osu_content(&decode_osu_bytes(&bytes_a).0);
+    let parsed_b = parse_osu_content(&decode_osu_bytes(&bytes_b).0);
+    let bucket_ms = bucket_ms.max(1);
+
+    let stat_values = |parsed: &ParsedOsu, bytes: &[u8]| -> Vec<(&'static str, f64)> {
+        let (circles, sliders, spinners, holds) = count_object_kinds(&parsed.hit_types);
+        let first = parsed.hit_starts.first().copied().unwrap_or(0);
+        let last = parsed.hit_ends.iter().max().copied().unwrap_or(0);
+        let bpm = compute_bpm_summary(&parsed.timing_points, last);
+        vec![
+            ("objects", parsed.hit_starts.len() as f64),
+            ("circles", circles as f64),
+            ("sliders", sliders as f64),
+            ("spinners", spinners as f64),
+            ("holds", holds as f64),
+            ("lengthMs", f64::from((last - first).max(0))),
+            ("drainTimeMs", f64::from(compute_drain_time_ms(parsed))),
+            ("breaks", parsed.break_periods.len() as f64),
+            ("bpm", bpm.dominant),
+            ("bpmMin", bpm.min),
+            ("bpmMax", bpm.max),
+            ("svChanges", parsed.sv_changes.len() as f64),
+            ("starRating", cached_star_rating(bytes, 0).unwrap_or(-1.0)),
+        ]
     };
 
-    // When mapper filter is active, pre-count matching files for accurate progress
-    let total_for_progress = count_matching_entries(
-        &osu_entries,
-        mappers.as_ref(),
-        if has_mapper { Some((window, dir_path)) } else { None },
-    );
-
-    // Shared state for streaming batches
-    let batch_counter = Arc::new(Mutex::new(0_usize));
-    let total_emitted = Arc::new(Mutex::new(0_usize));
-    let total_for_progress_arc = Arc::new(total_for_progress);
+    let stats = stat_values(&parsed_a, &bytes_a)
+        .into_iter()
+        .zip(stat_values(&parsed_b, &bytes_b))
+        .map(|((key, a), (_, b))| CompareStatRow {
+            key: key.to_string(),
+            a,
+            b,
+            delta: b - a,
+        })
+        .collect();
 
-    // Phase 2: Parse files in parallel, emit batches as they complete
-    let parallelism = std::thread::available_parallelism()
-        .map(|count| count.get())
-        .unwrap_or(4);
-    let max_threads = (parallelism.saturating_mul(2)).clamp(4, 32);
-    let worker_count = max_threads.min(osu_entries.len());
-    let chunk_size = osu_entries.len().div_ceil(worker_count);
-    let dir_string = dir_path.to_string();
+    let end = parsed_a
+        .hit_ends
+        .iter()
+        .chain(&parsed_b.hit_ends)
+        .max()
+        .copied()
+        .unwrap_or(0)
+        .max(0);
+    let bucket_count = (end / bucket_ms) as usize + 1;
+
+    Ok(MapComparisonPayload {
+        density_a: build_density_histogram(&parsed_a.hit_starts, bucket_ms, bucket_count),
+        density_b: build_density_histogram(&parsed_b.hit_starts, bucket_ms, bucket_count),
+        strain_a: build_strain_series(&parsed_a.hit_starts, bucket_ms, bucket_count),
+        strain_b: build_strain_series(&parsed_b.hit_starts, bucket_ms, bucket_count),
+        timing: compare_timing(&parsed_a.timing_points, &parsed_b.timing_points),
+        stats,
+        bucket_ms,
+        metadata_a: parsed_a.metadata,
+        metadata_b: parsed_b.metadata,
+        path_a,
+        path_b,
+    })
+}
 
-    std::thread::scope(|scope| {
-        let mut handles = Vec::with_capacity(worker_count);
+const DIFFED_KEY_VALUE_SECTIONS: [&str; 4] = ["General", "Editor", "Metadata", "Difficulty"];
 
-        for chunk in osu_entries.chunks(chunk_size) {
-            let chunk_entries: Vec<_> = chunk.to_vec();
-            let known = Arc::clone(&known);
-            let mappers = Arc::clone(&mappers);
-            let lazer_resolver = lazer_resolver.clone();
-            let batch_counter = Arc::clone(&batch_counter);
-            let total_emitted = Arc::clone(&total_emitted);
-            let total_for_progress = Arc::clone(&total_for_progress_arc);
-            let dir_str = dir_string.clone();
+/// Trimmed, non-comment lines of `[section]`, in file order.
+fn osu_section_lines<'a>(content: &'a str, section: &str) -> Vec<&'a str> {
+    let mut in_section = false;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            in_section = trimmed[1..trimmed.len() - 1].eq_ignore_ascii_case(section);
+            continue;
+        }
+        if in_section && !trimmed.is_empty() && !trimmed.starts_with("//") {
+            lines.push(trimmed);
+        }
+    }
+    lines
+}
 
-            handles.push(scope.spawn(move || {
-                let mut local_batch = Vec::with_capacity(50);
-                for (file_path, mtime_ms) in &chunk_entries {
-                    if let Some(payload) = scan_single_osu_file(
-                        file_path,
-                        *mtime_ms,
-                        &known,
-                        mappers.as_ref(),
-                        lazer_resolver.as_deref(),
-                    ) {
-                        local_batch.push(payload);
-                    }
+fn diff_key_value_sections(content_a: &str, content_b: &str) -> Vec<FieldChange> {
+    let collect = |content: &str, section: &str| -> Vec<(String, String)> {
+        osu_section_lines(content, section)
+            .into_iter()
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect()
+    };
 
-                    // Emit batch every 50 results
-                    if local_batch.len() >= 50 {
-                        let batch_idx = {
-                            let mut c = batch_counter.lock().unwrap();
-                            let idx = *c;
-                            *c += 1;
-                            idx
-                        };
-                        let count = local_batch.len();
-                        let _ = window.emit("scan-batch", ScanBatchEvent {
-                            files: std::mem::replace(&mut local_batch, Vec::with_capacity(50)),
-                            directory: dir_str.clone(),
-                            batch_index: batch_idx,
-                            total_files: *total_for_progress,
-                        });
-                        *total_emitted.lock().unwrap() += count;
-                    }
-                }
+    let mut changes = Vec::new();
+    for section in DIFFED_KEY_VALUE_SECTIONS {
+        let fields_a = collect(content_a, section);
+        let fields_b = collect(content_b, section);
+        let lookup = |fields: &[(String, String)], key: &str| {
+            fields.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.clone())
+        };
 
-                // Emit remaining
-                if !local_batch.is_empty() {
-                    let batch_idx = {
-                        let mut c = batch_counter.lock().unwrap();
-                        let idx = *c;
-                        *c += 1;
-                        idx
-                    };
-                    let count = local_batch.len();
-                    let _ = window.emit("scan-batch", ScanBatchEvent {
-                        files: local_batch,
-                        directory: dir_str.clone(),
-                        batch_index: batch_idx,
-                        total_files: *total_for_progress,
-                    });
-                    *total_emitted.lock().unwrap() += count;
-                }
-            }));
+        for (key, before) in &fields_a {
+            let after = lookup(&fields_b, key);
+            if after.as_ref() != Some(before) {
+                changes.push(FieldChange {
+                    section: section.to_string(),
+                    key: key.clone(),
+                    before: Some(before.clone()),
+                    after,
+                });
+            }
         }
-
-        for handle in handles {
-            let _ = handle.join();
+        for (key, after) in &fields_b {
+            if lookup(&fields_a, key).is_none() {
+                changes.push(FieldChange {
+                    section: section.to_string(),
+                    key: key.clone(),
+                    before: None,
+                    after: Some(after.clone()),
+                });
+            }
         }
-    });
-
-    let final_count = *total_emitted.lock().unwrap();
-    let _ = window.emit("scan-complete", ScanCompleteEvent {
-        directory: dir_path.to_string(),
-        total_files: final_count,
-    });
+    }
+    changes
 }
 
-fn scan_directory_internal(
-    dir_path: &str,
-    mapper_name: Option<String>,
-    known_files: Option<HashMap<String, f64>>,
-    client: OsuClient,
-) -> ScanDirectoryPayload {
-    let root = resolve_scan_root(dir_path, client);
-    if !root.exists() || !root.is_dir() {
-        return ScanDirectoryPayload {
-            files: vec![],
-            directory: dir_path.to_string(),
-        };
+/// Timing points are matched on (time, red/green); anything else that differs is a modification.
+fn diff_timing_points(content_a: &str, content_b: &str) -> Vec<TimingPointChange> {
+    let parse = |content: &str| -> Vec<(f64, bool, String)> {
+        osu_section_lines(content, "TimingPoints")
+            .into_iter()
+            .filter_map(|line| {
+                let time = csv_field(line, 0)?.trim().parse::<f64>().ok()?;
+                let uninherited = csv_field(line, 6).map(str::trim) != Some("0");
+                Some((time, uninherited, line.to_string()))
+            })
+            .collect()
+    };
+    let points_a = parse(content_a);
+    let mut points_b = parse(content_b);
+
+    let mut changes = Vec::new();
+    for (time, uninherited, line_a) in points_a {
+        let matched = points_b
+            .iter()
+            .position(|(t, u, _)| (t - time).abs() < 0.5 && *u == uninherited);
+        match matched.map(|index| points_b.remove(index)) {
+            Some((_, _, line_b)) if line_b == line_a => {}
+            Some((_, _, line_b)) => changes.push(TimingPointChange {
+                kind: DiffChangeKind::Modified,
+                time,
+                before: Some(line_a),
+                after: Some(line_b),
+            }),
+            None => changes.push(TimingPointChange {
+                kind: DiffChangeKind::Removed,
+                time,
+                before: Some(line_a),
+                after: None,
+            }),
+        }
     }
+    changes.extend(points_b.into_iter().map(|(time, _, line)| TimingPointChange {
+        kind: DiffChangeKind::Added,
+        time,
+        before: None,
+        after: Some(line),
+    }));
+    changes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    changes
+}
 
-    let osu_entries = find_osu_files_with_mtime(&root, client, None);
-    if osu_entries.is_empty() {
-        return ScanDirectoryPayload {
-            files: vec![],
-            directory: dir_path.to_string(),
-        };
+fn parse_hit_object_snapshots(content: &str) -> Vec<HitObjectSnapshot> {
+    osu_section_lines(content, "HitObjects")
+        .into_iter()
+        .filter_map(|line| {
+            let x = csv_field(line, 0)?.trim().parse::<f64>().ok()?;
+            let y = csv_field(line, 1)?.trim().parse::<f64>().ok()?;
+            let time = csv_field(line, 2)?.trim().parse::<i32>().ok()?;
+            let object_type = csv_field(line, 3)?.trim().parse::<i32>().ok()?;
+            Some(HitObjectSnapshot { time, x, y, object_type, line: line.to_string() })
+        })
+        .collect()
+}
+
+/// Everything about an object except its time and combo flags, used to recognise a retimed object.
+fn hit_object_retime_signature(object: &HitObjectSnapshot) -> String {
+    const COMBO_BITS: i32 = 4 | 0x70;
+    let rest = object.line.splitn(5, ',').nth(4).unwrap_or("");
+    format!("{},{},{},{}", object.x, object.y, object.object_type & !COMBO_BITS, rest)
+}
+
+/// Pair objects in three passes: identical lines, then same time (moved or edited in place),
+/// then same shape at a different time (retimed). Whatever is left was added or removed.
+fn diff_hit_objects(content_a: &str, content_b: &str) -> Vec<HitObjectChange> {
+    let mut remaining_a = parse_hit_object_snapshots(content_a);
+    let mut remaining_b = parse_hit_object_snapshots(content_b);
+    let mut changes = Vec::new();
+
+    let mut unchanged_b: HashMap<&str, usize> = HashMap::new();
+    for object in &remaining_b {
+        *unchanged_b.entry(object.line.as_str()).or_insert(0) += 1;
+    }
+    let mut unchanged_lines: HashMap<String, usize> = HashMap::new();
+    for object in &remaining_a {
+        if let Some(count) = unchanged_b.get_mut(object.line.as_str()) {
+            if *count > 0 {
+                *count -= 1;
+                *unchanged_lines.entry(object.line.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+    let take_unchanged = |counts: &mut HashMap<String, usize>, object: &HitObjectSnapshot| {
+        match counts.get_mut(&object.line) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        }
+    };
+    let mut counts_a = unchanged_lines.clone();
+    remaining_a.retain(|object| take_unchanged(&mut counts_a, object));
+    let mut counts_b = unchanged_lines;
+    remaining_b.retain(|object| take_unchanged(&mut counts_b, object));
+
+    let mut leftover_a = Vec::new();
+    for before in remaining_a {
+        match remaining_b.iter().position(|after| after.time == before.time) {
+            Some(index) => {
+                let after = remaining_b.remove(index);
+                let kind = if after.x != before.x || after.y != before.y {
+                    DiffChangeKind::Moved
+                } else {
+                    DiffChangeKind::Modified
+                };
+                changes.push(HitObjectChange { kind, before: Some(before), after: Some(after) });
+            }
+            None => leftover_a.push(before),
+        }
     }
 
-    let known = Arc::new(known_files.unwrap_or_default());
-    let mappers_raw = mapper_name.unwrap_or_default();
-    let mappers: Arc<Vec<String>> = Arc::new(
-        mappers_raw
-            .split(',')
-            .map(|s| s.trim().to_ascii_lowercase())
-            .filter(|s| !s.is_empty())
-            .collect()
-    );
-    let lazer_resolver = if client == OsuClient::Lazer {
-        match get_lazer_resolver(dir_path) {
-            Ok(resolver) => resolver,
-            Err(err) => {
-                eprintln!("failed to resolve lazer media from {dir_path}: {err}");
-                None
+    for before in leftover_a {
+        let signature = hit_object_retime_signature(&before);
+        let nearest = remaining_b
+            .iter()
+            .enumerate()
+            .filter(|(_, after)| hit_object_retime_signature(after) == signature)
+            .min_by_key(|(_, after)| (after.time - before.time).abs())
+            .map(|(index, _)| index);
+        match nearest {
+            Some(index) => {
+                let after = remaining_b.remove(index);
+                changes.push(HitObjectChange {
+                    kind: DiffChangeKind::Retimed,
+                    before: Some(before),
+                    after: Some(after),
+                });
             }
+            None => changes.push(HitObjectChange {
+                kind: DiffChangeKind::Removed,
+                before: Some(before),
+                after: None,
+            }),
         }
-    } else {
-        None
+    }
+    changes.extend(remaining_b.into_iter().map(|after| HitObjectChange {
+        kind: DiffChangeKind::Added,
+        before: None,
+        after: Some(after),
+    }));
+
+    let change_time = |change: &HitObjectChange| {
+        change.before.as_ref().or(change.after.as_ref()).map_or(0, |object| object.time)
     };
-    let total_for_progress = count_matching_entries(&osu_entries, mappers.as_ref(), None);
+    changes.sort_by_key(change_time);
+    changes
+}
 
-    let parallelism = std::thread::available_parallelism()
-        .map(|count| count.get())
-        .unwrap_or(4);
-    let max_threads = (parallelism.saturating_mul(2)).clamp(4, 32);
-    let worker_count = max_threads.min(osu_entries.len());
-    let chunk_size = osu_entries.len().div_ceil(worker_count);
+fn build_beatmap_diff(path_a: String, path_b: String) -> Result<BeatmapDiffPayload, String> {
+    let bytes_a = fs::read(&path_a).map_err(|err| err.to_string())?;
+    let bytes_b = fs::read(&path_b).map_err(|err| err.to_string())?;
+    let (content_a, _) = decode_osu_bytes(&bytes_a);
+    let (content_b, _) = decode_osu_bytes(&bytes_b);
+
+    let metadata_changes = diff_key_value_sections(&content_a, &content_b);
+    let timing_changes = diff_timing_points(&content_a, &content_b);
+    let hit_object_changes = diff_hit_objects(&content_a, &content_b);
+
+    let count = |kind: DiffChangeKind| hit_object_changes.iter().filter(|change| change.kind == kind).count();
+    let summary = BeatmapDiffSummary {
+        fields_changed: metadata_changes.len(),
+        timing_points_changed: timing_changes.len(),
+        objects_added: count(DiffChangeKind::Added),
+        objects_removed: count(DiffChangeKind::Removed),
+        objects_moved: count(DiffChangeKind::Moved),
+        objects_retimed: count(DiffChangeKind::Retimed),
+        objects_modified: count(DiffChangeKind::Modified),
+    };
 
-    let mut files: Vec<ScanFilePayload> = Vec::with_capacity(total_for_progress);
+    Ok(BeatmapDiffPayload {
+        path_a,
+        path_b,
+        metadata_changes,
+        timing_changes,
+        hit_object_changes,
+        summary,
+    })
+}
 
-    std::thread::scope(|scope| {
-        let mut handles = Vec::with_capacity(worker_count);
+fn read_audio_bitrate_kbps(path: &Path) -> Option<u32> {
+    use lofty::prelude::*;
 
-        for chunk in osu_entries.chunks(chunk_size) {
-            let chunk_entries: Vec<_> = chunk.to_vec();
-            let known = Arc::clone(&known);
-            let mappers = Arc::clone(&mappers);
-            let lazer_resolver = lazer_resolver.clone();
+    let tagged_file = read_tagged_audio(path, None)?;
+    let properties = tagged_file.properties();
+    properties.audio_bitrate().or_else(|| properties.overall_bitrate())
+}
 
-            handles.push(scope.spawn(move || {
-                let mut out = Vec::with_capacity(chunk_entries.len());
-                for (file_path, mtime_ms) in &chunk_entries {
-                    if let Some(payload) = scan_single_osu_file(
-                        file_path,
-                        *mtime_ms,
-                        &known,
-                        mappers.as_ref(),
-                        lazer_resolver.as_deref(),
-                    ) {
-                        out.push(payload);
-                    }
-                }
-                out
-            }));
-        }
+struct LintContext<'a> {
+    osu_path: &'a Path,
+    parsed: &'a ParsedOsu,
+    options: &'a LintOptions,
+    /// Loaded on first use by the set-wide rules.
+    set_difficulties: std::cell::OnceCell<Arc<Vec<SpreadDifficulty>>>,
+}
 
-        for handle in handles {
-            if let Ok(mut partial) = handle.join() {
-                files.append(&mut partial);
-            }
+impl LintContext<'_> {
+    fn asset_path(&self, relative: &str) -> Option<PathBuf> {
+        if relative.is_empty() {
+            return None;
         }
-    });
+        Some(self.osu_path.parent()?.join(relative))
+    }
 
-    files.sort_unstable_by(|a, b| a.file_path.cmp(&b.file_path));
+    /// Difficulties of the linted map's set in its own mode, easiest first.
+    fn set_difficulties(&self) -> Vec<&SpreadDifficulty> {
+        let difficulties = self.set_difficulties.get_or_init(|| {
+            self.osu_path.parent().and_then(|folder| cached_spread_difficulties(folder).ok()).unwrap_or_default()
+        });
+        difficulties.iter().filter(|difficulty| difficulty.mode == self.parsed.metadata.mode).collect()
+    }
+}
 
-    ScanDirectoryPayload {
-        files,
-        directory: dir_path.to_string(),
+type LintRule = fn(&LintContext) -> Vec<LintIssue>;
+
+/// Registered lint rules, keyed by the id used in `LintOptions::disabled_rules`.
+const LINT_RULES: [(&str, LintRule); 13] = [
+    ("unsnapped-object", lint_unsnapped_objects),
+    ("short-spinner", lint_short_spinners),
+    ("unsnapped-kiai", lint_unsnapped_kiai),
+    ("audio-bitrate", lint_audio_bitrate),
+    ("background-resolution", lint_background_resolution),
+    ("missing-preview-point", lint_missing_preview_point),
+    ("duplicate-red-lines", lint_duplicate_red_lines),
+    ("break-timing", lint_break_timing),
+    ("long-drain", lint_long_drain),
+    ("rc-general-min-drain", lint_min_drain),
+    ("rc-spread-lowest-difficulty", lint_lowest_difficulty),
+    ("rc-spread-drain-share", lint_drain_share),
+    ("widescreen-storyboard", lint_widescreen_storyboard),
+];
+
+fn lint_issue(rule: &str, severity: LintSeverity, message: String, time: Option<i32>) -> LintIssue {
+    LintIssue {
+        rule: rule.to_string(),
+        severity,
+        message,
+        time,
     }
 }
 
-fn resolve_app_version(app_handle: &tauri::AppHandle) -> String {
-    static PACKAGE_VERSION: OnceLock<Option<String>> = OnceLock::new();
-    let pkg_version = PACKAGE_VERSION.get_or_init(|| {
-        // package.json lives at the repo root (two levels up from src-tauri/src)
-        serde_json::from_str::<Value>(include_str!("../../package.json")).ok().and_then(|json| {
-            json.get("version").and_then(Value::as_str).map(|s| s.to_string())
+fn lint_unsnapped_objects(ctx: &LintContext) -> Vec<LintIssue> {
+    ctx.parsed
+        .hit_starts
+        .iter()
+        .filter_map(|&time| {
+            let red_line = active_uninherited_point(&ctx.parsed.timing_points, f64::from(time))?;
+            let snap = classify_snap(time, red_line);
+            snap.divisor.is_none().then(|| {
+                lint_issue(
+                    "unsnapped-object",
+                    LintSeverity::Error,
+                    format!("Object is unsnapped by {:.1} ms (nearest 1/{})", snap.offset_ms, snap.nearest_divisor),
+                    Some(time),
+                )
+            })
         })
-    });
-    pkg_version
-        .clone()
-        .unwrap_or_else(|| app_handle.package_info().version.to_string())
+        .collect()
 }
 
-#[tauri::command]
-fn get_app_version(app_handle: tauri::AppHandle) -> String {
-    resolve_app_version(&app_handle)
+fn lint_short_spinners(ctx: &LintContext) -> Vec<LintIssue> {
+    let parsed = ctx.parsed;
+    parsed
+        .hit_types
+        .iter()
+        .enumerate()
+        .filter(|(_, obj_type)| *obj_type & 8 != 0)
+        .filter_map(|(index, _)| {
+            let start = *parsed.hit_starts.get(index)?;
+            let length = parsed.hit_ends.get(index)? - start;
+            (length < ctx.options.min_spinner_length_ms).then(|| {
+                lint_issue(
+                    "short-spinner",
+                    LintSeverity::Warning,
+                    format!("Spinner is only {length} ms long"),
+                    Some(start),
+                )
+            })
+        })
+        .collect()
 }
 
-#[tauri::command]
-fn open_external_url(url: String) -> Result<(), String> {
-    if url.starts_with("https://") || url.starts_with("http://") {
-        open::that(url).map_err(|err| err.to_string())
-    } else {
-        Ok(())
+fn lint_unsnapped_kiai(ctx: &LintContext) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut kiai_active = false;
+
+    for point in &ctx.parsed.timing_points {
+        if point.kiai == kiai_active {
+            continue;
+        }
+        kiai_active = point.kiai;
+        let time = point.time.round() as i32;
+        let Some(red_line) = active_uninherited_point(&ctx.parsed.timing_points, point.time) else {
+            continue;
+        };
+        let snap = classify_snap(time, red_line);
+        if snap.divisor.is_none() {
+            issues.push(lint_issue(
+                "unsnapped-kiai",
+                LintSeverity::Warning,
+                format!(
+                    "Kiai {} is unsnapped by {:.1} ms",
+                    if kiai_active { "start" } else { "end" },
+                    snap.offset_ms
+                ),
+                Some(time),
+            ));
+        }
     }
+
+    issues
 }
 
-#[tauri::command]
-fn open_in_text_editor(file_path: String) -> Result<(), String> {
-    let path = PathBuf::from(&file_path);
-    if !path.exists() {
-        return Err("Beatmap file not found".to_string());
+fn lint_audio_bitrate(ctx: &LintContext) -> Vec<LintIssue> {
+    let Some(audio_path) = ctx.asset_path(&ctx.parsed.metadata.audio) else {
+        return Vec::new();
+    };
+    match read_audio_bitrate_kbps(&audio_path) {
+        Some(bitrate) if bitrate > ctx.options.max_audio_bitrate_kbps => vec![lint_issue(
+            "audio-bitrate",
+            LintSeverity::Error,
+            format!(
+                "Audio bitrate is {bitrate} kbps (max {} kbps)",
+                ctx.options.max_audio_bitrate_kbps
+            ),
+            None,
+        )],
+        Some(_) => Vec::new(),
+        None => vec![lint_issue(
+            "audio-bitrate",
+            LintSeverity::Info,
+            "Could not read the audio file's bitrate".to_string(),
+            None,
+        )],
     }
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        Command::new("notepad")
-            .arg(&path)
-            .spawn()
-            .map_err(|err| err.to_string())?;
-        return Ok(());
+fn lint_background_resolution(ctx: &LintContext) -> Vec<LintIssue> {
+    let Some(background_path) = ctx.asset_path(&ctx.parsed.metadata.background) else {
+        return Vec::new();
+    };
+    let Some(BackgroundImageInfo {
+        width,
+        height,
+        size_bytes,
+        flags,
+    }) = read_background_info(&background_path)
+    else {
+        return Vec::new();
+    };
+    let mut issues = Vec::new();
+    if width > ctx.options.max_background_width || height > ctx.options.max_background_height {
+        issues.push(lint_issue(
+            "background-resolution",
+            LintSeverity::Warning,
+            format!(
+                "Background is {width}x{height} (max {}x{})",
+                ctx.options.max_background_width, ctx.options.max_background_height
+            ),
+            None,
+        ));
+    } else if flags.iter().any(|flag| flag == "too-small") {
+        issues.push(lint_issue(
+            "background-resolution",
+            LintSeverity::Info,
+            format!(
+                "Background is {width}x{height}; consider at least {RANKED_BACKGROUND_MIN_WIDTH}x{RANKED_BACKGROUND_MIN_HEIGHT}"
+            ),
+            None,
+        ));
     }
-
-    #[cfg(not(target_os = "windows"))]
-    {
-        open::that(path).map_err(|err| err.to_string())
+    if flags.iter().any(|flag| flag == "file-too-large") {
+        issues.push(lint_issue(
+            "background-resolution",
+            LintSeverity::Warning,
+            format!("Background is {:.1} MB (max 2.5 MB)", size_bytes as f64 / 1_000_000.0),
+            None,
+        ));
     }
+    issues
 }
 
-#[tauri::command]
-async fn check_for_updates(app_handle: tauri::AppHandle) -> UpdateInfoPayload {
-    let current_version = resolve_app_version(&app_handle);
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(15))
-        .build();
+fn lint_missing_preview_point(ctx: &LintContext) -> Vec<LintIssue> {
+    if ctx.parsed.metadata.preview_time >= 0 {
+        return Vec::new();
+    }
+    vec![lint_issue(
+        "missing-preview-point",
+        LintSeverity::Warning,
+        "No preview point is set".to_string(),
+        None,
+    )]
+}
 
-    let client = match client {
-        Ok(client) => client,
-        Err(err) => {
-            return UpdateInfoPayload {
-                current_version,
-                latest_version: String::new(),
-                html_url: String::new(),
-                error: Some(err.to_string()),
-            }
+fn lint_duplicate_red_lines(ctx: &LintContext) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut previous_tick: Option<i32> = None;
+
+    for point in ctx.parsed.timing_points.iter().filter(|point| point.uninherited) {
+        let tick = point.time.floor() as i32;
+        if previous_tick == Some(tick) {
+            issues.push(lint_issue(
+                "duplicate-red-lines",
+                LintSeverity::Error,
+                "Multiple uninherited timing points on the same tick".to_string(),
+                Some(tick),
+            ));
         }
-    };
+        previous_tick = Some(tick);
+    }
 
-    let response = client
-        .get("https://api.github.com/repos/fax1015/mosu/releases/latest")
-        .header("User-Agent", "mosu-app")
-        .send()
-        .await;
+    issues
+}
 
-    match response {
-        Ok(resp) => {
-            if !resp.status().is_success() {
-                return UpdateInfoPayload {
-                    current_version,
-                    latest_version: String::new(),
-                    html_url: String::new(),
-                    error: Some("Failed to fetch latest release".to_string()),
-                };
-            }
+/// Object (start, end) spans in time order.
+fn object_spans(parsed: &ParsedOsu) -> Vec<(i32, i32)> {
+    let mut spans: Vec<(i32, i32)> = parsed
+        .hit_starts
+        .iter()
+        .zip(&parsed.hit_ends)
+        .map(|(&start, &end)| (start, end.max(start)))
+        .collect();
+    spans.sort_unstable();
+    spans
+}
 
-            let json: Value = match resp.json().await {
-                Ok(value) => value,
-                Err(err) => {
-                    return UpdateInfoPayload {
-                        current_version,
-                        latest_version: String::new(),
-                        html_url: String::new(),
-                        error: Some(err.to_string()),
-                    }
-                }
-            };
+fn lint_break_timing(ctx: &LintContext) -> Vec<LintIssue> {
+    let spans = object_spans(ctx.parsed);
+    let preempt = approach_rate_to_ms(ctx.parsed.metadata.difficulty.ar).round() as i32;
+    let mut issues = Vec::new();
+
+    for period in &ctx.parsed.break_periods {
+        let length = period.end - period.start;
+        if length < ctx.options.min_break_length_ms {
+            issues.push(lint_issue(
+                "break-timing",
+                LintSeverity::Error,
+                format!("Break is only {length} ms long (minimum {} ms)", ctx.options.min_break_length_ms),
+                Some(period.start),
+            ));
+        }
+        let previous_end = spans.iter().filter(|(start, _)| *start <= period.start).map(|(_, end)| *end).max();
+        if let Some(gap) = previous_end.map(|end| period.start - end).filter(|gap| *gap < BREAK_START_MIN_GAP_MS) {
+            issues.push(lint_issue(
+                "break-timing",
+                LintSeverity::Warning,
+                format!("Break starts {gap} ms after the previous object (needs {BREAK_START_MIN_GAP_MS} ms)"),
+                Some(period.start),
+            ));
+        }
+        let next_start = spans.iter().map(|(start, _)| *start).find(|start| *start >= period.end);
+        if let Some(gap) = next_start.map(|start| start - period.end).filter(|gap| *gap < preempt) {
+            issues.push(lint_issue(
+                "break-timing",
+                LintSeverity::Warning,
+                format!("Break ends {gap} ms before the next object, which fades in during the break (approach time {preempt} ms)"),
+                Some(period.end),
+            ));
+        }
+    }
 
-            let latest_version = json
-                .get("tag_name")
-                .and_then(Value::as_str)
-                .unwrap_or_default()
-                .trim_start_matches('v')
-                .to_string();
+    issues
+}
 
-            let html_url = json
-                .get("html_url")
-                .and_then(Value::as_str)
-                .unwrap_or_default()
-                .to_string();
+fn lint_long_drain(ctx: &LintContext) -> Vec<LintIssue> {
+    let spans = object_spans(ctx.parsed);
+    let (Some(first), Some(last)) = (spans.first(), spans.iter().map(|(_, end)| *end).max()) else {
+        return Vec::new();
+    };
 
-            UpdateInfoPayload {
-                current_version,
-                latest_version,
-                html_url,
-                error: None,
-            }
-        }
-        Err(err) => UpdateInfoPayload {
-            current_version,
-            latest_version: String::new(),
-            html_url: String::new(),
-            error: Some(err.to_string()),
-        },
+    // Sections of continuous play are the stretches between breaks.
+    let mut breaks = ctx.parsed.break_periods.clone();
+    breaks.sort_by_key(|period| period.start);
+    let mut section_start = first.0;
+    let mut sections = Vec::new();
+    for period in &breaks {
+        sections.push((section_start, period.start));
+        section_start = period.end;
     }
+    sections.push((section_start, last));
+
+    sections
+        .into_iter()
+        .filter(|(start, end)| end - start > ctx.options.max_drain_without_break_ms)
+        .map(|(start, end)| {
+            lint_issue(
+                "long-drain",
+                LintSeverity::Warning,
+                format!("{:.0} s of continuous drain without a break", f64::from(end - start) / 1000.0),
+                Some(start),
+            )
+        })
+        .collect()
 }
 
-#[tauri::command]
-fn read_image_file(file_path: String) -> Option<String> {
-    let path = PathBuf::from(file_path);
-    let bytes = fs::read(&path).ok()?;
-    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
-    Some(format!("data:{};base64,{}", get_mime_type(&path), encoded))
+/// Ranking Criteria (General): every difficulty needs at least this much drain time.
+const RC_MIN_DRAIN_MS: i32 = 30_000;
+
+fn lint_min_drain(ctx: &LintContext) -> Vec<LintIssue> {
+    let drain = compute_drain_time_ms(ctx.parsed);
+    if ctx.parsed.hit_starts.is_empty() || drain >= RC_MIN_DRAIN_MS {
+        return Vec::new();
+    }
+    vec![lint_issue(
+        "rc-general-min-drain",
+        LintSeverity::Error,
+        format!(
+            "Drain time is {}, below the {} s minimum (RC: General)",
+            format_duration_ms(drain),
+            RC_MIN_DRAIN_MS / 1000
+        ),
+        None,
+    )]
 }
 
-#[tauri::command]
-fn read_binary_file(file_path: String) -> Option<Vec<u8>> {
-    fs::read(file_path).ok()
+fn lint_lowest_difficulty(ctx: &LintContext) -> Vec<LintIssue> {
+    spread_lowest_difficulty_issue("rc-spread-lowest-difficulty", &ctx.set_difficulties()).into_iter().collect()
 }
 
-#[tauri::command]
-fn read_audio_file(file_path: String, file_name_hint: Option<String>) -> Option<String> {
-    let bytes = fs::read(&file_path).ok()?;
-    let mime = sniff_audio_mime_type(&bytes)
-        .or_else(|| audio_mime_type_from_hint(file_name_hint.as_deref()))
-        .unwrap_or("application/octet-stream");
-    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
-    Some(format!("data:{};base64,{}", mime, encoded))
+fn lint_drain_share(ctx: &LintContext) -> Vec<LintIssue> {
+    let difficulties = ctx.set_difficulties();
+    let Some(longest) = difficulties.iter().max_by_key(|difficulty| difficulty.drain_time_ms) else {
+        return Vec::new();
+    };
+    let version = &ctx.parsed.metadata.version;
+    let drain = compute_drain_time_ms(ctx.parsed);
+    spread_drain_share_issue("rc-spread-drain-share", version, drain, longest).into_iter().collect()
 }
 
-#[tauri::command]
-fn read_osu_file(file_path: String) -> Option<OsuFilePayload> {
-    let content = fs::read_to_string(&file_path).ok()?;
-    let mtime_ms = get_mtime_ms(Path::new(&file_path)).ok()?;
-    Some(OsuFilePayload {
-        file_path,
-        content,
-        stat: FileStatPayload { mtime_ms },
-        beatmap_hash: None,
-    })
+fn lint_widescreen_storyboard(ctx: &LintContext) -> Vec<LintIssue> {
+    if ctx.parsed.metadata.general.widescreen_storyboard {
+        return Vec::new();
+    }
+    let has_osb = ctx
+        .osu_path
+        .parent()
+        .and_then(|folder| fs::read_dir(folder).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .any(|entry| {
+            entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("osb"))
+        });
+    if ctx.parsed.storyboard_objects == 0 && !has_osb {
+        return Vec::new();
+    }
+    vec![lint_issue(
+        "widescreen-storyboard",
+        LintSeverity::Warning,
+        "The map has a storyboard but WidescreenStoryboard is off, so it renders 4:3".to_string(),
+        None,
+    )]
 }
 
-#[tauri::command]
-fn get_audio_duration(file_path: String, file_name_hint: Option<String>) -> Option<f64> {
-    use lofty::probe::Probe;
-    use lofty::prelude::*;
-    use std::fs::File;
-    use std::io::BufReader;
+fn run_lint_rules(osu_path: &Path, parsed: &ParsedOsu, options: &LintOptions) -> Vec<LintIssue> {
+    let ctx = LintContext {
+        osu_path,
+        parsed,
+        options,
+        set_difficulties: std::cell::OnceCell::new(),
+    };
+    let mut issues: Vec<LintIssue> = LINT_RULES
+        .iter()
+        .filter(|(id, _)| !options.disabled_rules.iter().any(|disabled| disabled == id))
+        .flat_map(|(_, rule)| rule(&ctx))
+        .collect();
+    issues.sort_by_key(|issue| issue.time.unwrap_or(i32::MIN));
+    issues
+}
 
-    let path = Path::new(&file_path);
-    let hinted_type = file_name_hint
-        .as_deref()
-        .and_then(|name| Path::new(name).extension())
-        .and_then(|ext| ext.to_str())
-        .and_then(FileType::from_ext);
+fn parse_header_mapper_fields(content: &str) -> (String, String, String) {
+    let mut in_metadata = false;
+    let mut creator = String::new();
+    let mut version = String::new();
+    let mut tags = String::new();
 
-    let tagged_file = if let Ok(probe) = Probe::open(path) {
-        match probe.read() {
-            Ok(tagged_file) => tagged_file,
-            Err(_) => {
-                let file = File::open(path).ok()?;
-                let reader = BufReader::new(file);
-                if let Some(file_type) = hinted_type {
-                    Probe::with_file_type(reader, file_type).read().ok()?
-                } else {
-                    Probe::new(reader).guess_file_type().ok()?.read().ok()?
-                }
-            }
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
         }
-    } else {
-        let file = File::open(path).ok()?;
-        let reader = BufReader::new(file);
-        if let Some(file_type) = hinted_type {
-            Probe::with_file_type(reader, file_type).read().ok()?
-        } else {
-            Probe::new(reader).guess_file_type().ok()?.read().ok()?
+        let bytes = trimmed.as_bytes();
+        if bytes.len() >= 2 && bytes[0] == b'/' && bytes[1] == b'/' {
+            continue;
         }
-    };
-    let duration = tagged_file.properties().duration();
-    Some(duration.as_millis() as f64)
+        if bytes[0] == b'[' && bytes[bytes.len() - 1] == b']' {
+            let header = &trimmed[1..trimmed.len() - 1];
+            in_metadata = eq_ascii_ci(header, "Metadata");
+            // If we already found both values and left metadata, stop
+            if !in_metadata && !creator.is_empty() && !version.is_empty() {
+                break;
+            }
+            continue;
+        }
+        if in_metadata {
+            if let Some((key, value)) = trimmed.split_once(':') {
+                let key = key.trim();
+                let value = value.trim();
+                if eq_ascii_ci(key, "Creator") {
+                    creator = value.to_string();
+                } else if eq_ascii_ci(key, "Version") {
+                    version = value.to_string();
+                } else if eq_ascii_ci(key, "Tags") {
+                    tags = value.to_string();
+                }
+                if !creator.is_empty() && !version.is_empty() && !tags.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+
+    (creator, version, tags)
 }
 
-#[tauri::command]
-async fn calculate_star_rating(file_path: String) -> Option<f64> {
-    tauri::async_runtime::spawn_blocking(move || {
-        let bytes = fs::read(file_path).ok()?;
-        let map = Beatmap::from_bytes(&bytes).ok()?;
-        let stars = Difficulty::new().calculate(&map).stars();
-        if stars.is_finite() && stars >= 0.0 {
-            Some(stars)
-        } else {
-            None
-        }
-    })
-    .await
-    .ok()
-    .flatten()
+/// Words that make up difficulty names, so "Hard" in "Kroytz Hard" is never mistaken for a mapper.
+const DIFFICULTY_NAME_WORDS: [&str; 24] = [
+    "easy", "normal", "hard", "insane", "expert", "extra", "extreme", "beginner", "basic", "advanced",
+    "hyper", "another", "light", "collab", "kantan", "futsuu", "muzukashii", "oni", "inner", "cup", "salad",
+    "platter", "rain", "overdose",
+];
+
+fn is_difficulty_name_word(word: &str) -> bool {
+    DIFFICULTY_NAME_WORDS.iter().any(|known| known.eq_ignore_ascii_case(word))
 }
 
-#[tauri::command]
-fn stat_file(file_path: String) -> Option<FileStatPayload> {
-    let mtime_ms = get_mtime_ms(Path::new(&file_path)).ok()?;
-    Some(FileStatPayload { mtime_ms })
+/// Owner of a possessive difficulty name: "Doyak's Insane", "Charles' Extra", "[Name]'s Hard".
+fn possessive_version_owner(version: &str) -> Option<&str> {
+    for (index, apostrophe) in version.match_indices(['\'', '\u{2019}']) {
+        let owner = version[..index].trim().trim_matches(['[', ']', '(', ')']).trim();
+        let rest = &version[index + apostrophe.len()..];
+        let ends_word = |tail: &str| tail.is_empty() || tail.starts_with(char::is_whitespace);
+        let is_possessive = match rest.strip_prefix(['s', 'S']) {
+            Some(tail) => ends_word(tail),
+            None => ends_word(rest) && owner.ends_with(['s', 'S']),
+        };
+        if is_possessive && !owner.is_empty() {
+            return Some(owner);
+        }
+    }
+    None
 }
 
-#[tauri::command]
-fn prepare_lazer_map_session(file_path: String, data_root: String) -> Result<LazerPreparedSession, String> {
-    let data_root_path = resolve_lazer_data_root(&data_root)
-        .ok_or_else(|| "osu!lazer data folder not found".to_string())?;
-    let beatmap_hash = beatmap_hash_from_lazer_path(&file_path)
-        .ok_or_else(|| "failed to derive lazer beatmap hash".to_string())?;
-    let manifest = get_lazer_manifest(&data_root_path, &beatmap_hash)?;
+/// Who mapped a difficulty when it isn't the set's creator. Possessive difficulty names win;
+/// otherwise a word of the difficulty name that is also listed in Tags ("Kroytz Extra" with
+/// "kroytz" tagged) is taken as the guest.
+fn detect_guest_mapper(creator: &str, version: &str, tags: &str) -> Option<String> {
+    let is_creator = |name: &str| name.eq_ignore_ascii_case(creator.trim());
 
-    if !manifest.h.eq_ignore_ascii_case(&beatmap_hash) {
-        return Err("realm-resolver returned a mismatched beatmap manifest".to_string());
+    if let Some(owner) = possessive_version_owner(version) {
+        return (!is_creator(owner)).then(|| owner.to_string());
     }
 
-    if manifest.f.is_empty() {
-        return Err("no files available to unpack for this beatmap set".to_string());
-    }
+    version
+        .split(|ch: char| ch.is_whitespace() || matches!(ch, '(' | ')' | '[' | ']'))
+        .filter(|word| word.chars().count() >= 3 && !is_difficulty_name_word(word) && !is_creator(word))
+        .find(|word| tags.split_whitespace().any(|tag| tag.eq_ignore_ascii_case(word)))
+        .map(str::to_string)
+}
 
-    let session_dir = create_lazer_session_dir(&beatmap_hash)?;
-    let unpacked_dir = session_dir.join("unpacked");
-    fs::create_dir_all(&unpacked_dir).map_err(|err| err.to_string())?;
+/// osu! API v1 `language_id` names, indexed by id; 0 and 1 are "any" and "unspecified".
+const OSU_LANGUAGES: [&str; 15] = [
+    "", "", "english", "japanese", "chinese", "instrumental", "korean", "french", "german", "swedish", "spanish",
+    "italian", "russian", "polish", "other",
+];
 
-    let mut files = Vec::with_capacity(manifest.f.len());
-    let target_osu_name = manifest.o.as_deref().map(|name| name.replace('\\', "/").to_ascii_lowercase());
-    let mut unpacked_osu_relative_path: Option<String> = None;
+/// osu! API v1 `genre_id` names, indexed by id; 0, 1 and 8 have no genre.
+const OSU_GENRES: [&str; 15] = [
+    "", "", "video game", "anime", "rock", "pop", "other", "novelty", "", "hip hop", "electronic", "metal",
+    "classical", "folk", "jazz",
+];
 
-    for entry in manifest.f {
-        let Some(relative_path) = normalize_relative_session_path(&entry.n) else {
-            continue;
-        };
+/// Scripts that give away a title's language, checked in order: kana means Japanese even
+/// next to kanji, so Han characters only count as Chinese without it.
+const SCRIPT_LANGUAGES: [(&str, &[(u32, u32)]); 4] = [
+    ("japanese", &[(0x3040, 0x30FF), (0x31F0, 0x31FF), (0xFF66, 0xFF9F)]),
+    ("korean", &[(0x1100, 0x11FF), (0x3130, 0x318F), (0xAC00, 0xD7AF)]),
+    ("chinese", &[(0x3400, 0x4DBF), (0x4E00, 0x9FFF)]),
+    ("russian", &[(0x0400, 0x04FF)]),
+];
 
-        let destination = unpacked_dir.join(&relative_path);
-        if let Some(parent) = destination.parent() {
-            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+/// Words in Source/Tags that point to a genre. Multi-word keywords match consecutive tags.
+const GENRE_KEYWORDS: [(&str, &[&str]); 11] = [
+    ("video game", &["game", "video game", "vgm", "touhou", "arcaea", "beatmania", "maimai", "sound voltex"]),
+    ("anime", &["anime", "tv size", "opening", "ending", "op", "ed"]),
+    ("rock", &["rock", "j-rock", "punk", "alternative", "emo"]),
+    ("pop", &["pop", "j-pop", "k-pop", "c-pop", "idol"]),
+    ("novelty", &["meme", "novelty", "parody"]),
+    ("hip hop", &["hip hop", "hip-hop", "rap", "trap"]),
+    (
+        "electronic",
+        &[
+            "electronic", "edm", "dubstep", "drum and bass", "dnb", "trance", "house", "techno", "hardcore",
+            "hardstyle", "future bass", "electro", "breakcore", "speedcore",
+        ],
+    ),
+    ("metal", &["metal", "metalcore", "deathcore", "djent"]),
+    ("classical", &["classical", "orchestral", "orchestra", "piano", "symphony"]),
+    ("folk", &["folk"]),
+    ("jazz", &["jazz", "swing", "fusion"]),
+];
+
+/// Probable language of a map. A language named in Tags wins; otherwise the script of the
+/// unicode artist/title decides. Latin-script maps stay unknown.
+fn guess_language(metadata: &ParsedMetadata) -> &'static str {
+    let tagged = OSU_LANGUAGES[2..14]
+        .iter()
+        .find(|language| metadata.tags.split_whitespace().any(|tag| tag.eq_ignore_ascii_case(language)));
+    if let Some(language) = tagged {
+        return language;
+    }
+
+    let text = format!("{} {}", metadata.artist_unicode, metadata.title_unicode);
+    SCRIPT_LANGUAGES
+        .iter()
+        .find(|(_, ranges)| {
+            text.chars().any(|ch| ranges.iter().any(|(low, high)| (*low..=*high).contains(&u32::from(ch))))
+        })
+        .map_or("", |(language, _)| language)
+}
+
+/// Probable genre from keyword hits in Source (counted double) and Tags; empty without any.
+fn guess_genre(source: &str, tags: &str) -> &'static str {
+    let words = |text: &str| {
+        let words: String = text
+            .to_lowercase()
+            .chars()
+            .map(|ch| if ch.is_alphanumeric() || ch == '-' { ch } else { ' ' })
+            .collect();
+        format!(" {} ", words.split_whitespace().collect::<Vec<_>>().join(" "))
+    };
+    let (source, tags) = (words(source), words(tags));
+
+    let mut best = ("", 0);
+    for (genre, keywords) in GENRE_KEYWORDS {
+        let score: usize = keywords
+            .iter()
+            .map(|keyword| {
+                let keyword = format!(" {keyword} ");
+                2 * usize::from(source.contains(&keyword)) + usize::from(tags.contains(&keyword))
+            })
+            .sum();
+        if score > best.1 {
+            best = (genre, score);
         }
+    }
+    best.0
+}
 
-        fs::copy(&entry.p, &destination).map_err(|err| {
-            format!("failed to unpack {}: {err}", relative_path.to_string_lossy())
-        })?;
+/// Lower-cased mapper names from the scan filter.
+#[derive(Debug, Default)]
+struct MapperFilter {
+    names: Vec<String>,
+    /// Keep difficulties other people guested in the filtered mapper's sets. The mapper's own
+    /// guest difficulties in other people's sets always match.
+    include_guest_difficulties: bool,
+    /// Also match names listed in Tags. Only for names resolved from an osu! user id, where a
+    /// tag hit is the mapper rather than a common word.
+    match_tags: bool,
+}
 
-        let relative_string = relative_path.to_string_lossy().replace('\\', "/");
-        if unpacked_osu_relative_path.is_none() {
-            let is_target = target_osu_name
-                .as_deref()
-                .is_some_and(|target| target == relative_string.to_ascii_lowercase());
-            let is_osu_file = relative_string.to_ascii_lowercase().ends_with(".osu");
-            if is_target || is_osu_file {
-                unpacked_osu_relative_path = Some(relative_string.clone());
-            }
+impl MapperFilter {
+    fn parse(mapper_name: Option<String>, options: &ScanOptions) -> Self {
+        let names = mapper_name
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_ascii_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+        Self {
+            names,
+            include_guest_difficulties: options.include_guest_difficulties(),
+            match_tags: options.mapper_user_id().is_some(),
         }
+    }
 
-        files.push(LazerSessionFileEntry {
-            relative_path: relative_string,
-            source_path: entry.p,
-        });
+    /// Tags can't hold spaces, so "Some Mapper" is usually tagged as "some_mapper".
+    fn tagged(&self, tags: &str, name: &str) -> bool {
+        self.match_tags
+            && tags.split_whitespace().any(|tag| {
+                tag.eq_ignore_ascii_case(name) || tag.eq_ignore_ascii_case(&name.replace(' ', "_"))
+            })
     }
 
-    if files.is_empty() {
-        return Err("no unpackable files were found for this beatmap set".to_string());
+    fn is_empty(&self) -> bool {
+        self.names.is_empty()
     }
 
-    let state = LazerSessionState {
-        source_file_path: file_path,
-        unpacked_osu_relative_path: unpacked_osu_relative_path.clone(),
-        files,
-    };
-    write_lazer_session_state(&session_dir, &state)?;
+    fn matches(&self, creator: &str, version: &str, tags: &str) -> bool {
+        if self.names.is_empty() {
+            return true;
+        }
+        let guest = detect_guest_mapper(creator, version, tags).map(|guest| guest.to_ascii_lowercase());
+        let creator = creator.to_ascii_lowercase();
+        let version = version.to_ascii_lowercase();
+        self.names.iter().any(|name| {
+            let guested_by_mapper = version.contains(name)
+                || guest.as_deref().is_some_and(|guest| guest.contains(name))
+                || self.tagged(tags, name);
+            if creator.contains(name) {
+                self.include_guest_difficulties || guest.is_none() || guested_by_mapper
+            } else {
+                guested_by_mapper
+            }
+        })
+    }
+}
 
-    let unpacked_osu_path = unpacked_osu_relative_path
-        .as_ref()
-        .map(|relative| unpacked_dir.join(relative).to_string_lossy().to_string());
+/// Canonical order of .osu sections, used to place a section that has to be created.
+const OSU_SECTION_ORDER: [&str; 8] = [
+    "General",
+    "Editor",
+    "Metadata",
+    "Difficulty",
+    "Events",
+    "TimingPoints",
+    "Colours",
+    "HitObjects",
+];
 
-    Ok(LazerPreparedSession {
-        session_dir: session_dir.to_string_lossy().to_string(),
-        unpacked_dir: unpacked_dir.to_string_lossy().to_string(),
-        unpacked_osu_path,
-    })
+fn detect_line_ending(content: &str) -> &'static str {
+    if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
 }
 
-#[tauri::command]
-fn commit_lazer_map_session(session_dir: String) -> Result<(), String> {
-    let session_dir_path = PathBuf::from(&session_dir);
-    let unpacked_dir = session_dir_path.join("unpacked");
-    let state = read_lazer_session_state(&session_dir_path)?;
+/// osu! writes `Key: Value` in [General]/[Editor] and `Key:Value` everywhere else.
+fn osu_section_key_separator(section: &str) -> &'static str {
+    if eq_ascii_ci(section, "General") || eq_ascii_ci(section, "Editor") {
+        ": "
+    } else {
+        ":"
+    }
+}
 
-    for file in &state.files {
-        let relative_path = Path::new(&file.relative_path);
-        let unpacked_path = unpacked_dir.join(relative_path);
-        if !unpacked_path.is_file() {
+/// Set `key` inside `[section]`, replacing the existing line or appending one at the end of the section
+/// (creating the section in canonical order if needed). Every other byte of the file is preserved.
+fn upsert_osu_section_value(content: &str, section: &str, key: &str, value: &str) -> Result<String, String> {
+    if value.contains(['\r', '\n']) {
+        return Err(format!("value for {key} must be a single line"));
+    }
+
+    let newline = detect_line_ending(content);
+    let mut out = String::with_capacity(content.len() + key.len() + value.len() + 16);
+    let mut headers: Vec<(String, usize)> = Vec::new();
+    let mut in_section = false;
+    let mut replaced = false;
+    // Offset in `out` right after the last non-empty line of the target section
+    let mut insert_at: Option<usize> = None;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if replaced {
+            out.push_str(line);
             continue;
         }
 
-        if let Some(parent) = Path::new(&file.source_path).parent() {
-            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        if trimmed.len() >= 2 && trimmed.starts_with('[') && trimmed.ends_with(']') {
+            let header = &trimmed[1..trimmed.len() - 1];
+            headers.push((header.to_string(), out.len()));
+            in_section = eq_ascii_ci(header, section);
+            out.push_str(line);
+            if in_section {
+                insert_at = Some(out.len());
+            }
+            continue;
         }
 
-        fs::copy(&unpacked_path, &file.source_path).map_err(|err| {
-            format!("failed to repack {}: {err}", file.relative_path)
-        })?;
+        if in_section {
+            if let Some((line_key, _)) = trimmed.split_once(':') {
+                if eq_ascii_ci(line_key.trim(), key) {
+                    let body = line.trim_end_matches(['\r', '\n']);
+                    let ending = &line[body.len()..];
+                    let colon = body.find(':').unwrap_or(body.len());
+                    let after = &body[(colon + 1).min(body.len())..];
+                    let spacing = &after[..after.len() - after.trim_start().len()];
+                    out.push_str(&body[..colon]);
+                    out.push(':');
+                    out.push_str(spacing);
+                    out.push_str(value);
+                    out.push_str(ending);
+                    replaced = true;
+                    continue;
+                }
+            }
+            out.push_str(line);
+            if !trimmed.is_empty() {
+                insert_at = Some(out.len());
+            }
+            continue;
+        }
+
+        out.push_str(line);
     }
 
-    let _ = fs::remove_dir_all(&session_dir_path);
-    Ok(())
+    if replaced {
+        return Ok(out);
+    }
+
+    let entry = format!("{key}{}{value}{newline}", osu_section_key_separator(section));
+    if let Some(position) = insert_at {
+        let needs_break = position > 0 && !out[..position].ends_with('\n');
+        let insertion = if needs_break { format!("{newline}{entry}") } else { entry };
+        out.insert_str(position, &insertion);
+        return Ok(out);
+    }
+
+    let section_rank = OSU_SECTION_ORDER
+        .iter()
+        .position(|name| eq_ascii_ci(name, section))
+        .unwrap_or(OSU_SECTION_ORDER.len());
+    let following = headers.iter().find(|(header, _)| {
+        OSU_SECTION_ORDER
+            .iter()
+            .position(|name| eq_ascii_ci(name, header))
+            .is_some_and(|rank| rank > section_rank)
+    });
+    let block = format!("[{section}]{newline}{entry}{newline}");
+    match following {
+        Some((_, position)) => out.insert_str(*position, &block),
+        None => {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push_str(newline);
+            }
+            out.push_str(newline);
+            out.push_str(&block);
+        }
+    }
+    Ok(out)
 }
 
-#[tauri::command]
-fn parse_stable_collections(path: String) -> Result<Vec<OsuCollectionPayload>, String> {
-    let db = read_stable_collections_file(Path::new(&path))?;
-    Ok(db.collections)
+/// Apply `edit` to a .osu file and write it back atomically, returning the backup path if one was made.
+/// Non-UTF-8 files are refused so untouched lines really stay byte-for-byte identical.
+fn rewrite_osu_file<F>(path: &Path, create_backup: bool, edit: F) -> Result<Option<PathBuf>, String>
+where
+    F: FnOnce(&str) -> Result<String, String>,
+{
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    let content = String::from_utf8(bytes)
+        .map_err(|_| "beatmap is not valid UTF-8; refusing to rewrite it".to_string())?;
+    let updated = edit(&content)?;
+    if updated == content {
+        return Ok(None);
+    }
+
+    let backup_path = if create_backup {
+        Some(create_timestamped_backup(path)?)
+    } else {
+        None
+    };
+    write_file_atomically(path, updated.as_bytes())?;
+    Ok(backup_path)
 }
 
-#[tauri::command]
-fn add_to_stable_collection(
-    collection_db_path: String,
-    collection_name: String,
-    beatmap_hash: String,
-) -> CollectionMutationPayload {
-    let path = PathBuf::from(&collection_db_path);
-    let normalized_name = collection_name.trim();
-    let normalized_hash = beatmap_hash.trim().to_ascii_lowercase();
+/// All .osu files sitting next to `file_path`, i.e. every difficulty of the set (including `file_path`).
+fn list_set_osu_files(file_path: &Path) -> Result<Vec<PathBuf>, String> {
+    let folder = file_path
+        .parent()
+        .ok_or_else(|| "beatmap has no parent folder".to_string())?;
+    list_osu_files_in_folder(folder)
+}
 
-    if normalized_name.is_empty() || normalized_hash.is_empty() {
-        return CollectionMutationPayload {
-            success: false,
-            error: Some("invalid_input".to_string()),
-        };
-    }
+fn list_osu_files_in_folder(folder: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(folder)
+        .map_err(|err| err.to_string())?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("osu"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
 
-    let mut db = match read_stable_collections_file(&path) {
-        Ok(db) => db,
-        Err(error) => {
-            let locked = fs::File::open(&path)
-                .err()
-                .is_some_and(|err| is_locked_io_error(&err));
-            return CollectionMutationPayload {
-                success: false,
-                error: Some(if locked { "file_locked".to_string() } else { error }),
-            };
+/// Drop every `key` line from `[section]`, leaving the rest of the file untouched.
+fn remove_osu_section_value(content: &str, section: &str, key: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut in_section = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.len() >= 2 && trimmed.starts_with('[') && trimmed.ends_with(']') {
+            in_section = eq_ascii_ci(&trimmed[1..trimmed.len() - 1], section);
+        } else if in_section {
+            if let Some((line_key, _)) = trimmed.split_once(':') {
+                if eq_ascii_ci(line_key.trim(), key) {
+                    continue;
+                }
+            }
         }
-    };
+        out.push_str(line);
+    }
 
-    let Some(collection) = db.collections.iter_mut().find(|collection| {
-        collection.name.eq_ignore_ascii_case(normalized_name)
-    }) else {
-        return CollectionMutationPayload {
-            success: false,
-            error: Some("collection not found".to_string()),
-        };
-    };
+    out
+}
 
-    if collection
-        .beatmap_hashes
-        .iter()
-        .any(|hash| hash.eq_ignore_ascii_case(&normalized_hash))
-    {
-        return CollectionMutationPayload {
-            success: true,
-            error: None,
-        };
+/// Add `offset_ms` to a time field, keeping integers as integers and fractional times fractional.
+fn shift_time_value(raw: &str, offset_ms: i32) -> Option<String> {
+    let trimmed = raw.trim();
+    if let Ok(value) = trimmed.parse::<i64>() {
+        return Some((value + i64::from(offset_ms)).to_string());
     }
+    trimmed
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .map(|value| (value + f64::from(offset_ms)).to_string())
+}
 
-    collection.beatmap_hashes.push(normalized_hash);
+fn shift_csv_fields(line: &str, indices: &[usize], offset_ms: i32) -> String {
+    let mut fields: Vec<String> = line.split(',').map(str::to_string).collect();
+    for &index in indices {
+        let Some(field) = fields.get_mut(index) else {
+            continue;
+        };
+        // Mania hold ends carry the hit sample after a colon: `end:0:0:0:0:`
+        let (time_part, rest) = match field.split_once(':') {
+            Some((time, rest)) => (time.to_string(), Some(rest.to_string())),
+            None => (field.clone(), None),
+        };
+        if let Some(shifted) = shift_time_value(&time_part, offset_ms) {
+            *field = match rest {
+                Some(rest) => format!("{shifted}:{rest}"),
+                None => shifted,
+            };
+        }
+    }
+    fields.join(",")
+}
+
+/// Shift every absolute timestamp in a .osu by `offset_ms`: timing points, hit objects (including
+/// spinner/hold ends), events, bookmarks and the preview point, which stays at 0 or later.
+/// Line endings are preserved.
+fn shift_osu_content(content: &str, offset_ms: i32) -> String {
+    let mut out = String::with_capacity(content.len() + 64);
+    let mut section = OsuSection::None;
+
+    for line in content.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[body.len()..];
+        let trimmed = body.trim();
+
+        if trimmed.len() >= 2 && trimmed.starts_with('[') && trimmed.ends_with(']') {
+            section = OsuSection::from_header(&trimmed[1..trimmed.len() - 1]);
+            out.push_str(line);
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            out.push_str(line);
+            continue;
+        }
+
+        let shifted = match section {
+            OsuSection::General => trimmed.split_once(':').and_then(|(key, value)| {
+                let preview = value.trim().parse::<i32>().ok()?;
+                (eq_ascii_ci(key.trim(), "PreviewTime") && preview >= 0)
+                    .then(|| replace_line_value(body, &(preview + offset_ms).max(0).to_string()))
+            }),
+            OsuSection::Editor => trimmed.split_once(':').and_then(|(key, value)| {
+                eq_ascii_ci(key.trim(), "Bookmarks").then(|| {
+                    let times = value
+                        .split(',')
+                        .filter_map(|chunk| shift_time_value(chunk, offset_ms))
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    replace_line_value(body, &times)
+                })
+            }),
+            OsuSection::Events => shift_event_line(body, offset_ms),
+            OsuSection::TimingPoints => Some(shift_csv_fields(body, &[0], offset_ms)),
+            OsuSection::HitObjects => {
+                let obj_type = csv_field(trimmed, 3)
+                    .and_then(|value| value.trim().parse::<i32>().ok())
+                    .unwrap_or(0);
+                if obj_type & (8 | 128) != 0 {
+                    Some(shift_csv_fields(body, &[2, 5], offset_ms))
+                } else {
+                    Some(shift_csv_fields(body, &[2], offset_ms))
+                }
+            }
+            _ => None,
+        };
+
+        match shifted {
+            Some(shifted) => {
+                out.push_str(&shifted);
+                out.push_str(ending);
+            }
+            None => out.push_str(line),
+        }
+    }
+
+    out
+}
+
+/// Storyboard commands whose start and end times sit in fields 2 and 3.
+const STORYBOARD_TIMED_COMMANDS: [&str; 10] = ["F", "M", "MX", "MY", "S", "V", "R", "C", "P", "T"];
+
+/// Shift the absolute times of an `[Events]` line: breaks, videos, samples and storyboard
+/// commands. Commands nested in a loop or trigger are relative to it, so they are left alone.
+fn shift_event_line(body: &str, offset_ms: i32) -> Option<String> {
+    let depth = body.len() - body.trim_start_matches([' ', '_']).len();
+    let kind = csv_field(body, 0).unwrap_or("").trim_start_matches([' ', '_']).trim();
+    match depth {
+        0 if kind == "2" || eq_ascii_ci(kind, "Break") => Some(shift_csv_fields(body, &[1, 2], offset_ms)),
+        0 if matches!(kind, "1" | "5") || eq_ascii_ci(kind, "Video") || eq_ascii_ci(kind, "Sample") => {
+            Some(shift_csv_fields(body, &[1], offset_ms))
+        }
+        1 if kind == "L" => Some(shift_csv_fields(body, &[1], offset_ms)),
+        1 if STORYBOARD_TIMED_COMMANDS.contains(&kind) => Some(shift_csv_fields(body, &[2, 3], offset_ms)),
+        _ => None,
+    }
+}
+
+/// Replace the value part of a `Key: Value` line, keeping the key and its spacing as written.
+fn replace_line_value(body: &str, value: &str) -> String {
+    let Some(colon) = body.find(':') else {
+        return body.to_string();
+    };
+    let after = &body[colon + 1..];
+    let spacing = &after[..after.len() - after.trim_start().len()];
+    format!("{}:{spacing}{value}", &body[..colon])
+}
+
+/// Raw (trimmed) value of `key` in `[section]`, without going through the full parser.
+fn read_osu_section_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            if in_section {
+                return None;
+            }
+            in_section = eq_ascii_ci(&trimmed[1..trimmed.len() - 1], section);
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        if let Some((line_key, value)) = trimmed.split_once(':') {
+            if eq_ascii_ci(line_key.trim(), key) {
+                return Some(value.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Read the BeatmapID/BeatmapSetID pair from [Metadata]; zero or negative ids count as missing.
+fn parse_osu_beatmap_ids(content: &str) -> (Option<i64>, Option<i64>) {
+    let mut in_metadata = false;
+    let mut beatmap_id = None;
+    let mut beatmap_set_id = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            if in_metadata {
+                break;
+            }
+            in_metadata = eq_ascii_ci(&trimmed[1..trimmed.len() - 1], "Metadata");
+            continue;
+        }
+        if !in_metadata {
+            continue;
+        }
+        if let Some((key, value)) = trimmed.split_once(':') {
+            let id = value.trim().parse::<i64>().ok().filter(|id| *id > 0);
+            if eq_ascii_ci(key.trim(), "BeatmapID") {
+                beatmap_id = id;
+            } else if eq_ascii_ci(key.trim(), "BeatmapSetID") {
+                beatmap_set_id = id;
+            }
+        }
+    }
+
+    (beatmap_id, beatmap_set_id)
+}
+
+/// Discover osu beatmap files and their mtimes using WalkDir metadata.
+/// Stable scans use the .osu extension; lazer scans sniff beatmap text files in the hashed store.
+fn find_osu_files_with_mtime(
+    root: &Path,
+    client: OsuClient,
+    status: Option<(&tauri::Window, &str)>,
+) -> Vec<(String, f64)> {
+    match client {
+        OsuClient::Stable => {
+            let mut results = Vec::with_capacity(4096);
+            for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+
+                let path = entry.path();
+                if !path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| ext.eq_ignore_ascii_case("osu"))
+                    .unwrap_or(false)
+                {
+                    continue;
+                }
+
+                let metadata = match entry.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(_) => continue,
+                };
+
+                let mtime_ms = metadata
+                    .modified()
+                    .ok()
+                    .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() * 1000.0)
+                    .unwrap_or(0.0);
+                results.push((path.to_string_lossy().to_string(), mtime_ms));
+            }
+            results
+        }
+        OsuClient::Lazer => {
+            let mut candidates = Vec::with_capacity(8192);
+            for entry in WalkDir::new(root).into_iter().filter_map(Result::ok) {
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+
+                let metadata = match entry.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(_) => continue,
+                };
+                let mtime_ms = metadata
+                    .modified()
+                    .ok()
+                    .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() * 1000.0)
+                    .unwrap_or(0.0);
+
+                candidates.push((
+                    entry.path().to_string_lossy().to_string(),
+                    metadata.len(),
+                    mtime_ms,
+                ));
+            }
+
+            let total_candidates = candidates.len();
+            if let Some((window, dir_path)) = status {
+                emit_scan_status(window, dir_path, "discovering", 0, total_candidates, Some(0));
+            }
+
+            let mut results = Vec::with_capacity((total_candidates / 8).max(256));
+            let mut discovered = 0_usize;
+
+            for (index, (file_path, file_size, mtime_ms)) in candidates.into_iter().enumerate() {
+                if is_probable_lazer_osu_file(Path::new(&file_path), file_size) {
+                    discovered += 1;
+                    results.push((file_path, mtime_ms));
+                }
+
+                let current = index + 1;
+                if let Some((window, dir_path)) = status {
+                    if should_emit_scan_status(current, total_candidates) {
+                        emit_scan_status(
+                            window,
+                            dir_path,
+                            "discovering",
+                            current,
+                            total_candidates,
+                            Some(discovered),
+                        );
+                    }
+                }
+            }
+
+            results
+        }
+    }
+}
+
+fn count_matching_entries(
+    osu_entries: &[(String, f64)],
+    mappers: &MapperFilter,
+    status: Option<(&tauri::Window, &str)>,
+) -> usize {
+    if mappers.is_empty() {
+        return osu_entries.len();
+    }
+
+    let total = osu_entries.len();
+    if let Some((window, dir_path)) = status {
+        emit_scan_status(window, dir_path, "filtering", 0, total, None);
+    }
+
+    let mut matched = 0_usize;
+    for (index, (path, _)) in osu_entries.iter().enumerate() {
+        if file_matches_mapper(path, mappers) {
+            matched += 1;
+        }
+
+        let current = index + 1;
+        if let Some((window, dir_path)) = status {
+            if should_emit_scan_status(current, total) {
+                emit_scan_status(window, dir_path, "filtering", current, total, None);
+            }
+        }
+    }
+
+    matched
+}
+
+/// Process a single .osu file. `mtime_ms` is pre-fetched from WalkDir.
+fn scan_single_osu_file(
+    file_path: &str,
+    mtime_ms: f64,
+    known: &HashMap<String, f64>,
+    mappers: &MapperFilter,
+    lazer_resolver: Option<&LazerResolvedAssets>,
+    detail: ScanDetailLevel,
+) -> Result<Option<ScanFilePayload>, ScanFileError> {
+    let has_mapper = !mappers.is_empty();
+
+    // Fast path: check cache by mtime
+    if let Some(cached_mtime) = known.get(file_path) {
+        if (cached_mtime - mtime_ms).abs() < 0.5 {
+            if has_mapper {
+                // Only read first 8KB header for mapper filter on cached files
+                let path = Path::new(file_path);
+                if let Ok(file) = fs::File::open(path) {
+                    let mut reader = BufReader::with_capacity(8192, file);
+                    let mut buf = Vec::with_capacity(8192);
+                    let _ = reader.by_ref().take(8192).read_to_end(&mut buf);
+                    let header = String::from_utf8_lossy(&buf);
+                    let (creator, version, tags) = parse_header_mapper_fields(&header);
+                    if !mappers.matches(&creator, &version, &tags) {
+                        return Ok(None);
+                    }
+                } else {
+                    return Ok(None);
+                }
+            }
+
+            return Ok(Some(ScanFilePayload {
+                file_path: file_path.to_string(),
+                stat: FileStatPayload { mtime_ms },
+                beatmap_hash: beatmap_hash_from_lazer_path(file_path),
+                unchanged: Some(true),
+                metadata: None,
+                hit_starts: None,
+                hit_ends: None,
+                break_periods: None,
+                bookmarks: None,
+                sv_changes: None,
+                bpm: None,
+                removed: None,
+                decode_warnings: None,
+                library_root: None,
+                timeline: None,
+            }));
+        }
+    }
+
+    // Full parse path: read entire file with buffered I/O
+    let path = Path::new(file_path);
+    let file = fs::File::open(path).map_err(|err| ScanFileError::from_io(file_path, &err))?;
+    let mut reader = BufReader::with_capacity(32768, file);
+    let mut bytes = Vec::with_capacity(32768);
+    reader
+        .read_to_end(&mut bytes)
+        .map_err(|err| ScanFileError::from_io(file_path, &err))?;
+    if bytes.is_empty() {
+        return Err(ScanFileError::new(file_path, "malformed", "File is empty"));
+    }
+    let (content, decode_warnings) = decode_osu_bytes(&bytes);
+
+    let mut parsed = parse_osu_content_at(&content, detail);
+    let has_objects = match detail {
+        ScanDetailLevel::Metadata => content.contains("[HitObjects]"),
+        _ => !parsed.hit_starts.is_empty(),
+    };
+    if !has_objects && !content.trim_start().starts_with("osu file format") {
+        return Err(ScanFileError::new(
+            file_path,
+            "malformed",
+            "No osu file format header and no hit objects",
+        ));
+    }
+    let beatmap_hash = match lazer_resolver {
+        Some(_) => beatmap_hash_from_lazer_path(file_path),
+        None => Some(compute_osu_md5_hex(&bytes)),
+    };
+
+    if let (Some(resolver), Some(beatmap_hash)) =
+        (lazer_resolver, beatmap_hash.as_deref())
+    {
+        if let Some((audio_path, bg_path)) = resolver.map.get(beatmap_hash) {
+            if let Some(audio) = audio_path {
+                parsed.metadata.resolved_audio_path = audio.clone();
+            }
+            if let Some(bg) = bg_path {
+                parsed.metadata.resolved_background_path = bg.clone();
+            }
+        }
+    }
+
+    let metadata = &parsed.metadata;
+    if has_mapper && !mappers.matches(&metadata.creator, &metadata.version, &metadata.tags) {
+        return Ok(None);
+    }
+
+    let background_path = if !parsed.metadata.resolved_background_path.is_empty() {
+        Some(PathBuf::from(&parsed.metadata.resolved_background_path))
+    } else if !parsed.metadata.background.is_empty() {
+        path.parent().map(|folder| folder.join(&parsed.metadata.background))
+    } else {
+        None
+    };
+    parsed.metadata.background_info = background_path.as_deref().and_then(read_background_info);
+
+    let has_timeline = detail != ScanDetailLevel::Metadata;
+    let last_object_time = parsed.hit_ends.iter().max().copied().unwrap_or(0);
+    let bpm = has_timeline.then(|| compute_bpm_summary(&parsed.timing_points, last_object_time));
+
+    Ok(Some(ScanFilePayload {
+        file_path: file_path.to_string(),
+        stat: FileStatPayload { mtime_ms },
+        beatmap_hash,
+        unchanged: None,
+        metadata: Some(parsed.metadata),
+        hit_starts: has_timeline.then_some(parsed.hit_starts),
+        hit_ends: has_timeline.then_some(parsed.hit_ends),
+        break_periods: Some(parsed.break_periods),
+        bookmarks: Some(parsed.bookmarks),
+        sv_changes: (detail == ScanDetailLevel::Full).then_some(parsed.sv_changes),
+        bpm,
+        removed: None,
+        decode_warnings: (!decode_warnings.is_empty()).then_some(decode_warnings),
+        library_root: None,
+        timeline: None,
+    }))
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ScanBatchEvent {
+    files: Vec<ScanFilePayload>,
+    directory: String,
+    batch_index: usize,
+    total_files: usize,
+}
+
+/// `scan-batch` as sent when the scan asked for `compactEvents`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CompactScanBatchEvent {
+    /// Always "msgpack", so the renderer can tell the two shapes apart.
+    encoding: &'static str,
+    /// Base64 of the `ScanBatchEvent` as MessagePack with named fields.
+    data: String,
+    batch_index: usize,
+    total_files: usize,
+}
+
+/// Batches a channel-fed scan may have in flight before it waits for the renderer to catch up.
+const SCAN_CHANNEL_MAX_IN_FLIGHT: usize = 4;
+/// Give up waiting after this and send without backpressure, so a renderer that stopped acking
+/// can't stall the scan.
+const SCAN_CHANNEL_ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Batches each scan channel's caller has handled, by channel id; bumped by `ack_scan_batch`.
+static SCAN_CHANNEL_ACKS: Mutex<BTreeMap<u32, usize>> = Mutex::new(BTreeMap::new());
+static SCAN_CHANNEL_ACKED: Condvar = Condvar::new();
+
+/// The caller's own channel for `scan-batch` payloads, replacing the broadcast window event.
+#[derive(Clone)]
+struct ScanBatchChannel {
+    channel: tauri::ipc::Channel<ScanBatchEvent>,
+    sent: Arc<AtomicUsize>,
+    unacked: Arc<AtomicBool>,
+}
+
+impl std::fmt::Debug for ScanBatchChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScanBatchChannel").field("id", &self.channel.id()).finish()
+    }
+}
+
+impl ScanBatchChannel {
+    fn new(channel: tauri::ipc::Channel<ScanBatchEvent>) -> Self {
+        SCAN_CHANNEL_ACKS.lock().unwrap().insert(channel.id(), 0);
+        Self {
+            channel,
+            sent: Arc::new(AtomicUsize::new(0)),
+            unacked: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Wait until no more than `in_flight` sent batches are still unacknowledged, returning how
+    /// many are.
+    fn wait_for_acks(&self, in_flight: usize) -> usize {
+        if self.unacked.load(Ordering::Acquire) {
+            return 0;
+        }
+        let id = self.channel.id();
+        let sent = self.sent.load(Ordering::Acquire);
+        let acks = SCAN_CHANNEL_ACKS.lock().unwrap();
+        let (acks, wait) = SCAN_CHANNEL_ACKED
+            .wait_timeout_while(acks, SCAN_CHANNEL_ACK_TIMEOUT, |acks| {
+                acks.get(&id).is_some_and(|acked| sent.saturating_sub(*acked) > in_flight)
+            })
+            .unwrap();
+        if wait.timed_out() {
+            eprintln!("scan channel {id} stopped acknowledging batches, sending without backpressure");
+            self.unacked.store(true, Ordering::Release);
+            return 0;
+        }
+        acks.get(&id).map_or(0, |acked| sent.saturating_sub(*acked))
+    }
+
+    /// Send once the renderer is within the in-flight limit; returns the batches it still had
+    /// queued at that point.
+    fn send(&self, event: ScanBatchEvent) -> usize {
+        let lag = self.wait_for_acks(SCAN_CHANNEL_MAX_IN_FLIGHT - 1);
+        if self.channel.send(event).is_ok() {
+            self.sent.fetch_add(1, Ordering::AcqRel);
+        }
+        lag
+    }
+
+    /// Wait for the renderer to handle every batch, so `scan-complete` can't overtake them.
+    fn finish(&self) {
+        self.wait_for_acks(0);
+        SCAN_CHANNEL_ACKS.lock().unwrap().remove(&self.channel.id());
+    }
+}
+
+/// Send a `scan-batch` over the scan's channel if it has one, otherwise emit it, packed as
+/// MessagePack when `compactEvents` is set. JSON.parse on huge batches blocks the renderer far
+/// longer than decoding the same data from a typed array. Returns how many earlier batches the
+/// renderer had yet to acknowledge, which is always 0 for events.
+fn emit_scan_batch(window: &tauri::Window, event: ScanBatchEvent, options: &ScanOptions) -> usize {
+    if let Some(channel) = &options.batch_channel {
+        return channel.send(event);
+    }
+    if options.compact_events {
+        match rmp_serde::to_vec_named(&event) {
+            Ok(bytes) => {
+                let _ = window.emit("scan-batch", CompactScanBatchEvent {
+                    encoding: "msgpack",
+                    data: base64::engine::general_purpose::STANDARD.encode(bytes),
+                    batch_index: event.batch_index,
+                    total_files: event.total_files,
+                });
+                return 0;
+            }
+            Err(err) => eprintln!("failed to pack scan batch, sending JSON: {err}"),
+        }
+    }
+    let _ = window.emit("scan-batch", event);
+    0
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ScanCompleteEvent {
+    directory: String,
+    total_files: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<ScanErrorSummary>,
+}
+
+impl ScanCompleteEvent {
+    fn new(directory: &str, total_files: usize, errors: &[ScanFileError]) -> Self {
+        let errors = (!errors.is_empty()).then(|| {
+            let mut by_kind = BTreeMap::new();
+            for error in errors {
+                *by_kind.entry(error.kind.to_string()).or_insert(0) += 1;
+            }
+            ScanErrorSummary { total: errors.len(), by_kind }
+        });
+        Self {
+            directory: directory.to_string(),
+            total_files,
+            errors,
+        }
+    }
+}
+
+/// Why a beatmap was left out of a scan.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ScanFileError {
+    file_path: String,
+    kind: &'static str,
+    message: String,
+}
+
+impl ScanFileError {
+    fn new(file_path: &str, kind: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            file_path: file_path.to_string(),
+            kind,
+            message: message.into(),
+        }
+    }
+
+    fn from_io(file_path: &str, err: &std::io::Error) -> Self {
+        let kind = match err.kind() {
+            std::io::ErrorKind::PermissionDenied => "permission-denied",
+            std::io::ErrorKind::NotFound => "not-found",
+            _ => "read-failed",
+        };
+        Self::new(file_path, kind, err.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ScanErrorSummary {
+    total: usize,
+    by_kind: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ScanErrorsEvent {
+    directory: String,
+    errors: Vec<ScanFileError>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ScanStatusEvent {
+    directory: String,
+    stage: String,
+    current: usize,
+    total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    discovered_files: Option<usize>,
+}
+
+#[inline]
+fn should_emit_scan_status(current: usize, total: usize) -> bool {
+    current == total || current == 1 || current % 250 == 0
+}
+
+fn emit_scan_status(
+    window: &tauri::Window,
+    dir_path: &str,
+    stage: &str,
+    current: usize,
+    total: usize,
+    discovered_files: Option<usize>,
+) {
+    let _ = window.emit("scan-status", ScanStatusEvent {
+        directory: dir_path.to_string(),
+        stage: stage.to_string(),
+        current,
+        total,
+        discovered_files,
+    });
+}
+
+/// Quick header-only check to see if a file matches the mapper filter.
+/// Returns true if the file should be included (matches mapper or no mapper filter).
+fn file_matches_mapper(file_path: &str, mappers: &MapperFilter) -> bool {
+    let path = Path::new(file_path);
+    if let Ok(file) = fs::File::open(path) {
+        let mut reader = BufReader::with_capacity(8192, file);
+        let mut buf = Vec::with_capacity(8192);
+        let _ = reader.by_ref().take(8192).read_to_end(&mut buf);
+        let header = String::from_utf8_lossy(&buf);
+        let (creator, version, tags) = parse_header_mapper_fields(&header);
+        return mappers.matches(&creator, &version, &tags);
+    }
+    false
+}
+
+fn scan_directory_streaming(
+    dir_path: &str,
+    mapper_name: Option<String>,
+    known_files: Option<HashMap<String, f64>>,
+    client: OsuClient,
+    options: &ScanOptions,
+    window: &tauri::Window,
+) {
+    let outcome = scan_root_streaming(dir_path, mapper_name, known_files, client, options, window);
+    options.finish_batches();
+    let _ = window.emit(
+        "scan-complete",
+        ScanCompleteEvent::new(dir_path, outcome.emitted, &outcome.errors),
+    );
+    notify_stale_star_ratings(window);
+}
+
+/// Stream one directory's batches without announcing completion, so several roots can share a scan.
+fn scan_root_streaming(
+    dir_path: &str,
+    mapper_name: Option<String>,
+    known_files: Option<HashMap<String, f64>>,
+    client: OsuClient,
+    options: &ScanOptions,
+    window: &tauri::Window,
+) -> ScanOutcome {
+    let _active_scan = ActiveScanGuard::enter();
+    let root = resolve_scan_root(dir_path, client);
+    if !root.exists() || !root.is_dir() {
+        return ScanOutcome::default();
+    }
+
+    // Phase 1: Discover all .osu files with their mtimes in one WalkDir pass
+    let osu_entries = find_osu_files_with_mtime(&root, client, Some((window, dir_path)));
+    if let Some(known) = &known_files {
+        emit_removed_known_files(window, dir_path, Some(&root), known, &osu_entries);
+    }
+    if osu_entries.is_empty() {
+        return ScanOutcome::default();
+    }
+
+    let known = Arc::new(known_files.unwrap_or_default());
+    let mappers = Arc::new(MapperFilter::parse(mapper_name, options));
+    let has_mapper = !mappers.is_empty();
+    let lazer_resolver = resolve_lazer_assets_for_scan(window, dir_path, client);
+
+    // When mapper filter is active, pre-count matching files for accurate progress
+    let total_for_progress = count_matching_entries(
+        &osu_entries,
+        mappers.as_ref(),
+        if has_mapper { Some((window, dir_path)) } else { None },
+    );
+
+    // Phase 2: Parse files in parallel, emit batches as they complete
+    emit_scan_batches(
+        window,
+        dir_path,
+        &osu_entries,
+        known,
+        mappers,
+        lazer_resolver,
+        total_for_progress,
+        0,
+        options,
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct LibraryRoot {
+    path: String,
+    /// "stable" or "lazer", as taken by `OsuClient::from_option`.
+    client: String,
+    added_at_ms: u64,
+}
+
+fn library_roots_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|err| err.to_string())?;
+    Ok(data_dir.join("library-roots.json"))
+}
+
+fn load_library_roots(path: &Path) -> Vec<LibraryRoot> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn save_library_roots(path: &Path, roots: &[LibraryRoot]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let bytes = serde_json::to_vec_pretty(roots).map_err(|err| err.to_string())?;
+    write_file_atomically(path, &bytes)
+}
+
+#[tauri::command]
+fn list_library_roots(app_handle: tauri::AppHandle) -> Result<Vec<LibraryRoot>, String> {
+    Ok(load_library_roots(&library_roots_path(&app_handle)?))
+}
+
+/// Register a Songs (or lazer data) folder as part of the library. Adding a known root again
+/// only updates its client.
+#[tauri::command]
+fn add_library_root(
+    app_handle: tauri::AppHandle,
+    path: String,
+    client_type: Option<String>,
+) -> Result<Vec<LibraryRoot>, String> {
+    if !Path::new(&path).is_dir() {
+        return Err(format!("not a directory: {path}"));
+    }
+    let client = match OsuClient::from_option(client_type) {
+        OsuClient::Lazer => "lazer",
+        OsuClient::Stable => "stable",
+    }
+    .to_string();
+
+    let roots_path = library_roots_path(&app_handle)?;
+    let mut roots = load_library_roots(&roots_path);
+    let key = path_cache_key(Path::new(&path));
+    match roots.iter_mut().find(|root| path_cache_key(Path::new(&root.path)) == key) {
+        Some(existing) => existing.client = client,
+        None => roots.push(LibraryRoot {
+            path,
+            client,
+            added_at_ms: unix_time_ms() as u64,
+        }),
+    }
+    save_library_roots(&roots_path, &roots)?;
+    Ok(roots)
+}
+
+/// Unregister a root and forget its cached beatmaps. Nothing on disk is touched.
+#[tauri::command]
+fn remove_library_root(app_handle: tauri::AppHandle, path: String) -> Result<Vec<LibraryRoot>, String> {
+    let roots_path = library_roots_path(&app_handle)?;
+    let mut roots = load_library_roots(&roots_path);
+    let key = path_cache_key(Path::new(&path));
+    roots.retain(|root| path_cache_key(Path::new(&root.path)) != key);
+    save_library_roots(&roots_path, &roots)?;
+
+    {
+        let mut cache = library_cache().lock().map_err(|err| err.to_string())?;
+        let orphaned: Vec<String> = cache
+            .entries
+            .values()
+            .filter(|entry| match &entry.library_root {
+                Some(root) => path_cache_key(Path::new(root)) == key,
+                None => Path::new(&entry.file_path).starts_with(&path),
+            })
+            .map(|entry| entry.file_path.clone())
+            .collect();
+        for file_path in &orphaned {
+            cache.remove(file_path);
+        }
+    }
+    flush_library_cache();
+    Ok(roots)
+}
+
+/// Scan every registered root (or only `roots`, to rescan some independently) one after another.
+/// Each root ends with `scan-root-complete`; a final `scan-complete` without a directory covers
+/// them all, so the renderer merges the results instead of replacing one folder's items.
+#[tauri::command]
+async fn scan_library_roots(
+    window: tauri::Window,
+    app_handle: tauri::AppHandle,
+    roots: Option<Vec<String>>,
+    mapper_name: Option<String>,
+    known_files: Option<HashMap<String, f64>>,
+    options: Option<ScanOptions>,
+) -> Result<(), String> {
+    let mut library_roots = load_library_roots(&library_roots_path(&app_handle)?);
+    if let Some(selected) = roots {
+        let selected: Vec<String> = selected.iter().map(|root| path_cache_key(Path::new(root))).collect();
+        library_roots.retain(|root| selected.contains(&path_cache_key(Path::new(&root.path))));
+    }
+    let options = options.unwrap_or_default();
+    let mapper_name = expand_mapper_filter(mapper_name, &options).await;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut total = 0;
+        let mut errors = Vec::new();
+        for root in &library_roots {
+            let client = OsuClient::from_option(Some(root.client.clone()));
+            let outcome = scan_root_streaming(
+                &root.path,
+                mapper_name.clone(),
+                known_files.clone(),
+                client,
+                &options,
+                &window,
+            );
+            let _ = window.emit(
+                "scan-root-complete",
+                ScanCompleteEvent::new(&root.path, outcome.emitted, &outcome.errors),
+            );
+            total += outcome.emitted;
+            errors.extend(outcome.errors);
+        }
+        let _ = window.emit("scan-complete", ScanCompleteEvent::new("", total, &errors));
+        notify_stale_star_ratings(&window);
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ScanRemovedEvent {
+    directory: String,
+    file_paths: Vec<String>,
+}
+
+/// Report `known` paths (under `root`, if given) that the scan no longer found on disk,
+/// and drop them from the library cache.
+fn emit_removed_known_files(
+    window: &tauri::Window,
+    dir_path: &str,
+    root: Option<&Path>,
+    known: &HashMap<String, f64>,
+    osu_entries: &[(String, f64)],
+) {
+    let on_disk: std::collections::HashSet<&str> = osu_entries.iter().map(|(path, _)| path.as_str()).collect();
+    let mut removed: Vec<String> = known
+        .keys()
+        .filter(|path| root.is_none_or(|root| Path::new(path).starts_with(root)))
+        .filter(|path| !on_disk.contains(path.as_str()))
+        // A walk can miss files it couldn't stat; only report what is really gone.
+        .filter(|path| !Path::new(path).exists())
+        .cloned()
+        .collect();
+    if removed.is_empty() {
+        return;
+    }
+    removed.sort();
+
+    {
+        let mut cache = library_cache().lock().unwrap();
+        for path in &removed {
+            cache.remove(path);
+        }
+    }
+    let _ = window.emit("scan-removed", ScanRemovedEvent {
+        directory: dir_path.to_string(),
+        file_paths: removed,
+    });
+}
+
+fn resolve_lazer_assets_for_scan(
+    window: &tauri::Window,
+    dir_path: &str,
+    client: OsuClient,
+) -> Option<Arc<LazerResolvedAssets>> {
+    if client != OsuClient::Lazer {
+        return None;
+    }
+    emit_scan_status(window, dir_path, "resolving-media", 0, 0, None);
+    match get_lazer_resolver(dir_path) {
+        Ok(resolver) => resolver,
+        Err(err) => {
+            eprintln!("failed to resolve lazer media from {dir_path}: {err}");
+            None
+        }
+    }
+}
+
+/// Walk `dir_path` and emit only what changed since the library cache was last updated:
+/// added and modified maps get a full parse, vanished ones a `removed: true` marker.
+/// Everything is emitted with an empty `directory` so the renderer merges instead of replacing.
+fn rescan_changed_streaming(
+    dir_path: &str,
+    mapper_name: Option<String>,
+    client: OsuClient,
+    options: &ScanOptions,
+    window: &tauri::Window,
+) {
+    let _active_scan = ActiveScanGuard::enter();
+    let root = resolve_scan_root(dir_path, client);
+    let mut final_count = 0;
+    let mut errors = Vec::new();
+
+    if root.is_dir() {
+        let osu_entries = find_osu_files_with_mtime(&root, client, Some((window, dir_path)));
+        let (changed, removed) = {
+            let cache = library_cache().lock().unwrap();
+            // Match by `path_cache_key`, so cached entries recorded under another spelling of the
+            // same path (case, symlinks, `\\?\` prefixes) are still recognised.
+            let root_key = PathBuf::from(path_cache_key(&root));
+            let mut cached: HashMap<String, &LibraryCacheEntry> = cache
+                .entries
+                .values()
+                .map(|entry| (path_cache_key(Path::new(&entry.file_path)), entry))
+                .filter(|(key, _)| Path::new(key).starts_with(&root_key))
+                .collect();
+            let mut changed = Vec::new();
+            let mut removed = Vec::new();
+            for (path, mtime_ms) in &osu_entries {
+                match cached.remove(&path_cache_key(Path::new(path))) {
+                    Some(entry) if entry.file_path != *path => {
+                        // Re-record it under the walked path rather than keeping both spellings.
+                        removed.push(entry.file_path.clone());
+                        changed.push((path.clone(), *mtime_ms));
+                    }
+                    Some(entry) if (entry.mtime_ms - mtime_ms).abs() < 0.5 => {}
+                    _ => changed.push((path.clone(), *mtime_ms)),
+                }
+            }
+            // A walk can miss files it couldn't stat; only report what is really gone.
+            removed.extend(
+                cached
+                    .into_values()
+                    .filter(|entry| !Path::new(&entry.file_path).exists())
+                    .map(|entry| entry.file_path.clone()),
+            );
+            (changed, removed)
+        };
+
+        if !removed.is_empty() {
+            let mut cache = library_cache().lock().unwrap();
+            for path in &removed {
+                cache.remove(path);
+            }
+        }
+        // Removal markers are sized like every other batch: `batchSize`, or adaptively.
+        let mut sizing = AdaptiveBatchSize::new(options);
+        let mut removed_batch_count = 0;
+        let mut remaining = removed.as_slice();
+        while !remaining.is_empty() {
+            let (chunk, rest) = remaining.split_at(sizing.size.min(remaining.len()));
+            remaining = rest;
+            let started = Instant::now();
+            let lag = emit_scan_batch(window, ScanBatchEvent {
+                files: chunk.iter().cloned().map(ScanFilePayload::removed).collect(),
+                directory: String::new(),
+                batch_index: removed_batch_count,
+                total_files: removed.len() + changed.len(),
+            }, options);
+            sizing.record(started.elapsed(), lag);
+            removed_batch_count += 1;
+        }
+        final_count += removed.len();
+
+        if !changed.is_empty() {
+            let mappers = Arc::new(MapperFilter::parse(mapper_name, options));
+            let lazer_resolver = resolve_lazer_assets_for_scan(window, dir_path, client);
+            let total_for_progress = count_matching_entries(&changed, mappers.as_ref(), None);
+            let outcome = emit_scan_batches(
+                window,
+                "",
+                &changed,
+                Arc::new(HashMap::new()),
+                mappers,
+                lazer_resolver,
+                total_for_progress,
+                removed_batch_count,
+                options,
+            );
+            final_count += outcome.emitted;
+            errors = outcome.errors;
+        } else {
+            flush_library_cache();
+        }
+    }
+
+    options.finish_batches();
+    let _ = window.emit("scan-complete", ScanCompleteEvent::new("", final_count, &errors));
+    notify_stale_star_ratings(window);
+}
+
+/// Default time a scan worker may spend on a single file before the watchdog gives up on it.
+const DEFAULT_SCAN_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_SCAN_BATCH_SIZE: usize = 50;
+
+/// How much of each changed map a scan parses.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum ScanDetailLevel {
+    /// Everything but timing and hit objects: metadata, difficulty, bookmarks, breaks and combo
+    /// colours. Not cached, since it lacks the object count and length a cache entry carries.
+    Metadata,
+    /// Also hit times and BPM, but no SV series.
+    Timeline,
+    #[default]
+    Full,
+}
+
+/// Per-scan tuning for slow disks, network drives and laptops on battery.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ScanOptions {
+    /// Upper bound on parser threads; defaults to twice the core count, clamped to 4..=32.
+    max_threads: Option<usize>,
+    /// Results per `scan-batch` event. Unset, the size adapts to how quickly the renderer keeps up.
+    batch_size: Option<usize>,
+    /// Pause after each fully parsed file, to leave the disk some headroom.
+    file_delay_ms: Option<u64>,
+    /// Run workers in background mode (lower CPU and I/O priority). Windows only.
+    low_priority: bool,
+    stall_timeout_ms: Option<u64>,
+    /// See `MapperFilter::include_guest_difficulties`; on unless set to false.
+    include_guest_difficulties: Option<bool>,
+    /// osu! user id whose current and previous usernames are added to the mapper filter.
+    mapper_user_id: Option<String>,
+    /// Send hit times of maps with `BINARY_TIMELINE_MIN_OBJECTS` or more objects as binary over the
+    /// `mosu-timeline` protocol instead of JSON arrays; huge marathon maps otherwise spend most of
+    /// their IPC time serializing them.
+    binary_timeline: bool,
+    /// Send `scan-batch` payloads as base64 MessagePack rather than plain JSON. Window events only.
+    compact_events: bool,
+    detail_level: ScanDetailLevel,
+    /// Set from the command's `onBatch` argument rather than deserialized.
+    #[serde(skip)]
+    batch_channel: Option<ScanBatchChannel>,
+}
+
+impl ScanOptions {
+    fn worker_count(&self, entry_count: usize) -> usize {
+        let parallelism = std::thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(4);
+        let default_threads = (parallelism.saturating_mul(2)).clamp(4, 32);
+        let max_threads = self.max_threads.filter(|threads| *threads > 0).unwrap_or(default_threads);
+        max_threads.min(entry_count)
+    }
+
+    /// Let a channel-fed scan's batches all land before its `scan-complete` goes out.
+    fn finish_batches(&self) {
+        if let Some(channel) = &self.batch_channel {
+            channel.finish();
+        }
+    }
+
+    fn batch_size(&self) -> usize {
+        self.batch_size.filter(|size| *size > 0).unwrap_or(DEFAULT_SCAN_BATCH_SIZE)
+    }
+
+    fn file_delay(&self) -> Option<Duration> {
+        self.file_delay_ms.filter(|ms| *ms > 0).map(Duration::from_millis)
+    }
+
+    fn include_guest_difficulties(&self) -> bool {
+        self.include_guest_difficulties.unwrap_or(true)
+    }
+
+    fn mapper_user_id(&self) -> Option<&str> {
+        self.mapper_user_id.as_deref().map(str::trim).filter(|id| !id.is_empty())
+    }
+
+    fn stall_timeout(&self) -> Duration {
+        self.stall_timeout_ms
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_SCAN_STALL_TIMEOUT)
+    }
+}
+
+/// Bounds for adaptive batching, used unless `batchSize` pins the size.
+const MIN_ADAPTIVE_SCAN_BATCH_SIZE: usize = 10;
+const MAX_ADAPTIVE_SCAN_BATCH_SIZE: usize = 400;
+/// Handing a batch off (including any wait for acknowledgements) slower than this means the
+/// renderer is falling behind.
+const SLOW_SCAN_BATCH_SEND: Duration = Duration::from_millis(40);
+
+/// `scan-batch` size that grows while the renderer keeps up and halves as soon as it lags.
+struct AdaptiveBatchSize {
+    size: usize,
+    adaptive: bool,
+}
+
+impl AdaptiveBatchSize {
+    fn new(options: &ScanOptions) -> Self {
+        Self {
+            size: options.batch_size(),
+            adaptive: options.batch_size.is_none(),
+        }
+    }
+
+    /// `lag` is the number of earlier batches the renderer still had queued when this one went out.
+    fn record(&mut self, send_time: Duration, lag: usize) {
+        if !self.adaptive {
+            return;
+        }
+        if lag > 1 || send_time > SLOW_SCAN_BATCH_SEND {
+            self.size = (self.size / 2).max(MIN_ADAPTIVE_SCAN_BATCH_SIZE);
+        } else if lag == 0 && send_time < SLOW_SCAN_BATCH_SEND / 4 {
+            self.size = (self.size + self.size / 2).min(MAX_ADAPTIVE_SCAN_BATCH_SIZE);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn lower_current_thread_priority() {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_MODE_BACKGROUND_BEGIN,
+    };
+
+    // THREAD_MODE_BACKGROUND_BEGIN also drops the thread's I/O priority, which is what matters here.
+    unsafe {
+        SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn lower_current_thread_priority() {}
+
+enum ScanWorkerMessage {
+    Parsed(Box<ScanFilePayload>),
+    Failed(ScanFileError),
+    Finished,
+}
+
+#[derive(Default)]
+struct ScanOutcome {
+    emitted: usize,
+    errors: Vec<ScanFileError>,
+}
+
+/// What a worker is currently reading, so the watchdog can spot it hanging on dead I/O.
+#[derive(Default)]
+struct ScanWorkerSlot {
+    current: Mutex<Option<(usize, Instant)>>,
+    abandoned: AtomicBool,
+}
+
+struct ScanWorkerContext {
+    entries: Vec<(String, f64)>,
+    next_index: AtomicUsize,
+    known: Arc<HashMap<String, f64>>,
+    mappers: Arc<MapperFilter>,
+    lazer_resolver: Option<Arc<LazerResolvedAssets>>,
+    file_delay: Option<Duration>,
+    low_priority: bool,
+    detail_level: ScanDetailLevel,
+}
+
+/// Reports `Finished` when a worker exits, including by panic, unless the watchdog already wrote it off.
+struct ScanWorkerGuard {
+    slot: Arc<ScanWorkerSlot>,
+    tx: mpsc::Sender<ScanWorkerMessage>,
+}
+
+impl Drop for ScanWorkerGuard {
+    fn drop(&mut self) {
+        if !self.slot.abandoned.load(Ordering::Acquire) {
+            let _ = self.tx.send(ScanWorkerMessage::Finished);
+        }
+    }
+}
+
+fn spawn_scan_worker(
+    context: Arc<ScanWorkerContext>,
+    tx: mpsc::Sender<ScanWorkerMessage>,
+) -> Arc<ScanWorkerSlot> {
+    let slot = Arc::new(ScanWorkerSlot::default());
+    let guard = ScanWorkerGuard { slot: Arc::clone(&slot), tx };
+
+    // Detached on purpose: a worker stuck in a blocking read can't be interrupted, only left behind.
+    std::thread::spawn(move || {
+        if context.low_priority {
+            lower_current_thread_priority();
+        }
+
+        loop {
+            let index = context.next_index.fetch_add(1, Ordering::Relaxed);
+            let Some((file_path, mtime_ms)) = context.entries.get(index) else {
+                break;
+            };
+
+            *guard.slot.current.lock().unwrap() = Some((index, Instant::now()));
+            let payload = scan_single_osu_file(
+                file_path,
+                *mtime_ms,
+                &context.known,
+                context.mappers.as_ref(),
+                context.lazer_resolver.as_deref(),
+                context.detail_level,
+            );
+            {
+                let mut current = guard.slot.current.lock().unwrap();
+                *current = None;
+                if guard.slot.abandoned.load(Ordering::Acquire) {
+                    break;
+                }
+            }
+
+            let fully_parsed = matches!(&payload, Ok(Some(payload)) if payload.unchanged.is_none());
+            let message = match payload {
+                Ok(Some(payload)) => Some(ScanWorkerMessage::Parsed(Box::new(payload))),
+                Ok(None) => None,
+                Err(error) => Some(ScanWorkerMessage::Failed(error)),
+            };
+            if let Some(message) = message {
+                if guard.tx.send(message).is_err() {
+                    break;
+                }
+            }
+            if let (true, Some(delay)) = (fully_parsed, context.file_delay) {
+                std::thread::sleep(delay);
+            }
+        }
+    });
+
+    slot
+}
+
+/// Parse entries in parallel and stream `scan-batch` events as results complete.
+/// Files that can't be read are reported through `scan-errors` as they come in. Workers stuck on
+/// one file longer than the stall timeout are abandoned the same way: the file is reported as a
+/// `timeout` error, a replacement worker picks up the rest, and the scan carries on.
+#[allow(clippy::too_many_arguments)]
+fn emit_scan_batches(
+    window: &tauri::Window,
+    dir_path: &str,
+    osu_entries: &[(String, f64)],
+    known: Arc<HashMap<String, f64>>,
+    mappers: Arc<MapperFilter>,
+    lazer_resolver: Option<Arc<LazerResolvedAssets>>,
+    total_for_progress: usize,
+    first_batch_index: usize,
+    options: &ScanOptions,
+) -> ScanOutcome {
+    let worker_count = options.worker_count(osu_entries.len());
+    let mut sizing = AdaptiveBatchSize::new(options);
+    let mut batch_size = sizing.size;
+    let stall_timeout = options.stall_timeout();
+
+    let context = Arc::new(ScanWorkerContext {
+        entries: osu_entries.to_vec(),
+        next_index: AtomicUsize::new(0),
+        known,
+        mappers,
+        lazer_resolver,
+        file_delay: options.file_delay(),
+        low_priority: options.low_priority,
+        detail_level: options.detail_level,
+    });
+    let (tx, rx) = mpsc::channel();
+    let mut slots: Vec<Arc<ScanWorkerSlot>> = (0..worker_count)
+        .map(|_| spawn_scan_worker(Arc::clone(&context), tx.clone()))
+        .collect();
+    let mut active_workers = worker_count;
+
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut batch_index = first_batch_index;
+    let mut total_emitted = 0_usize;
+    // Returns the size the next batch should grow to.
+    let mut flush = |batch: &mut Vec<ScanFilePayload>| {
+        if batch.is_empty() {
+            return sizing.size;
+        }
+        if !dir_path.is_empty() {
+            for file in batch.iter_mut() {
+                file.library_root = Some(dir_path.to_string());
+            }
+        }
+        if options.detail_level != ScanDetailLevel::Metadata {
+            record_scan_results(batch);
+        }
+        total_emitted += batch.len();
+        if options.binary_timeline {
+            stash_binary_timelines(batch);
+        }
+        let started = Instant::now();
+        let lag = emit_scan_batch(window, ScanBatchEvent {
+            files: std::mem::replace(batch, Vec::with_capacity(sizing.size)),
+            directory: dir_path.to_string(),
+            batch_index,
+            total_files: total_for_progress,
+        }, options);
+        sizing.record(started.elapsed(), lag);
+        batch_index += 1;
+        sizing.size
+    };
+    let mut errors: Vec<ScanFileError> = Vec::new();
+    let mut reported_errors = 0;
+    let mut report_errors = |errors: &[ScanFileError]| {
+        if errors.len() > reported_errors {
+            let _ = window.emit("scan-errors", ScanErrorsEvent {
+                directory: dir_path.to_string(),
+                errors: errors[reported_errors..].to_vec(),
+            });
+            reported_errors = errors.len();
+        }
+    };
+
+    let poll_interval = (stall_timeout / 4).clamp(Duration::from_millis(50), Duration::from_secs(1));
+    let mut last_watchdog_check = Instant::now();
+
+    while active_workers > 0 {
+        match rx.recv_timeout(poll_interval) {
+            Ok(ScanWorkerMessage::Parsed(payload)) => {
+                batch.push(*payload);
+                if batch.len() >= batch_size {
+                    batch_size = flush(&mut batch);
+                    report_errors(&errors);
+                }
+            }
+            Ok(ScanWorkerMessage::Failed(error)) => errors.push(error),
+            Ok(ScanWorkerMessage::Finished) => active_workers -= 1,
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        if last_watchdog_check.elapsed() < poll_interval {
+            continue;
+        }
+        last_watchdog_check = Instant::now();
+
+        let mut replacements = 0;
+        for slot in &slots {
+            let current = slot.current.lock().unwrap();
+            let Some((index, started)) = *current else {
+                continue;
+            };
+            if slot.abandoned.load(Ordering::Acquire) || started.elapsed() < stall_timeout {
+                continue;
+            }
+            slot.abandoned.store(true, Ordering::Release);
+            drop(current);
+
+            let file_path = &context.entries[index].0;
+            eprintln!("scan worker stalled on {file_path}, skipping");
+            errors.push(ScanFileError::new(
+                file_path,
+                "timeout",
+                format!(
+                    "Timed out after {}s waiting for the file to be read",
+                    stall_timeout.as_secs_f64().round()
+                ),
+            ));
+            replacements += 1;
+        }
+
+        if replacements > 0 {
+            report_errors(&errors);
+            slots.retain(|slot| !slot.abandoned.load(Ordering::Acquire));
+            active_workers -= replacements;
+            // Only replace workers while there is still work left for them to pick up.
+            let remaining = context
+                .entries
+                .len()
+                .saturating_sub(context.next_index.load(Ordering::Relaxed));
+            for _ in 0..replacements.min(remaining) {
+                slots.push(spawn_scan_worker(Arc::clone(&context), tx.clone()));
+                active_workers += 1;
+            }
+        }
+    }
+
+    flush(&mut batch);
+    report_errors(&errors);
+    flush_library_cache();
+    ScanOutcome {
+        emitted: total_emitted,
+        errors,
+    }
+}
+
+/// Run the streaming parse pipeline over an explicit list of beatmap paths instead of walking a directory.
+/// Batches are emitted with an empty `directory` so the renderer merges them into the library.
+fn scan_file_list_streaming(
+    paths: Vec<String>,
+    mapper_name: Option<String>,
+    known_files: Option<HashMap<String, f64>>,
+    options: &ScanOptions,
+    window: &tauri::Window,
+) {
+    let _active_scan = ActiveScanGuard::enter();
+    let mut seen = std::collections::HashSet::new();
+    let osu_entries: Vec<(String, f64)> = paths
+        .into_iter()
+        .filter(|path| seen.insert(path.clone()))
+        .filter_map(|file_path| {
+            let path = Path::new(&file_path);
+            let metadata = fs::metadata(path).ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            let is_osu = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("osu"));
+            if !is_osu && !is_probable_lazer_osu_file(path, metadata.len()) {
+                return None;
+            }
+            let mtime_ms = metadata
+                .modified()
+                .ok()
+                .map(|t| t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() * 1000.0)
+                .unwrap_or(0.0);
+            Some((file_path, mtime_ms))
+        })
+        .collect();
+
+    if let Some(known) = &known_files {
+        // Only paths the caller asked about can be reported; known files elsewhere are out of scope.
+        let requested: HashMap<String, f64> = known
+            .iter()
+            .filter(|(path, _)| seen.contains(*path))
+            .map(|(path, mtime_ms)| (path.clone(), *mtime_ms))
+            .collect();
+        emit_removed_known_files(window, "", None, &requested, &osu_entries);
+    }
+
+    let mut outcome = ScanOutcome::default();
+    if !osu_entries.is_empty() {
+        let mappers = Arc::new(MapperFilter::parse(mapper_name, options));
+        let total_for_progress = count_matching_entries(&osu_entries, mappers.as_ref(), None);
+        outcome = emit_scan_batches(
+            window,
+            "",
+            &osu_entries,
+            Arc::new(known_files.unwrap_or_default()),
+            mappers,
+            None,
+            total_for_progress,
+            0,
+            options,
+        );
+    }
+
+    let _ = window.emit(
+        "scan-complete",
+        ScanCompleteEvent::new("", outcome.emitted, &outcome.errors),
+    );
+    notify_stale_star_ratings(window);
+}
+
+// ============================================================================
+// Persistent library cache + query engine
+// ============================================================================
+
+const LIBRARY_CACHE_FILE: &str = "library-cache.json";
+/// Star ratings arrive one map at a time; batch them up instead of rewriting the cache per map.
+const LIBRARY_CACHE_FLUSH_THRESHOLD: usize = 200;
+
+/// Summary of one parsed beatmap, kept on disk so the library can be queried without re-reading maps.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct LibraryCacheEntry {
+    file_path: String,
+    mtime_ms: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    beatmap_hash: Option<String>,
+    metadata: ParsedMetadata,
+    #[serde(default)]
+    bpm: BpmSummary,
+    /// End of the last object, matching the "length" osu! shows in song select.
+    #[serde(default)]
+    length_ms: i32,
+    #[serde(default)]
+    object_count: usize,
+    /// rosu-pp release behind `metadata.star_rating`; unknown for ratings cached before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    star_rating_version: Option<String>,
+    /// Library root the map was last scanned under, if it came from a directory scan.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    library_root: Option<String>,
+    /// Where `metadata.language`/`genre` came from: `heuristic` or `api`, as in
+    /// `BeatmapClassificationPayload`. Unset for entries cached before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    classification_source: Option<String>,
+}
+
+impl LibraryCacheEntry {
+    /// A rating is shown but came from another (or an unknown) calculator release.
+    fn has_stale_star_rating(&self) -> bool {
+        self.metadata.star_rating >= 0.0 && self.star_rating_version.as_deref() != Some(ROSU_PP_VERSION)
+    }
+
+    /// Build from a full scan result; `unchanged` placeholders carry no metadata and return `None`.
+    fn from_scan_payload(payload: &ScanFilePayload) -> Option<Self> {
+        let metadata = payload.metadata.clone()?;
+        let hit_ends = payload.hit_ends.as_deref().unwrap_or_default();
+        Some(Self {
+            file_path: payload.file_path.clone(),
+            mtime_ms: payload.stat.mtime_ms,
+            beatmap_hash: payload.beatmap_hash.clone(),
+            metadata,
+            bpm: payload.bpm.clone().unwrap_or_default(),
+            length_ms: hit_ends.iter().max().copied().unwrap_or(0),
+            object_count: hit_ends.len(),
+            star_rating_version: None,
+            library_root: payload.library_root.clone(),
+            classification_source: Some("heuristic".to_string()),
+        })
+    }
+}
+
+#[derive(Default)]
+struct LibraryCache {
+    path: Option<PathBuf>,
+    entries: HashMap<String, LibraryCacheEntry>,
+    pending_writes: usize,
+    /// Bumped on every change so derived views know when to rebuild.
+    generation: u64,
+    /// Bumped only when entries are added or removed, not for star ratings.
+    content_generation: u64,
+}
+
+/// Entries are keyed by the path string exactly as the scan reported it, so lookups stay
+/// syscall-free and deleted files can still be matched.
+impl LibraryCache {
+    fn load(dir: Option<&Path>) -> Self {
+        let path = dir.map(|dir| dir.join(LIBRARY_CACHE_FILE));
+        let mut pending_writes = 0;
+        let entries = path
+            .as_deref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice::<Vec<LibraryCacheEntry>>(&bytes).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|mut entry: LibraryCacheEntry| {
+                // Entries cached before language/genre were guessed get them once; the source is
+                // saved with them, so maps that guess to nothing aren't guessed again every start.
+                if entry.classification_source.is_none() {
+                    entry.metadata.language = guess_language(&entry.metadata).to_string();
+                    entry.metadata.genre = guess_genre(&entry.metadata.source, &entry.metadata.tags).to_string();
+                    entry.classification_source = Some("heuristic".to_string());
+                    pending_writes += 1;
+                }
+                (entry.file_path.clone(), entry)
+            })
+            .collect();
+        Self { path, entries, pending_writes, generation: 0, content_generation: 0 }
+    }
+
+    fn upsert(&mut self, mut entry: LibraryCacheEntry) {
+        // A rescan only re-guesses language and genre; keep what the osu! API said instead.
+        if let Some(previous) = self
+            .entries
+            .get(&entry.file_path)
+            .filter(|previous| previous.classification_source.as_deref() == Some("api"))
+        {
+            entry.metadata.language = previous.metadata.language.clone();
+            entry.metadata.genre = previous.metadata.genre.clone();
+            entry.classification_source = previous.classification_source.clone();
+        }
+        self.entries.insert(entry.file_path.clone(), entry);
+        self.pending_writes += 1;
+        self.generation += 1;
+        self.content_generation += 1;
+    }
+
+    fn remove(&mut self, file_path: &str) {
+        if self.entries.remove(file_path).is_some() {
+            self.pending_writes += 1;
+            self.generation += 1;
+            self.content_generation += 1;
+        }
+    }
+
+    fn set_beatmap_hash(&mut self, file_path: &str, beatmap_hash: String) {
+        if let Some(entry) = self.entries.get_mut(file_path) {
+            entry.beatmap_hash = Some(beatmap_hash);
+            self.pending_writes += 1;
+            self.generation += 1;
+            if self.pending_writes >= LIBRARY_CACHE_FLUSH_THRESHOLD {
+                let _ = self.flush();
+            }
+        }
+    }
+
+    fn set_star_rating(&mut self, file_path: &str, star_rating: f64) {
+        if let Some(entry) = self.entries.get_mut(file_path) {
+            entry.metadata.star_rating = star_rating;
+            entry.star_rating_version = Some(ROSU_PP_VERSION.to_string());
+            self.pending_writes += 1;
+            self.generation += 1;
+            if self.pending_writes >= LIBRARY_CACHE_FLUSH_THRESHOLD {
+                let _ = self.flush();
+            }
+        }
+    }
+
+    fn set_classification(&mut self, file_path: &str, payload: &BeatmapClassificationPayload) {
+        if let Some(entry) = self.entries.get_mut(file_path) {
+            entry.metadata.language = payload.language.clone();
+            entry.metadata.genre = payload.genre.clone();
+            entry.classification_source = Some(payload.source.to_string());
+            self.pending_writes += 1;
+            self.generation += 1;
+            if self.pending_writes >= LIBRARY_CACHE_FLUSH_THRESHOLD {
+                let _ = self.flush();
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        if self.pending_writes == 0 {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let entries: Vec<&LibraryCacheEntry> = self.entries.values().collect();
+        let bytes = serde_json::to_vec(&entries).map_err(|err| err.to_string())?;
+        write_file_atomically(&path, &bytes)?;
+        self.pending_writes = 0;
+        Ok(())
+    }
+}
+
+static LIBRARY_CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+static LIBRARY_CACHE: OnceLock<Mutex<LibraryCache>> = OnceLock::new();
+
+/// Loaded lazily on first use so a large cache doesn't hold up startup.
+fn library_cache() -> &'static Mutex<LibraryCache> {
+    LIBRARY_CACHE.get_or_init(|| Mutex::new(LibraryCache::load(LIBRARY_CACHE_DIR.get().map(PathBuf::as_path))))
+}
+
+fn record_scan_results(files: &[ScanFilePayload]) {
+    let backups = *BACKUP_SETTINGS.lock().unwrap();
+    let mut saves = Vec::new();
+    let mut unchanged = Vec::new();
+    {
+        let mut cache = library_cache().lock().unwrap();
+        for entry in files.iter().filter_map(LibraryCacheEntry::from_scan_payload) {
+            match cache.entries.get(&entry.file_path) {
+                Some(previous) if entry.mtime_ms - previous.mtime_ms >= 0.5 => {
+                    let objects_delta = entry.object_count as i64 - previous.object_count as i64;
+                    saves.push((entry.file_path.clone(), entry.mtime_ms, previous.mtime_ms, objects_delta));
+                }
+                _ if backups.enabled => unchanged.push(entry.file_path.clone()),
+                _ => {}
+            }
+            cache.upsert(entry);
+        }
+    }
+    if backups.enabled {
+        // The first time a map is seen with backups on, keep its current content as the baseline,
+        // so the version from before the first outside edit can still be restored.
+        for file_path in &unchanged {
+            let has_baseline = beatmap_backup_dir(file_path).is_some_and(|dir| dir.exists());
+            if !has_baseline {
+                if let Err(err) = backup_changed_map(file_path, backups.retention) {
+                    eprintln!("failed to back up {file_path}: {err}");
+                }
+            }
+        }
+    }
+    if saves.is_empty() {
+        return;
+    }
+    for (file_path, ..) in &saves {
+        if let Err(err) = snapshot_saved_map(file_path) {
+            eprintln!("failed to snapshot {file_path}: {err}");
+        }
+    }
+    if backups.enabled {
+        for (file_path, ..) in &saves {
+            if let Err(err) = backup_changed_map(file_path, backups.retention) {
+                eprintln!("failed to back up {file_path}: {err}");
+            }
+        }
+    }
+    let mut stats = work_stats().lock().unwrap();
+    for (file_path, saved_at_ms, previous_saved_at_ms, objects_delta) in saves {
+        stats.record_save(&file_path, saved_at_ms, previous_saved_at_ms, objects_delta);
+    }
+}
+
+fn flush_library_cache() {
+    let flushed = library_cache().lock().map_err(|err| err.to_string()).and_then(|mut cache| cache.flush());
+    if let Err(err) = flushed {
+        eprintln!("failed to write library cache: {err}");
+    }
+    let flushed = star_rating_cache().lock().map_err(|err| err.to_string()).and_then(|mut cache| cache.flush());
+    if let Err(err) = flushed {
+        eprintln!("failed to write star rating cache: {err}");
+    }
+    let flushed = work_stats().lock().map_err(|err| err.to_string()).and_then(|mut stats| stats.flush());
+    if let Err(err) = flushed {
+        eprintln!("failed to write work stats: {err}");
+    }
+}
+const STAR_RATING_CACHE_FILE: &str = "star-rating-cache.json";
+/// Recorded with every rating so older ratings are recalculated instead of silently disagreeing
+/// with fresh ones. Must match the exact `rosu-pp` pin in Cargo.toml.
+const ROSU_PP_VERSION: &str = "1.1.0";
+
+/// A rating and the calculator release that produced it. Caches written before ratings were
+/// versioned hold bare numbers, which take the file's version.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+enum StoredStarRating {
+    Versioned { stars: f64, version: String },
+    Bare(f64),
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct StarRatingCacheFile {
+    /// Release that wrote the file.
+    rosu_pp_version: String,
+    ratings: HashMap<String, StoredStarRating>,
+}
+
+/// Star ratings keyed by `<content md5>:<mods>`. Editing a map changes its md5, so stale
+/// ratings are never returned and simply age out of use. Ratings from another rosu-pp release
+/// are kept until recalculated but never returned either.
+#[derive(Default)]
+struct StarRatingCache {
+    path: Option<PathBuf>,
+    /// Rating and calculator version per key.
+    ratings: HashMap<String, (f64, String)>,
+    pending_writes: usize,
+}
+
+impl StarRatingCache {
+    fn load(dir: Option<&Path>) -> Self {
+        let path = dir.map(|dir| dir.join(STAR_RATING_CACHE_FILE));
+        let ratings = path
+            .as_deref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice::<StarRatingCacheFile>(&bytes).ok())
+            .map(|file| {
+                file.ratings
+                    .into_iter()
+                    .map(|(key, rating)| match rating {
+                        StoredStarRating::Versioned { stars, version } => (key, (stars, version)),
+                        StoredStarRating::Bare(stars) => (key, (stars, file.rosu_pp_version.clone())),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { path, ratings, pending_writes: 0 }
+    }
+
+    fn key(beatmap_hash: &str, mods: u32) -> String {
+        format!("{beatmap_hash}:{mods}")
+    }
+
+    fn get(&self, beatmap_hash: &str, mods: u32) -> Option<f64> {
+        self.ratings
+            .get(&Self::key(beatmap_hash, mods))
+            .filter(|(_, version)| version == ROSU_PP_VERSION)
+            .map(|(stars, _)| *stars)
+    }
+
+    fn insert(&mut self, beatmap_hash: &str, mods: u32, star_rating: f64) {
+        self.ratings.insert(Self::key(beatmap_hash, mods), (star_rating, ROSU_PP_VERSION.to_string()));
+        self.pending_writes += 1;
+        if self.pending_writes >= LIBRARY_CACHE_FLUSH_THRESHOLD {
+            let _ = self.flush();
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        if self.pending_writes == 0 {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let ratings = self
+            .ratings
+            .iter()
+            .map(|(key, (stars, version))| {
+                (key.clone(), StoredStarRating::Versioned { stars: *stars, version: version.clone() })
+            })
+            .collect();
+        let file = StarRatingCacheFile { rosu_pp_version: ROSU_PP_VERSION.to_string(), ratings };
+        let bytes = serde_json::to_vec(&file).map_err(|err| err.to_string())?;
+        write_file_atomically(&path, &bytes)?;
+        self.pending_writes = 0;
+        Ok(())
+    }
+}
+
+static STAR_RATING_CACHE: OnceLock<Mutex<StarRatingCache>> = OnceLock::new();
+
+fn star_rating_cache() -> &'static Mutex<StarRatingCache> {
+    STAR_RATING_CACHE.get_or_init(|| Mutex::new(StarRatingCache::load(LIBRARY_CACHE_DIR.get().map(PathBuf::as_path))))
+}
+
+/// Star rating from the persistent cache, computing and storing it on a miss.
+fn cached_star_rating(bytes: &[u8], mods: u32) -> Option<f64> {
+    let beatmap_hash = compute_osu_md5_hex(bytes);
+    if let Some(star_rating) = star_rating_cache().lock().ok().and_then(|cache| cache.get(&beatmap_hash, mods)) {
+        return Some(star_rating);
+    }
+    let star_rating = compute_star_rating(bytes, mods)?;
+    if let Ok(mut cache) = star_rating_cache().lock() {
+        cache.insert(&beatmap_hash, mods, star_rating);
+    }
+    Some(star_rating)
+}
+
+const WORK_STATS_FILE: &str = "work-stats.json";
+/// Saves further apart than this start a new edit session.
+const WORK_SESSION_GAP_MS: f64 = 30.0 * 60.0 * 1000.0;
+/// Time credited before the first save of a session, when the previous save is too old to tell.
+const WORK_SESSION_LEAD_IN_MS: f64 = 2.0 * 60.0 * 1000.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WorkSession {
+    started_at_ms: f64,
+    ended_at_ms: f64,
+    active_ms: f64,
+    saves: u32,
+    /// Net change in object count; negative when a session mostly deleted objects.
+    objects_added: i64,
+}
+
+/// Edit sessions per beatmap, reconstructed from the mtime changes rescans pick up.
+#[derive(Default)]
+struct WorkStatsStore {
+    path: Option<PathBuf>,
+    maps: HashMap<String, Vec<WorkSession>>,
+    pending_writes: usize,
+}
+
+impl WorkStatsStore {
+    fn load(dir: Option<&Path>) -> Self {
+        let path = dir.map(|dir| dir.join(WORK_STATS_FILE));
+        let maps = path
+            .as_deref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Self { path, maps, pending_writes: 0 }
+    }
+
+    /// Record a save at `saved_at_ms`; `previous_saved_at_ms` is the mtime the library last saw.
+    fn record_save(&mut self, file_path: &str, saved_at_ms: f64, previous_saved_at_ms: f64, objects_delta: i64) {
+        let sessions = self.maps.entry(file_path.to_string()).or_default();
+        match sessions.last_mut() {
+            Some(session) if saved_at_ms <= session.ended_at_ms => return,
+            Some(session) if saved_at_ms - session.ended_at_ms <= WORK_SESSION_GAP_MS => {
+                session.active_ms += saved_at_ms - session.ended_at_ms;
+                session.ended_at_ms = saved_at_ms;
+                session.saves += 1;
+                session.objects_added += objects_delta;
+            }
+            _ => {
+                let since_previous = saved_at_ms - previous_saved_at_ms;
+                let started_at_ms = if since_previous > 0.0 && since_previous <= WORK_SESSION_GAP_MS {
+                    previous_saved_at_ms
+                } else {
+                    saved_at_ms - WORK_SESSION_LEAD_IN_MS
+                };
+                sessions.push(WorkSession {
+                    started_at_ms,
+                    ended_at_ms: saved_at_ms,
+                    active_ms: saved_at_ms - started_at_ms,
+                    saves: 1,
+                    objects_added: objects_delta,
+                });
+            }
+        }
+        self.pending_writes += 1;
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        if self.pending_writes == 0 {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        let bytes = serde_json::to_vec(&self.maps).map_err(|err| err.to_string())?;
+        write_file_atomically(&path, &bytes)?;
+        self.pending_writes = 0;
+        Ok(())
+    }
+}
+
+static WORK_STATS: OnceLock<Mutex<WorkStatsStore>> = OnceLock::new();
+
+fn work_stats() -> &'static Mutex<WorkStatsStore> {
+    WORK_STATS.get_or_init(|| Mutex::new(WorkStatsStore::load(LIBRARY_CACHE_DIR.get().map(PathBuf::as_path))))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LibraryFilterOp {
+    Contains,
+    Exact,
+    NotEq,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+}
+
+#[derive(Debug, Clone)]
+struct LibraryFilterCondition {
+    key: String,
+    op: LibraryFilterOp,
+    value: String,
+}
+
+#[derive(Debug, Default)]
+struct LibraryFilter {
+    conditions: Vec<LibraryFilterCondition>,
+    text_terms: Vec<String>,
+}
+
+const LIBRARY_NUMERIC_KEYS: [&str; 14] = [
+    "stars", "star", "sr", "bpm", "length", "cs", "ar", "od", "hp", "keys", "objects", "mode", "m", "preview",
+];
+const LIBRARY_TEXT_KEYS: [&str; 13] = [
+    "creator", "mapper", "author", "artist", "title", "version", "diff", "difficulty", "source", "tag", "language",
+    "lang", "genre",
+];
+
+/// Split on whitespace, keeping `"quoted values"` together and dropping the quotes.
+fn tokenize_library_filter(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for ch in input.chars() {
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            ch if ch.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            ch => current.push(ch),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Parse osu!-style filter syntax: `stars>5.5 bpm>=200 creator=Sotarks length<200 mode=mania`.
+/// Tokens that aren't `key<op>value` for a known key are free-text terms.
+fn parse_library_filter(input: &str) -> LibraryFilter {
+    const OPERATORS: [(&str, LibraryFilterOp); 8] = [
+        (">=", LibraryFilterOp::Gte),
+        ("<=", LibraryFilterOp::Lte),
+        ("!=", LibraryFilterOp::NotEq),
+        ("==", LibraryFilterOp::Exact),
+        (">", LibraryFilterOp::Gt),
+        ("<", LibraryFilterOp::Lt),
+        ("=", LibraryFilterOp::Contains),
+        (":", LibraryFilterOp::Contains),
+    ];
+
+    let mut filter = LibraryFilter::default();
+    for token in tokenize_library_filter(input) {
+        let condition = token.find(['<', '>', '=', '!', ':']).and_then(|op_start| {
+            let key = token[..op_start].to_ascii_lowercase();
+            if !LIBRARY_NUMERIC_KEYS.contains(&key.as_str()) && !LIBRARY_TEXT_KEYS.contains(&key.as_str()) {
+                return None;
+            }
+            let rest = &token[op_start..];
+            let (symbol, op) = OPERATORS.iter().find(|(symbol, _)| rest.starts_with(symbol))?;
+            let value = rest[symbol.len()..].trim().to_string();
+            (!value.is_empty()).then_some(LibraryFilterCondition { key, op: *op, value })
+        });
+        match condition {
+            Some(condition) => filter.conditions.push(condition),
+            None => filter.text_terms.push(token.to_lowercase()),
+        }
+    }
+    filter
+}
+
+fn parse_mode_filter_value(value: &str) -> Option<f64> {
+    match value.to_ascii_lowercase().as_str() {
+        "osu" | "std" | "standard" | "0" => Some(0.0),
+        "taiko" | "1" => Some(1.0),
+        "catch" | "fruits" | "ctb" | "2" => Some(2.0),
+        "mania" | "3" => Some(3.0),
+        _ => None,
+    }
+}
+
+fn library_numeric_value(entry: &LibraryCacheEntry, key: &str) -> Option<f64> {
+    let metadata = &entry.metadata;
+    let value = match key {
+        "stars" | "star" | "sr" => metadata.star_rating,
+        "bpm" => entry.bpm.dominant,
+        "length" => f64::from(entry.length_ms) / 1000.0,
+        "cs" => metadata.difficulty.cs,
+        "keys" if metadata.mode == 3 => metadata.difficulty.cs,
+        "ar" => metadata.difficulty.ar,
+        "od" => metadata.difficulty.od,
+        "hp" => metadata.difficulty.hp,
+        "objects" => entry.object_count as f64,
+        "mode" | "m" => f64::from(metadata.mode),
+        "preview" => f64::from(metadata.preview_time),
+        _ => return None,
+    };
+    // Star ratings are -1 until calculated; unknown values never satisfy a comparison.
+    (value >= 0.0).then_some(value)
+}
+
+fn library_text_value<'a>(entry: &'a LibraryCacheEntry, key: &str) -> Vec<&'a str> {
+    let metadata = &entry.metadata;
+    match key {
+        "creator" | "mapper" | "author" => vec![&metadata.creator],
+        "artist" => vec![&metadata.artist, &metadata.artist_unicode],
+        "title" => vec![&metadata.title, &metadata.title_unicode],
+        "version" | "diff" | "difficulty" => vec![&metadata.version],
+        "source" => vec![&metadata.source],
+        "tag" => vec![&metadata.tags],
+        "language" | "lang" => vec![&metadata.language],
+        "genre" => vec![&metadata.genre],
+        _ => Vec::new(),
+    }
+}
+
+fn library_condition_matches(entry: &LibraryCacheEntry, condition: &LibraryFilterCondition) -> bool {
+    let LibraryFilterCondition { key, op, value } = condition;
+
+    if LIBRARY_NUMERIC_KEYS.contains(&key.as_str()) {
+        let target = if matches!(key.as_str(), "mode" | "m") {
+            parse_mode_filter_value(value)
+        } else {
+            value.trim_end_matches(['s', 'x']).parse::<f64>().ok()
+        };
+        let (Some(actual), Some(target)) = (library_numeric_value(entry, key), target) else {
+            return false;
+        };
+        // Match what a user means by "bpm=200" or "stars=5.5" rather than exact float equality;
+        // the ordering operators treat values within the tolerance as equal too, so `<` and `>=`
+        // always split the library the same way `=` does.
+        let tolerance = match key.as_str() {
+            "stars" | "star" | "sr" => 0.005,
+            "bpm" | "length" => 0.5,
+            _ => 0.05,
+        };
+        return match op {
+            LibraryFilterOp::Contains | LibraryFilterOp::Exact => (actual - target).abs() < tolerance,
+            LibraryFilterOp::NotEq => (actual - target).abs() >= tolerance,
+            LibraryFilterOp::Lt => actual <= target - tolerance,
+            LibraryFilterOp::Lte => actual < target + tolerance,
+            LibraryFilterOp::Gt => actual >= target + tolerance,
+            LibraryFilterOp::Gte => actual > target - tolerance,
+        };
+    }
+
+    let needle = value.to_lowercase();
+    let haystacks = library_text_value(entry, key);
+    match op {
+        LibraryFilterOp::Exact => haystacks.iter().any(|text| text.to_lowercase() == needle),
+        LibraryFilterOp::NotEq => !haystacks.iter().any(|text| text.to_lowercase().contains(&needle)),
+        _ => haystacks.iter().any(|text| text.to_lowercase().contains(&needle)),
+    }
+}
+
+fn library_entry_matches(entry: &LibraryCacheEntry, filter: &LibraryFilter) -> bool {
+    if !filter.conditions.iter().all(|condition| library_condition_matches(entry, condition)) {
+        return false;
+    }
+    if filter.text_terms.is_empty() {
+        return true;
+    }
+    let metadata = &entry.metadata;
+    let searchable = [
+        &metadata.artist,
+        &metadata.artist_unicode,
+        &metadata.title,
+        &metadata.title_unicode,
+        &metadata.creator,
+        &metadata.version,
+        &metadata.source,
+        &metadata.tags,
+    ]
+    .iter()
+    .map(|text| text.to_lowercase())
+    .collect::<Vec<_>>()
+    .join(" ");
+    filter.text_terms.iter().all(|term| searchable.contains(term.as_str()))
+}
+
+fn compare_library_entries(a: &LibraryCacheEntry, b: &LibraryCacheEntry, sort_key: &str) -> std::cmp::Ordering {
+    let text = |value: &str| value.to_lowercase();
+    match sort_key {
+        "artist" => text(&a.metadata.artist).cmp(&text(&b.metadata.artist)),
+        "creator" | "mapper" => text(&a.metadata.creator).cmp(&text(&b.metadata.creator)),
+        "version" | "difficulty" => text(&a.metadata.version).cmp(&text(&b.metadata.version)),
+        "date" | "modified" => a.mtime_ms.total_cmp(&b.mtime_ms),
+        "stars" | "bpm" | "length" | "cs" | "ar" | "od" | "hp" | "objects" | "mode" => {
+            let value = |entry| library_numeric_value(entry, sort_key).unwrap_or(-1.0);
+            value(a).total_cmp(&value(b))
+        }
+        _ => text(&a.metadata.title).cmp(&text(&b.metadata.title)),
+    }
+    .then_with(|| a.file_path.cmp(&b.file_path))
+}
+
+/// Evaluate an osu!-style filter against the persistent scan cache and return matching paths,
+/// sorted by `sort_key` (title by default).
+#[tauri::command]
+async fn query_library(
+    filter: String,
+    sort_key: Option<String>,
+    descending: Option<bool>,
+) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let filter = parse_library_filter(&filter);
+        let sort_key = sort_key.unwrap_or_default().to_ascii_lowercase();
+        let cache = library_cache().lock().map_err(|err| err.to_string())?;
+        let mut matches: Vec<&LibraryCacheEntry> = cache
+            .entries
+            .values()
+            .filter(|entry| library_entry_matches(entry, &filter))
+            .collect();
+        matches.sort_by(|a, b| compare_library_entries(a, b, &sort_key));
+        if descending.unwrap_or(false) {
+            matches.reverse();
+        }
+        Ok(matches.into_iter().map(|entry| entry.file_path.clone()).collect())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// The sorted, filtered path list behind the last page request, reused while the filter,
+/// sort and library generation stay the same.
+struct LibraryView {
+    generation: u64,
+    filter: String,
+    sort_key: String,
+    descending: bool,
+    paths: Vec<String>,
+}
+
+impl LibraryView {
+    fn build(cache: &LibraryCache, filter: String, sort_key: String, descending: bool) -> Self {
+        let parsed_filter = parse_library_filter(&filter);
+        let mut matches: Vec<&LibraryCacheEntry> = cache
+            .entries
+            .values()
+            .filter(|entry| library_entry_matches(entry, &parsed_filter))
+            .collect();
+        matches.sort_by(|a, b| compare_library_entries(a, b, &sort_key));
+        if descending {
+            matches.reverse();
+        }
+        Self {
+            generation: cache.generation,
+            paths: matches.into_iter().map(|entry| entry.file_path.clone()).collect(),
+            filter,
+            sort_key,
+            descending,
+        }
+    }
+
+    fn matches(&self, cache: &LibraryCache, filter: &str, sort_key: &str, descending: bool) -> bool {
+        self.generation == cache.generation
+            && self.filter == filter
+            && self.sort_key == sort_key
+            && self.descending == descending
+    }
+}
+
+/// In-memory library state shared by the list commands. Entries themselves live in the
+/// library cache; this holds the current sort and the last computed view.
+#[derive(Default)]
+struct Library {
+    /// Set by `sort_library`; page requests without their own sort use it.
+    sort_key: String,
+    descending: bool,
+    view: Option<LibraryView>,
+    search_index: Option<SearchIndex>,
+}
+
+impl Library {
+    /// The view for `filter` and the given sort, rebuilt only when something changed.
+    fn view(&mut self, cache: &LibraryCache, filter: String, sort_key: String, descending: bool) -> &LibraryView {
+        let view = match self.view.take() {
+            Some(view) if view.matches(cache, &filter, &sort_key, descending) => view,
+            _ => LibraryView::build(cache, filter, sort_key, descending),
+        };
+        self.view.insert(view)
+    }
+}
+
+#[derive(Default)]
+struct LibraryState(Arc<Mutex<Library>>);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LibraryPagePayload {
+    /// Matching entries across all pages.
+    total: usize,
+    offset: usize,
+    entries: Vec<LibraryCacheEntry>,
+}
+
+/// One page of the library cache, filtered and sorted like `query_library`, so the list can be
+/// virtualized instead of holding every scan result in the renderer.
+#[tauri::command]
+async fn get_library_page(
+    state: tauri::State<'_, LibraryState>,
+    offset: usize,
+    limit: usize,
+    sort_key: Option<String>,
+    descending: Option<bool>,
+    filter: Option<String>,
+) -> Result<LibraryPagePayload, String> {
+    let library = Arc::clone(&state.0);
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache = library_cache().lock().map_err(|err| err.to_string())?;
+        let mut library = library.lock().map_err(|err| err.to_string())?;
+        let sort_key = sort_key.unwrap_or_else(|| library.sort_key.clone()).to_ascii_lowercase();
+        let descending = descending.unwrap_or(library.descending);
+        let view = library.view(&cache, filter.unwrap_or_default(), sort_key, descending);
+        Ok(LibraryPagePayload {
+            total: view.paths.len(),
+            offset,
+            entries: view
+                .paths
+                .iter()
+                .skip(offset)
+                .take(limit)
+                .filter_map(|path| cache.entries.get(path).cloned())
+                .collect(),
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Set the library's sort (`dir` is "asc" or "desc") and return every path in that order.
+#[tauri::command]
+async fn sort_library(
+    state: tauri::State<'_, LibraryState>,
+    key: String,
+    dir: Option<String>,
+) -> Result<Vec<String>, String> {
+    let library = Arc::clone(&state.0);
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache = library_cache().lock().map_err(|err| err.to_string())?;
+        let mut library = library.lock().map_err(|err| err.to_string())?;
+        library.sort_key = key.to_ascii_lowercase();
+        library.descending = dir.is_some_and(|dir| dir.eq_ignore_ascii_case("desc"));
+        let (sort_key, descending) = (library.sort_key.clone(), library.descending);
+        Ok(library.view(&cache, String::new(), sort_key, descending).paths.clone())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LibraryStatsPayload {
+    total: usize,
+    mode_counts: BTreeMap<String, usize>,
+    /// "submitted" when the difficulty has a BeatmapSetID, "unsubmitted" otherwise.
+    status_counts: BTreeMap<String, usize>,
+    /// Difficulties per whole star, 0★ through 10★+.
+    star_distribution: Vec<usize>,
+    /// Difficulties without a calculated star rating yet.
+    unrated: usize,
+}
+
+/// Counts per mode and submission status plus a star histogram over the whole library cache.
+#[tauri::command]
+async fn get_library_stats() -> Result<LibraryStatsPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache = library_cache().lock().map_err(|err| err.to_string())?;
+        let mut stats = LibraryStatsPayload {
+            total: cache.entries.len(),
+            mode_counts: BTreeMap::new(),
+            status_counts: BTreeMap::new(),
+            star_distribution: vec![0; 11],
+            unrated: 0,
+        };
+        for entry in cache.entries.values() {
+            let metadata = &entry.metadata;
+            *stats.mode_counts.entry(mode_display_name(metadata.mode).to_string()).or_insert(0) += 1;
+            let submitted = metadata.beatmap_set_id.starts_with("https://")
+                || metadata.beatmap_set_id.parse::<i64>().is_ok_and(|id| id > 0);
+            let status = if submitted { "submitted" } else { "unsubmitted" };
+            *stats.status_counts.entry(status.to_string()).or_insert(0) += 1;
+            if metadata.star_rating >= 0.0 {
+                stats.star_distribution[(metadata.star_rating.floor() as usize).min(10)] += 1;
+            } else {
+                stats.unrated += 1;
+            }
+        }
+        Ok(stats)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Share of a query term's trigrams a document must contain to count as a (fuzzy) match.
+const SEARCH_MIN_TERM_SIMILARITY: f64 = 0.5;
+const SEARCH_DEFAULT_LIMIT: usize = 100;
+
+/// Trigram index over title/artist/creator/version/tags. Built from the library cache and
+/// rebuilt when a scan changes its entries.
+struct SearchIndex {
+    content_generation: u64,
+    paths: Vec<String>,
+    /// Lowercased title and artist, for ranking.
+    headlines: Vec<String>,
+    /// Lowercased searchable text of every field.
+    texts: Vec<String>,
+    trigrams: HashMap<[char; 3], Vec<u32>>,
+}
+
+/// Trigrams of each word padded with spaces, so short words and word starts still index.
+fn word_trigrams(text: &str) -> Vec<[char; 3]> {
+    let mut trigrams = Vec::new();
+    for word in text.split_whitespace() {
+        let padded: Vec<char> = std::iter::once(' ').chain(word.chars()).chain(std::iter::once(' ')).collect();
+        trigrams.extend(padded.windows(3).map(|window| [window[0], window[1], window[2]]));
+    }
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    trigrams
+}
+
+impl SearchIndex {
+    fn build(cache: &LibraryCache) -> Self {
+        let mut index = Self {
+            content_generation: cache.content_generation,
+            paths: Vec::with_capacity(cache.entries.len()),
+            headlines: Vec::with_capacity(cache.entries.len()),
+            texts: Vec::with_capacity(cache.entries.len()),
+            trigrams: HashMap::new(),
+        };
+        for entry in cache.entries.values() {
+            let metadata = &entry.metadata;
+            let doc = index.paths.len() as u32;
+            let headline = format!("{} {}", metadata.title, metadata.artist).to_lowercase();
+            let text = format!(
+                "{headline} {} {} {} {} {}",
+                metadata.title_unicode, metadata.artist_unicode, metadata.creator, metadata.version, metadata.tags
+            )
+            .to_lowercase();
+            for trigram in word_trigrams(&text) {
+                index.trigrams.entry(trigram).or_default().push(doc);
+            }
+            index.paths.push(entry.file_path.clone());
+            index.headlines.push(headline);
+            index.texts.push(text);
+        }
+        index
+    }
+
+    /// Documents matching every query term, best first. A term matches when enough of its
+    /// trigrams appear, so small typos still find the map; exact and headline hits rank higher.
+    fn search(&self, query: &str, limit: usize) -> Vec<(u32, f64)> {
+        let query = query.to_lowercase();
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: HashMap<u32, (usize, f64)> = HashMap::new();
+        for term in &terms {
+            let trigrams = word_trigrams(term);
+            let mut hits: HashMap<u32, usize> = HashMap::new();
+            for trigram in &trigrams {
+                for &doc in self.trigrams.get(trigram).into_iter().flatten() {
+                    *hits.entry(doc).or_insert(0) += 1;
+                }
+            }
+            for (doc, count) in hits {
+                let similarity = count as f64 / trigrams.len() as f64;
+                if similarity < SEARCH_MIN_TERM_SIMILARITY {
+                    continue;
+                }
+                let mut score = similarity;
+                if self.texts[doc as usize].contains(term) {
+                    score += 0.5;
+                }
+                if self.headlines[doc as usize].contains(term) {
+                    score += 0.5;
+                }
+                let slot = scores.entry(doc).or_insert((0, 0.0));
+                slot.0 += 1;
+                slot.1 += score;
+            }
+        }
+
+        let mut ranked: Vec<(u32, f64)> = scores
+            .into_iter()
+            .filter(|(_, (matched_terms, _))| *matched_terms == terms.len())
+            .map(|(doc, (_, score))| (doc, score))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| self.paths[a.0 as usize].cmp(&self.paths[b.0 as usize])));
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LibrarySearchHit {
+    file_path: String,
+    score: f64,
+}
+
+/// Fuzzy full-text search over the library, ranked by how closely each map matches `query`.
+#[tauri::command]
+async fn search_library(
+    state: tauri::State<'_, LibraryState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<LibrarySearchHit>, String> {
+    let library = Arc::clone(&state.0);
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache = library_cache().lock().map_err(|err| err.to_string())?;
+        let mut library = library.lock().map_err(|err| err.to_string())?;
+        let index = match library.search_index.take() {
+            Some(index) if index.content_generation == cache.content_generation => index,
+            _ => SearchIndex::build(&cache),
+        };
+        let hits = index
+            .search(&query, limit.unwrap_or(SEARCH_DEFAULT_LIMIT))
+            .into_iter()
+            .map(|(doc, score)| LibrarySearchHit { file_path: index.paths[doc as usize].clone(), score })
+            .collect();
+        library.search_index = Some(index);
+        Ok(hits)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const DEFAULT_EXPORT_FIELDS: [&str; 15] = [
+    "filePath", "artist", "title", "creator", "version", "mode", "stars", "bpm", "lengthMs", "objects", "cs",
+    "ar", "od", "hp", "beatmapSetId",
+];
+
+/// Column value for `field` (camelCase, as in the JSON payloads); `None` for unknown fields.
+/// Star ratings that haven't been calculated yet export as null.
+fn library_export_value(entry: &LibraryCacheEntry, field: &str) -> Option<Value> {
+    let metadata = &entry.metadata;
+    let value = match field {
+        "filePath" => Value::from(entry.file_path.as_str()),
+        "beatmapHash" => entry.beatmap_hash.as_deref().map_or(Value::Null, Value::from),
+        "beatmapSetId" => Value::from(metadata.beatmap_set_id.as_str()),
+        "title" => Value::from(metadata.title.as_str()),
+        "titleUnicode" => Value::from(metadata.title_unicode.as_str()),
+        "artist" => Value::from(metadata.artist.as_str()),
+        "artistUnicode" => Value::from(metadata.artist_unicode.as_str()),
+        "creator" => Value::from(metadata.creator.as_str()),
+        "version" => Value::from(metadata.version.as_str()),
+        "source" => Value::from(metadata.source.as_str()),
+        "tags" => Value::from(metadata.tags.as_str()),
+        "language" => Value::from(metadata.language.as_str()),
+        "genre" => Value::from(metadata.genre.as_str()),
+        "mode" => Value::from(metadata.mode),
+        "stars" => (metadata.star_rating >= 0.0).then_some(metadata.star_rating).map_or(Value::Null, Value::from),
+        "bpm" => Value::from(entry.bpm.dominant),
+        "bpmMin" => Value::from(entry.bpm.min),
+        "bpmMax" => Value::from(entry.bpm.max),
+        "lengthMs" => Value::from(entry.length_ms),
+        "objects" => Value::from(entry.object_count),
+        "cs" => Value::from(metadata.difficulty.cs),
+        "ar" => Value::from(metadata.difficulty.ar),
+        "od" => Value::from(metadata.difficulty.od),
+        "hp" => Value::from(metadata.difficulty.hp),
+        "previewTime" => Value::from(metadata.preview_time),
+        "audio" => Value::from(metadata.audio.as_str()),
+        "background" => Value::from(metadata.background.as_str()),
+        "video" => Value::from(metadata.video.as_str()),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn csv_escape(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Write every cached library entry to `destination` as CSV or JSON and return the row count.
+#[tauri::command]
+async fn export_scan_results(
+    format: String,
+    destination: String,
+    fields: Option<Vec<String>>,
+) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let fields: Vec<String> = fields
+            .filter(|fields| !fields.is_empty())
+            .unwrap_or_else(|| DEFAULT_EXPORT_FIELDS.iter().map(|field| field.to_string()).collect());
+
+        let cache = library_cache().lock().map_err(|err| err.to_string())?;
+        let mut entries: Vec<&LibraryCacheEntry> = cache.entries.values().collect();
+        entries.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+        let mut rows = Vec::with_capacity(entries.len());
+        for entry in &entries {
+            let row = fields
+                .iter()
+                .map(|field| {
+                    library_export_value(entry, field).ok_or_else(|| format!("unknown export field: {field}"))
+                })
+                .collect::<Result<Vec<Value>, String>>()?;
+            rows.push(row);
+        }
+        drop(cache);
+
+        let bytes = match format.to_ascii_lowercase().as_str() {
+            "csv" => {
+                let mut output = fields.join(",");
+                output.push_str("\r\n");
+                for row in &rows {
+                    let line: Vec<String> = row.iter().map(csv_escape).collect();
+                    output.push_str(&line.join(","));
+                    output.push_str("\r\n");
+                }
+                output.into_bytes()
+            }
+            "json" => {
+                let objects: Vec<serde_json::Map<String, Value>> = rows
+                    .iter()
+                    .map(|row| fields.iter().cloned().zip(row.iter().cloned()).collect())
+                    .collect();
+                serde_json::to_vec_pretty(&objects).map_err(|err| err.to_string())?
+            }
+            other => return Err(format!("unsupported export format: {other}")),
+        };
+
+        write_file_atomically(Path::new(&destination), &bytes)?;
+        Ok(rows.len())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+fn scan_directory_internal(
+    dir_path: &str,
+    mapper_name: Option<String>,
+    known_files: Option<HashMap<String, f64>>,
+    client: OsuClient,
+) -> ScanDirectoryPayload {
+    let _active_scan = ActiveScanGuard::enter();
+    let root = resolve_scan_root(dir_path, client);
+    if !root.exists() || !root.is_dir() {
+        return ScanDirectoryPayload {
+            files: vec![],
+            directory: dir_path.to_string(),
+        };
+    }
+
+    let osu_entries = find_osu_files_with_mtime(&root, client, None);
+    if osu_entries.is_empty() {
+        return ScanDirectoryPayload {
+            files: vec![],
+            directory: dir_path.to_string(),
+        };
+    }
+
+    let known = Arc::new(known_files.unwrap_or_default());
+    let mappers = Arc::new(MapperFilter::parse(mapper_name, &ScanOptions::default()));
+    let lazer_resolver = if client == OsuClient::Lazer {
+        match get_lazer_resolver(dir_path) {
+            Ok(resolver) => resolver,
+            Err(err) => {
+                eprintln!("failed to resolve lazer media from {dir_path}: {err}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let total_for_progress = count_matching_entries(&osu_entries, mappers.as_ref(), None);
+
+    let worker_count = ScanOptions::default().worker_count(osu_entries.len());
+    // Workers pull the next file from a shared index instead of owning a fixed chunk,
+    // so a run of huge marathon maps can't leave one thread busy while the rest idle.
+    let next_index = AtomicUsize::new(0);
+
+    let mut files: Vec<ScanFilePayload> = Vec::with_capacity(total_for_progress);
+
+    std::thread::scope(|scope| {
+        let mut handles = Vec::with_capacity(worker_count);
+
+        for _ in 0..worker_count {
+            let osu_entries = &osu_entries;
+            let next_index = &next_index;
+            let known = Arc::clone(&known);
+            let mappers = Arc::clone(&mappers);
+            let lazer_resolver = lazer_resolver.clone();
+
+            handles.push(scope.spawn(move || {
+                let mut out = Vec::new();
+                while let Some((file_path, mtime_ms)) =
+                    osu_entries.get(next_index.fetch_add(1, Ordering::Relaxed))
+                {
+                    if let Ok(Some(payload)) = scan_single_osu_file(
+                        file_path,
+                        *mtime_ms,
+                        &known,
+                        mappers.as_ref(),
+                        lazer_resolver.as_deref(),
+                        ScanDetailLevel::Full,
+                    ) {
+                        out.push(payload);
+                    }
+                }
+                out
+            }));
+        }
+
+        for handle in handles {
+            if let Ok(mut partial) = handle.join() {
+                files.append(&mut partial);
+            }
+        }
+    });
+
+    files.sort_unstable_by(|a, b| a.file_path.cmp(&b.file_path));
+
+    ScanDirectoryPayload {
+        files,
+        directory: dir_path.to_string(),
+    }
+}
+
+fn resolve_app_version(app_handle: &tauri::AppHandle) -> String {
+    static PACKAGE_VERSION: OnceLock<Option<String>> = OnceLock::new();
+    let pkg_version = PACKAGE_VERSION.get_or_init(|| {
+        // package.json lives at the repo root (two levels up from src-tauri/src)
+        serde_json::from_str::<Value>(include_str!("../../package.json")).ok().and_then(|json| {
+            json.get("version").and_then(Value::as_str).map(|s| s.to_string())
+        })
+    });
+    pkg_version
+        .clone()
+        .unwrap_or_else(|| app_handle.package_info().version.to_string())
+}
+
+#[tauri::command]
+fn get_app_version(app_handle: tauri::AppHandle) -> String {
+    resolve_app_version(&app_handle)
+}
+
+const SUPPORT_BUNDLE_LOG_TAIL_BYTES: u64 = 512 * 1024;
+const CRASH_REPORT_PREFIX: &str = "crash-";
+const REDACTED_KEY_MARKERS: [&str; 7] = ["apikey", "token", "secret", "password", "webhook", "cookie", "authorization"];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirectoryStats {
+    path: String,
+    file_count: usize,
+    total_bytes: u64,
+}
+
+fn directory_stats(dir: &Path) -> Option<DirectoryStats> {
+    if !dir.is_dir() {
+        return None;
+    }
+    let (file_count, total_bytes) = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .fold((0, 0), |(count, bytes), entry| {
+            (count + 1, bytes + entry.metadata().map(|meta| meta.len()).unwrap_or(0))
+        });
+    Some(DirectoryStats {
+        path: dir.to_string_lossy().to_string(),
+        file_count,
+        total_bytes,
+    })
+}
+
+/// Files directly inside `dir`, newest first.
+fn files_by_recency(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(PathBuf, std::time::SystemTime)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            meta.is_file().then(|| (entry.path(), meta.modified().unwrap_or(UNIX_EPOCH)))
+        })
+        .collect();
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    files.into_iter().map(|(path, _)| path).collect()
+}
+
+fn read_file_tail(path: &Path, max_bytes: u64) -> Result<Vec<u8>, String> {
+    use std::io::{Seek, SeekFrom};
+    let mut file = fs::File::open(path).map_err(|err| err.to_string())?;
+    let len = file.metadata().map_err(|err| err.to_string())?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(max_bytes)))
+        .map_err(|err| err.to_string())?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).map_err(|err| err.to_string())?;
+    Ok(bytes)
+}
+
+/// Write every panic to `crash-<unix ms>.log` in the app's log directory, where
+/// `create_support_bundle` picks up the latest one. The default hook still prints to stderr.
+fn install_crash_reporter(app_handle: &tauri::AppHandle) {
+    let Ok(log_dir) = app_handle.path().app_log_dir() else {
+        return;
+    };
+    let app_version = resolve_app_version(app_handle);
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let thread = std::thread::current();
+        let report = format!(
+            "mosu {app_version} ({} {})\nthread '{}' {info}\n\n{}\n",
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            thread.name().unwrap_or("<unnamed>"),
+            std::backtrace::Backtrace::force_capture(),
+        );
+        let path = log_dir.join(format!("{CRASH_REPORT_PREFIX}{}.log", unix_time_ms()));
+        let _ = fs::create_dir_all(&log_dir).and_then(|_| fs::write(path, report));
+    }));
+}
+
+/// Blank out anything that looks like a credential before it leaves the machine.
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, entry) in map.iter_mut() {
+                let normalized = key.to_ascii_lowercase().replace(['_', '-'], "");
+                if REDACTED_KEY_MARKERS.iter().any(|marker| normalized.contains(marker)) {
+                    if !entry.is_null() {
+                        *entry = Value::String("[redacted]".to_string());
+                    }
+                } else {
+                    redact_secrets(entry);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+fn build_support_bundle(app_handle: &tauri::AppHandle, destination: &Path) -> Result<(), String> {
+    use zip::write::SimpleFileOptions;
+
+    let paths = app_handle.path();
+    let config_dir = paths.app_config_dir().ok();
+    let data_dir = paths.app_data_dir().ok();
+    let cache_dir = paths.app_cache_dir().ok();
+    let log_dir = paths.app_log_dir().ok();
+
+    let file = fs::File::create(destination).map_err(|err| err.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let add_entry = |zip: &mut zip::ZipWriter<fs::File>, name: &str, bytes: &[u8]| -> Result<(), String> {
+        zip.start_file(name, options).map_err(|err| err.to_string())?;
+        zip.write_all(bytes).map_err(|err| err.to_string())
+    };
+
+    let diagnostics = serde_json::json!({
+        "appVersion": resolve_app_version(app_handle),
+        "os": std::env::consts::OS,
+        "osFamily": std::env::consts::FAMILY,
+        "arch": std::env::consts::ARCH,
+        "parallelism": std::thread::available_parallelism().map(|count| count.get()).ok(),
+        "generatedAtMs": unix_time_ms().to_string(),
+        "configDir": config_dir.as_ref().map(|dir| dir.to_string_lossy().to_string()),
+        "dataDir": data_dir.as_ref().map(|dir| dir.to_string_lossy().to_string()),
+    });
+    add_entry(&mut zip, "diagnostics.json", &serde_json::to_vec_pretty(&diagnostics).unwrap_or_default())?;
+
+    let lazer_resolvers = LAZER_RESOLVER_CACHE
+        .get()
+        .and_then(|cache| cache.lock().ok().map(|cache| cache.len()))
+        .unwrap_or(0);
+    let cache_stats = serde_json::json!({
+        "lazerResolverEntries": lazer_resolvers,
+        "cacheDir": cache_dir.as_deref().and_then(directory_stats),
+        "dataDir": data_dir.as_deref().and_then(directory_stats),
+    });
+    add_entry(&mut zip, "cache-stats.json", &serde_json::to_vec_pretty(&cache_stats).unwrap_or_default())?;
+
+    if let Some(config_dir) = &config_dir {
+        for path in files_by_recency(config_dir) {
+            let is_json = path.extension().and_then(|ext| ext.to_str()) == Some("json");
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            // Only JSON can be redacted reliably; anything else stays out of the bundle.
+            let Some(mut json) = is_json
+                .then(|| fs::read(&path).ok())
+                .flatten()
+                .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())
+            else {
+                continue;
+            };
+            redact_secrets(&mut json);
+            add_entry(
+                &mut zip,
+                &format!("settings/{name}"),
+                &serde_json::to_vec_pretty(&json).unwrap_or_default(),
+            )?;
+        }
+    }
+
+    let crash_report = log_dir.as_deref().map(files_by_recency).and_then(|logs| {
+        logs.into_iter().find(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(CRASH_REPORT_PREFIX))
+        })
+    });
+    if let Some(path) = crash_report {
+        if let Ok(bytes) = read_file_tail(&path, SUPPORT_BUNDLE_LOG_TAIL_BYTES) {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("crash.log");
+            add_entry(&mut zip, &format!("crash/{name}"), &bytes)?;
+        }
+    }
+
+    zip.finish().map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+/// Package diagnostics, redacted settings, cache stats and the latest crash report written by
+/// `install_crash_reporter` into one zip for bug reports. Returns the path that was written.
+#[tauri::command]
+async fn create_support_bundle(app_handle: tauri::AppHandle, path: String) -> Result<String, String> {
+    let destination = PathBuf::from(&path);
+    let destination = if destination.is_dir() {
+        destination.join(format!("mosu-support-{}.zip", unix_time_ms()))
+    } else {
+        destination
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        build_support_bundle(&app_handle, &destination).map(|_| destination.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Bump together with a new step in `migrate_settings`.
+const SETTINGS_SCHEMA_VERSION: u64 = 2;
+
+/// Serialises read-modify-write cycles on settings.json.
+static SETTINGS_LOCK: Mutex<()> = Mutex::new(());
+
+type SettingsMap = serde_json::Map<String, Value>;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SettingsChangedEvent {
+    settings: SettingsMap,
+    changed_keys: Vec<String>,
+}
+
+fn settings_file_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app_handle.path().app_config_dir().map_err(|err| err.to_string())?;
+    Ok(config_dir.join("settings.json"))
+}
+
+/// Bring settings written by an older version up to `SETTINGS_SCHEMA_VERSION`.
+/// Version 0 is the unversioned object the renderer kept in localStorage.
+fn migrate_settings(mut version: u64, settings: &mut SettingsMap) {
+    if version < 1 {
+        // The mutually exclusive auto-detect toggles became autoRescan + rescanMode.
+        let auto_detect = settings.remove("autoDetectMaps").and_then(|value| value.as_bool());
+        let auto_rescan_mapper = settings.remove("autoRescanMapper").and_then(|value| value.as_bool());
+        if (auto_detect.is_some() || auto_rescan_mapper.is_some()) && !settings.contains_key("autoRescan") {
+            let auto_detect = auto_detect.unwrap_or(false);
+            settings.insert("autoRescan".into(), Value::from(auto_detect || auto_rescan_mapper.unwrap_or(false)));
+            settings.insert("rescanMode".into(), Value::from(if auto_detect { "all" } else { "mapper" }));
+        }
+        version = 1;
+    }
+    if version < 2 {
+        // A single songsDir was split per client.
+        let is_lazer = settings.get("osuClient").and_then(Value::as_str) == Some("lazer");
+        let songs_dir = settings.get("songsDir").cloned().unwrap_or(Value::Null);
+        let (own_key, other_key) = if is_lazer {
+            ("lazerDataDir", "stableSongsDir")
+        } else {
+            ("stableSongsDir", "lazerDataDir")
+        };
+        settings.entry(own_key).or_insert(songs_dir);
+        settings.entry(other_key).or_insert(Value::Null);
+    }
+}
+
+/// `None` until settings have been written natively for the first time.
+fn read_settings_file(path: &Path) -> Result<Option<SettingsMap>, String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.to_string()),
+    };
+    let mut document: SettingsMap = serde_json::from_slice(&bytes).map_err(|err| err.to_string())?;
+    let version = document.get("schemaVersion").and_then(Value::as_u64).unwrap_or(0);
+    let mut settings = match document.remove("settings") {
+        Some(Value::Object(settings)) => settings,
+        _ => SettingsMap::new(),
+    };
+    if version < SETTINGS_SCHEMA_VERSION {
+        migrate_settings(version, &mut settings);
+        write_settings_file(path, &settings)?;
+    }
+    Ok(Some(settings))
+}
+
+fn write_settings_file(path: &Path, settings: &SettingsMap) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let document = serde_json::json!({
+        "schemaVersion": SETTINGS_SCHEMA_VERSION,
+        "settings": settings,
+    });
+    let bytes = serde_json::to_vec_pretty(&document).map_err(|err| err.to_string())?;
+    write_file_atomically(path, &bytes)
+}
+
+#[tauri::command]
+fn get_settings(app_handle: tauri::AppHandle) -> Result<Option<SettingsMap>, String> {
+    let _guard = SETTINGS_LOCK.lock().map_err(|err| err.to_string())?;
+    read_settings_file(&settings_file_path(&app_handle)?)
+}
+
+/// Merge `settings` into the stored settings (or replace them outright with `replace`), persist,
+/// and broadcast `settings-changed` to every window when anything actually changed.
+/// `schema_version` marks where unversioned input (e.g. a localStorage import) comes from.
+#[tauri::command]
+fn set_settings(
+    app_handle: tauri::AppHandle,
+    mut settings: SettingsMap,
+    replace: Option<bool>,
+    schema_version: Option<u64>,
+) -> Result<SettingsMap, String> {
+    let _guard = SETTINGS_LOCK.lock().map_err(|err| err.to_string())?;
+    let path = settings_file_path(&app_handle)?;
+    if let Some(version) = schema_version.filter(|version| *version < SETTINGS_SCHEMA_VERSION) {
+        migrate_settings(version, &mut settings);
+    }
+
+    let previous = read_settings_file(&path)?.unwrap_or_default();
+    let updated = if replace.unwrap_or(false) {
+        settings
+    } else {
+        let mut merged = previous.clone();
+        merged.extend(settings);
+        merged
+    };
+
+    let mut changed_keys: Vec<String> = updated
+        .iter()
+        .filter(|(key, value)| previous.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .chain(previous.keys().filter(|key| !updated.contains_key(*key)).cloned())
+        .collect();
+    if changed_keys.is_empty() {
+        return Ok(updated);
+    }
+    changed_keys.sort();
+
+    write_settings_file(&path, &updated)?;
+    apply_network_settings(&updated);
+    apply_backup_settings(&updated);
+    apply_shortcut_settings(&app_handle, &updated);
+    let _ = app_handle.emit("settings-changed", SettingsChangedEvent {
+        settings: updated.clone(),
+        changed_keys,
+    });
+    Ok(updated)
+}
+
+/// Error returned by every network call while offline mode is on, so the UI can tell it apart.
+const OFFLINE_ERROR: &str = "offline";
+
+#[derive(Debug, Clone, Default)]
+struct NetworkSettings {
+    /// `None` uses the system proxy (environment / OS settings); `Some("")` connects directly.
+    proxy: Option<String>,
+    offline: bool,
+}
+
+static NETWORK_SETTINGS: Mutex<NetworkSettings> = Mutex::new(NetworkSettings { proxy: None, offline: false });
+
+/// Pick up `httpProxy` ("system", "none" or a proxy URL) and `offlineMode` from settings.
+fn apply_network_settings(settings: &SettingsMap) {
+    let proxy = match settings.get("httpProxy").and_then(Value::as_str).map(str::trim) {
+        None | Some("") | Some("system") => None,
+        Some("none") => Some(String::new()),
+        Some(url) => Some(url.to_string()),
+    };
+    let offline = settings.get("offlineMode").and_then(Value::as_bool).unwrap_or(false);
+    *NETWORK_SETTINGS.lock().unwrap() = NetworkSettings { proxy, offline };
+}
+
+/// Client builder shared by every request, honouring the proxy setting and failing fast offline.
+fn http_client_builder(timeout: Duration) -> Result<reqwest::ClientBuilder, String> {
+    let network = NETWORK_SETTINGS.lock().map_err(|err| err.to_string())?.clone();
+    if network.offline {
+        return Err(OFFLINE_ERROR.to_string());
+    }
+    let builder = reqwest::Client::builder().timeout(timeout);
+    Ok(match network.proxy.as_deref() {
+        None => builder,
+        Some("") => builder.no_proxy(),
+        Some(url) => builder.proxy(reqwest::Proxy::all(url).map_err(|err| format!("invalid proxy: {err}"))?),
+    })
+}
+
+fn http_client(timeout: Duration) -> Result<reqwest::Client, String> {
+    http_client_builder(timeout)?.build().map_err(|err| err.to_string())
+}
+
+const NETWORK_MAX_ATTEMPTS: u32 = 4;
+const NETWORK_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const NETWORK_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+const DEFAULT_HOST_REQUEST_INTERVAL: Duration = Duration::from_millis(200);
+/// osu! API v1 allows far more requests than the website's HTML pages do.
+const OSU_API_REQUEST_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Set once at startup so network helpers can report retries without threading a handle through.
+static NETWORK_EVENTS: OnceLock<tauri::AppHandle> = OnceLock::new();
+/// Next free request slot per rate-limit bucket.
+static HOST_NEXT_REQUEST: Mutex<BTreeMap<String, Instant>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct NetworkRetryEvent {
+    host: String,
+    attempt: u32,
+    max_attempts: u32,
+    delay_ms: u64,
+    reason: String,
+}
+
+/// Rate-limit bucket and spacing for a URL: per host, with the osu! API split from the website.
+fn rate_limit_bucket(url: &reqwest::Url) -> (String, Duration) {
+    let host = url.host_str().unwrap_or_default().to_string();
+    if host == "osu.ppy.sh" {
+        if url.path().starts_with("/api/") {
+            return (format!("{host}/api"), OSU_API_REQUEST_INTERVAL);
+        }
+        return (host, OSU_WEBSITE_REQUEST_INTERVAL);
+    }
+    (host, DEFAULT_HOST_REQUEST_INTERVAL)
+}
+
+/// Reserve the bucket's next request slot and wait for it, so bursts queue up instead of getting 429'd.
+async fn wait_for_request_slot(bucket: &str, interval: Duration) {
+    let wait = {
+        let mut next_requests = HOST_NEXT_REQUEST.lock().unwrap();
+        let now = Instant::now();
+        let slot = next_requests.get(bucket).map_or(now, |next| (*next).max(now));
+        next_requests.insert(bucket.to_string(), slot + interval);
+        slot - now
+    };
+    tokio::time::sleep(wait).await;
+}
+
+/// Up to half the delay again, so clients retrying together spread out.
+fn retry_jitter(delay: Duration) -> Duration {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or(0);
+    delay.mul_f64(f64::from(nanos % 1000) / 2000.0)
+}
+
+/// Send `request` through the rate limiter, retrying timeouts, connection errors, 429s and 5xx
+/// responses with exponential backoff. Each retry is announced as a `network-retry` event.
+/// Non-idempotent requests (POST, PATCH) are only retried when the connection never opened, since
+/// any response or timeout may mean the server already acted on them.
+async fn send_with_retry(request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+    let (bucket, interval, idempotent) = request
+        .try_clone()
+        .and_then(|request| request.build().ok())
+        .map(|request| {
+            let (bucket, interval) = rate_limit_bucket(request.url());
+            (bucket, interval, request.method().is_idempotent())
+        })
+        .unwrap_or_default();
+
+    let mut attempt = 1;
+    loop {
+        // Streaming bodies can't be replayed, so those requests get a single attempt.
+        let Some(current) = request.try_clone().filter(|_| attempt < NETWORK_MAX_ATTEMPTS) else {
+            wait_for_request_slot(&bucket, interval).await;
+            return request.send().await.map_err(|err| err.to_string());
+        };
+        wait_for_request_slot(&bucket, interval).await;
+
+        let (reason, retry_after) = match current.send().await {
+            Ok(response)
+                if idempotent
+                    && (response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error()) =>
+            {
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                (format!("HTTP {}", response.status()), retry_after)
+            }
+            Ok(response) => return Ok(response),
+            Err(err) if err.is_connect() || (idempotent && (err.is_timeout() || err.is_request())) => {
+                (err.to_string(), None)
+            }
+            Err(err) => return Err(err.to_string()),
+        };
+
+        let backoff = NETWORK_RETRY_BASE_DELAY * 2_u32.pow(attempt - 1);
+        let delay = retry_after.unwrap_or_else(|| backoff + retry_jitter(backoff)).min(NETWORK_RETRY_MAX_DELAY);
+        if let Some(app_handle) = NETWORK_EVENTS.get() {
+            let _ = app_handle.emit("network-retry", NetworkRetryEvent {
+                host: bucket.clone(),
+                attempt,
+                max_attempts: NETWORK_MAX_ATTEMPTS,
+                delay_ms: delay.as_millis() as u64,
+                reason,
+            });
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Keychain service name; entries show up under it in Credential Manager / Keychain Access.
+const SECRET_SERVICE: &str = "com.fax1015.mosu";
+
+fn secret_entry(name: &str) -> Result<keyring::Entry, String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.bytes().all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.'));
+    if !valid {
+        return Err(format!("invalid secret name: {name}"));
+    }
+    keyring::Entry::new(SECRET_SERVICE, name).map_err(|err| err.to_string())
+}
+
+/// Save a credential in the OS keychain (Windows Credential Manager, Secret Service, macOS Keychain).
+/// An empty value deletes it.
+#[tauri::command]
+fn store_secret(name: String, value: String) -> Result<(), String> {
+    let entry = secret_entry(&name)?;
+    if value.is_empty() {
+        return match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(err.to_string()),
+        };
+    }
+    entry.set_password(&value).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn get_secret(name: String) -> Result<Option<String>, String> {
+    match secret_entry(&name)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Snapshots of one beatmap live in `<app data>/snapshots/<md5 of its path>/<unix ms>-<content md5>.zip`.
+fn beatmap_snapshot_dir(file_path: &str) -> Result<PathBuf, String> {
+    LIBRARY_CACHE_DIR
+        .get()
+        .map(|dir| dir.join("snapshots").join(compute_osu_md5_hex(file_path.as_bytes())))
+        .ok_or_else(|| "app data folder unavailable".to_string())
+}
+
+/// Once a map has been snapshotted by hand, every save the watcher picks up is snapshotted too,
+/// giving it a local version history.
+fn snapshot_saved_map(file_path: &str) -> Result<(), String> {
+    let snapshot_dir = beatmap_snapshot_dir(file_path)?;
+    if !snapshot_dir.exists() {
+        return Ok(());
+    }
+    let bytes = fs::read(file_path).map_err(|err| err.to_string())?;
+    store_beatmap_snapshot(&snapshot_dir, file_path, &bytes).map(|_| ())
+}
+
+fn parse_snapshot_id(id: &str) -> Option<(u64, &str)> {
+    let (created_at, hash) = id.split_once('-')?;
+    let valid_hash = hash.len() == 32 && hash.bytes().all(|byte| byte.is_ascii_hexdigit());
+    valid_hash.then_some((created_at.parse().ok()?, hash))
+}
+
+/// Newest first.
+fn read_beatmap_snapshots(snapshot_dir: &Path, file_path: &str) -> Vec<BeatmapSnapshotInfo> {
+    let Ok(entries) = fs::read_dir(snapshot_dir) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<BeatmapSnapshotInfo> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("zip") {
+                return None;
+            }
+            let id = path.file_stem()?.to_str()?.to_string();
+            let (created_at_ms, hash) = parse_snapshot_id(&id)?;
+            Some(BeatmapSnapshotInfo {
+                content_hash: hash.to_string(),
+                id,
+                file_path: file_path.to_string(),
+                created_at_ms,
+                size_bytes: entry.metadata().map(|meta| meta.len()).unwrap_or(0),
+            })
+        })
+        .collect();
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.created_at_ms));
+    snapshots
+}
+
+/// Store `bytes` as a new snapshot unless the newest one already has the same content.
+fn store_beatmap_snapshot(snapshot_dir: &Path, file_path: &str, bytes: &[u8]) -> Result<BeatmapSnapshotInfo, String> {
+    use zip::write::SimpleFileOptions;
+
+    let content_hash = compute_osu_md5_hex(bytes);
+    if let Some(latest) = read_beatmap_snapshots(snapshot_dir, file_path).into_iter().next() {
+        if latest.content_hash == content_hash {
+            return Ok(latest);
+        }
+    }
+
+    fs::create_dir_all(snapshot_dir).map_err(|err| err.to_string())?;
+    let created_at_ms = unix_time_ms() as u64;
+    let id = format!("{created_at_ms}-{content_hash}");
+    let entry_name = Path::new(file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "beatmap.osu".to_string());
+
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file(entry_name, options).map_err(|err| err.to_string())?;
+    zip.write_all(bytes).map_err(|err| err.to_string())?;
+    let archive = zip.finish().map_err(|err| err.to_string())?.into_inner();
+
+    write_file_atomically(&snapshot_dir.join(format!("{id}.zip")), &archive)?;
+    Ok(BeatmapSnapshotInfo {
+        id,
+        file_path: file_path.to_string(),
+        created_at_ms,
+        content_hash,
+        size_bytes: archive.len() as u64,
+    })
+}
+
+fn read_beatmap_snapshot(snapshot_dir: &Path, id: &str) -> Result<Vec<u8>, String> {
+    let (_, expected_hash) = parse_snapshot_id(id).ok_or_else(|| "invalid snapshot id".to_string())?;
+    let file = fs::File::open(snapshot_dir.join(format!("{id}.zip"))).map_err(|err| err.to_string())?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|err| err.to_string())?;
+    let mut entry = archive.by_index(0).map_err(|err| err.to_string())?;
+    let mut bytes = Vec::with_capacity(entry.size() as usize);
+    entry.read_to_end(&mut bytes).map_err(|err| err.to_string())?;
+    if compute_osu_md5_hex(&bytes) != expected_hash {
+        return Err("snapshot is corrupted".to_string());
+    }
+    Ok(bytes)
+}
+
+const DEFAULT_BACKUP_RETENTION: usize = 20;
+
+#[derive(Debug, Clone, Copy)]
+struct BackupSettings {
+    enabled: bool,
+    retention: usize,
+}
+
+static BACKUP_SETTINGS: Mutex<BackupSettings> =
+    Mutex::new(BackupSettings { enabled: false, retention: DEFAULT_BACKUP_RETENTION });
+
+/// Pick up `autoBackup` and `backupRetention` (versions kept per map) from settings.
+fn apply_backup_settings(settings: &SettingsMap) {
+    let enabled = settings.get("autoBackup").and_then(Value::as_bool).unwrap_or(false);
+    let retention = settings
+        .get("backupRetention")
+        .and_then(Value::as_u64)
+        .map(|count| count.max(1) as usize)
+        .unwrap_or(DEFAULT_BACKUP_RETENTION);
+    *BACKUP_SETTINGS.lock().unwrap() = BackupSettings { enabled, retention };
+}
+
+/// Automatic backups use the snapshot format under `<app data>/backups/<md5 of its path>/`.
+fn beatmap_backup_dir(file_path: &str) -> Option<PathBuf> {
+    LIBRARY_CACHE_DIR
+        .get()
+        .map(|dir| dir.join("backups").join(compute_osu_md5_hex(file_path.as_bytes())))
+}
+
+/// Copy the map's current content into its rolling backups and drop versions beyond the retention.
+/// Every version the watcher sees is kept, so the one before a bad save is always the second newest.
+fn backup_changed_map(file_path: &str, retention: usize) -> Result<(), String> {
+    let backup_dir = beatmap_backup_dir(file_path).ok_or_else(|| "app data folder unavailable".to_string())?;
+    let bytes = fs::read(file_path).map_err(|err| err.to_string())?;
+    store_beatmap_snapshot(&backup_dir, file_path, &bytes)?;
+    for expired in read_beatmap_snapshots(&backup_dir, file_path).into_iter().skip(retention) {
+        fs::remove_file(backup_dir.join(format!("{}.zip", expired.id))).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// Snapshot `path` now; from then on `snapshot_saved_map` also snapshots each save the watcher sees.
+#[tauri::command]
+async fn snapshot_beatmap(path: String) -> Result<BeatmapSnapshotInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = fs::read(&path).map_err(|err| err.to_string())?;
+        let snapshot_dir = beatmap_snapshot_dir(&path)?;
+        store_beatmap_snapshot(&snapshot_dir, &path, &bytes)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+fn list_snapshots(path: String) -> Result<Vec<BeatmapSnapshotInfo>, String> {
+    let snapshot_dir = beatmap_snapshot_dir(&path)?;
+    Ok(read_beatmap_snapshots(&snapshot_dir, &path))
+}
+
+/// Overwrite `path` with snapshot `id`. The current content is snapshotted first and returned,
+/// so a restore can always be undone.
+#[tauri::command]
+async fn restore_snapshot(path: String, id: String) -> Result<BeatmapSnapshotInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let snapshot_dir = beatmap_snapshot_dir(&path)?;
+        let restored = read_beatmap_snapshot(&snapshot_dir, &id)?;
+        let current = fs::read(&path).map_err(|err| err.to_string())?;
+        let previous = store_beatmap_snapshot(&snapshot_dir, &path, &current)?;
+        if current != restored {
+            write_file_atomically(Path::new(&path), &restored)?;
+        }
+        Ok(previous)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Automatic backups of `path`, newest first.
+#[tauri::command]
+fn list_backups(path: String) -> Result<Vec<BeatmapSnapshotInfo>, String> {
+    let backup_dir = beatmap_backup_dir(&path).ok_or_else(|| "app data folder unavailable".to_string())?;
+    Ok(read_beatmap_snapshots(&backup_dir, &path))
+}
+
+/// Overwrite `path` with backup `id`, backing up the current content first and returning it.
+#[tauri::command]
+async fn restore_backup(path: String, id: String) -> Result<BeatmapSnapshotInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let backup_dir = beatmap_backup_dir(&path).ok_or_else(|| "app data folder unavailable".to_string())?;
+        let restored = read_beatmap_snapshot(&backup_dir, &id)?;
+        let current = fs::read(&path).map_err(|err| err.to_string())?;
+        let previous = store_beatmap_snapshot(&backup_dir, &path, &current)?;
+        if current != restored {
+            write_file_atomically(Path::new(&path), &restored)?;
+        }
+        Ok(previous)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// How long the most recent `trash_files` batch can still be restored from inside the app.
+const TRASH_UNDO_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+struct TrashBatch {
+    paths: Vec<PathBuf>,
+    trashed_at: Instant,
+    /// Unix seconds, to tell this batch's entries apart from older ones in the trash.
+    trashed_at_secs: i64,
+}
+
+static LAST_TRASH_BATCH: Mutex<Option<TrashBatch>> = Mutex::new(None);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashFailure {
+    path: String,
+    error: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrashFilesPayload {
+    trashed: Vec<String>,
+    failed: Vec<TrashFailure>,
+    /// Milliseconds `undo_trash` stays available for this batch; 0 where restoring isn't supported.
+    undo_window_ms: u64,
+}
+
+/// The trash records absolute, symlink-free paths, so batches are remembered the same way to be
+/// found again on undo. Windows' `\\?\` prefix is dropped to match what it records.
+fn canonical_trash_path(path: &str) -> Result<PathBuf, String> {
+    let canonical = fs::canonicalize(path).map_err(|err| err.to_string())?;
+    let text = canonical.to_string_lossy();
+    match text.strip_prefix("\\\\?\\") {
+        Some(stripped) if !stripped.starts_with("UNC\\") => Ok(PathBuf::from(stripped)),
+        _ => Ok(canonical),
+    }
+}
+
+/// Move files to the OS recycle bin. Deleting features go through this rather than `fs::remove_file`,
+/// and the batch can be put back with `undo_trash` for a few minutes.
+#[tauri::command]
+async fn trash_files(paths: Vec<String>) -> Result<TrashFilesPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let trashed_at_secs = (unix_time_ms() / 1000) as i64;
+        let mut trashed = Vec::new();
+        let mut trashed_paths = Vec::new();
+        let mut failed = Vec::new();
+        for path in paths {
+            match canonical_trash_path(&path).and_then(|canonical| {
+                trash::delete(&canonical).map(|()| canonical).map_err(|err| err.to_string())
+            }) {
+                Ok(canonical) => {
+                    trashed.push(path);
+                    trashed_paths.push(canonical);
+                }
+                Err(error) => failed.push(TrashFailure { path, error }),
+            }
+        }
+
+        let undo_window_ms = if cfg!(target_os = "macos") {
+            0
+        } else {
+            if !trashed_paths.is_empty() {
+                *LAST_TRASH_BATCH.lock().map_err(|err| err.to_string())? = Some(TrashBatch {
+                    paths: trashed_paths,
+                    trashed_at: Instant::now(),
+                    trashed_at_secs,
+                });
+            }
+            TRASH_UNDO_WINDOW.as_millis() as u64
+        };
+        Ok(TrashFilesPayload { trashed, failed, undo_window_ms })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Restore the most recent `trash_files` batch, returning the paths put back. A failed restore
+/// keeps the batch so it can be retried within the window.
+#[tauri::command]
+async fn undo_trash() -> Result<Vec<String>, String> {
+    #[cfg(target_os = "macos")]
+    {
+        Err("restoring from the trash is not supported on macOS".to_string())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let batch = LAST_TRASH_BATCH
+            .lock()
+            .map_err(|err| err.to_string())?
+            .take()
+            .ok_or_else(|| "nothing to restore".to_string())?;
+        if batch.trashed_at.elapsed() > TRASH_UNDO_WINDOW {
+            return Err("the undo window has expired".to_string());
+        }
+
+        tauri::async_runtime::spawn_blocking(move || {
+            let mut newest: HashMap<PathBuf, trash::TrashItem> = HashMap::new();
+            for item in trash::os_limited::list().map_err(|err| err.to_string())? {
+                let original_path = item.original_path();
+                if item.time_deleted < batch.trashed_at_secs || !batch.paths.contains(&original_path) {
+                    continue;
+                }
+                match newest.get(&original_path) {
+                    Some(kept) if kept.time_deleted >= item.time_deleted => {}
+                    _ => {
+                        newest.insert(original_path, item);
+                    }
+                }
+            }
+            let restored: Vec<String> = newest.keys().map(|path| path.to_string_lossy().to_string()).collect();
+            if let Err(err) = trash::os_limited::restore_all(newest.into_values()) {
+                let mut last_batch = LAST_TRASH_BATCH.lock().map_err(|err| err.to_string())?;
+                if last_batch.is_none() {
+                    *last_batch = Some(batch);
+                }
+                return Err(err.to_string());
+            }
+            Ok(restored)
+        })
+        .await
+        .map_err(|err| err.to_string())?
+    }
+}
+
+#[tauri::command]
+fn open_external_url(url: String) -> Result<(), String> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        open::that(url).map_err(|err| err.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[tauri::command]
+fn open_in_text_editor(file_path: String) -> Result<(), String> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err("Beatmap file not found".to_string());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Command::new("notepad")
+            .arg(&path)
+            .spawn()
+            .map_err(|err| err.to_string())?;
+        return Ok(());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        open::that(path).map_err(|err| err.to_string())
+    }
+}
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/fax1015/mosu/releases/latest";
+
+#[tauri::command]
+async fn check_for_updates(app_handle: tauri::AppHandle) -> UpdateInfoPayload {
+    let current_version = resolve_app_version(&app_handle);
+    let client = http_client(Duration::from_secs(15));
+
+    let client = match client {
+        Ok(client) => client,
+        Err(err) => {
+            return UpdateInfoPayload {
+                current_version,
+                latest_version: String::new(),
+                html_url: String::new(),
+                error: Some(err.to_string()),
+            }
+        }
+    };
+
+    let response = send_with_retry(client.get(LATEST_RELEASE_URL).header("User-Agent", "mosu-app")).await;
+
+    match response {
+        Ok(resp) => {
+            if !resp.status().is_success() {
+                return UpdateInfoPayload {
+                    current_version,
+                    latest_version: String::new(),
+                    html_url: String::new(),
+                    error: Some("Failed to fetch latest release".to_string()),
+                };
+            }
+
+            let json: Value = match resp.json().await {
+                Ok(value) => value,
+                Err(err) => {
+                    return UpdateInfoPayload {
+                        current_version,
+                        latest_version: String::new(),
+                        html_url: String::new(),
+                        error: Some(err.to_string()),
+                    }
+                }
+            };
+
+            let latest_version = json
+                .get("tag_name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .trim_start_matches('v')
+                .to_string();
+
+            let html_url = json
+                .get("html_url")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+
+            UpdateInfoPayload {
+                current_version,
+                latest_version,
+                html_url,
+                error: None,
+            }
+        }
+        Err(err) => UpdateInfoPayload {
+            current_version,
+            latest_version: String::new(),
+            html_url: String::new(),
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+const UPDATE_PROGRESS_INTERVAL_BYTES: u64 = 256 * 1024;
+
+/// The last verified download, consumed by `apply_update`. Keeping it backend-side means the
+/// frontend can't point the installer step at an arbitrary file.
+static PENDING_UPDATE: Mutex<Option<DownloadedUpdatePayload>> = Mutex::new(None);
+
+fn update_asset_extension_matches(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    if cfg!(target_os = "windows") {
+        name.ends_with("-setup.exe") || name.ends_with(".msi")
+    } else if cfg!(target_os = "macos") {
+        name.ends_with(".dmg")
+    } else {
+        name.ends_with(".appimage")
+    }
+}
+
+fn update_asset_arch_matches(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let aliases: &[&str] = match std::env::consts::ARCH {
+        "x86_64" => &["x64", "x86_64", "amd64"],
+        "aarch64" => &["aarch64", "arm64"],
+        "x86" => &["x86", "i686"],
+        other => return name.contains(other),
+    };
+    aliases.iter().any(|alias| name.contains(alias))
+}
+
+/// Pick the installer for this platform from a release's `assets`, preferring one built for
+/// the current architecture. Returns `(name, download_url)`.
+fn select_update_asset(release: &Value) -> Option<(String, String)> {
+    let candidates: Vec<(String, String)> = release
+        .get("assets")
+        .and_then(Value::as_array)?
+        .iter()
+        .filter_map(|asset| {
+            let name = asset.get("name").and_then(Value::as_str)?;
+            let url = asset.get("browser_download_url").and_then(Value::as_str)?;
+            update_asset_extension_matches(name).then(|| (name.to_string(), url.to_string()))
+        })
+        .collect();
+
+    candidates
+        .iter()
+        .find(|(name, _)| update_asset_arch_matches(name))
+        .or_else(|| candidates.first())
+        .cloned()
+}
+
+fn is_sha256_hex(token: &str) -> bool {
+    token.len() == 64 && token.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// Find the checksum for `asset_name` in `sha256sum`-style text, such as a checksum table in
+/// the release notes or a `SHA256SUMS` file. Only lines that mention the asset are considered.
+fn find_asset_sha256(text: &str, asset_name: &str) -> Option<String> {
+    text.lines()
+        .filter(|line| line.contains(asset_name))
+        .flat_map(|line| line.split(|ch: char| ch.is_whitespace() || matches!(ch, '|' | '`' | ':' | '*')))
+        .find(|token| is_sha256_hex(token))
+        .map(str::to_ascii_lowercase)
+}
+
+/// Resolve the expected SHA-256 for `asset_name`: the release notes first, then a
+/// `<asset>.sha256` or `SHA256SUMS` file attached to the release.
+async fn resolve_update_sha256(client: &reqwest::Client, release: &Value, asset_name: &str) -> Option<String> {
+    let notes = release.get("body").and_then(Value::as_str).unwrap_or_default();
+    if let Some(hash) = find_asset_sha256(notes, asset_name) {
+        return Some(hash);
+    }
+
+    let sidecar_name = format!("{asset_name}.sha256");
+    for asset in release.get("assets").and_then(Value::as_array)? {
+        let name = asset.get("name").and_then(Value::as_str).unwrap_or_default();
+        let is_sidecar = name.eq_ignore_ascii_case(&sidecar_name);
+        let is_sums = name.to_ascii_lowercase().starts_with("sha256sums");
+        if !is_sidecar && !is_sums {
+            continue;
+        }
+        let Some(url) = asset.get("browser_download_url").and_then(Value::as_str) else {
+            continue;
+        };
+        let Ok(response) = client.get(url).header("User-Agent", "mosu-app").send().await else {
+            continue;
+        };
+        let Ok(text) = response.text().await else {
+            continue;
+        };
+        // A per-asset sidecar often holds just the bare hash.
+        let hash = find_asset_sha256(&text, asset_name).or_else(|| {
+            is_sidecar
+                .then(|| text.split_whitespace().find(|token| is_sha256_hex(token)))
+                .flatten()
+                .map(str::to_ascii_lowercase)
+        });
+        if hash.is_some() {
+            return hash;
+        }
+    }
+    None
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    sha256_digest_hex(Sha256::digest(bytes).as_slice())
+}
+
+fn sha256_digest_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Download the installer for the latest release into the app cache, emitting
+/// `update-download-progress` while it streams. The file is only kept when its SHA-256 matches
+/// the checksum published with the release.
+#[tauri::command]
+async fn download_update(window: tauri::Window, app_handle: tauri::AppHandle) -> Result<DownloadedUpdatePayload, String> {
+    let client = http_client(Duration::from_secs(600))?;
+
+    let response = client
+        .get(LATEST_RELEASE_URL)
+        .header("User-Agent", "mosu-app")
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !response.status().is_success() {
+        return Err("Failed to fetch latest release".to_string());
+    }
+    let release: Value = response.json().await.map_err(|err| err.to_string())?;
+    let version = release
+        .get("tag_name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim_start_matches('v')
+        .to_string();
+
+    let (asset_name, download_url) =
+        select_update_asset(&release).ok_or("No installer for this platform in the latest release")?;
+    let expected_sha256 = resolve_update_sha256(&client, &release, &asset_name)
+        .await
+        .ok_or("The latest release does not publish a SHA-256 for its installer")?;
+
+    let update_dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|err| err.to_string())?
+        .join("updates");
+    fs::create_dir_all(&update_dir).map_err(|err| err.to_string())?;
+    let file_name = sanitize_file_name(&asset_name);
+    let final_path = update_dir.join(&file_name);
+    let partial_path = update_dir.join(format!("{file_name}.part"));
+
+    let mut response = client
+        .get(&download_url)
+        .header("User-Agent", "mosu-app")
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download {asset_name} ({})", response.status()));
+    }
+    let total_bytes = response.content_length();
+
+    let mut file = fs::File::create(&partial_path).map_err(|err| err.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut downloaded_bytes = 0u64;
+    let mut last_reported = 0u64;
+    let emit_progress = |downloaded_bytes: u64| {
+        let _ = window.emit("update-download-progress", UpdateDownloadProgressEvent {
+            asset_name: asset_name.clone(),
+            downloaded_bytes,
+            total_bytes,
+        });
+    };
+    emit_progress(0);
+
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(err) => {
+                let _ = fs::remove_file(&partial_path);
+                return Err(err.to_string());
+            }
+        };
+        if let Err(err) = file.write_all(&chunk) {
+            let _ = fs::remove_file(&partial_path);
+            return Err(err.to_string());
+        }
+        hasher.update(&chunk);
+        downloaded_bytes += chunk.len() as u64;
+        if downloaded_bytes - last_reported >= UPDATE_PROGRESS_INTERVAL_BYTES {
+            last_reported = downloaded_bytes;
+            emit_progress(downloaded_bytes);
+        }
+    }
+    emit_progress(downloaded_bytes);
+    drop(file);
+
+    let actual_sha256 = sha256_digest_hex(hasher.finalize().as_slice());
+    if actual_sha256 != expected_sha256 {
+        let _ = fs::remove_file(&partial_path);
+        return Err(format!(
+            "Checksum mismatch for {asset_name}: expected {expected_sha256}, got {actual_sha256}"
+        ));
+    }
+    fs::rename(&partial_path, &final_path).map_err(|err| err.to_string())?;
+
+    let downloaded = DownloadedUpdatePayload {
+        version,
+        asset_name,
+        file_path: final_path.to_string_lossy().to_string(),
+        sha256: actual_sha256,
+        size_bytes: downloaded_bytes,
+    };
+    *PENDING_UPDATE.lock().map_err(|err| err.to_string())? = Some(downloaded.clone());
+    Ok(downloaded)
+}
+
+/// Install the update fetched by `download_update`. The installer is re-hashed first so a file
+/// modified after download is never run.
+///
+/// On Windows the installer is launched and the app exits so it can replace the running
+/// executable; on Linux the running AppImage is swapped in place and the app restarts; on
+/// macOS the disk image is opened for the user and the app exits.
+#[tauri::command]
+fn apply_update(app_handle: tauri::AppHandle) -> Result<(), String> {
+    let pending = PENDING_UPDATE
+        .lock()
+        .map_err(|err| err.to_string())?
+        .clone()
+        .ok_or("No downloaded update to apply")?;
+    let installer = PathBuf::from(&pending.file_path);
+    let bytes = fs::read(&installer).map_err(|err| err.to_string())?;
+    if sha256_hex(&bytes) != pending.sha256 {
+        return Err("The downloaded update changed on disk; download it again".to_string());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if pending.asset_name.to_ascii_lowercase().ends_with(".msi") {
+            Command::new("msiexec")
+                .arg("/i")
+                .arg(&installer)
+                .arg("/passive")
+                .spawn()
+                .map_err(|err| err.to_string())?;
+        } else {
+            Command::new(&installer)
+                .arg("/P")
+                .spawn()
+                .map_err(|err| err.to_string())?;
+        }
+        app_handle.exit(0);
+        Ok(())
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        open::that(&installer).map_err(|err| err.to_string())?;
+        app_handle.exit(0);
+        Ok(())
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let appimage = std::env::var_os("APPIMAGE")
+            .map(PathBuf::from)
+            .ok_or("Updates can only be installed automatically for the AppImage build")?;
+        let staged = appimage.with_extension("AppImage.update");
+        fs::write(&staged, &bytes).map_err(|err| err.to_string())?;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755)).map_err(|err| err.to_string())?;
+        fs::rename(&staged, &appimage).map_err(|err| {
+            let _ = fs::remove_file(&staged);
+            err.to_string()
+        })?;
+        let _ = fs::remove_file(&installer);
+        app_handle.restart()
+    }
+}
+
+#[tauri::command]
+fn read_image_file(file_path: String) -> Option<String> {
+    let path = PathBuf::from(file_path);
+    let bytes = fs::read(&path).ok()?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    Some(format!("data:{};base64,{}", get_mime_type(&path), encoded))
+}
+
+#[tauri::command]
+fn read_binary_file(file_path: String) -> Option<Vec<u8>> {
+    fs::read(file_path).ok()
+}
+
+#[tauri::command]
+fn read_audio_file(file_path: String, file_name_hint: Option<String>) -> Option<String> {
+    let bytes = fs::read(&file_path).ok()?;
+    let mime = sniff_audio_mime_type(&bytes)
+        .or_else(|| audio_mime_type_from_hint(file_name_hint.as_deref()))
+        .unwrap_or("application/octet-stream");
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+    Some(format!("data:{};base64,{}", mime, encoded))
+}
+
+#[tauri::command]
+fn read_osu_file(file_path: String) -> Option<OsuFilePayload> {
+    let bytes = fs::read(&file_path).ok()?;
+    let (content, decode_warnings) = decode_osu_bytes(&bytes);
+    let mtime_ms = get_mtime_ms(Path::new(&file_path)).ok()?;
+    Some(OsuFilePayload {
+        content: content.into_owned(),
+        file_path,
+        stat: FileStatPayload { mtime_ms },
+        beatmap_hash: None,
+        decode_warnings: (!decode_warnings.is_empty()).then_some(decode_warnings),
+    })
+}
+
+/// Read an audio file's properties, retrying with the extension of `file_name_hint` (or a
+/// content sniff) when the path's own extension is missing or wrong, as with lazer's hashed files.
+fn read_tagged_audio(path: &Path, file_name_hint: Option<&str>) -> Option<lofty::file::TaggedFile> {
+    use lofty::probe::Probe;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    if let Ok(tagged_file) = Probe::open(path).and_then(|probe| probe.read()) {
+        return Some(tagged_file);
+    }
+
+    let hinted_type = file_name_hint
+        .and_then(|name| Path::new(name).extension())
+        .and_then(|ext| ext.to_str())
+        .and_then(FileType::from_ext);
+    let reader = BufReader::new(File::open(path).ok()?);
+    if let Some(file_type) = hinted_type {
+        Probe::with_file_type(reader, file_type).read().ok()
+    } else {
+        Probe::new(reader).guess_file_type().ok()?.read().ok()
+    }
+}
+
+#[tauri::command]
+fn get_audio_duration(file_path: String, file_name_hint: Option<String>) -> Option<f64> {
+    use lofty::prelude::*;
+
+    let tagged_file = read_tagged_audio(Path::new(&file_path), file_name_hint.as_deref())?;
+    let duration = tagged_file.properties().duration();
+    Some(duration.as_millis() as f64)
+}
+
+const RANKED_AUDIO_MAX_BITRATE_KBPS: u32 = 192;
+const RANKED_AUDIO_MIN_BITRATE_KBPS: u32 = 128;
+const HITSOUND_EXTENSIONS: [&str; 3] = ["wav", "ogg", "mp3"];
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioProbePayload {
+    file_path: String,
+    codec: String,
+    duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bitrate_kbps: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_rate_hz: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channels: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bit_depth: Option<u8>,
+    file_size_bytes: u64,
+    warnings: Vec<String>,
+}
+
+fn audio_codec_name(file_type: FileType) -> String {
+    match file_type {
+        FileType::Mpeg => "mp3".to_string(),
+        FileType::Vorbis => "ogg".to_string(),
+        FileType::Opus => "opus".to_string(),
+        FileType::Flac => "flac".to_string(),
+        FileType::Wav => "wav".to_string(),
+        FileType::Aac | FileType::Mp4 => "aac".to_string(),
+        other => format!("{other:?}").to_ascii_lowercase(),
+    }
+}
+
+/// Sample rates of the other audio files next to `song` (hitsounds), grouped by rate.
+fn sibling_sample_rates(song: &Path) -> BTreeMap<u32, usize> {
+    use lofty::prelude::*;
+
+    let mut rates = BTreeMap::new();
+    let Some(entries) = song.parent().and_then(|folder| fs::read_dir(folder).ok()) else {
+        return rates;
+    };
+    for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+        let is_audio = HITSOUND_EXTENSIONS.iter().any(|ext| has_extension(&path, ext));
+        if !is_audio || path == song {
+            continue;
+        }
+        if let Some(rate) = read_tagged_audio(&path, None).and_then(|file| file.properties().sample_rate()) {
+            *rates.entry(rate).or_insert(0) += 1;
+        }
+    }
+    rates
+}
+
+/// Probe duration, bitrate, sample rate, channels and codec, and flag what the ranking
+/// criteria reject: non-MP3/OGG audio, bitrates outside 128–192 kbps and hitsounds recorded at
+/// a different sample rate than the song.
+#[tauri::command]
+async fn probe_audio(file_path: String, file_name_hint: Option<String>) -> Result<AudioProbePayload, String> {
+    use lofty::prelude::*;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&file_path);
+        let file_size_bytes = fs::metadata(path).map_err(|err| err.to_string())?.len();
+        let tagged_file = read_tagged_audio(path, file_name_hint.as_deref())
+            .ok_or_else(|| "Unsupported or unreadable audio file".to_string())?;
+        let properties = tagged_file.properties();
+        let codec = audio_codec_name(tagged_file.file_type());
+        let bitrate_kbps = properties.audio_bitrate().or_else(|| properties.overall_bitrate());
+        let sample_rate_hz = properties.sample_rate();
+
+        let mut warnings = Vec::new();
+        if codec != "mp3" && codec != "ogg" {
+            warnings.push(format!("Audio is {codec}; ranked maps need MP3 or OGG"));
+        }
+        match bitrate_kbps {
+            Some(bitrate) if bitrate > RANKED_AUDIO_MAX_BITRATE_KBPS => warnings.push(format!(
+                "Bitrate is {bitrate} kbps (max {RANKED_AUDIO_MAX_BITRATE_KBPS} kbps)"
+            )),
+            Some(bitrate) if bitrate < RANKED_AUDIO_MIN_BITRATE_KBPS => warnings.push(format!(
+                "Bitrate is {bitrate} kbps (min {RANKED_AUDIO_MIN_BITRATE_KBPS} kbps)"
+            )),
+            _ => {}
+        }
+        if let Some(song_rate) = sample_rate_hz {
+            for (rate, count) in sibling_sample_rates(path) {
+                if rate != song_rate {
+                    warnings.push(format!(
+                        "{count} hitsound file(s) use {rate} Hz while the song is {song_rate} Hz"
+                    ));
+                }
+            }
+        }
+
+        Ok(AudioProbePayload {
+            file_path,
+            codec,
+            duration_ms: properties.duration().as_millis() as f64,
+            bitrate_kbps,
+            sample_rate_hz,
+            channels: properties.channels(),
+            bit_depth: properties.bit_depth(),
+            file_size_bytes,
+            warnings,
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Interleaved PCM of a whole track.
+struct DecodedAudio {
+    sample_rate: u32,
+    channels: usize,
+    samples: Vec<f32>,
+}
+
+impl DecodedAudio {
+    fn frame_count(&self) -> usize {
+        self.samples.len() / self.channels.max(1)
+    }
+
+    fn frames_to_ms(&self, frames: usize) -> f64 {
+        frames as f64 * 1000.0 / f64::from(self.sample_rate.max(1))
+    }
+}
+
+/// Decode an audio file to f32 PCM. `file_name_hint` supplies the extension for lazer's hashed files.
+fn decode_audio_file(path: &Path, file_name_hint: Option<&str>) -> Result<DecodedAudio, String> {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+    use symphonia::core::errors::Error as DecodeError;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let file = fs::File::open(path).map_err(|err| err.to_string())?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = file_name_hint
+        .and_then(|name| Path::new(name).extension())
+        .or_else(|| path.extension())
+        .and_then(|ext| ext.to_str())
+    {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|err| format!("unsupported audio: {err}"))?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| "no audio track".to_string())?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|err| format!("unsupported audio: {err}"))?;
+
+    let mut audio = DecodedAudio {
+        sample_rate: track.codec_params.sample_rate.unwrap_or(44_100),
+        channels: track.codec_params.channels.map(|channels| channels.count()).unwrap_or(2),
+        samples: Vec::new(),
+    };
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(DecodeError::ResetRequired) => break,
+            Err(err) => return Err(err.to_string()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = *decoded.spec();
+                audio.sample_rate = spec.rate;
+                audio.channels = spec.channels.count().max(1);
+                let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+                buffer.copy_interleaved_ref(decoded);
+                audio.samples.extend_from_slice(buffer.samples());
+            }
+            // A corrupt frame shouldn't sink the whole track.
+            Err(DecodeError::DecodeError(_)) => continue,
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+    if audio.samples.is_empty() {
+        return Err("audio decoded to no samples".to_string());
+    }
+    Ok(audio)
+}
+
+/// -60 dBFS.
+const AUDIO_SILENCE_THRESHOLD: f32 = 0.001;
+const AUDIO_CLIPPING_THRESHOLD: f32 = 0.999;
+/// Anything louder than this is almost certainly a brickwalled or double-mastered encode.
+const AUDIO_LOUD_MASTER_LUFS: f64 = -7.0;
+const AUDIO_LONG_LEAD_IN_MS: f64 = 2000.0;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioQualityPayload {
+    file_path: String,
+    duration_ms: f64,
+    sample_rate_hz: u32,
+    channels: usize,
+    leading_silence_ms: f64,
+    trailing_silence_ms: f64,
+    /// `None` for a track that is silent throughout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peak_dbfs: Option<f64>,
+    clipped_samples: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    integrated_lufs: Option<f64>,
+    warnings: Vec<String>,
+}
+
+/// Second-order IIR section in direct form I.
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&self, input: f64, state: &mut [f64; 4]) -> f64 {
+        let output = self.b[0] * input + self.b[1] * state[0] + self.b[2] * state[1]
+            - self.a[0] * state[2]
+            - self.a[1] * state[3];
+        *state = [input, state[0], output, state[2]];
+        output
+    }
+}
+
+/// The two BS.1770 K-weighting stages (high shelf, then high-pass) for `sample_rate`.
+fn k_weighting_filters(sample_rate: u32) -> [Biquad; 2] {
+    let fs = f64::from(sample_rate);
+
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+    [shelf, high_pass]
+}
+
+/// Integrated loudness per ITU-R BS.1770-4: 400 ms blocks with 75% overlap, gated at -70 LUFS
+/// and then 10 LU below the ungated mean. Every channel is weighted 1.0.
+fn integrated_loudness_lufs(audio: &DecodedAudio) -> Option<f64> {
+    let channels = audio.channels.max(1);
+    let filters = k_weighting_filters(audio.sample_rate);
+    let mut states = vec![[[0.0f64; 4]; 2]; channels];
+    // K-weighted energy per 100 ms step, summed as samples stream past; each 400 ms block is four
+    // consecutive steps, which gives the 75% overlap.
+    let step_frames = (f64::from(audio.sample_rate) * 0.1) as usize;
+    if step_frames == 0 {
+        return None;
+    }
+    let step_samples = step_frames * channels;
+    let mut step_energies = Vec::with_capacity(audio.samples.len() / step_samples);
+    let mut energy = 0.0;
+    for (index, &sample) in audio.samples.iter().enumerate() {
+        let state = &mut states[index % channels];
+        let shelved = filters[0].process(f64::from(sample), &mut state[0]);
+        let filtered = filters[1].process(shelved, &mut state[1]);
+        energy += filtered * filtered;
+        if (index + 1) % step_samples == 0 {
+            step_energies.push(energy);
+            energy = 0.0;
+        }
+    }
+
+    let block_frames = (step_frames * 4) as f64;
+    let block_loudness = |power: f64| -0.691 + 10.0 * power.log10();
+    let block_powers: Vec<f64> = step_energies
+        .windows(4)
+        .map(|steps| steps.iter().sum::<f64>() / block_frames)
+        .filter(|power| *power > 0.0 && block_loudness(*power) > -70.0)
+        .collect();
+    if block_powers.is_empty() {
+        return None;
+    }
+    let relative_gate = block_loudness(block_powers.iter().sum::<f64>() / block_powers.len() as f64) - 10.0;
+    let gated: Vec<f64> = block_powers.into_iter().filter(|power| block_loudness(*power) > relative_gate).collect();
+    (!gated.is_empty()).then(|| block_loudness(gated.iter().sum::<f64>() / gated.len() as f64))
+}
+
+fn analyze_decoded_audio(file_path: String, audio: &DecodedAudio) -> AudioQualityPayload {
+    let channels = audio.channels.max(1);
+    let frame_is_silent = |frame: &[f32]| frame.iter().all(|sample| sample.abs() < AUDIO_SILENCE_THRESHOLD);
+    let frames: Vec<&[f32]> = audio.samples.chunks_exact(channels).collect();
+    let leading_frames = frames.iter().position(|frame| !frame_is_silent(frame)).unwrap_or(frames.len());
+    let trailing_frames = frames
+        .iter()
+        .rev()
+        .position(|frame| !frame_is_silent(frame))
+        .unwrap_or(0);
+
+    let peak = audio.samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    let clipped_samples = audio
+        .samples
+        .iter()
+        .filter(|sample| sample.abs() >= AUDIO_CLIPPING_THRESHOLD)
+        .count() as u64;
+    let integrated_lufs = integrated_loudness_lufs(audio);
+
+    let leading_silence_ms = audio.frames_to_ms(leading_frames);
+    let mut warnings = Vec::new();
+    if clipped_samples > 0 {
+        warnings.push(format!("{clipped_samples} samples are at or above full scale; the encode is clipping"));
+    }
+    if leading_silence_ms > AUDIO_LONG_LEAD_IN_MS {
+        warnings.push(format!("{:.1} s of silence before the music starts", leading_silence_ms / 1000.0));
+    }
+    if integrated_lufs.is_some_and(|lufs| lufs > AUDIO_LOUD_MASTER_LUFS) {
+        warnings.push("the track is mastered very loud; it may sound distorted in game".to_string());
+    }
+
+    AudioQualityPayload {
+        file_path,
+        duration_ms: audio.frames_to_ms(frames.len()),
+        sample_rate_hz: audio.sample_rate,
+        channels,
+        leading_silence_ms,
+        trailing_silence_ms: audio.frames_to_ms(trailing_frames),
+        peak_dbfs: (peak > 0.0).then(|| 20.0 * f64::from(peak).log10()),
+        clipped_samples,
+        integrated_lufs,
+        warnings,
+    }
+}
+
+/// Decode the track and report silence at either end, peak level, clipping and integrated loudness.
+#[tauri::command]
+async fn analyze_audio_quality(file_path: String, file_name_hint: Option<String>) -> Result<AudioQualityPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let audio = decode_audio_file(Path::new(&file_path), file_name_hint.as_deref())?;
+        Ok(analyze_decoded_audio(file_path, &audio))
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const ONSET_FRAME_SIZE: usize = 1024;
+const ONSET_HOP_SIZE: usize = 256;
+/// Peaks closer together than this are one onset.
+const ONSET_MIN_GAP_MS: f64 = 30.0;
+/// Half-width of the moving average a flux peak has to clear.
+const ONSET_THRESHOLD_WINDOW_MS: f64 = 100.0;
+const ONSET_THRESHOLD_DELTA: f64 = 0.05;
+/// Objects further than this from any onset count as unmatched (possibly overmapped).
+const ONSET_MATCH_WINDOW_MS: f64 = 50.0;
+/// Offsets within this are neither early nor late.
+const ONSET_ON_TIME_TOLERANCE_MS: f64 = 5.0;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectOnsetOffset {
+    time_ms: i32,
+    /// Object time minus the nearest onset: positive is late. `None` when no onset is near.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OnsetAlignmentPayload {
+    file_path: String,
+    onset_count: usize,
+    matched_count: usize,
+    early_count: usize,
+    late_count: usize,
+    /// Objects with no onset within the match window.
+    unmatched_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mean_offset_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    median_offset_ms: Option<f64>,
+    /// Shift to pass to `apply_offset` so the median object lands on its onset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suggested_offset_ms: Option<i32>,
+    objects: Vec<ObjectOnsetOffset>,
+}
+
+/// In-place iterative radix-2 FFT; both slices must have the same power-of-two length.
+fn fft_in_place(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (even, odd) = (start + k, start + k + len / 2);
+                let odd_re = re[odd] * cos - im[odd] * sin;
+                let odd_im = re[odd] * sin + im[odd] * cos;
+                re[odd] = re[even] - odd_re;
+                im[odd] = im[even] - odd_im;
+                re[even] += odd_re;
+                im[even] += odd_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Onset times in ms, by peak-picking the log-compressed spectral flux of the downmixed track.
+fn detect_onsets(audio: &DecodedAudio) -> Vec<f64> {
+    let channels = audio.channels.max(1);
+    let mono: Vec<f64> = audio
+        .samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().map(|sample| f64::from(*sample)).sum::<f64>() / channels as f64)
+        .collect();
+    if mono.len() < ONSET_FRAME_SIZE {
+        return Vec::new();
+    }
+
+    let window: Vec<f64> = (0..ONSET_FRAME_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / ONSET_FRAME_SIZE as f64).cos())
+        .collect();
+    let frame_count = (mono.len() - ONSET_FRAME_SIZE) / ONSET_HOP_SIZE + 1;
+    let mut previous = vec![0.0; ONSET_FRAME_SIZE / 2];
+    let mut flux = Vec::with_capacity(frame_count);
+    let (mut re, mut im) = (vec![0.0; ONSET_FRAME_SIZE], vec![0.0; ONSET_FRAME_SIZE]);
+    for frame in 0..frame_count {
+        let offset = frame * ONSET_HOP_SIZE;
+        for i in 0..ONSET_FRAME_SIZE {
+            re[i] = mono[offset + i] * window[i];
+            im[i] = 0.0;
+        }
+        fft_in_place(&mut re, &mut im);
+        let mut sum = 0.0;
+        for (bin, last) in previous.iter_mut().enumerate() {
+            let magnitude = (1.0 + 10.0 * re[bin].hypot(im[bin])).ln();
+            sum += (magnitude - *last).max(0.0);
+            *last = magnitude;
+        }
+        flux.push(if frame == 0 { 0.0 } else { sum });
+    }
+    let max_flux = flux.iter().copied().fold(0.0, f64::max);
+    if max_flux <= 0.0 {
+        return Vec::new();
+    }
+    flux.iter_mut().for_each(|value| *value /= max_flux);
+
+    let frame_ms = ONSET_HOP_SIZE as f64 * 1000.0 / f64::from(audio.sample_rate.max(1));
+    let threshold_frames = (ONSET_THRESHOLD_WINDOW_MS / frame_ms).ceil() as usize;
+    let gap_frames = (ONSET_MIN_GAP_MS / frame_ms).ceil() as usize;
+    let center_ms = ONSET_FRAME_SIZE as f64 / 2.0 * 1000.0 / f64::from(audio.sample_rate.max(1));
+    let mut onsets: Vec<f64> = Vec::new();
+    let mut last_onset_frame: Option<usize> = None;
+    for frame in 1..flux.len() {
+        let around = frame.saturating_sub(threshold_frames)..(frame + threshold_frames + 1).min(flux.len());
+        let threshold = flux[around.clone()].iter().sum::<f64>() / around.len() as f64 + ONSET_THRESHOLD_DELTA;
+        let local = frame.saturating_sub(gap_frames)..(frame + gap_frames + 1).min(flux.len());
+        let is_peak = flux[local].iter().all(|value| *value <= flux[frame]);
+        if flux[frame] > threshold && is_peak && last_onset_frame.is_none_or(|last| frame - last >= gap_frames) {
+            onsets.push(frame as f64 * frame_ms + center_ms);
+            last_onset_frame = Some(frame);
+        }
+    }
+    onsets
+}
+
+/// Signed distance from `time` to the nearest onset (positive when `time` is after it).
+fn nearest_onset_offset(onsets: &[f64], time: f64) -> Option<f64> {
+    let index = onsets.partition_point(|onset| *onset < time);
+    [index.checked_sub(1), Some(index)]
+        .into_iter()
+        .flatten()
+        .filter_map(|index| onsets.get(index))
+        .map(|onset| time - onset)
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+}
+
+/// Compare every object with the nearest audio onset: the early/late bias says whether the offset
+/// is right, and objects with no onset nearby point at overmapping.
+#[tauri::command]
+async fn check_onset_alignment(file_path: String, audio_path: Option<String>) -> Result<OnsetAlignmentPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let parsed = load_parsed_osu(&file_path)?;
+        let audio_path = match audio_path {
+            Some(path) => PathBuf::from(path),
+            None => Path::new(&file_path)
+                .parent()
+                .ok_or_else(|| "invalid beatmap path".to_string())?
+                .join(&parsed.metadata.audio),
+        };
+        let audio = decode_audio_file(&audio_path, Some(&parsed.metadata.audio))?;
+        let onsets = detect_onsets(&audio);
+
+        let objects: Vec<ObjectOnsetOffset> = parsed
+            .hit_starts
+            .iter()
+            .map(|&time_ms| ObjectOnsetOffset {
+                time_ms,
+                offset_ms: nearest_onset_offset(&onsets, f64::from(time_ms))
+                    .filter(|offset| offset.abs() <= ONSET_MATCH_WINDOW_MS),
+            })
+            .collect();
+        let mut offsets: Vec<f64> = objects.iter().filter_map(|object| object.offset_ms).collect();
+        offsets.sort_by(f64::total_cmp);
+        let median_offset_ms = (!offsets.is_empty()).then(|| offsets[offsets.len() / 2]);
+
+        Ok(OnsetAlignmentPayload {
+            onset_count: onsets.len(),
+            matched_count: offsets.len(),
+            early_count: offsets.iter().filter(|offset| **offset < -ONSET_ON_TIME_TOLERANCE_MS).count(),
+            late_count: offsets.iter().filter(|offset| **offset > ONSET_ON_TIME_TOLERANCE_MS).count(),
+            unmatched_count: objects.len() - offsets.len(),
+            mean_offset_ms: (!offsets.is_empty()).then(|| offsets.iter().sum::<f64>() / offsets.len() as f64),
+            median_offset_ms,
+            suggested_offset_ms: median_offset_ms
+                .filter(|median| median.abs() > ONSET_ON_TIME_TOLERANCE_MS)
+                .map(|median| -median.round() as i32),
+            file_path,
+            objects,
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const CLICK_TRACK_SAMPLE_RATE: u32 = 44_100;
+const CLICK_TICK_LENGTH_MS: f64 = 30.0;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ClickTrackPayload {
+    destination: String,
+    duration_ms: f64,
+    tick_count: usize,
+    downbeat_count: usize,
+}
+
+/// 16-bit PCM WAV from interleaved f32 samples.
+fn encode_wav_pcm16(samples: &[f32], sample_rate: u32, channels: u16) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let block_align = channels * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&channels.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * u32::from(block_align)).to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&((sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16).to_le_bytes());
+    }
+    wav
+}
+
+/// Red lines faster than this (6000 BPM) are broken or abusive and are skipped by the metronome.
+const MIN_METRONOME_BEAT_LENGTH_MS: f64 = 10.0;
+
+/// How far past the song a caller-supplied click track length may reach.
+const CLICK_TRACK_MAX_OVERRUN_MS: f64 = 10_000.0;
+
+/// Beat times of every red line up to the next one (the first is extended back to 0), with
+/// whether each beat starts a measure.
+fn metronome_beats(timing_points: &[TimingPoint], duration_ms: f64) -> Vec<(f64, bool)> {
+    let red_lines: Vec<&TimingPoint> = timing_points
+        .iter()
+        .filter(|point| {
+            point.uninherited
+                && point.time.is_finite()
+                && point.beat_length.is_finite()
+                && point.beat_length >= MIN_METRONOME_BEAT_LENGTH_MS
+        })
+        .collect();
+    let mut beats = Vec::new();
+    for (index, point) in red_lines.iter().enumerate() {
+        let end = red_lines.get(index + 1).map_or(duration_ms, |next| next.time.min(duration_ms));
+        let meter = i64::from(point.meter.max(1));
+        let mut beat = if index == 0 { -(point.time / point.beat_length).floor() as i64 } else { 0 };
+        loop {
+            let time = point.time + beat as f64 * point.beat_length;
+            if time >= end {
+                break;
+            }
+            if time >= 0.0 {
+                beats.push((time, beat.rem_euclid(meter) == 0));
+            }
+            beat += 1;
+        }
+    }
+    beats
+}
+
+/// Song length for the click track: the audio's duration, or the last object plus a little tail.
+fn click_track_duration_ms(file_path: &str, parsed: &ParsedOsu) -> f64 {
+    use lofty::prelude::*;
+
+    Path::new(file_path)
+        .parent()
+        .map(|folder| folder.join(&parsed.metadata.audio))
+        .and_then(|audio_path| read_tagged_audio(&audio_path, None))
+        .map(|tagged_file| tagged_file.properties().duration().as_millis() as f64)
+        .filter(|duration| *duration > 0.0)
+        .unwrap_or_else(|| f64::from(parsed.hit_ends.iter().max().copied().unwrap_or(0)) + 2000.0)
+}
+
+/// Write a WAV metronome of the map's red lines (downbeats accented), to check timing by ear
+/// against the song outside the editor. `duration_ms` overrides the song length, up to 10 seconds
+/// past its end.
+#[tauri::command]
+async fn render_click_track(
+    file_path: String,
+    destination: String,
+    duration_ms: Option<f64>,
+) -> Result<ClickTrackPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let parsed = load_parsed_osu(&file_path)?;
+        let song_duration_ms = click_track_duration_ms(&file_path, &parsed);
+        let duration_ms = duration_ms
+            .filter(|duration| duration.is_finite() && *duration > 0.0)
+            .map_or(song_duration_ms, |duration| duration.min(song_duration_ms + CLICK_TRACK_MAX_OVERRUN_MS));
+        let beats = metronome_beats(&parsed.timing_points, duration_ms);
+        if beats.is_empty() {
+            return Err("the map has no timing points".to_string());
+        }
+
+        let rate = f64::from(CLICK_TRACK_SAMPLE_RATE);
+        let mut samples = vec![0.0f32; (duration_ms / 1000.0 * rate).ceil() as usize];
+        let tick_samples = (CLICK_TICK_LENGTH_MS / 1000.0 * rate) as usize;
+        for &(time, downbeat) in &beats {
+            let (frequency, amplitude) = if downbeat { (1500.0, 0.9) } else { (1000.0, 0.6) };
+            let start = (time / 1000.0 * rate) as usize;
+            for (i, sample) in samples.iter_mut().skip(start).take(tick_samples).enumerate() {
+                let t = i as f64 / rate;
+                let envelope = (-t * 200.0).exp();
+                *sample += (amplitude * envelope * (2.0 * std::f64::consts::PI * frequency * t).sin()) as f32;
+            }
+        }
+
+        write_file_atomically(Path::new(&destination), &encode_wav_pcm16(&samples, CLICK_TRACK_SAMPLE_RATE, 1))?;
+        Ok(ClickTrackPayload {
+            destination,
+            duration_ms,
+            tick_count: beats.len(),
+            downbeat_count: beats.iter().filter(|(_, downbeat)| *downbeat).count(),
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const AUDIO_CLIP_FADE_MS: f64 = 10.0;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioClipPayload {
+    destination: String,
+    format: String,
+    start_ms: f64,
+    duration_ms: f64,
+}
+
+/// Cut `start_ms..end_ms` out of decoded audio, with a short fade at both ends so the cut doesn't click.
+fn slice_audio(audio: &DecodedAudio, start_ms: f64, end_ms: f64) -> Vec<f32> {
+    let channels = audio.channels.max(1);
+    let rate = f64::from(audio.sample_rate);
+    let to_frame = |ms: f64| ((ms.max(0.0) / 1000.0 * rate) as usize).min(audio.frame_count());
+    let (start, end) = (to_frame(start_ms), to_frame(end_ms));
+    let mut clip = audio.samples[start * channels..end * channels].to_vec();
+    let frames = end - start;
+    let fade_frames = ((AUDIO_CLIP_FADE_MS / 1000.0 * rate) as usize).min(frames / 2);
+    for i in 0..fade_frames {
+        let gain = i as f32 / fade_frames as f32;
+        for channel in 0..channels {
+            clip[i * channels + channel] *= gain;
+            clip[(frames - 1 - i) * channels + channel] *= gain;
+        }
+    }
+    clip
+}
+
+/// Decode a time slice of the song and write it as 16-bit WAV, for auditioning a preview point or
+/// sharing a section. WAV is the only `format`: mosu bundles no lossy encoder.
+#[tauri::command]
+async fn export_audio_clip(
+    file_path: String,
+    start_ms: f64,
+    end_ms: f64,
+    destination: String,
+    format: Option<String>,
+) -> Result<AudioClipPayload, String> {
+    if !(start_ms.is_finite() && end_ms.is_finite() && end_ms > start_ms) {
+        return Err("the clip must end after it starts".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let format = format.unwrap_or_else(|| "wav".to_string()).to_lowercase();
+        if format != "wav" {
+            return Err(format!("unsupported clip format: {format} (only wav is supported)"));
+        }
+        let audio = decode_audio_file(Path::new(&file_path), None)?;
+        let clip = slice_audio(&audio, start_ms, end_ms);
+        if clip.is_empty() {
+            return Err("the clip is outside the song".to_string());
+        }
+        let wav = encode_wav_pcm16(&clip, audio.sample_rate, audio.channels as u16);
+        write_file_atomically(Path::new(&destination), &wav)?;
+        Ok(AudioClipPayload {
+            destination,
+            format,
+            start_ms: start_ms.max(0.0),
+            duration_ms: audio.frames_to_ms(clip.len() / audio.channels.max(1)),
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const RATE_PREVIEW_DEFAULT_RATE: f64 = 1.5;
+/// Half Time and Double Time bracket every rate osu! offers, with room for custom rates.
+const RATE_PREVIEW_MIN_RATE: f64 = 0.5;
+const RATE_PREVIEW_MAX_RATE: f64 = 2.0;
+/// Rendered previews older than this are deleted the next time one is rendered.
+const RATE_PREVIEW_MAX_AGE: Duration = Duration::from_secs(10 * 60);
+/// WSOLA segment length in frames; segments overlap by half.
+const WSOLA_WINDOW: usize = 2048;
+/// How far a segment may shift from its nominal position to line up with the previous one.
+const WSOLA_TOLERANCE: usize = 256;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RatePreviewPayload {
+    path: String,
+    rate: f64,
+    preserve_pitch: bool,
+    duration_ms: f64,
+}
+
+/// Play `samples` faster by linear-interpolation resampling, raising the pitch like Nightcore.
+fn resample_for_rate(samples: &[f32], channels: usize, rate: f64) -> Vec<f32> {
+    let frames = samples.len() / channels;
+    let out_frames = (frames as f64 / rate) as usize;
+    let mut out = Vec::with_capacity(out_frames * channels);
+    for frame in 0..out_frames {
+        let position = frame as f64 * rate;
+        let index = position as usize;
+        let fraction = (position - index as f64) as f32;
+        let next = (index + 1).min(frames - 1);
+        for channel in 0..channels {
+            let a = samples[index * channels + channel];
+            let b = samples[next * channels + channel];
+            out.push(a + (b - a) * fraction);
+        }
+    }
+    out
+}
+
+/// Speed `samples` up by `rate` without changing pitch, like Double Time, using WSOLA: Hann-windowed
+/// segments taken at `rate` times the output hop, each nudged to best match the previous one.
+fn time_stretch_for_rate(samples: &[f32], channels: usize, rate: f64) -> Vec<f32> {
+    let frames = samples.len() / channels;
+    let hop = WSOLA_WINDOW / 2;
+    if frames < WSOLA_WINDOW + WSOLA_TOLERANCE {
+        return resample_for_rate(samples, channels, rate);
+    }
+    let mono: Vec<f32> = samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+    let window: Vec<f32> = (0..WSOLA_WINDOW)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / WSOLA_WINDOW as f32).cos())
+        .collect();
+
+    let out_frames = (frames as f64 / rate) as usize + WSOLA_WINDOW;
+    let mut out = vec![0.0f32; out_frames * channels];
+    let mut previous_start = 0usize;
+    for segment in 0.. {
+        let out_start = segment * hop;
+        let nominal = (out_start as f64 * rate) as usize;
+        if nominal + WSOLA_WINDOW + WSOLA_TOLERANCE > frames || out_start + WSOLA_WINDOW > out_frames {
+            break;
+        }
+        let start = if segment == 0 {
+            0
+        } else {
+            let continuation = previous_start + hop;
+            let lowest = nominal.saturating_sub(WSOLA_TOLERANCE);
+            (lowest..=nominal + WSOLA_TOLERANCE)
+                .filter(|candidate| continuation + hop <= frames && candidate + WSOLA_WINDOW <= frames)
+                .max_by(|a, b| {
+                    let score = |candidate: usize| -> f32 {
+                        (0..hop).step_by(4).map(|i| mono[candidate + i] * mono[continuation + i]).sum()
+                    };
+                    score(*a).total_cmp(&score(*b))
+                })
+                .unwrap_or(nominal)
+        };
+        for i in 0..WSOLA_WINDOW {
+            for channel in 0..channels {
+                out[(out_start + i) * channels + channel] += samples[(start + i) * channels + channel] * window[i];
+            }
+        }
+        previous_start = start;
+    }
+    out.truncate((frames as f64 / rate) as usize * channels);
+    out
+}
+
+/// Delete previews the renderer has long finished playing.
+fn purge_old_rate_previews(preview_dir: &Path) {
+    let Ok(entries) = fs::read_dir(preview_dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let expired = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > RATE_PREVIEW_MAX_AGE);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Render a region of the song at a mod rate (1.5× by default, clamped to 0.5–2×) to a temporary
+/// WAV and return its path. `preserve_pitch` gives Double Time; otherwise the pitch rises as with
+/// Nightcore.
+#[tauri::command]
+async fn render_rate_preview(
+    file_path: String,
+    start_ms: f64,
+    end_ms: f64,
+    rate: Option<f64>,
+    preserve_pitch: Option<bool>,
+) -> Result<RatePreviewPayload, String> {
+    let rate = rate
+        .filter(|rate| rate.is_finite())
+        .unwrap_or(RATE_PREVIEW_DEFAULT_RATE)
+        .clamp(RATE_PREVIEW_MIN_RATE, RATE_PREVIEW_MAX_RATE);
+    let preserve_pitch = preserve_pitch.unwrap_or(false);
+    if !(start_ms.is_finite() && end_ms.is_finite() && end_ms > start_ms) {
+        return Err("the region must end after it starts".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let audio = decode_audio_file(Path::new(&file_path), None)?;
+        let channels = audio.channels.max(1);
+        let region = slice_audio(&audio, start_ms, end_ms);
+        if region.is_empty() {
+            return Err("the region is outside the song".to_string());
+        }
+        let rendered = if preserve_pitch {
+            time_stretch_for_rate(&region, channels, rate)
+        } else {
+            resample_for_rate(&region, channels, rate)
+        };
+
+        let preview_dir = std::env::temp_dir().join("mosu-rate-previews");
+        purge_old_rate_previews(&preview_dir);
+        fs::create_dir_all(&preview_dir).map_err(|err| err.to_string())?;
+        let path = preview_dir.join(format!("{}.wav", unix_time_ms()));
+        fs::write(&path, encode_wav_pcm16(&rendered, audio.sample_rate, channels as u16)).map_err(|err| err.to_string())?;
+        Ok(RatePreviewPayload {
+            path: path.to_string_lossy().to_string(),
+            rate,
+            preserve_pitch,
+            duration_ms: audio.frames_to_ms(rendered.len() / channels),
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const RANKED_VIDEO_MAX_WIDTH: u32 = 1280;
+const RANKED_VIDEO_MAX_HEIGHT: u32 = 720;
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct VideoProbePayload {
+    file_path: String,
+    container: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codec: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<f64>,
+    file_size_bytes: u64,
+    has_audio_track: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio_codec: Option<String>,
+    /// "ffprobe", or "mp4" when ffprobe isn't installed and the built-in MP4 reader was used.
+    probed_with: String,
+    warnings: Vec<String>,
+}
+
+/// Probe with ffprobe from PATH. `None` when ffprobe is missing or can't read the file.
+fn probe_video_ffprobe(path: &Path) -> Option<VideoProbePayload> {
+    let mut command = Command::new("ffprobe");
+    command
+        .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let json: Value = serde_json::from_slice(&output.stdout).ok()?;
+
+    let mut probe = VideoProbePayload {
+        probed_with: "ffprobe".to_string(),
+        ..VideoProbePayload::default()
+    };
+    let format = json.get("format");
+    probe.container = format
+        .and_then(|format| format.get("format_name"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    probe.duration_ms = format
+        .and_then(|format| format.get("duration"))
+        .and_then(Value::as_str)
+        .and_then(|seconds| seconds.parse::<f64>().ok())
+        .map(|seconds| seconds * 1000.0);
+
+    for stream in json.get("streams").and_then(Value::as_array).into_iter().flatten() {
+        let codec = stream.get("codec_name").and_then(Value::as_str).map(str::to_string);
+        match stream.get("codec_type").and_then(Value::as_str) {
+            Some("video") if probe.codec.is_none() => {
+                probe.codec = codec;
+                probe.width = stream.get("width").and_then(Value::as_u64).map(|width| width as u32);
+                probe.height = stream.get("height").and_then(Value::as_u64).map(|height| height as u32);
+            }
+            Some("audio") if !probe.has_audio_track => {
+                probe.has_audio_track = true;
+                probe.audio_codec = codec;
+            }
+            _ => {}
+        }
+    }
+    Some(probe)
+}
+
+/// Split an ISO-BMFF payload into `(type, body)` child boxes.
+fn mp4_boxes(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut boxes = Vec::new();
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as u64;
+        let kind = [data[4], data[5], data[6], data[7]];
+        let (header, size) = match size {
+            0 => (8, data.len() as u64),
+            1 if data.len() >= 16 => (16, u64::from_be_bytes(data[8..16].try_into().unwrap_or_default())),
+            size => (8, size),
+        };
+        if size < header as u64 || size > data.len() as u64 {
+            break;
+        }
+        boxes.push((kind, &data[header..size as usize]));
+        data = &data[size as usize..];
+    }
+    boxes
+}
+
+fn mp4_child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    mp4_boxes(data).into_iter().find(|(child, _)| child == kind).map(|(_, body)| body)
+}
+
+fn mp4_codec_name(fourcc: &[u8]) -> String {
+    match fourcc {
+        b"avc1" | b"avc3" => "h264".to_string(),
+        b"hvc1" | b"hev1" => "hevc".to_string(),
+        b"av01" => "av1".to_string(),
+        b"vp09" => "vp9".to_string(),
+        b"mp4v" => "mpeg4".to_string(),
+        b"mp4a" => "aac".to_string(),
+        b".mp3" => "mp3".to_string(),
+        other => String::from_utf8_lossy(other).trim().to_string(),
+    }
+}
+
+/// Largest `moov` box read; the metadata of even long videos stays far below this.
+const MAX_MP4_MOOV_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Read the `moov` box of an MP4 without loading the (possibly huge) media data.
+fn read_mp4_moov(path: &Path) -> Option<Vec<u8>> {
+    use std::io::{Seek, SeekFrom};
+
+    let mut file = fs::File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+    let mut offset = 0u64;
+    while offset + 8 <= file_len {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut header = [0u8; 16];
+        file.read_exact(&mut header[..8]).ok()?;
+        let mut size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let mut header_len = 8;
+        if size == 1 {
+            file.read_exact(&mut header[8..16]).ok()?;
+            size = u64::from_be_bytes(header[8..16].try_into().ok()?);
+            header_len = 16;
+        } else if size == 0 {
+            size = file_len - offset;
+        }
+        if size < header_len {
+            return None;
+        }
+        if &header[4..8] == b"moov" {
+            // The box size comes from the file; a moov past the end or beyond any real
+            // video's metadata is malformed.
+            if size > file_len - offset || size - header_len > MAX_MP4_MOOV_BYTES {
+                return None;
+            }
+            let mut moov = vec![0u8; (size - header_len) as usize];
+            file.read_exact(&mut moov).ok()?;
+            return Some(moov);
+        }
+        offset += size;
+    }
+    None
+}
+
+/// Pure-Rust fallback for MP4/MOV videos, the most common mapset video container.
+fn probe_video_mp4(path: &Path) -> Option<VideoProbePayload> {
+    let moov = read_mp4_moov(path)?;
+    let mut probe = VideoProbePayload {
+        container: "mp4".to_string(),
+        probed_with: "mp4".to_string(),
+        ..VideoProbePayload::default()
+    };
+
+    if let Some(mvhd) = mp4_child(&moov, b"mvhd") {
+        let read_u32 = |at: usize| mvhd.get(at..at + 4).map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap_or_default()));
+        let read_u64 = |at: usize| mvhd.get(at..at + 8).map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap_or_default()));
+        let (timescale, duration) = if mvhd.first() == Some(&1) {
+            (read_u32(20), read_u64(24))
+        } else {
+            (read_u32(12), read_u32(16).map(u64::from))
+        };
+        if let (Some(timescale), Some(duration)) = (timescale.filter(|scale| *scale > 0), duration) {
+            probe.duration_ms = Some(duration as f64 * 1000.0 / f64::from(timescale));
+        }
+    }
+
+    for (kind, trak) in mp4_boxes(&moov) {
+        if &kind != b"trak" {
+            continue;
+        }
+        let Some(mdia) = mp4_child(trak, b"mdia") else {
+            continue;
+        };
+        let handler = mp4_child(mdia, b"hdlr").and_then(|hdlr| hdlr.get(8..12));
+        let codec = mp4_child(mdia, b"minf")
+            .and_then(|minf| mp4_child(minf, b"stbl"))
+            .and_then(|stbl| mp4_child(stbl, b"stsd"))
+            .and_then(|stsd| stsd.get(12..16))
+            .map(mp4_codec_name);
+        match handler {
+            Some(b"vide") if probe.codec.is_none() => {
+                probe.codec = codec;
+                // tkhd ends with width and height as 16.16 fixed point.
+                if let Some(tkhd) = mp4_child(trak, b"tkhd").filter(|tkhd| tkhd.len() >= 8) {
+                    let dims = &tkhd[tkhd.len() - 8..];
+                    probe.width = Some(u32::from_be_bytes(dims[0..4].try_into().unwrap_or_default()) >> 16);
+                    probe.height = Some(u32::from_be_bytes(dims[4..8].try_into().unwrap_or_default()) >> 16);
+                }
+            }
+            Some(b"soun") if !probe.has_audio_track => {
+                probe.has_audio_track = true;
+                probe.audio_codec = codec;
+            }
+            _ => {}
+        }
+    }
+    Some(probe)
+}
+
+/// Probe a mapset video's resolution, duration, codec and audio tracks. Uses ffprobe when it
+/// is installed and falls back to a built-in MP4 reader otherwise.
+#[tauri::command]
+async fn probe_video(file_path: String) -> Result<VideoProbePayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&file_path);
+        let file_size_bytes = fs::metadata(path).map_err(|err| err.to_string())?.len();
+        let mut probe = probe_video_ffprobe(path)
+            .or_else(|| probe_video_mp4(path))
+            .ok_or_else(|| "Could not read the video; install ffprobe for containers other than MP4".to_string())?;
+        probe.file_path = file_path;
+        probe.file_size_bytes = file_size_bytes;
+
+        if probe.has_audio_track {
+            probe
+                .warnings
+                .push("The video has an audio track; ranked videos must have audio removed".to_string());
+        }
+        if let (Some(width), Some(height)) = (probe.width, probe.height) {
+            if width > RANKED_VIDEO_MAX_WIDTH || height > RANKED_VIDEO_MAX_HEIGHT {
+                probe.warnings.push(format!(
+                    "Video is {width}x{height} (max {RANKED_VIDEO_MAX_WIDTH}x{RANKED_VIDEO_MAX_HEIGHT})"
+                ));
+            }
+        }
+        Ok(probe)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Star rating for the map's own mode; with `includeConverts`, also the osu!standard → taiko,
+/// catch and mania convert ratings.
+#[tauri::command]
+async fn calculate_star_rating(file_path: String, options: Option<StarRatingOptions>) -> Option<StarRatingResult> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = fs::read(&file_path).ok()?;
+        if options.include_converts {
+            let ratings = compute_convert_star_ratings(&bytes)?;
+            if let Ok(mut cache) = library_cache().lock() {
+                cache.set_star_rating(&file_path, ratings.stars);
+            }
+            return Some(StarRatingResult::WithConverts(ratings));
+        }
+        let star_rating = cached_star_rating(&bytes, options.mods)?;
+        if options.mods == 0 {
+            if let Ok(mut cache) = library_cache().lock() {
+                cache.set_star_rating(&file_path, star_rating);
+            }
+        }
+        Some(StarRatingResult::Stars(star_rating))
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Cached star rating only, never calculating; `None` means the map needs `calculate_star_rating`.
+#[tauri::command]
+async fn get_cached_star_rating(file_path: String, mods: Option<u32>) -> Option<f64> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = fs::read(&file_path).ok()?;
+        star_rating_cache().lock().ok()?.get(&compute_osu_md5_hex(&bytes), mods.unwrap_or(0))
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct StaleStarRating {
+    file_path: String,
+    star_rating: f64,
+    /// Calculator release the rating came from, when known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    calculator_version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct StaleStarRatingsEvent {
+    count: usize,
+    calculator_version: &'static str,
+}
+
+fn stale_star_ratings() -> Result<Vec<StaleStarRating>, String> {
+    let cache = library_cache().lock().map_err(|err| err.to_string())?;
+    Ok(cache
+        .entries
+        .values()
+        .filter(|entry| entry.has_stale_star_rating())
+        .map(|entry| StaleStarRating {
+            file_path: entry.file_path.clone(),
+            star_rating: entry.metadata.star_rating,
+            calculator_version: entry.star_rating_version.clone(),
+        })
+        .collect())
+}
+
+/// Let the renderer offer a refresh when library ratings came from an older rosu-pp.
+fn notify_stale_star_ratings(window: &tauri::Window) {
+    let Ok(cache) = library_cache().lock() else {
+        return;
+    };
+    let count = cache.entries.values().filter(|entry| entry.has_stale_star_rating()).count();
+    drop(cache);
+    if count > 0 {
+        let _ = window.emit(
+            "star-ratings-stale",
+            StaleStarRatingsEvent { count, calculator_version: ROSU_PP_VERSION },
+        );
+    }
+}
+
+/// Library maps whose displayed star rating was calculated by another rosu-pp release.
+#[tauri::command]
+async fn get_stale_ratings() -> Result<Vec<StaleStarRating>, String> {
+    tauri::async_runtime::spawn_blocking(stale_star_ratings)
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+/// Queue a recalculation of every stale rating (or only `file_paths`) with the bundled rosu-pp.
+/// Runs as a `staleStarRatings` job, so progress arrives as `job-progress` events.
+#[tauri::command]
+fn recalculate_stale_ratings(app_handle: tauri::AppHandle, file_paths: Option<Vec<String>>) -> Result<u64, String> {
+    queue_job(app_handle, JobKind::StaleStarRatings, file_paths)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OsuSkillAttributes {
+    aim: f64,
+    speed: f64,
+    flashlight: f64,
+    /// Aim rating without sliders divided by the full one; lower means sliders carry more of the aim.
+    slider_factor: f64,
+    /// Roughly how many notes count towards the speed rating.
+    speed_note_count: f64,
+    approach_rate: f64,
+    overall_difficulty: f64,
+    circles: u32,
+    sliders: u32,
+    spinners: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaikoSkillAttributes {
+    stamina: f64,
+    rhythm: f64,
+    colour: f64,
+    peak: f64,
+    great_hit_window: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CatchSkillAttributes {
+    approach_rate: f64,
+    fruits: u32,
+    droplets: u32,
+    tiny_droplets: u32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManiaSkillAttributes {
+    hit_window: f64,
+    objects: u32,
+}
+
+/// Full difficulty attributes for one map and mod combination. Only the entry for the map's
+/// mode is set.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DifficultyAttributesPayload {
+    mode: i32,
+    mods: u32,
+    stars: f64,
+    max_combo: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    osu: Option<OsuSkillAttributes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    taiko: Option<TaikoSkillAttributes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    catch: Option<CatchSkillAttributes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mania: Option<ManiaSkillAttributes>,
+}
+
+fn difficulty_attributes_payload(attributes: &rosu_pp::DifficultyAttributes, mods: u32) -> DifficultyAttributesPayload {
+    use rosu_pp::DifficultyAttributes;
+
+    let mut payload = DifficultyAttributesPayload {
+        mode: 0,
+        mods,
+        stars: attributes.stars(),
+        max_combo: attributes.max_combo(),
+        osu: None,
+        taiko: None,
+        catch: None,
+        mania: None,
+    };
+    match attributes {
+        DifficultyAttributes::Osu(osu) => {
+            payload.osu = Some(OsuSkillAttributes {
+                aim: osu.aim,
+                speed: osu.speed,
+                flashlight: osu.flashlight,
+                slider_factor: osu.slider_factor,
+                speed_note_count: osu.speed_note_count,
+                approach_rate: osu.ar,
+                overall_difficulty: osu.od,
+                circles: osu.n_circles,
+                sliders: osu.n_sliders,
+                spinners: osu.n_spinners,
+            })
+        }
+        DifficultyAttributes::Taiko(taiko) => {
+            payload.mode = 1;
+            payload.taiko = Some(TaikoSkillAttributes {
+                stamina: taiko.stamina,
+                rhythm: taiko.rhythm,
+                colour: taiko.color,
+                peak: taiko.peak,
+                great_hit_window: taiko.great_hit_window,
+            })
+        }
+        DifficultyAttributes::Catch(catch) => {
+            payload.mode = 2;
+            payload.catch = Some(CatchSkillAttributes {
+                approach_rate: catch.ar,
+                fruits: catch.n_fruits,
+                droplets: catch.n_droplets,
+                tiny_droplets: catch.n_tiny_droplets,
+            })
+        }
+        DifficultyAttributes::Mania(mania) => {
+            payload.mode = 3;
+            payload.mania = Some(ManiaSkillAttributes { hit_window: mania.hit_window, objects: mania.n_objects })
+        }
+    }
+    payload
+}
+
+/// Per-skill breakdown behind the star rating (aim/speed/flashlight for standard,
+/// stamina/colour/rhythm for taiko, ...) for the analysis page's skill charts.
+#[tauri::command]
+async fn calculate_difficulty_attributes(
+    file_path: String,
+    mods: Option<u32>,
+) -> Result<DifficultyAttributesPayload, String> {
+    let mods = mods.unwrap_or(0);
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = fs::read(&file_path).map_err(|err| err.to_string())?;
+        let map = Beatmap::from_bytes(&bytes).map_err(|err| err.to_string())?;
+        let attributes = Difficulty::new().mods(mods).calculate(&map);
+        Ok(difficulty_attributes_payload(&attributes, mods))
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StarRatingProgressPoint {
+    /// Objects counted so far.
+    objects: usize,
+    /// Start time of the last counted object. Catch steps also count droplets, so catch maps
+    /// are labelled by `objects` alone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time: Option<i32>,
+    stars: f64,
+}
+
+/// Star rating after every `step_objects` objects (and after the last one), so WIP maps can
+/// plot how difficulty builds up section by section.
+#[tauri::command]
+async fn calculate_star_rating_progression(
+    file_path: String,
+    step_objects: Option<usize>,
+    mods: Option<u32>,
+) -> Result<Vec<StarRatingProgressPoint>, String> {
+    use rosu_pp::model::mode::GameMode;
+
+    let step_objects = step_objects.unwrap_or(10).max(1);
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = fs::read(&file_path).map_err(|err| err.to_string())?;
+        let map = Beatmap::from_bytes(&bytes).map_err(|err| err.to_string())?;
+        let mut times: Vec<i32> = if map.mode == GameMode::Catch {
+            Vec::new()
+        } else {
+            map.hit_objects.iter().map(|object| object.start_time as i32).collect()
+        };
+        times.sort_unstable();
+
+        let mut gradual = Difficulty::new().mods(mods.unwrap_or(0)).gradual_difficulty(&map);
+        let total = gradual.len();
+        let mut points = Vec::new();
+        let mut objects = 0;
+        while objects < total {
+            let step = step_objects.min(total - objects);
+            let Some(attributes) = gradual.nth(step - 1) else {
+                break;
+            };
+            objects += step;
+            points.push(StarRatingProgressPoint {
+                objects,
+                time: times.get(objects - 1).copied(),
+                stars: attributes.stars(),
+            });
+        }
+        Ok(points)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Scans in flight; background jobs hold off until every scan has finished.
+static ACTIVE_SCANS: AtomicUsize = AtomicUsize::new(0);
+
+struct ActiveScanGuard;
+
+impl ActiveScanGuard {
+    fn enter() -> Self {
+        ACTIVE_SCANS.fetch_add(1, Ordering::AcqRel);
+        Self
+    }
+}
+
+impl Drop for ActiveScanGuard {
+    fn drop(&mut self) {
+        ACTIVE_SCANS.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+const JOB_PROGRESS_INTERVAL: usize = 25;
+const JOB_SCAN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum JobKind {
+    /// Star rating for every cached map that doesn't have one yet.
+    StarRatings,
+    /// Content MD5 for every cached map that doesn't have one yet.
+    Hashes,
+    /// Star rating for every cached map rated by another rosu-pp release.
+    StaleStarRatings,
+}
+
+struct Job {
+    id: u64,
+    kind: JobKind,
+    /// Limits the job to these maps; every matching cached map otherwise.
+    file_paths: Option<Vec<String>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+struct JobQueue {
+    next_id: u64,
+    pending: std::collections::VecDeque<Job>,
+    running: Option<(u64, Arc<AtomicBool>)>,
+    worker_running: bool,
+}
+
+static JOB_QUEUE: Mutex<JobQueue> = Mutex::new(JobQueue {
+    next_id: 1,
+    pending: std::collections::VecDeque::new(),
+    running: None,
+    worker_running: false,
+});
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct JobProgressEvent {
+    job_id: u64,
+    kind: JobKind,
+    /// `queued`, `waiting` (for a scan to finish), `running`, `done` or `cancelled` (also sent
+    /// when the job fails).
+    state: &'static str,
+    current: usize,
+    total: usize,
+}
+
+fn emit_job_progress(app_handle: &tauri::AppHandle, job: &Job, state: &'static str, current: usize, total: usize) {
+    let _ = app_handle.emit("job-progress", JobProgressEvent { job_id: job.id, kind: job.kind, state, current, total });
+}
+
+/// Jobs run one at a time on a single low-priority thread that exits once the queue is empty.
+fn run_job_worker(app_handle: tauri::AppHandle) {
+    lower_current_thread_priority();
+    loop {
+        let job = {
+            // The queue must always be released here, or no job would ever run again.
+            let mut queue = JOB_QUEUE.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            let Some(job) = queue.pending.pop_front() else {
+                queue.running = None;
+                queue.worker_running = false;
+                return;
+            };
+            queue.running = Some((job.id, Arc::clone(&job.cancelled)));
+            job
+        };
+        if let Err(err) = run_job(&app_handle, &job) {
+            eprintln!("job {} failed: {err}", job.id);
+            emit_job_progress(&app_handle, &job, "cancelled", 0, 0);
+        }
+    }
+}
+
+fn run_job(app_handle: &tauri::AppHandle, job: &Job) -> Result<(), String> {
+    let mut file_paths: Vec<String> = {
+        let cache = library_cache().lock().map_err(|err| err.to_string())?;
+        cache
+            .entries
+            .values()
+            .filter(|entry| match job.kind {
+                JobKind::StarRatings => entry.metadata.star_rating < 0.0,
+                JobKind::Hashes => entry.beatmap_hash.is_none(),
+                JobKind::StaleStarRatings => entry.has_stale_star_rating(),
+            })
+            .filter(|entry| job.file_paths.as_ref().is_none_or(|file_paths| file_paths.contains(&entry.file_path)))
+            .map(|entry| entry.file_path.clone())
+            .collect()
+    };
+    file_paths.sort();
+    let total = file_paths.len();
+
+    for (index, file_path) in file_paths.iter().enumerate() {
+        if ACTIVE_SCANS.load(Ordering::Acquire) > 0 {
+            emit_job_progress(app_handle, job, "waiting", index, total);
+            while ACTIVE_SCANS.load(Ordering::Acquire) > 0 && !job.cancelled.load(Ordering::Acquire) {
+                std::thread::sleep(JOB_SCAN_POLL_INTERVAL);
+            }
+        }
+        if job.cancelled.load(Ordering::Acquire) {
+            flush_library_cache();
+            emit_job_progress(app_handle, job, "cancelled", index, total);
+            return Ok(());
+        }
+        if index % JOB_PROGRESS_INTERVAL == 0 {
+            emit_job_progress(app_handle, job, "running", index, total);
+        }
+
+        let Ok(bytes) = fs::read(file_path) else {
+            continue;
+        };
+        match job.kind {
+            JobKind::StarRatings | JobKind::StaleStarRatings => {
+                if let Some(star_rating) = cached_star_rating(&bytes, 0) {
+                    library_cache()
+                        .lock()
+                        .map_err(|err| err.to_string())?
+                        .set_star_rating(file_path, star_rating);
+                }
+            }
+            JobKind::Hashes => {
+                library_cache()
+                    .lock()
+                    .map_err(|err| err.to_string())?
+                    .set_beatmap_hash(file_path, compute_osu_md5_hex(&bytes));
+            }
+        }
+    }
+
+    flush_library_cache();
+    emit_job_progress(app_handle, job, "done", total, total);
+    Ok(())
+}
+
+/// Queue a long-running library job and return its id. Progress arrives as `job-progress` events;
+/// jobs start after any running scan completes.
+#[tauri::command]
+fn enqueue_job(app_handle: tauri::AppHandle, kind: JobKind) -> Result<u64, String> {
+    queue_job(app_handle, kind, None)
+}
+
+fn queue_job(app_handle: tauri::AppHandle, kind: JobKind, file_paths: Option<Vec<String>>) -> Result<u64, String> {
+    let mut queue = JOB_QUEUE.lock().map_err(|err| err.to_string())?;
+    let job = Job { id: queue.next_id, kind, file_paths, cancelled: Arc::new(AtomicBool::new(false)) };
+    queue.next_id += 1;
+    emit_job_progress(&app_handle, &job, "queued", 0, 0);
+    let job_id = job.id;
+    queue.pending.push_back(job);
+    if !queue.worker_running {
+        queue.worker_running = true;
+        std::thread::spawn(move || run_job_worker(app_handle));
+    }
+    Ok(job_id)
+}
+
+/// Cancel a queued or running job. Returns false when no such job is pending.
+#[tauri::command]
+fn cancel_job(app_handle: tauri::AppHandle, job_id: u64) -> Result<bool, String> {
+    let mut queue = JOB_QUEUE.lock().map_err(|err| err.to_string())?;
+    if let Some(position) = queue.pending.iter().position(|job| job.id == job_id) {
+        if let Some(job) = queue.pending.remove(position) {
+            emit_job_progress(&app_handle, &job, "cancelled", 0, 0);
+        }
+        return Ok(true);
+    }
+    match &queue.running {
+        Some((running_id, cancelled)) if *running_id == job_id => {
+            cancelled.store(true, Ordering::Release);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+#[tauri::command]
+async fn check_snapping(file_path: String) -> Result<SnappingReportPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let parsed = load_parsed_osu(&file_path)?;
+        build_snapping_report(file_path, &parsed)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DifficultySettingsPayload {
+    file_path: String,
+    base: DifficultySettings,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    adjusted: Option<DifficultySettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mods: Option<u32>,
+}
+
+/// CS/AR/OD/HP for one map, plus the mod-adjusted values when `mods` is given.
+#[tauri::command]
+fn get_difficulty_settings(file_path: String, mods: Option<u32>) -> Result<DifficultySettingsPayload, String> {
+    let base = load_parsed_osu(&file_path)?.metadata.difficulty;
+    Ok(DifficultySettingsPayload {
+        file_path,
+        base,
+        adjusted: mods.map(|mods| base.with_mods(mods)),
+        mods,
+    })
+}
+
+/// osu!standard judgement windows (±ms) as the player experiences them.
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct HitWindows {
+    great: f64,
+    ok: f64,
+    meh: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModdedDifficultyPayload {
+    file_path: String,
+    mods: u32,
+    clock_rate: f64,
+    base: DifficultySettings,
+    adjusted: DifficultySettings,
+    hit_windows: HitWindows,
+    approach_time_ms: f64,
+    bpm: BpmSummary,
+}
+
+/// CS/AR/OD/HP with `mods` applied, plus the real-time hit windows, approach time and BPM
+/// under DT/NC/HT.
+#[tauri::command]
+fn apply_mods_to_difficulty(file_path: String, mods: u32) -> Result<ModdedDifficultyPayload, String> {
+    let parsed = load_parsed_osu(&file_path)?;
+    let base = parsed.metadata.difficulty;
+    let clock_rate = mods_clock_rate(mods);
+    // Windows are derived from the EZ/HR-scaled values and then shortened by the clock rate.
+    let scaled = base.with_mods(mods & (MOD_HARD_ROCK | MOD_EASY));
+    let hit_windows = HitWindows {
+        great: (80.0 - 6.0 * scaled.od) / clock_rate,
+        ok: (140.0 - 8.0 * scaled.od) / clock_rate,
+        meh: (200.0 - 10.0 * scaled.od) / clock_rate,
+    };
+
+    let last_object_time = parsed.hit_ends.iter().max().copied().unwrap_or(0);
+    let mut bpm = compute_bpm_summary(&parsed.timing_points, last_object_time);
+    bpm.dominant *= clock_rate;
+    bpm.min *= clock_rate;
+    bpm.max *= clock_rate;
+
+    Ok(ModdedDifficultyPayload {
+        file_path,
+        mods,
+        clock_rate,
+        base,
+        adjusted: base.with_mods(mods),
+        hit_windows,
+        approach_time_ms: approach_rate_to_ms(scaled.ar) / clock_rate,
+        bpm,
+    })
+}
+
+const OSU_PLAYFIELD_WIDTH: f64 = 512.0;
+const OSU_PLAYFIELD_HEIGHT: f64 = 384.0;
+const CURVE_SAMPLES_PER_SEGMENT: usize = 50;
+const HIT_OBJECT_FADE_IN_MS: f64 = 400.0;
+const HIT_OBJECT_FADE_OUT_MS: f64 = 240.0;
+/// Combo colours of the default skin, used when the map has no `[Colours]` section.
+const DEFAULT_COMBO_COLOURS: [(u8, u8, u8); 4] = [(255, 192, 0), (0, 202, 0), (18, 124, 255), (242, 24, 57)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum SliderCurveType {
+    Linear,
+    Perfect,
+    Bezier,
+    Catmull,
+}
+
+impl SliderCurveType {
+    fn from_letter(letter: &str) -> Self {
+        match letter.trim() {
+            "L" => Self::Linear,
+            "P" => Self::Perfect,
+            "C" => Self::Catmull,
+            _ => Self::Bezier,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SliderShape {
+    curve: SliderCurveType,
+    /// Control points in playfield pixels, starting with the slider head.
+    control_points: Vec<(f64, f64)>,
+    pixel_length: f64,
+    slides: i32,
+}
+
+/// A hit object with its playfield position, for rendering and slider geometry.
+#[derive(Debug, Clone)]
+struct PlayfieldObject {
+    time: i32,
+    end_time: i32,
+    x: f64,
+    y: f64,
+    object_type: i32,
+    hitsound: i32,
+    slider: Option<SliderShape>,
+}
+
+/// Positions and slider shapes of `parsed`'s hit objects, which `ParsedOsu` itself doesn't keep.
+/// Slider ends use the same timing as `parse_osu_content`, before its gap filling.
+fn parse_playfield_objects(content: &str, parsed: &ParsedOsu) -> Vec<PlayfieldObject> {
+    let slider_multiplier = parsed.metadata.difficulty.slider_multiplier;
+
+    osu_section_lines(content, "HitObjects")
+        .into_iter()
+        .filter_map(|line| {
+            let x = csv_field(line, 0)?.trim().parse::<f64>().ok()?;
+            let y = csv_field(line, 1)?.trim().parse::<f64>().ok()?;
+            let time = csv_field(line, 2)?.trim().parse::<i32>().ok()?;
+            let object_type = csv_field(line, 3)?.trim().parse::<i32>().ok()?;
+            let hitsound = csv_field(line, 4).and_then(|v| v.trim().parse::<i32>().ok()).unwrap_or(0);
+            let mut object = PlayfieldObject { time, end_time: time, x, y, object_type, hitsound, slider: None };
+
+            if object_type & 2 != 0 {
+                let mut curve_fields = csv_field(line, 5).unwrap_or("").split('|');
+                let curve = SliderCurveType::from_letter(curve_fields.next().unwrap_or("B"));
+                let mut control_points = vec![(x, y)];
+                control_points.extend(curve_fields.filter_map(|point| {
+                    let (px, py) = point.split_once(':')?;
+                    Some((px.trim().parse::<f64>().ok()?, py.trim().parse::<f64>().ok()?))
+                }));
+                let slides = csv_field(line, 6).and_then(|v| v.trim().parse::<i32>().ok()).unwrap_or(1).max(1);
+                let pixel_length = csv_field(line, 7).and_then(|v| v.trim().parse::<f64>().ok()).unwrap_or(0.0);
+                let timing = timing_at(&parsed.timing_points, f64::from(time));
+                let duration = slider_duration_ms(pixel_length, f64::from(slides), slider_multiplier, timing);
+                object.end_time = time + duration.max(0.0).floor() as i32;
+                object.slider = Some(SliderShape { curve, control_points, pixel_length, slides });
+            } else if object_type & (8 | 128) != 0 {
+                // Spinner end time, or a mania hold's end before its `:`-separated hit sample.
+                object.end_time = csv_field(line, 5)
+                    .and_then(|v| v.split(':').next()?.trim().parse::<i32>().ok())
+                    .unwrap_or(time)
+                    .max(time);
+            }
+            Some(object)
+        })
+        .collect()
+}
+
+fn lerp_point(a: (f64, f64), b: (f64, f64), t: f64) -> (f64, f64) {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+}
+
+fn point_distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (b.0 - a.0).hypot(b.1 - a.1)
+}
+
+fn bezier_segment_points(points: &[(f64, f64)], out: &mut Vec<(f64, f64)>) {
+    if points.len() <= 2 {
+        out.extend_from_slice(points);
+        return;
+    }
+    let mut scratch = points.to_vec();
+    for step in 0..=CURVE_SAMPLES_PER_SEGMENT {
+        let t = step as f64 / CURVE_SAMPLES_PER_SEGMENT as f64;
+        scratch.copy_from_slice(points);
+        for level in (1..scratch.len()).rev() {
+            for i in 0..level {
+                scratch[i] = lerp_point(scratch[i], scratch[i + 1], t);
+            }
+        }
+        out.push(scratch[0]);
+    }
+}
+
+/// Circular arc through three points; `None` when they are (nearly) collinear.
+fn perfect_arc_points(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Option<Vec<(f64, f64)>> {
+    let d = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+    if d.abs() < 1e-6 {
+        return None;
+    }
+    let a_sq = a.0 * a.0 + a.1 * a.1;
+    let b_sq = b.0 * b.0 + b.1 * b.1;
+    let c_sq = c.0 * c.0 + c.1 * c.1;
+    let center = (
+        (a_sq * (b.1 - c.1) + b_sq * (c.1 - a.1) + c_sq * (a.1 - b.1)) / d,
+        (a_sq * (c.0 - b.0) + b_sq * (a.0 - c.0) + c_sq * (b.0 - a.0)) / d,
+    );
+    let radius = point_distance(a, center);
+    let start_angle = (a.1 - center.1).atan2(a.0 - center.0);
+    let mut end_angle = (c.1 - center.1).atan2(c.0 - center.0);
+    while end_angle < start_angle {
+        end_angle += std::f64::consts::TAU;
+    }
+    let mut range = end_angle - start_angle;
+    // Go the other way round when the middle point is on the clockwise side.
+    let clockwise = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0) < 0.0;
+    if clockwise {
+        range -= std::f64::consts::TAU;
+    }
+    let steps = ((range.abs() * radius) / 4.0).ceil().clamp(2.0, 1000.0) as usize;
+    Some(
+        (0..=steps)
+            .map(|step| {
+                let angle = start_angle + range * step as f64 / steps as f64;
+                (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+            })
+            .collect(),
+    )
+}
+
+/// Untruncated polyline of a slider curve, sampled from its control points.
+fn slider_curve_points(curve: SliderCurveType, control_points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    match curve {
+        SliderCurveType::Linear => control_points.to_vec(),
+        SliderCurveType::Perfect if control_points.len() == 3 => {
+            perfect_arc_points(control_points[0], control_points[1], control_points[2])
+                .unwrap_or_else(|| slider_curve_points(SliderCurveType::Bezier, control_points))
+        }
+        SliderCurveType::Catmull => {
+            let mut out = Vec::new();
+            for i in 0..control_points.len().saturating_sub(1) {
+                let v2 = control_points[i];
+                let v3 = control_points[i + 1];
+                let v1 = if i > 0 { control_points[i - 1] } else { v2 };
+                let v4 = control_points.get(i + 2).copied().unwrap_or((2.0 * v3.0 - v2.0, 2.0 * v3.1 - v2.1));
+                for step in 0..CURVE_SAMPLES_PER_SEGMENT {
+                    let t = step as f64 / CURVE_SAMPLES_PER_SEGMENT as f64;
+                    let (t2, t3) = (t * t, t * t * t);
+                    let axis = |p1: f64, p2: f64, p3: f64, p4: f64| {
+                        0.5 * (2.0 * p2
+                            + (-p1 + p3) * t
+                            + (2.0 * p1 - 5.0 * p2 + 4.0 * p3 - p4) * t2
+                            + (-p1 + 3.0 * p2 - 3.0 * p3 + p4) * t3)
+                    };
+                    out.push((axis(v1.0, v2.0, v3.0, v4.0), axis(v1.1, v2.1, v3.1, v4.1)));
+                }
+            }
+            out.extend(control_points.last().copied());
+            out
+        }
+        _ => {
+            // Repeated control points (red anchors) split the curve into separate bezier segments.
+            let mut out = Vec::new();
+            let mut segment_start = 0;
+            for i in 1..=control_points.len() {
+                if i == control_points.len() || control_points[i] == control_points[i - 1] {
+                    bezier_segment_points(&control_points[segment_start..i], &mut out);
+                    segment_start = i;
+                }
+            }
+            out
+        }
+    }
+}
+
+/// Objects closer than this (osu! pixels) stack onto each other.
+const STACK_DISTANCE: f64 = 3.0;
+
+/// Where an object ends up: the slider tail after an odd number of spans, otherwise its head.
+fn playfield_end_position(object: &PlayfieldObject) -> (f64, f64) {
+    let head = (object.x, object.y);
+    match &object.slider {
+        Some(slider) if slider.slides % 2 == 1 => fit_path_to_length(
+            &slider_curve_points(slider.curve, &slider.control_points),
+            slider.pixel_length,
+        )
+        .last()
+        .copied()
+        .unwrap_or(head),
+        _ => head,
+    }
+}
+
+/// Stack heights from osu!'s stacking pass (the beatmap version 6+ one), walking backwards so
+/// each stack builds up from its last object. Objects stack when they start within
+/// `preempt * stack_leniency` of the one after them, within `STACK_DISTANCE`.
+fn compute_stack_heights(objects: &[PlayfieldObject], preempt: f64, stack_leniency: f64) -> Vec<i32> {
+    let threshold = preempt * stack_leniency;
+    let ends: Vec<(f64, f64)> = objects.iter().map(playfield_end_position).collect();
+    let head = |index: usize| (objects[index].x, objects[index].y);
+    let is_spinner = |index: usize| objects[index].object_type & 8 != 0;
+    let mut heights = vec![0_i32; objects.len()];
+
+    for i in (1..objects.len()).rev() {
+        if heights[i] != 0 || is_spinner(i) {
+            continue;
+        }
+        let mut current = i;
+        let mut n = i;
+        if objects[i].slider.is_none() {
+            while n > 0 {
+                n -= 1;
+                if is_spinner(n) {
+                    continue;
+                }
+                if f64::from(objects[current].time - objects[n].end_time) > threshold {
+                    break;
+                }
+                // A circle sitting on a slider's tail pushes the circles after it the other way.
+                if objects[n].slider.is_some() && point_distance(ends[n], head(current)) < STACK_DISTANCE {
+                    let offset = heights[current] - heights[n] + 1;
+                    for (j, height) in heights.iter_mut().enumerate().take(i + 1).skip(n + 1) {
+                        if point_distance(ends[n], head(j)) < STACK_DISTANCE {
+                            *height -= offset;
+                        }
+                    }
+                    break;
+                }
+                if point_distance(head(n), head(current)) < STACK_DISTANCE {
+                    heights[n] = heights[current] + 1;
+                    current = n;
+                }
+            }
+        } else {
+            while n > 0 {
+                n -= 1;
+                if is_spinner(n) {
+                    continue;
+                }
+                if f64::from(objects[current].time - objects[n].time) > threshold {
+                    break;
+                }
+                if point_distance(ends[n], head(current)) < STACK_DISTANCE {
+                    heights[n] = heights[current] + 1;
+                    current = n;
+                }
+            }
+        }
+    }
+    heights
+}
+
+/// Playfield pixels each stack level shifts an object up and to the left, for circle size `cs`.
+fn stack_offset_px(cs: f64) -> f64 {
+    (54.4 - 4.48 * cs) / 10.0
+}
+
+fn polyline_length(points: &[(f64, f64)]) -> f64 {
+    points.windows(2).map(|pair| point_distance(pair[0], pair[1])).sum()
+}
+
+/// Cut (or linearly extend) a polyline to exactly `length` pixels, as the game does with
+/// a slider's declared pixel length.
+fn fit_path_to_length(points: &[(f64, f64)], length: f64) -> Vec<(f64, f64)> {
+    let mut out = Vec::with_capacity(points.len());
+    let Some(&first) = points.first() else {
+        return out;
+    };
+    out.push(first);
+    let mut travelled = 0.0;
+    for pair in points.windows(2) {
+        let segment = point_distance(pair[0], pair[1]);
+        if travelled + segment >= length {
+            let t = if segment > 0.0 { (length - travelled) / segment } else { 0.0 };
+            out.push(lerp_point(pair[0], pair[1], t));
+            return out;
+        }
+        travelled += segment;
+        out.push(pair[1]);
+    }
+    // Declared length is longer than the curve: extend along the last segment's direction.
+    if let [.., before, last] = points {
+        let segment = point_distance(*before, *last);
+        if segment > 0.0 {
+            out.push(lerp_point(*before, *last, 1.0 + (length - travelled) / segment));
+        }
+    }
+    out
+}
+
+/// Point `distance` pixels along a polyline.
+fn point_along_path(points: &[(f64, f64)], distance: f64) -> Option<(f64, f64)> {
+    let mut travelled = 0.0;
+    for pair in points.windows(2) {
+        let segment = point_distance(pair[0], pair[1]);
+        if travelled + segment >= distance && segment > 0.0 {
+            return Some(lerp_point(pair[0], pair[1], (distance - travelled) / segment));
+        }
+        travelled += segment;
+    }
+    points.last().copied()
+}
+
+fn parse_hex_colour(value: &str) -> Option<(u8, u8, u8)> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct PlayfieldSnapshotOptions {
+    /// Output width in pixels; height follows the game's 4:3 frame.
+    width: u32,
+    /// Legacy mod bitflags; HR flips the playfield and HR/EZ change circle size and approach.
+    mods: u32,
+    show_approach_circles: bool,
+    /// `#rrggbb` overrides for the map's combo colours, e.g. from the user's skin.
+    combo_colours: Vec<String>,
+}
+
+impl Default for PlayfieldSnapshotOptions {
+    fn default() -> Self {
+        Self {
+            width: 320,
+            mods: 0,
+            show_approach_circles: true,
+            combo_colours: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayfieldSnapshotPayload {
+    /// PNG as a data URL, ready to use as an `<img>` source.
+    image: String,
+    width: u32,
+    height: u32,
+    visible_objects: usize,
+}
+
+fn render_playfield_png(
+    content: &str,
+    parsed: &ParsedOsu,
+    time_ms: f64,
+    options: &PlayfieldSnapshotOptions,
+) -> Result<PlayfieldSnapshotPayload, String> {
+    use tiny_skia::{Color, FillRule, LineCap, LineJoin, Paint, PathBuilder, Pixmap, Stroke, Transform};
+
+    if parsed.metadata.mode != 0 {
+        return Err("Playfield snapshots are only available for osu!standard maps".to_string());
+    }
+    let width = options.width.clamp(64, 1920);
+    let height = width * 3 / 4;
+    let mut pixmap = Pixmap::new(width, height).ok_or("Invalid snapshot size")?;
+    pixmap.fill(Color::from_rgba8(12, 12, 16, 255));
+
+    // Same frame as the game: the 512x384 playfield centred in a 640x480 window.
+    let scale = width as f32 / 640.0;
+    let transform = Transform::from_row(scale, 0.0, 0.0, scale, 64.0 * scale, 48.0 * scale);
+
+    let difficulty = parsed.metadata.difficulty.with_mods(options.mods & (MOD_HARD_ROCK | MOD_EASY));
+    let radius = (54.4 - 4.48 * difficulty.cs) as f32;
+    let preempt = approach_rate_to_ms(difficulty.ar);
+    let fade_in = HIT_OBJECT_FADE_IN_MS.min(preempt);
+    let flip_y = options.mods & MOD_HARD_ROCK != 0;
+
+    let mut colours: Vec<(u8, u8, u8)> = options.combo_colours.iter().filter_map(|hex| parse_hex_colour(hex)).collect();
+    if colours.is_empty() {
+        colours = parsed.metadata.combo_colours.iter().map(|colour| (colour.r, colour.g, colour.b)).collect();
+    }
+    if colours.is_empty() {
+        colours = DEFAULT_COMBO_COLOURS.to_vec();
+    }
+
+    let objects = parse_playfield_objects(content, parsed);
+    let stack_heights = compute_stack_heights(&objects, preempt, parsed.metadata.general.stack_leniency);
+    let stack_offset = stack_offset_px(difficulty.cs);
+    let mut colour_index = 0usize;
+    let mut object_colours = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        let is_spinner = object.object_type & 8 != 0;
+        let previous_spinner = index > 0 && objects[index - 1].object_type & 8 != 0;
+        if index > 0 && !is_spinner && (object.object_type & 4 != 0 || previous_spinner) {
+            colour_index += 1 + ((object.object_type >> 4) & 7) as usize;
+        }
+        object_colours.push(colours[colour_index % colours.len()]);
+    }
+
+    let position = |(x, y): (f64, f64)| -> (f32, f32) {
+        let y = if flip_y { OSU_PLAYFIELD_HEIGHT - y } else { y };
+        (x as f32, y as f32)
+    };
+    let colour_paint = |(r, g, b): (u8, u8, u8), alpha: f64| {
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(r, g, b, (alpha.clamp(0.0, 1.0) * 255.0) as u8);
+        paint.anti_alias = true;
+        paint
+    };
+    let round_stroke = |width: f32| Stroke {
+        width,
+        line_cap: LineCap::Round,
+        line_join: LineJoin::Round,
+        ..Stroke::default()
+    };
+
+    let visible: Vec<usize> = (0..objects.len())
+        .filter(|&index| {
+            let object = &objects[index];
+            time_ms >= f64::from(object.time) - preempt
+                && time_ms <= f64::from(object.end_time) + HIT_OBJECT_FADE_OUT_MS
+        })
+        .collect();
+
+    // Later objects sit underneath earlier ones, so draw back to front.
+    for &index in visible.iter().rev() {
+        let object = &objects[index];
+        let colour = object_colours[index];
+        let appear = f64::from(object.time) - preempt;
+        let alpha = if time_ms > f64::from(object.end_time) {
+            1.0 - (time_ms - f64::from(object.end_time)) / HIT_OBJECT_FADE_OUT_MS
+        } else {
+            (time_ms - appear) / fade_in
+        };
+
+        if object.object_type & 8 != 0 {
+            let center = position((OSU_PLAYFIELD_WIDTH / 2.0, OSU_PLAYFIELD_HEIGHT / 2.0));
+            if let Some(path) = PathBuilder::from_circle(center.0, center.1, 150.0) {
+                pixmap.stroke_path(&path, &colour_paint((255, 255, 255), alpha * 0.8), &round_stroke(6.0), transform, None);
+            }
+            continue;
+        }
+
+        // Stacks shift up-left on screen, so apply the offset after any Hard Rock flip.
+        let shift = (f64::from(stack_heights[index]) * stack_offset) as f32;
+        let place = |point: (f64, f64)| {
+            let (x, y) = position(point);
+            (x - shift, y - shift)
+        };
+        let head = place((object.x, object.y));
+        if let Some(slider) = &object.slider {
+            let points = fit_path_to_length(
+                &slider_curve_points(slider.curve, &slider.control_points),
+                slider.pixel_length,
+            );
+            let mut builder = PathBuilder::new();
+            for (i, point) in points.iter().enumerate() {
+                let (x, y) = place(*point);
+                if i == 0 {
+                    builder.move_to(x, y);
+                } else {
+                    builder.line_to(x, y);
+                }
+            }
+            if let Some(body) = builder.finish() {
+                pixmap.stroke_path(&body, &colour_paint((255, 255, 255), alpha * 0.9), &round_stroke(radius * 2.0), transform, None);
+                let (r, g, b) = colour;
+                let darker = (r / 2, g / 2, b / 2);
+                pixmap.stroke_path(&body, &colour_paint(darker, alpha), &round_stroke(radius * 1.75), transform, None);
+            }
+            if let Some(tail) = points.last().map(|point| place(*point)) {
+                if let Some(path) = PathBuilder::from_circle(tail.0, tail.1, radius * 0.9) {
+                    pixmap.stroke_path(&path, &colour_paint((255, 255, 255), alpha), &round_stroke(radius * 0.12), transform, None);
+                }
+            }
+
+            let duration = f64::from(object.end_time - object.time);
+            if time_ms >= f64::from(object.time) && time_ms <= f64::from(object.end_time) && duration > 0.0 {
+                let progress = (time_ms - f64::from(object.time)) / duration * f64::from(slider.slides);
+                let span = progress.floor().min(f64::from(slider.slides - 1));
+                let mut fraction = progress - span;
+                if span as i32 % 2 == 1 {
+                    fraction = 1.0 - fraction;
+                }
+                if let Some(ball) = point_along_path(&points, fraction * slider.pixel_length).map(place) {
+                    if let Some(path) = PathBuilder::from_circle(ball.0, ball.1, radius * 0.85) {
+                        pixmap.fill_path(&path, &colour_paint(colour, alpha), FillRule::Winding, transform, None);
+                    }
+                }
+                continue;
+            }
+        }
+
+        if let Some(circle) = PathBuilder::from_circle(head.0, head.1, radius * 0.92) {
+            pixmap.fill_path(&circle, &colour_paint(colour, alpha), FillRule::Winding, transform, None);
+            pixmap.stroke_path(&circle, &colour_paint((255, 255, 255), alpha), &round_stroke(radius * 0.15), transform, None);
+        }
+        if options.show_approach_circles && time_ms < f64::from(object.time) {
+            let approach_scale = 1.0 + 3.0 * (f64::from(object.time) - time_ms) / preempt;
+            if let Some(approach) = PathBuilder::from_circle(head.0, head.1, radius * approach_scale as f32) {
+                pixmap.stroke_path(&approach, &colour_paint(colour, alpha), &round_stroke(radius * 0.1), transform, None);
+            }
+        }
+    }
+
+    let png = pixmap.encode_png().map_err(|err| err.to_string())?;
+    Ok(PlayfieldSnapshotPayload {
+        image: format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        ),
+        width,
+        height,
+        visible_objects: visible.len(),
+    })
+}
+
+/// Rasterize the osu!standard objects visible at `time_ms` into a small PNG preview.
+#[tauri::command]
+async fn render_playfield_snapshot(
+    file_path: String,
+    time_ms: f64,
+    options: Option<PlayfieldSnapshotOptions>,
+) -> Result<PlayfieldSnapshotPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (content, parsed) = load_osu_with_content(&file_path)?;
+        render_playfield_png(&content, &parsed, time_ms, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StackedObjectPayload {
+    time: i32,
+    end_time: i32,
+    object_type: i32,
+    /// Position as written in the .osu file.
+    x: f64,
+    y: f64,
+    stack_height: i32,
+    /// Position after the stack offset, where the game actually draws the object.
+    stacked_x: f64,
+    stacked_y: f64,
+}
+
+/// osu!standard objects with their raw and stack-adjusted positions (no mods applied).
+#[tauri::command]
+async fn get_stacked_objects(file_path: String) -> Result<Vec<StackedObjectPayload>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (content, parsed) = load_osu_with_content(&file_path)?;
+        if parsed.metadata.mode != 0 {
+            return Err("Stacking only applies to osu!standard maps".to_string());
+        }
+        let objects = parse_playfield_objects(&content, &parsed);
+        let preempt = approach_rate_to_ms(parsed.metadata.difficulty.ar);
+        let heights = compute_stack_heights(&objects, preempt, parsed.metadata.general.stack_leniency);
+        let offset = stack_offset_px(parsed.metadata.difficulty.cs);
+        Ok(objects
+            .iter()
+            .zip(heights)
+            .map(|(object, stack_height)| StackedObjectPayload {
+                time: object.time,
+                end_time: object.end_time,
+                object_type: object.object_type,
+                x: object.x,
+                y: object.y,
+                stack_height,
+                stacked_x: object.x - f64::from(stack_height) * offset,
+                stacked_y: object.y - f64::from(stack_height) * offset,
+            })
+            .collect())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ReadabilityOptions {
+    /// Overlaps only matter at or below this approach rate; faster reads stay clear.
+    low_ar_threshold: f64,
+    /// Share of a circle's diameter two objects on screen together must cover to count as heavily overlapped.
+    overlap_ratio: f64,
+    /// Consecutive objects this close in time (ms) and space (osu!pixels) make the rhythm ambiguous.
+    ambiguous_gap_ms: i32,
+    ambiguous_distance_px: f64,
+}
+
+impl Default for ReadabilityOptions {
+    fn default() -> Self {
+        Self {
+            low_ar_threshold: 7.0,
+            overlap_ratio: 0.5,
+            ambiguous_gap_ms: 60,
+            ambiguous_distance_px: 16.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum ReadabilityIssueKind {
+    Overlap,
+    HiddenStack,
+    AmbiguousRhythm,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadabilityIssue {
+    kind: ReadabilityIssueKind,
+    time: i32,
+    /// The earlier object involved.
+    other_time: i32,
+    /// Distance between the two, in osu!pixels.
+    distance: f64,
+    message: String,
+    /// Editor timestamp selecting both objects, for the mod post.
+    timestamp: String,
+}
+
+/// Combo number of every object, counted the way the editor shows them.
+fn combo_numbers(objects: &[PlayfieldObject]) -> Vec<i32> {
+    let mut combo = 0;
+    let mut after_spinner = true;
+    objects
+        .iter()
+        .map(|object| {
+            combo = if object.object_type & 4 != 0 || after_spinner { 1 } else { combo + 1 };
+            after_spinner = object.object_type & 8 != 0;
+            combo
+        })
+        .collect()
+}
+
+fn find_readability_issues(
+    content: &str,
+    parsed: &ParsedOsu,
+    options: &ReadabilityOptions,
+) -> Result<Vec<ReadabilityIssue>, String> {
+    if parsed.metadata.mode != 0 {
+        return Err("Readability checks are only available for osu!standard maps".to_string());
+    }
+    let objects = parse_playfield_objects(content, parsed);
+    let difficulty = &parsed.metadata.difficulty;
+    let preempt = approach_rate_to_ms(difficulty.ar);
+    let diameter = 2.0 * (54.4 - 4.48 * difficulty.cs);
+    let heights = compute_stack_heights(&objects, preempt, parsed.metadata.general.stack_leniency);
+    let offset = stack_offset_px(difficulty.cs);
+    let shifted = |index: usize, (x, y): (f64, f64)| {
+        let shift = f64::from(heights[index]) * offset;
+        (x - shift, y - shift)
+    };
+    let ends: Vec<(f64, f64)> = objects.iter().map(playfield_end_position).collect();
+    let combos = combo_numbers(&objects);
+    let is_spinner = |index: usize| objects[index].object_type & 8 != 0;
+
+    let mut issues = Vec::new();
+    let mut push = |kind, index: usize, other: usize, distance: f64, message: String| {
+        let mut selection = vec![combos[other].to_string(), combos[index].to_string()];
+        if objects[other].time == objects[index].time {
+            selection.dedup();
+        }
+        issues.push(ReadabilityIssue {
+            kind,
+            time: objects[index].time,
+            other_time: objects[other].time,
+            distance,
+            message,
+            timestamp: format_editor_timestamp_text(objects[other].time, &selection),
+        });
+    };
+
+    for i in (0..objects.len()).filter(|&i| !is_spinner(i)) {
+        let object = &objects[i];
+        let head = (object.x, object.y);
+
+        if difficulty.ar <= options.low_ar_threshold {
+            // Only the nearest earlier object on screen at the same time is reported.
+            let overlapping = (0..i)
+                .rev()
+                .take_while(|&j| f64::from(object.time - objects[j].time) < preempt)
+                .filter(|&j| !is_spinner(j))
+                .find_map(|j| {
+                    let distance =
+                        point_distance(head, (objects[j].x, objects[j].y)).min(point_distance(head, ends[j]));
+                    let overlap = 1.0 - distance / diameter;
+                    (distance >= STACK_DISTANCE && overlap >= options.overlap_ratio).then_some((j, distance, overlap))
+                });
+            if let Some((j, distance, overlap)) = overlapping {
+                push(
+                    ReadabilityIssueKind::Overlap,
+                    i,
+                    j,
+                    distance,
+                    format!(
+                        "Overlaps {:.0}% of the object {} ms earlier at AR {}",
+                        overlap * 100.0,
+                        object.time - objects[j].time,
+                        difficulty.ar
+                    ),
+                );
+            }
+        }
+
+        // A slider's tail stays on screen until it ends, so anything stacked exactly on it is hidden.
+        let hidden_under = (0..i).rev().filter(|&j| objects[j].slider.is_some() && !is_spinner(j)).find(|&j| {
+            f64::from(object.time) - preempt < f64::from(objects[j].end_time)
+                && point_distance(shifted(j, ends[j]), shifted(i, head)) < STACK_DISTANCE
+        });
+        if let Some(j) = hidden_under {
+            push(
+                ReadabilityIssueKind::HiddenStack,
+                i,
+                j,
+                point_distance(shifted(j, ends[j]), shifted(i, head)),
+                "Appears exactly under the end of a slider that is still on screen".to_string(),
+            );
+        }
+
+        if let Some(previous) = (0..i).rev().find(|&j| !is_spinner(j)) {
+            let gap = object.time - objects[previous].end_time;
+            let distance = point_distance(ends[previous], head);
+            if gap > 0
+                && gap <= options.ambiguous_gap_ms
+                && (STACK_DISTANCE..=options.ambiguous_distance_px).contains(&distance)
+            {
+                push(
+                    ReadabilityIssueKind::AmbiguousRhythm,
+                    i,
+                    previous,
+                    distance,
+                    format!("Only {distance:.1}px from the previous object {gap} ms earlier; spacing doesn't show the rhythm"),
+                );
+            }
+        }
+    }
+    Ok(issues)
+}
+
+/// Readability heuristics for modding: heavy overlaps at low AR, stacks hidden under slider
+/// ends and near-overlapping objects whose rhythm is unclear.
+#[tauri::command]
+async fn check_readability(
+    file_path: String,
+    options: Option<ReadabilityOptions>,
+) -> Result<Vec<ReadabilityIssue>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (content, parsed) = load_osu_with_content(&file_path)?;
+        find_readability_issues(&content, &parsed, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Gaps longer than this many beats are pauses, not part of a rhythm.
+const SPACING_MAX_GAP_BEATS: f64 = 2.0;
+/// Gaps within this many ms count as the same rhythm.
+const SPACING_SAME_GAP_MS: i32 = 2;
+
+/// Spacing between one object and the one before it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpacingSample {
+    time: i32,
+    previous_time: i32,
+    /// From the end of the previous object to the start of this one.
+    gap_ms: i32,
+    distance: f64,
+    px_per_beat: f64,
+    /// Spacing as the editor's distance snap multiplier, relative to slider velocity.
+    distance_snap: f64,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum SpacingIssueKind {
+    /// Same rhythm as the previous pair but noticeably different spacing.
+    InconsistentSpacing,
+    /// A longer gap with shorter spacing than the previous pair, or the other way round.
+    TimeDistanceViolation,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpacingIssue {
+    kind: SpacingIssueKind,
+    time: i32,
+    message: String,
+    /// Editor timestamp selecting the three objects compared.
+    timestamp: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DistanceSnapPayload {
+    samples: Vec<SpacingSample>,
+    issues: Vec<SpacingIssue>,
+}
+
+fn analyze_spacing(content: &str, parsed: &ParsedOsu, tolerance: f64) -> Result<DistanceSnapPayload, String> {
+    if parsed.metadata.mode != 0 {
+        return Err("Distance snap analysis is only available for osu!standard maps".to_string());
+    }
+    let objects = parse_playfield_objects(content, parsed);
+    let combos = combo_numbers(&objects);
+    let slider_multiplier = parsed.metadata.difficulty.slider_multiplier;
+
+    // (sample, index of the previous object, index of this one); pauses, spinners and stacks break the chain.
+    let mut chain: Vec<Option<(SpacingSample, usize, usize)>> = Vec::new();
+    for i in 1..objects.len() {
+        let (previous, object) = (&objects[i - 1], &objects[i]);
+        let gap_ms = object.time - previous.end_time;
+        let (beat_length, sv) = timing_at(&parsed.timing_points, f64::from(object.time));
+        let beats = f64::from(gap_ms) / beat_length;
+        let distance = point_distance(playfield_end_position(previous), (object.x, object.y));
+        if (previous.object_type | object.object_type) & 8 != 0
+            || gap_ms <= 0
+            || beats > SPACING_MAX_GAP_BEATS
+            || distance < STACK_DISTANCE
+        {
+            chain.push(None);
+            continue;
+        }
+        let px_per_beat = distance / beats;
+        let sample = SpacingSample {
+            time: object.time,
+            previous_time: previous.time,
+            gap_ms,
+            distance,
+            px_per_beat,
+            distance_snap: px_per_beat / (slider_multiplier * 100.0 * sv),
+        };
+        chain.push(Some((sample, i - 1, i)));
+    }
+
+    let mut issues = Vec::new();
+    for pair in chain.windows(2) {
+        let (Some((before, first, _)), Some((after, _, last))) = (&pair[0], &pair[1]) else {
+            continue;
+        };
+        let timestamp = || {
+            let selection: Vec<String> = (*first..=*last).map(|index| combos[index].to_string()).collect();
+            format_editor_timestamp_text(objects[*first].time, &selection)
+        };
+        let distance_change = after.distance / before.distance - 1.0;
+        if (after.gap_ms - before.gap_ms).abs() <= SPACING_SAME_GAP_MS {
+            if distance_change.abs() > tolerance {
+                issues.push(SpacingIssue {
+                    kind: SpacingIssueKind::InconsistentSpacing,
+                    time: after.time,
+                    message: format!(
+                        "Spacing changes from {:.2}x to {:.2}x at the same {} ms rhythm",
+                        before.distance_snap, after.distance_snap, after.gap_ms
+                    ),
+                    timestamp: timestamp(),
+                });
+            }
+            continue;
+        }
+        let gap_change = f64::from(after.gap_ms) / f64::from(before.gap_ms) - 1.0;
+        if gap_change.signum() != distance_change.signum() && distance_change.abs() > tolerance {
+            issues.push(SpacingIssue {
+                kind: SpacingIssueKind::TimeDistanceViolation,
+                time: after.time,
+                message: format!(
+                    "Gap goes from {} to {} ms but spacing goes from {:.0} to {:.0}px",
+                    before.gap_ms, after.gap_ms, before.distance, after.distance
+                ),
+                timestamp: timestamp(),
+            });
+        }
+    }
+
+    Ok(DistanceSnapPayload {
+        samples: chain.into_iter().flatten().map(|(sample, _, _)| sample).collect(),
+        issues,
+    })
+}
+
+/// Spacing between consecutive osu!standard objects, flagging spacing that jumps at a constant
+/// rhythm or contradicts the time gap. `tolerance` is the relative spacing change allowed.
+#[tauri::command]
+async fn analyze_distance_snap(file_path: String, tolerance: Option<f64>) -> Result<DistanceSnapPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (content, parsed) = load_osu_with_content(&file_path)?;
+        analyze_spacing(&content, &parsed, tolerance.unwrap_or(0.2).max(0.0))
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Visual and playable ends further apart than this (in osu!pixels) are flagged.
+const SLIDER_END_MISMATCH_PX: f64 = 4.0;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SliderPathPayload {
+    time: i32,
+    curve: SliderCurveType,
+    control_points: Vec<(f64, f64)>,
+    /// Interpolated path cut to the declared pixel length.
+    path: Vec<(f64, f64)>,
+    /// Where the slider actually ends during play.
+    end_position: (f64, f64),
+    /// Where the curve drawn from the control points ends.
+    visual_end_position: (f64, f64),
+    declared_length: f64,
+    curve_length: f64,
+    /// `declared_length - curve_length`; negative when the slider is cut short.
+    length_error: f64,
+    end_mismatch: bool,
+}
+
+fn build_slider_path(object: &PlayfieldObject, slider: &SliderShape) -> SliderPathPayload {
+    let curve = slider_curve_points(slider.curve, &slider.control_points);
+    let path = fit_path_to_length(&curve, slider.pixel_length);
+    let head = (object.x, object.y);
+    let end_position = path.last().copied().unwrap_or(head);
+    let visual_end_position = curve.last().copied().unwrap_or(head);
+    let curve_length = polyline_length(&curve);
+    SliderPathPayload {
+        time: object.time,
+        curve: slider.curve,
+        control_points: slider.control_points.clone(),
+        path,
+        end_position,
+        visual_end_position,
+        declared_length: slider.pixel_length,
+        curve_length,
+        length_error: slider.pixel_length - curve_length,
+        end_mismatch: point_distance(end_position, visual_end_position) > SLIDER_END_MISMATCH_PX,
+    }
+}
+
+/// Computed geometry for every slider in a map, or only the one starting at `time_ms`.
+#[tauri::command]
+async fn get_slider_paths(file_path: String, time_ms: Option<i32>) -> Result<Vec<SliderPathPayload>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (content, parsed) = load_osu_with_content(&file_path)?;
+        Ok(parse_playfield_objects(&content, &parsed)
+            .iter()
+            .filter(|object| time_ms.is_none() || time_ms == Some(object.time))
+            .filter_map(|object| Some(build_slider_path(object, object.slider.as_ref()?)))
+            .collect())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Per-column note breakdown of a mania map.
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct ManiaColumnStats {
+    column: usize,
+    notes: usize,
+    holds: usize,
+    /// Notes following the previous note in this column within half a beat.
+    jacks: usize,
+    /// Notes that continue a quarter-beat run coming from another column.
+    stream_notes: usize,
+    /// `jacks` per second of drain time.
+    jack_density: f64,
+    /// `stream_notes` per second of drain time.
+    stream_density: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ManiaAnalysisPayload {
+    file_path: String,
+    /// Key count, taken from CS as the game does for mania maps.
+    key_count: usize,
+    total_notes: usize,
+    total_holds: usize,
+    ln_percentage: f64,
+    columns: Vec<ManiaColumnStats>,
+}
+
+fn analyze_mania_map(file_path: String, content: &str, parsed: &ParsedOsu) -> Result<ManiaAnalysisPayload, String> {
+    if parsed.metadata.mode != 3 {
+        return Err("Not an osu!mania map".to_string());
+    }
+    let key_count = parsed.metadata.difficulty.cs.round().clamp(1.0, 18.0) as usize;
+    let mut columns: Vec<ManiaColumnStats> = (0..key_count)
+        .map(|column| ManiaColumnStats { column, ..ManiaColumnStats::default() })
+        .collect();
+    let mut last_in_column: Vec<Option<i32>> = vec![None; key_count];
+    let mut previous: Option<(i32, usize)> = None;
+
+    for object in parse_playfield_objects(content, parsed) {
+        let column = ((object.x * key_count as f64 / OSU_PLAYFIELD_WIDTH).floor().max(0.0) as usize).min(key_count - 1);
+        let (beat_length, _) = timing_at(&parsed.timing_points, f64::from(object.time));
+        let stats = &mut columns[column];
+        stats.notes += 1;
+        if object.object_type & 128 != 0 {
+            stats.holds += 1;
+        }
+        if let Some(last) = last_in_column[column] {
+            if f64::from(object.time - last) <= beat_length / 2.0 {
+                stats.jacks += 1;
+            }
+        }
+        if let Some((previous_time, previous_column)) = previous {
+            let gap = f64::from(object.time - previous_time);
+            if previous_column != column && gap > 0.0 && gap <= beat_length / 4.0 + 1.0 {
+                stats.stream_notes += 1;
+            }
+        }
+        last_in_column[column] = Some(object.time);
+        previous = Some((object.time, column));
+    }
+
+    let drain_seconds = f64::from(compute_drain_time_ms(parsed)) / 1000.0;
+    for stats in &mut columns {
+        if drain_seconds > 0.0 {
+            stats.jack_density = stats.jacks as f64 / drain_seconds;
+            stats.stream_density = stats.stream_notes as f64 / drain_seconds;
+        }
+    }
+    let total_notes: usize = columns.iter().map(|stats| stats.notes).sum();
+    let total_holds: usize = columns.iter().map(|stats| stats.holds).sum();
+    Ok(ManiaAnalysisPayload {
+        file_path,
+        key_count,
+        total_notes,
+        total_holds,
+        ln_percentage: if total_notes > 0 { total_holds as f64 * 100.0 / total_notes as f64 } else { 0.0 },
+        columns,
+    })
+}
+
+/// Column layout, long note share and jack/stream density for an osu!mania map.
+#[tauri::command]
+async fn analyze_mania(file_path: String) -> Result<ManiaAnalysisPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (content, parsed) = load_osu_with_content(&file_path)?;
+        analyze_mania_map(file_path, &content, &parsed)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const TAIKO_PATTERN_LENGTHS: [usize; 2] = [3, 4];
+const TAIKO_TOP_PATTERNS: usize = 10;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaikoPatternCount {
+    /// `d`/`k` for don/kat, uppercase for finishers, e.g. "ddkD".
+    pattern: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaikoSpan {
+    time: i32,
+    duration_ms: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaikoAnalysisPayload {
+    file_path: String,
+    dons: usize,
+    kats: usize,
+    finishers: usize,
+    color_changes: usize,
+    /// Don/kat switches per second of drain time.
+    color_change_density: f64,
+    patterns: Vec<TaikoPatternCount>,
+    drumrolls: Vec<TaikoSpan>,
+    swells: Vec<TaikoSpan>,
+}
+
+fn analyze_taiko_map(file_path: String, content: &str, parsed: &ParsedOsu) -> Result<TaikoAnalysisPayload, String> {
+    if parsed.metadata.mode != 1 {
+        return Err("Not an osu!taiko map".to_string());
+    }
+    let mut analysis = TaikoAnalysisPayload {
+        file_path,
+        dons: 0,
+        kats: 0,
+        finishers: 0,
+        color_changes: 0,
+        color_change_density: 0.0,
+        patterns: Vec::new(),
+        drumrolls: Vec::new(),
+        swells: Vec::new(),
+    };
+
+    // Consecutive notes no more than half a beat apart form one run; patterns don't cross runs.
+    let mut runs: Vec<Vec<char>> = Vec::new();
+    let mut previous: Option<(i32, bool)> = None;
+    for object in parse_playfield_objects(content, parsed) {
+        let span = TaikoSpan { time: object.time, duration_ms: object.end_time - object.time };
+        if object.object_type & 2 != 0 {
+            analysis.drumrolls.push(span);
+            previous = None;
+            continue;
+        }
+        if object.object_type & 8 != 0 {
+            analysis.swells.push(span);
+            previous = None;
+            continue;
+        }
+
+        // Whistle or clap makes a kat, finish makes it big.
+        let is_kat = object.hitsound & (2 | 8) != 0;
+        let is_finisher = object.hitsound & 4 != 0;
+        if is_kat {
+            analysis.kats += 1;
+        } else {
+            analysis.dons += 1;
+        }
+        if is_finisher {
+            analysis.finishers += 1;
+        }
+        let symbol = match (is_kat, is_finisher) {
+            (false, false) => 'd',
+            (false, true) => 'D',
+            (true, false) => 'k',
+            (true, true) => 'K',
+        };
+
+        let (beat_length, _) = timing_at(&parsed.timing_points, f64::from(object.time));
+        match previous {
+            Some((previous_time, previous_kat)) if f64::from(object.time - previous_time) <= beat_length / 2.0 + 1.0 => {
+                if previous_kat != is_kat {
+                    analysis.color_changes += 1;
+                }
+                if let Some(run) = runs.last_mut() {
+                    run.push(symbol);
+                }
+            }
+            _ => runs.push(vec![symbol]),
+        }
+        previous = Some((object.time, is_kat));
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for run in &runs {
+        for length in TAIKO_PATTERN_LENGTHS {
+            for window in run.windows(length) {
+                *counts.entry(window.iter().collect()).or_default() += 1;
+            }
+        }
+    }
+    let mut patterns: Vec<TaikoPatternCount> = counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(pattern, count)| TaikoPatternCount { pattern, count })
+        .collect();
+    patterns.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.pattern.cmp(&b.pattern)));
+    patterns.truncate(TAIKO_TOP_PATTERNS);
+    analysis.patterns = patterns;
+
+    let drain_seconds = f64::from(compute_drain_time_ms(parsed)) / 1000.0;
+    if drain_seconds > 0.0 {
+        analysis.color_change_density = analysis.color_changes as f64 / drain_seconds;
+    }
+    Ok(analysis)
+}
+
+/// Don/kat breakdown, colour-change density, common patterns and drumroll/swell lengths
+/// for an osu!taiko map.
+#[tauri::command]
+async fn analyze_taiko(file_path: String) -> Result<TaikoAnalysisPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (content, parsed) = load_osu_with_content(&file_path)?;
+        analyze_taiko_map(file_path, &content, &parsed)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const CATCHER_BASE_SIZE: f64 = 106.75;
+const CATCHER_WALK_SPEED: f64 = 0.5;
+const CATCHER_DASH_SPEED: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum CatchMovement {
+    Walk,
+    Dash,
+    Hyperdash,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CatchFruit {
+    time: i32,
+    x: f64,
+    /// Movement needed to reach the next fruit; `None` for the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    movement: Option<CatchMovement>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CatchAnalysisPayload {
+    file_path: String,
+    catcher_width: f64,
+    walks: usize,
+    dashes: usize,
+    hyperdashes: usize,
+    hyperdash_times: Vec<i32>,
+    fruits: Vec<CatchFruit>,
+}
+
+/// Fruits of a catch map: circles, plus the head, repeats and tail of every slider.
+fn catch_fruits(content: &str, parsed: &ParsedOsu) -> Vec<CatchFruit> {
+    let mut fruits = Vec::new();
+    for object in parse_playfield_objects(content, parsed) {
+        if object.object_type & 8 != 0 {
+            continue;
+        }
+        fruits.push(CatchFruit { time: object.time, x: object.x, movement: None });
+        let Some(slider) = &object.slider else {
+            continue;
+        };
+        let path = fit_path_to_length(&slider_curve_points(slider.curve, &slider.control_points), slider.pixel_length);
+        let end_x = path.last().map_or(object.x, |point| point.0);
+        let span_ms = f64::from(object.end_time - object.time) / f64::from(slider.slides);
+        for span in 1..=slider.slides {
+            fruits.push(CatchFruit {
+                time: object.time + (span_ms * f64::from(span)).round() as i32,
+                x: if span % 2 == 1 { end_x } else { object.x },
+                movement: None,
+            });
+        }
+    }
+    fruits.sort_by_key(|fruit| fruit.time);
+    fruits
+}
+
+fn analyze_catch_map(file_path: String, content: &str, parsed: &ParsedOsu) -> Result<CatchAnalysisPayload, String> {
+    if parsed.metadata.mode != 2 {
+        return Err("Not an osu!catch map".to_string());
+    }
+    let scale = 1.0 - 0.7 * (parsed.metadata.difficulty.cs - 5.0) / 5.0;
+    let catcher_width = CATCHER_BASE_SIZE * scale.abs();
+    let half_width = catcher_width / 2.0;
+    let mut fruits = catch_fruits(content, parsed);
+
+    // Same walk as the game's hyperdash generation: leftover reach from the previous
+    // movement carries over when continuing in the same direction.
+    let mut last_direction = 0;
+    let mut last_excess = half_width;
+    let mut hyperdash_times = Vec::new();
+    let (mut walks, mut dashes) = (0, 0);
+    for index in 0..fruits.len().saturating_sub(1) {
+        let (current, next) = (&fruits[index], &fruits[index + 1]);
+        let direction = if next.x > current.x { 1 } else { -1 };
+        // A quarter of a 60 fps frame of grace.
+        let time_to_next = f64::from(next.time - current.time) - 1000.0 / 60.0 / 4.0;
+        let reach = if direction == last_direction { last_excess } else { half_width };
+        let distance = (next.x - current.x).abs() - reach;
+        let distance_to_hyper = time_to_next * CATCHER_DASH_SPEED - distance;
+
+        let movement = if distance_to_hyper < 0.0 {
+            last_excess = half_width;
+            hyperdash_times.push(current.time);
+            CatchMovement::Hyperdash
+        } else {
+            last_excess = distance_to_hyper.clamp(0.0, half_width);
+            if distance <= time_to_next * CATCHER_WALK_SPEED {
+                walks += 1;
+                CatchMovement::Walk
+            } else {
+                dashes += 1;
+                CatchMovement::Dash
+            }
+        };
+        last_direction = direction;
+        fruits[index].movement = Some(movement);
+    }
+
+    Ok(CatchAnalysisPayload {
+        file_path,
+        catcher_width,
+        walks,
+        dashes,
+        hyperdashes: hyperdash_times.len(),
+        hyperdash_times,
+        fruits,
+    })
+}
+
+/// Fruit positions and walk/dash/hyperdash classification for an osu!catch map.
+#[tauri::command]
+async fn analyze_catch(file_path: String) -> Result<CatchAnalysisPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (content, parsed) = load_osu_with_content(&file_path)?;
+        analyze_catch_map(file_path, &content, &parsed)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+const LAZER_MAX_SCORE: f64 = 1_000_000.0;
+const SIMULATED_ACCURACIES: [f64; 8] = [90.0, 93.0, 95.0, 97.0, 98.0, 99.0, 99.5, 100.0];
+/// Fastest spin the game counts, in rotations per second (477 rpm).
+const MAX_SPINNER_RPS: f64 = 477.0 / 60.0;
+
+/// ScoreV1 mod multipliers; unlisted mods count as 1.0x.
+fn score_v1_mod_multiplier(mods: u32) -> f64 {
+    let mut multiplier = 1.0;
+    for (flag, factor) in [
+        (MOD_EASY, 0.5),
+        (MOD_HALF_TIME, 0.3),
+        (MOD_HIDDEN, 1.06),
+        (MOD_HARD_ROCK, 1.06),
+        (MOD_DOUBLE_TIME, 1.12),
+        (MOD_FLASHLIGHT, 1.12),
+    ] {
+        if mods & flag != 0 {
+            multiplier *= factor;
+        }
+    }
+    multiplier
+}
+
+/// Lazer (standardised) scoring mod multipliers.
+fn lazer_mod_multiplier(mods: u32) -> f64 {
+    let mut multiplier = 1.0;
+    for (flag, factor) in [
+        (MOD_EASY, 0.5),
+        (MOD_HALF_TIME, 0.3),
+        (MOD_HIDDEN, 1.06),
+        (MOD_HARD_ROCK, 1.06),
+        (MOD_DOUBLE_TIME, 1.1),
+        (MOD_FLASHLIGHT, 1.12),
+    ] {
+        if mods & flag != 0 {
+            multiplier *= factor;
+        }
+    }
+    multiplier
+}
+
+/// Slider ticks in one span, dropping any that land within 10 ms of the span's end.
+fn slider_ticks_per_span(slider: &SliderShape, difficulty: &DifficultySettings, beat_length: f64, sv: f64) -> u32 {
+    let scoring_distance = 100.0 * difficulty.slider_multiplier * sv;
+    let tick_distance = scoring_distance / difficulty.slider_tick_rate.max(0.01);
+    if tick_distance <= 0.0 || beat_length <= 0.0 {
+        return 0;
+    }
+    let min_distance_from_end = scoring_distance / beat_length * 10.0;
+    let mut ticks = 0;
+    let mut distance = tick_distance;
+    while distance < slider.pixel_length - min_distance_from_end {
+        ticks += 1;
+        distance += tick_distance;
+    }
+    ticks
+}
+
+/// Maximum ScoreV1 for an osu!standard map: every object hit for 300 at full combo, all slider
+/// ticks and ends, and spinners spun at the 477 rpm cap.
+fn max_score_v1(content: &str, parsed: &ParsedOsu, mods: u32) -> u64 {
+    let difficulty = parsed.metadata.difficulty;
+    let objects = parse_playfield_objects(content, parsed);
+    let drain_seconds = (f64::from(compute_drain_time_ms(parsed)) / 1000.0).max(1.0);
+    let density_points = (objects.len() as f64 / drain_seconds * 8.0).clamp(0.0, 16.0);
+    let difficulty_multiplier =
+        ((difficulty.hp + difficulty.od + difficulty.cs + density_points) / 38.0 * 5.0).round();
+    let combo_multiplier = difficulty_multiplier * score_v1_mod_multiplier(mods);
+
+    let mut score = 0.0_f64;
+    let mut combo: u32 = 0;
+    for object in &objects {
+        if let Some(slider) = &object.slider {
+            let (beat_length, sv) = timing_at(&parsed.timing_points, f64::from(object.time));
+            let ticks = slider_ticks_per_span(slider, &difficulty, beat_length, sv) * slider.slides as u32;
+            let repeats = slider.slides as u32 - 1;
+            // Head, repeats and tail are worth 30, ticks 10, and each adds to the combo.
+            score += 30.0 * f64::from(2 + repeats) + 10.0 * f64::from(ticks);
+            combo += 2 + repeats + ticks;
+        } else if object.object_type & 8 != 0 {
+            let seconds = f64::from(object.end_time - object.time) / 1000.0;
+            let od = difficulty.od;
+            let required_per_second = if od > 5.0 { 5.0 + 2.5 * (od - 5.0) / 5.0 } else { 3.0 + 0.4 * od };
+            let spins = (seconds * MAX_SPINNER_RPS).floor();
+            let required = (seconds * required_per_second).floor();
+            // 100 per spin, plus 1000 for every spin past the required count.
+            score += 100.0 * spins.min(required) + 1100.0 * (spins - required).max(0.0);
+            combo += 1;
+        } else {
+            combo += 1;
+        }
+        // The object's own 300, scaled by the combo before it.
+        score += 300.0 + (300.0 * f64::from(combo - 1) * combo_multiplier / 25.0).floor();
+    }
+    score as u64
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AccuracyPpPoint {
+    accuracy: f64,
+    pp: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScoreSimulationPayload {
+    file_path: String,
+    mods: u32,
+    max_combo: u32,
+    /// Only computed for osu!standard maps.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_score_v1: Option<u64>,
+    max_score_lazer: u64,
+    stars: f64,
+    pp_curve: Vec<AccuracyPpPoint>,
+}
+
+/// Max ScoreV1 and lazer score, max combo, and pp at a range of accuracies for one map.
+#[tauri::command]
+async fn simulate_score(file_path: String, mods: Option<u32>) -> Result<ScoreSimulationPayload, String> {
+    use rosu_pp::Performance;
+
+    let mods = mods.unwrap_or(0);
+    tauri::async_runtime::spawn_blocking(move || {
+        let bytes = fs::read(&file_path).map_err(|err| err.to_string())?;
+        let map = Beatmap::from_bytes(&bytes).map_err(|err| err.to_string())?;
+        let attributes = Difficulty::new().mods(mods).calculate(&map);
+        let pp_curve = SIMULATED_ACCURACIES
+            .iter()
+            .map(|&accuracy| AccuracyPpPoint {
+                accuracy,
+                pp: Performance::new(attributes.clone()).mods(mods).accuracy(accuracy).calculate().pp(),
+            })
+            .collect();
+
+        let content = decode_osu_bytes(&bytes).0;
+        let parsed = parse_osu_content(&content);
+        let max_score_v1 = (parsed.metadata.mode == 0).then(|| max_score_v1(&content, &parsed, mods));
+        Ok(ScoreSimulationPayload {
+            file_path,
+            mods,
+            max_combo: attributes.max_combo(),
+            max_score_v1,
+            max_score_lazer: (LAZER_MAX_SCORE * lazer_mod_multiplier(mods)).round() as u64,
+            stars: attributes.stars(),
+            pp_curve,
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Mod acronyms in the order tournaments write them, e.g. "HDDT".
+const MOD_ACRONYMS: [(u32, &str); 7] = [
+    (MOD_EASY, "EZ"),
+    (MOD_HIDDEN, "HD"),
+    (MOD_HARD_ROCK, "HR"),
+    (MOD_NIGHTCORE, "NC"),
+    (MOD_DOUBLE_TIME, "DT"),
+    (MOD_HALF_TIME, "HT"),
+    (MOD_FLASHLIGHT, "FL"),
+];
+
+fn mods_acronym(mods: u32) -> String {
+    let mods = if mods & MOD_NIGHTCORE != 0 { mods & !MOD_DOUBLE_TIME } else { mods };
+    let acronym: String = MOD_ACRONYMS
+        .iter()
+        .filter(|(bit, _)| mods & bit != 0)
+        .map(|(_, acronym)| *acronym)
+        .collect();
+    if acronym.is_empty() {
+        "NM".to_string()
+    } else {
+        acronym
+    }
+}
+
+/// Mods implied by a slot name: "NM1", "HD2", "HR3", "DT1"... Freemod and tiebreaker slots are nomod.
+fn mappool_slot_mods(slot: &str) -> u32 {
+    let prefix: String = slot
+        .chars()
+        .take_while(|ch| ch.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    match prefix.as_str() {
+        "NC" => MOD_NIGHTCORE | MOD_DOUBLE_TIME,
+        _ => MOD_ACRONYMS
+            .iter()
+            .find(|(_, acronym)| *acronym == prefix)
+            .map_or(0, |(bit, _)| *bit),
+    }
+}
+
+/// Replace characters Windows doesn't allow in file names.
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|ch| match ch {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            ch if ch.is_control() => '_',
+            ch => ch,
+        })
+        .collect::<String>()
+        .trim_end_matches(['.', ' '])
+        .to_string()
+}
+
+/// Every file under a set folder, as `/`-separated archive names.
+fn list_osz_folder_files(folder: &Path) -> Vec<(String, PathBuf)> {
+    WalkDir::new(folder)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let name = entry.path().strip_prefix(folder).ok()?.to_string_lossy().replace('\\', "/");
+            Some((name, entry.into_path()))
+        })
+        .collect()
+}
+
+/// Archive names and source files of the set a difficulty belongs to. osu!lazer stores files under
+/// their hash in shared `files/xx` folders, so its sets come from the realm manifest instead.
+fn list_osz_set_files(osu_path: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let lazer_root = osu_path
+        .ancestors()
+        .skip(1)
+        .find_map(|dir| resolve_lazer_data_root(&dir.to_string_lossy()))
+        .filter(|data_root| osu_path.starts_with(data_root.join("files")));
+    if let Some(data_root) = lazer_root {
+        let beatmap_hash = beatmap_hash_from_lazer_path(&osu_path.to_string_lossy())
+            .ok_or_else(|| "failed to derive lazer beatmap hash".to_string())?;
+        let manifest = get_lazer_manifest(&data_root, &beatmap_hash)?;
+        return Ok(manifest
+            .f
+            .into_iter()
+            .filter_map(|entry| {
+                let name = normalize_relative_session_path(&entry.n)?.to_string_lossy().replace('\\', "/");
+                Some((name, PathBuf::from(entry.p)))
+            })
+            .collect());
+    }
+    let folder = osu_path.parent().ok_or_else(|| "beatmap has no parent folder".to_string())?;
+    Ok(list_osz_folder_files(folder))
+}
+
+const OSZ_VIDEO_EXTENSIONS: [&str; 9] = ["mp4", "m4v", "avi", "flv", "mkv", "webm", "mov", "wmv", "mpg"];
+/// Beatmap skin elements aren't referenced anywhere, so "unused" never drops files named like them.
+const OSZ_SKIN_ELEMENT_PREFIXES: [&str; 22] = [
+    "hitcircle", "approachcircle", "sliderb", "sliderfollowcircle", "sliderstartcircle", "sliderendcircle",
+    "sliderscorepoint", "reversearrow", "spinner-", "default-", "followpoint", "lighting", "hit0", "hit50",
+    "hit100", "hit300", "particle", "comboburst", "fruit-", "taiko", "mania-", "count",
+];
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct OszExportOptions {
+    exclude_storyboard: bool,
+    exclude_video: bool,
+    /// Drop files no difficulty or storyboard references (hitsound samples and skin elements are kept).
+    exclude_unused: bool,
+    /// Re-encode the song audio at this bitrate with ffmpeg from PATH.
+    audio_bitrate_kbps: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct OszExportProgressEvent {
+    destination: String,
+    processed: usize,
+    total: usize,
+    current_file: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OszExportPayload {
+    destination: String,
+    file_count: usize,
+    size_bytes: u64,
+    excluded: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Files the set's difficulties and storyboards point at, as lowercase `/`-separated relative paths.
+#[derive(Default)]
+struct OszAssetRefs {
+    gameplay: std::collections::HashSet<String>,
+    song_audio: std::collections::HashSet<String>,
+    video: std::collections::HashSet<String>,
+    storyboard: std::collections::HashSet<String>,
+    /// Animation paths; their frames are stored as `<stem><index>.<ext>`.
+    animations: Vec<String>,
+}
+
+fn normalize_asset_ref(value: &str) -> String {
+    value.trim().trim_matches('"').replace('\\', "/").to_lowercase()
+}
+
+impl OszAssetRefs {
+    fn collect(&mut self, content: &str) {
+        for line in osu_section_lines(content, "General") {
+            if let Some((key, value)) = line.split_once(':') {
+                if eq_ascii_ci(key.trim(), "AudioFilename") && !value.trim().is_empty() {
+                    self.gameplay.insert(normalize_asset_ref(value));
+                    self.song_audio.insert(normalize_asset_ref(value));
+                }
+            }
+        }
+        for line in osu_section_lines(content, "Events") {
+            let kind = csv_field(line, 0).unwrap_or("").trim();
+            let field = |index| csv_field(line, index).map(normalize_asset_ref).filter(|value| !value.is_empty());
+            match kind {
+                "0" => self.gameplay.extend(field(2)),
+                "1" | "Video" => self.video.extend(field(2)),
+                "4" | "Sprite" | "5" | "Sample" => self.storyboard.extend(field(3)),
+                "6" | "Animation" => self.animations.extend(field(3)),
+                _ => {}
+            }
+        }
+        for line in osu_section_lines(content, "HitObjects") {
+            let Some(hit_sample) = line.rsplit(',').next() else {
+                continue;
+            };
+            let filename = hit_sample.splitn(5, ':').nth(4).unwrap_or("");
+            if !filename.trim().is_empty() {
+                self.gameplay.insert(normalize_asset_ref(filename));
+            }
+        }
+    }
+
+    fn is_animation_frame(&self, key: &str) -> bool {
+        self.animations.iter().any(|animation| {
+            let (stem, ext) = animation.rsplit_once('.').unwrap_or((animation, ""));
+            key.strip_prefix(stem)
+                .and_then(|rest| rest.strip_suffix(ext))
+                .and_then(|rest| rest.strip_suffix('.'))
+                .is_some_and(|index| !index.is_empty() && index.bytes().all(|byte| byte.is_ascii_digit()))
+        })
+    }
+
+    fn storyboard_only(&self, key: &str) -> bool {
+        (self.storyboard.contains(key) || self.is_animation_frame(key)) && !self.gameplay.contains(key)
+    }
+
+    fn referenced(&self, key: &str) -> bool {
+        self.gameplay.contains(key) || self.video.contains(key) || self.storyboard.contains(key) || self.is_animation_frame(key)
+    }
+}
+
+fn asset_extension(key: &str) -> &str {
+    key.rsplit_once('.').map_or("", |(_, ext)| ext)
+}
+
+/// Custom hitsound samples follow osu!'s `<sampleset>-<hitsound><index>` naming and are found by name.
+fn is_hitsound_sample_name(key: &str) -> bool {
+    let name = key.rsplit('/').next().unwrap_or(key);
+    matches!(asset_extension(name), "wav" | "ogg" | "mp3")
+        && ["normal-", "soft-", "drum-"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+fn is_skin_element_name(key: &str) -> bool {
+    !key.contains('/') && OSZ_SKIN_ELEMENT_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+}
+
+/// Why `key` is left out of the export, if it is.
+fn osz_exclusion_reason(key: &str, refs: &OszAssetRefs, options: &OszExportOptions) -> Option<&'static str> {
+    match asset_extension(key) {
+        "osu" => return None,
+        "osb" if options.exclude_storyboard => return Some("storyboard"),
+        _ => {}
+    }
+    if options.exclude_video
+        && !refs.gameplay.contains(key)
+        && (refs.video.contains(key) || OSZ_VIDEO_EXTENSIONS.contains(&asset_extension(key)))
+    {
+        return Some("video");
+    }
+    if options.exclude_storyboard && refs.storyboard_only(key) {
+        return Some("storyboard");
+    }
+    if options.exclude_unused
+        && asset_extension(key) != "osb"
+        && !refs.referenced(key)
+        && !is_hitsound_sample_name(key)
+        && !is_skin_element_name(key)
+    {
+        return Some("unused");
+    }
+    None
+}
+
+/// Re-encode `path` at `bitrate_kbps` with ffmpeg, keeping the container so `AudioFilename` stays valid.
+fn recompress_audio_ffmpeg(path: &Path, bitrate_kbps: u32) -> Result<Vec<u8>, String> {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let codec = match ext.as_str() {
+        "mp3" => "libmp3lame",
+        "ogg" => "libvorbis",
+        other => return Err(format!("can't recompress .{other} audio")),
+    };
+    let output_path = std::env::temp_dir().join(format!("mosu-osz-{}.{ext}", unix_time_ms()));
+    let result = run_ffmpeg_encode(path, &["-c:a", codec, "-b:a", &format!("{bitrate_kbps}k")], &output_path)
+        .and_then(|()| fs::read(&output_path).map_err(|err| err.to_string()));
+    let _ = fs::remove_file(&output_path);
+    result
+}
+
+/// Encode `input` to `output` with ffmpeg from PATH, dropping any video stream.
+fn run_ffmpeg_encode(input: &Path, codec_args: &[&str], output: &Path) -> Result<(), String> {
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-y", "-v", "error", "-i"])
+        .arg(input)
+        .arg("-vn")
+        .args(codec_args)
+        .arg(output);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let result = command.output().map_err(|err| format!("ffmpeg unavailable: {err}"))?;
+    if result.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&result.stderr).trim().to_string())
+    }
+}
+
+/// `<set id> <artist> - <title>.osz` from the first difficulty, falling back to the folder name.
+fn default_osz_file_name(folder: &Path) -> String {
+    let metadata = fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("osu")))
+        .and_then(|path| load_parsed_osu(&path.to_string_lossy()).ok())
+        .map(|parsed| parsed.metadata);
+    let name = match metadata {
+        Some(metadata) if !metadata.artist.is_empty() || !metadata.title.is_empty() => {
+            match metadata.beatmap_set_id.parse::<i64>() {
+                Ok(set_id) if set_id > 0 => format!("{set_id} {} - {}", metadata.artist, metadata.title),
+                _ => format!("{} - {}", metadata.artist, metadata.title),
+            }
+        }
+        _ => folder
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "beatmap".to_string()),
+    };
+    sanitize_file_name(&format!("{name}.osz"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MappoolSlotInput {
+    slot: String,
+    file_path: String,
+    /// Overrides the mods implied by the slot name.
+    mods: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MappoolEntry {
+    slot: String,
+    file_path: String,
+    artist: String,
+    title: String,
+    version: String,
+    creator: String,
+    mods: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stars: Option<f64>,
+    bpm: f64,
+    length_ms: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beatmap_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beatmap_set_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MappoolExportPayload {
+    destination: String,
+    entries: Vec<MappoolEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle_path: Option<String>,
+}
+
+fn build_mappool_entry(input: &MappoolSlotInput) -> Result<MappoolEntry, String> {
+    let bytes = fs::read(&input.file_path).map_err(|err| format!("{}: {err}", input.slot))?;
+    let content = decode_osu_bytes(&bytes).0;
+    let parsed = parse_osu_content(&content);
+    let mods = input.mods.unwrap_or_else(|| mappool_slot_mods(&input.slot));
+    let rate = mods_clock_rate(mods);
+
+    let stars = Beatmap::from_bytes(&bytes)
+        .ok()
+        .map(|map| Difficulty::new().mods(mods).calculate(&map).stars())
+        .filter(|stars| stars.is_finite());
+    let last_object_time = parsed.hit_ends.iter().max().copied().unwrap_or(0);
+    let bpm = compute_bpm_summary(&parsed.timing_points, last_object_time).dominant * rate;
+    let drain_time_ms = compute_drain_time_ms(&parsed);
+    let (beatmap_id, beatmap_set_id) = parse_osu_beatmap_ids(&content);
+    let mode = mode_display_name(parsed.metadata.mode);
+    let link = match (beatmap_set_id, beatmap_id) {
+        (Some(set_id), Some(map_id)) => Some(format!("https://osu.ppy.sh/beatmapsets/{set_id}#{mode}/{map_id}")),
+        (Some(set_id), None) => Some(format!("https://osu.ppy.sh/beatmapsets/{set_id}")),
+        _ => None,
+    };
+
+    let metadata = parsed.metadata;
+    Ok(MappoolEntry {
+        slot: input.slot.clone(),
+        file_path: input.file_path.clone(),
+        artist: metadata.artist,
+        title: metadata.title,
+        version: metadata.version,
+        creator: metadata.creator,
+        mods: mods_acronym(mods),
+        stars,
+        bpm,
+        length_ms: (f64::from(drain_time_ms) / rate).round() as i32,
+        beatmap_id,
+        beatmap_set_id,
+        link,
+    })
+}
+
+/// Write a mappool sheet (CSV or JSON) with mod-adjusted stars, BPM and length per slot.
+/// With `bundle_path`, every set in the pool is also packed as an .osz into one zip for players.
+#[tauri::command]
+async fn export_mappool(
+    slots: Vec<MappoolSlotInput>,
+    format: String,
+    destination: String,
+    bundle_path: Option<String>,
+) -> Result<MappoolExportPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let entries = slots
+            .iter()
+            .map(build_mappool_entry)
+            .collect::<Result<Vec<MappoolEntry>, String>>()?;
+
+        let bytes = match format.to_ascii_lowercase().as_str() {
+            "csv" => {
+                let mut output = String::from("slot,artist,title,version,creator,mods,stars,bpm,lengthMs,link\r\n");
+                for entry in &entries {
+                    let row = [
+                        Value::from(entry.slot.as_str()),
+                        Value::from(entry.artist.as_str()),
+                        Value::from(entry.title.as_str()),
+                        Value::from(entry.version.as_str()),
+                        Value::from(entry.creator.as_str()),
+                        Value::from(entry.mods.as_str()),
+                        entry.stars.map_or(Value::Null, |stars| Value::from((stars * 100.0).round() / 100.0)),
+                        Value::from(entry.bpm.round()),
+                        Value::from(entry.length_ms),
+                        entry.link.as_deref().map_or(Value::Null, Value::from),
+                    ];
+                    let line: Vec<String> = row.iter().map(csv_escape).collect();
+                    output.push_str(&line.join(","));
+                    output.push_str("\r\n");
+                }
+                output.into_bytes()
+            }
+            "json" => serde_json::to_vec_pretty(&entries).map_err(|err| err.to_string())?,
+            other => return Err(format!("unsupported export format: {other}")),
+        };
+        write_file_atomically(Path::new(&destination), &bytes)?;
+
+        if let Some(bundle_path) = &bundle_path {
+            use zip::write::SimpleFileOptions;
+
+            let file = fs::File::create(bundle_path).map_err(|err| err.to_string())?;
+            let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+            // .osz files are already compressed.
+            let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+            // Each set is written to a temporary .osz next to the bundle and streamed in from there.
+            let set_archive = PathBuf::from(format!("{bundle_path}.set.osz"));
+            let mut packed_sets = std::collections::HashSet::new();
+            let result = entries.iter().try_for_each(|entry| {
+                let files = list_osz_set_files(Path::new(&entry.file_path))?;
+                // Difficulties of one set list the same files; only the first is packed.
+                let mut sources: Vec<PathBuf> = files.iter().map(|(_, path)| path.clone()).collect();
+                sources.sort();
+                if !packed_sets.insert(sources) {
+                    return Ok(());
+                }
+                write_osz_archive(&set_archive, files, &OszExportOptions::default(), |_, _, _| {})?;
+                let name = sanitize_file_name(&format!("{} {} - {}.osz", entry.slot, entry.artist, entry.title));
+                zip.start_file(name, options).map_err(|err| err.to_string())?;
+                let mut source = fs::File::open(&set_archive).map_err(|err| err.to_string())?;
+                std::io::copy(&mut source, &mut zip).map(drop).map_err(|err| err.to_string())
+            });
+            let _ = fs::remove_file(&set_archive);
+            result?;
+            zip.finish().map_err(|err| err.to_string())?;
+        }
+
+        Ok(MappoolExportPayload {
+            destination,
+            entries,
+            bundle_path,
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+struct OszWriteSummary {
+    file_count: usize,
+    excluded: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Stream a set's files into an .osz at `destination`, leaving out what `options` excludes. The
+/// archive is built as `<destination>.partial` and only renamed into place once complete.
+/// `on_progress` gets the files written so far, the total and the file about to be written.
+fn write_osz_archive(
+    destination: &Path,
+    files: Vec<(String, PathBuf)>,
+    options: &OszExportOptions,
+    mut on_progress: impl FnMut(usize, usize, &str),
+) -> Result<OszWriteSummary, String> {
+    use zip::write::SimpleFileOptions;
+
+    let mut refs = OszAssetRefs::default();
+    for (name, path) in &files {
+        if matches!(asset_extension(&name.to_lowercase()), "osu" | "osb") {
+            if let Ok(bytes) = fs::read(path) {
+                refs.collect(&decode_osu_bytes(&bytes).0);
+            }
+        }
+    }
+
+    let mut excluded = Vec::new();
+    let mut included = Vec::new();
+    for (name, path) in files {
+        match osz_exclusion_reason(&name.to_lowercase(), &refs, options) {
+            Some(reason) => excluded.push(format!("{name} ({reason})")),
+            None => included.push((name, path)),
+        }
+    }
+
+    let partial_path = destination.with_extension("osz.partial");
+    let file = fs::File::create(&partial_path).map_err(|err| err.to_string())?;
+    let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+    let mut warnings = Vec::new();
+    let total = included.len();
+    let write_result = included.iter().enumerate().try_for_each(|(index, (name, path))| {
+        on_progress(index, total, name);
+        let key = name.to_lowercase();
+        let mut recompressed = None;
+        if let Some(bitrate_kbps) = options.audio_bitrate_kbps.filter(|_| refs.song_audio.contains(&key)) {
+            let original_len = fs::metadata(path).map_err(|err| format!("{name}: {err}"))?.len();
+            match recompress_audio_ffmpeg(path, bitrate_kbps) {
+                Ok(bytes) if (bytes.len() as u64) < original_len => recompressed = Some(bytes),
+                Ok(_) => warnings.push(format!("{name}: already smaller than {bitrate_kbps} kbps, kept as is")),
+                Err(err) => warnings.push(format!("{name}: {err}")),
+            }
+        }
+        // Audio, images and video are already compressed.
+        let method = if matches!(asset_extension(&key), "osu" | "osb" | "txt" | "ini") {
+            zip::CompressionMethod::Deflated
+        } else {
+            zip::CompressionMethod::Stored
+        };
+        zip.start_file(name.as_str(), SimpleFileOptions::default().compression_method(method))
+            .map_err(|err| err.to_string())?;
+        match recompressed {
+            Some(bytes) => zip.write_all(&bytes).map_err(|err| err.to_string()),
+            None => {
+                let mut source = fs::File::open(path).map_err(|err| format!("{name}: {err}"))?;
+                std::io::copy(&mut source, &mut zip).map(drop).map_err(|err| format!("{name}: {err}"))
+            }
+        }
+    });
+    let finish_result = write_result.and_then(|()| {
+        zip.finish()
+            .map_err(|err| err.to_string())?
+            .into_inner()
+            .map_err(|err| err.to_string())
+            .map(drop)
+    });
+    if let Err(err) = finish_result.and_then(|()| fs::rename(&partial_path, destination).map_err(|err| err.to_string())) {
+        let _ = fs::remove_file(&partial_path);
+        return Err(err);
+    }
+    Ok(OszWriteSummary { file_count: total, excluded, warnings })
+}
+
+/// Zip a beatmap set folder into an .osz for testplayers, optionally leaving out the storyboard,
+/// video or unused files and recompressing the song audio. `destination` may be a folder, in which
+/// case the archive is named after the set. Progress arrives as `osz-export-progress` events.
+#[tauri::command]
+async fn export_osz(
+    app_handle: tauri::AppHandle,
+    folder: String,
+    destination: String,
+    options: Option<OszExportOptions>,
+) -> Result<OszExportPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let options = options.unwrap_or_default();
+        let folder = PathBuf::from(folder);
+        if !folder.is_dir() {
+            return Err("beatmap folder not found".to_string());
+        }
+        let mut destination = PathBuf::from(destination);
+        if destination.is_dir() {
+            destination = destination.join(default_osz_file_name(&folder));
+        }
+        let destination_str = destination.to_string_lossy().to_string();
+
+        let files = list_osz_folder_files(&folder);
+        let written = write_osz_archive(&destination, files, &options, |processed, total, name| {
+            let _ = app_handle.emit("osz-export-progress", OszExportProgressEvent {
+                destination: destination_str.clone(),
+                processed,
+                total,
+                current_file: name.to_string(),
+            });
+        })?;
+        let total = written.file_count;
+
+        let _ = app_handle.emit("osz-export-progress", OszExportProgressEvent {
+            destination: destination_str.clone(),
+            processed: total,
+            total,
+            current_file: String::new(),
+        });
+        Ok(OszExportPayload {
+            size_bytes: fs::metadata(&destination).map(|meta| meta.len()).unwrap_or(0),
+            destination: destination_str,
+            file_count: total,
+            excluded: written.excluded,
+            warnings: written.warnings,
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+async fn lint_beatmap(file_path: String, options: Option<LintOptions>) -> Result<LintReportPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let parsed = load_parsed_osu(&file_path)?;
+        let issues = run_lint_rules(Path::new(&file_path), &parsed, &options.unwrap_or_default());
+        Ok(LintReportPayload { file_path, issues })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BreakSuggestion {
+    start: i32,
+    end: i32,
+    length_ms: i32,
+    /// An existing break already covers this window.
+    existing: bool,
+}
+
+/// Candidate break windows at the map's largest gaps, longest first. Each leaves the usual
+/// margin after the previous object and one approach time before the next.
+#[tauri::command]
+async fn suggest_breaks(file_path: String, min_length_ms: Option<i32>) -> Result<Vec<BreakSuggestion>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let parsed = load_parsed_osu(&file_path)?;
+        let min_length_ms = min_length_ms.unwrap_or(LintOptions::default().min_break_length_ms);
+        let preempt = approach_rate_to_ms(parsed.metadata.difficulty.ar).round() as i32;
+        let spans = object_spans(&parsed);
+
+        let mut previous_end = i32::MIN;
+        let mut suggestions = Vec::new();
+        for (start, end) in spans {
+            if previous_end != i32::MIN {
+                let window_start = previous_end + BREAK_START_MIN_GAP_MS;
+                let window_end = start - preempt;
+                if window_end - window_start >= min_length_ms {
+                    suggestions.push(BreakSuggestion {
+                        start: window_start,
+                        end: window_end,
+                        length_ms: window_end - window_start,
+                        existing: parsed
+                            .break_periods
+                            .iter()
+                            .any(|period| period.start < window_end && period.end > window_start),
+                    });
+                }
+            }
+            previous_end = previous_end.max(end);
+        }
+
+        suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.length_ms));
+        Ok(suggestions)
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// osu!standard difficulty names by lower star rating bound.
+const SPREAD_TIERS: [(f64, &str); 6] = [
+    (0.0, "Easy"),
+    (2.0, "Normal"),
+    (2.7, "Hard"),
+    (4.0, "Insane"),
+    (5.3, "Expert"),
+    (6.5, "Expert+"),
+];
+/// Drain time limits under which the set's lowest difficulty may be no harder than the tier.
+const SPREAD_LOWEST_TIER_LIMITS: [(i32, usize); 3] = [(210_000, 1), (255_000, 2), (300_000, 3)];
+/// Each difficulty must keep at least this share of the longest one's drain time.
+const SPREAD_MIN_DRAIN_RATIO: f64 = 0.8;
+
+fn spread_tier(star_rating: f64) -> usize {
+    SPREAD_TIERS.iter().rposition(|(min, _)| star_rating >= *min).unwrap_or(0)
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SpreadDifficulty {
+    file_path: String,
+    version: String,
+    mode: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    star_rating: Option<f64>,
+    tier: &'static str,
+    drain_time_ms: i32,
+    length_ms: i32,
+    object_count: usize,
+    /// Objects relative to the busiest difficulty of the same mode.
+    object_ratio: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpreadAnalysisPayload {
+    folder: String,
+    difficulties: Vec<SpreadDifficulty>,
+    issues: Vec<LintIssue>,
+}
+
+/// Every difficulty in a set folder, easiest first within each mode.
+fn load_spread_difficulties(folder: &Path) -> Result<Vec<SpreadDifficulty>, String> {
+    let mut difficulties = Vec::new();
+    for path in list_osu_files_in_folder(folder)? {
+        let bytes = fs::read(&path).map_err(|err| err.to_string())?;
+        let parsed = parse_osu_content(&decode_osu_bytes(&bytes).0);
+        let star_rating = cached_star_rating(&bytes, 0);
+        let first = parsed.hit_starts.iter().min().copied().unwrap_or(0);
+        let last = parsed.hit_ends.iter().max().copied().unwrap_or(0);
+        difficulties.push(SpreadDifficulty {
+            file_path: path.to_string_lossy().to_string(),
+            version: parsed.metadata.version.clone(),
+            mode: parsed.metadata.mode,
+            star_rating,
+            tier: SPREAD_TIERS[spread_tier(star_rating.unwrap_or(0.0))].1,
+            drain_time_ms: compute_drain_time_ms(&parsed),
+            length_ms: (last - first).max(0),
+            object_count: parsed.hit_starts.len(),
+            object_ratio: 0.0,
+        });
+    }
+    difficulties.sort_by(|a, b| {
+        a.mode
+            .cmp(&b.mode)
+            .then_with(|| a.star_rating.unwrap_or(0.0).total_cmp(&b.star_rating.unwrap_or(0.0)))
+    });
+    for mode in 0..4 {
+        let max_objects = difficulties
+            .iter()
+            .filter(|difficulty| difficulty.mode == mode)
+            .map(|difficulty| difficulty.object_count)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        for difficulty in difficulties.iter_mut().filter(|difficulty| difficulty.mode == mode) {
+            difficulty.object_ratio = difficulty.object_count as f64 / max_objects as f64;
+        }
+    }
+    Ok(difficulties)
+}
+
+/// Set folders that have been parsed, reused until one of their .osu files changes.
+struct SpreadCacheEntry {
+    files: Vec<(PathBuf, Option<std::time::SystemTime>)>,
+    difficulties: Arc<Vec<SpreadDifficulty>>,
+}
+
+const SPREAD_CACHE_MAX_SETS: usize = 64;
+static SPREAD_CACHE: Mutex<BTreeMap<PathBuf, SpreadCacheEntry>> = Mutex::new(BTreeMap::new());
+
+/// `load_spread_difficulties`, cached per set so linting one difficulty after another doesn't
+/// re-parse and re-rate the whole folder each time.
+fn cached_spread_difficulties(folder: &Path) -> Result<Arc<Vec<SpreadDifficulty>>, String> {
+    let files: Vec<(PathBuf, Option<std::time::SystemTime>)> = list_osu_files_in_folder(folder)?
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+            (path, modified)
+        })
+        .collect();
+    let cache = SPREAD_CACHE.lock().map_err(|err| err.to_string())?;
+    if let Some(entry) = cache.get(folder).filter(|entry| entry.files == files) {
+        return Ok(Arc::clone(&entry.difficulties));
+    }
+    drop(cache);
+
+    let difficulties = Arc::new(load_spread_difficulties(folder)?);
+    let mut cache = SPREAD_CACHE.lock().map_err(|err| err.to_string())?;
+    if cache.len() >= SPREAD_CACHE_MAX_SETS && !cache.contains_key(folder) {
+        cache.pop_first();
+    }
+    cache.insert(folder.to_path_buf(), SpreadCacheEntry { files, difficulties: Arc::clone(&difficulties) });
+    Ok(difficulties)
+}
+
+/// Tier the set's lowest difficulty may be at most for a longest drain of `longest_drain`, or
+/// `None` once the set is long enough to be exempt.
+fn spread_lowest_allowed_tier(longest_drain: i32) -> Option<usize> {
+    SPREAD_LOWEST_TIER_LIMITS.iter().find(|(limit, _)| longest_drain < *limit).map(|(_, tier)| *tier)
+}
+
+/// Flags a set (one mode, easiest first) whose lowest difficulty is too hard for its drain time.
+fn spread_lowest_difficulty_issue(rule: &str, difficulties: &[&SpreadDifficulty]) -> Option<LintIssue> {
+    // Star rating tiers are calibrated for osu!standard only.
+    let lowest = difficulties.first().filter(|lowest| lowest.mode == 0)?;
+    let longest_drain = difficulties.iter().map(|difficulty| difficulty.drain_time_ms).max().unwrap_or(0);
+    let allowed = spread_lowest_allowed_tier(longest_drain)?;
+    let lowest_tier = spread_tier(lowest.star_rating.unwrap_or(0.0));
+    (lowest_tier > allowed).then(|| {
+        lint_issue(
+            rule,
+            LintSeverity::Error,
+            format!(
+                "With {} of drain the set needs a {} or easier, but its lowest difficulty [{}] is a {} (RC: Difficulty spread)",
+                format_duration_ms(longest_drain),
+                SPREAD_TIERS[allowed].1,
+                lowest.version,
+                SPREAD_TIERS[lowest_tier].1
+            ),
+            None,
+        )
+    })
+}
+
+/// Flags a difficulty whose drain time falls under the required share of the longest one's.
+fn spread_drain_share_issue(rule: &str, version: &str, drain: i32, longest: &SpreadDifficulty) -> Option<LintIssue> {
+    if f64::from(drain) >= f64::from(longest.drain_time_ms) * SPREAD_MIN_DRAIN_RATIO {
+        return None;
+    }
+    Some(lint_issue(
+        rule,
+        LintSeverity::Warning,
+        format!(
+            "[{version}] drain time {} is {}% of [{}]'s {} (minimum {:.0}%, RC: Difficulty spread)",
+            format_duration_ms(drain),
+            drain * 100 / longest.drain_time_ms.max(1),
+            longest.version,
+            format_duration_ms(longest.drain_time_ms),
+            SPREAD_MIN_DRAIN_RATIO * 100.0
+        ),
+        None,
+    ))
+}
+
+fn analyze_mapset_spread(folder: &Path) -> Result<SpreadAnalysisPayload, String> {
+    let difficulties = cached_spread_difficulties(folder)?;
+
+    let mut issues = Vec::new();
+    let mut modes: Vec<i32> = difficulties.iter().map(|difficulty| difficulty.mode).collect();
+    modes.dedup();
+    for mode in modes {
+        let same_mode: Vec<&SpreadDifficulty> =
+            difficulties.iter().filter(|difficulty| difficulty.mode == mode).collect();
+        if let Some(longest) = same_mode.iter().max_by_key(|difficulty| difficulty.drain_time_ms) {
+            issues.extend(same_mode.iter().filter_map(|difficulty| {
+                spread_drain_share_issue("spread-drain-time", &difficulty.version, difficulty.drain_time_ms, longest)
+            }));
+        }
+
+        // Star rating tiers are calibrated for osu!standard only.
+        if mode != 0 {
+            continue;
+        }
+        let tiers: Vec<usize> =
+            same_mode.iter().map(|difficulty| spread_tier(difficulty.star_rating.unwrap_or(0.0))).collect();
+        for (pair, window) in same_mode.windows(2).zip(tiers.windows(2)) {
+            if window[1] > window[0] + 1 {
+                issues.push(lint_issue(
+                    "spread-tier-gap",
+                    LintSeverity::Error,
+                    format!(
+                        "Spread skips from [{}] ({}) to [{}] ({})",
+                        pair[0].version,
+                        SPREAD_TIERS[window[0]].1,
+                        pair[1].version,
+                        SPREAD_TIERS[window[1]].1
+                    ),
+                    None,
+                ));
+            }
+        }
+        issues.extend(spread_lowest_difficulty_issue("spread-lowest-difficulty", &same_mode));
+    }
+
+    Ok(SpreadAnalysisPayload {
+        folder: folder.to_string_lossy().to_string(),
+        difficulties: difficulties.as_ref().clone(),
+        issues,
+    })
+}
+
+/// Compare every difficulty in a mapset folder and flag ranking-criteria spread problems.
+#[tauri::command]
+async fn analyze_spread(folder: String) -> Result<SpreadAnalysisPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || analyze_mapset_spread(Path::new(&folder)))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetFieldMismatch {
+    field: &'static str,
+    value: String,
+    /// What most of the set uses; ties go to the first difficulty by file name.
+    expected: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DifficultyMetadataCheck {
+    file_path: String,
+    version: String,
+    mismatches: Vec<SetFieldMismatch>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MetadataConsistencyPayload {
+    folder: String,
+    consistent: bool,
+    /// Every difficulty, in file name order; those matching the set have no mismatches.
+    difficulties: Vec<DifficultyMetadataCheck>,
+}
+
+/// Compares raw values, untouched by `normalize_metadata`; tags only forgive spacing differences.
+fn check_mapset_metadata(folder: &Path) -> Result<MetadataConsistencyPayload, String> {
+    let set_fields: Vec<(&str, &'static str)> = SET_CONSISTENT_METADATA_FIELDS
+        .iter()
+        .map(|field| ("Metadata", *field))
+        .chain([("General", "AudioFilename")])
+        .collect();
+
+    let mut rows = Vec::new();
+    for path in list_osu_files_in_folder(folder)? {
+        let bytes = fs::read(&path).map_err(|err| err.to_string())?;
+        let content = decode_osu_bytes(&bytes).0;
+        let values: Vec<String> = set_fields
+            .iter()
+            .map(|(section, key)| {
+                let value = read_osu_section_value(&content, section, key).unwrap_or_default();
+                if *key == "Tags" {
+                    value.split_whitespace().collect::<Vec<_>>().join(" ")
+                } else {
+                    value
+                }
+            })
+            .collect();
+        let version = read_osu_section_value(&content, "Metadata", "Version").unwrap_or_default();
+        rows.push((path, version, values));
+    }
+
+    let expected: Vec<String> = (0..set_fields.len())
+        .map(|field| {
+            let mut counts: Vec<(&str, usize)> = Vec::new();
+            for (_, _, values) in &rows {
+                match counts.iter_mut().find(|(value, _)| *value == values[field]) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((&values[field], 1)),
+                }
+            }
+            // max_by_key keeps the last maximum, so walk backwards to favour the first difficulty.
+            counts
+                .iter()
+                .rev()
+                .max_by_key(|(_, count)| *count)
+                .map(|(value, _)| value.to_string())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let difficulties: Vec<DifficultyMetadataCheck> = rows
+        .into_iter()
+        .map(|(path, version, values)| DifficultyMetadataCheck {
+            file_path: path.to_string_lossy().to_string(),
+            version,
+            mismatches: values
+                .into_iter()
+                .enumerate()
+                .filter(|(field, value)| *value != expected[*field])
+                .map(|(field, value)| SetFieldMismatch {
+                    field: set_fields[field].1,
+                    value,
+                    expected: expected[field].clone(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(MetadataConsistencyPayload {
+        folder: folder.to_string_lossy().to_string(),
+        consistent: difficulties.iter().all(|difficulty| difficulty.mismatches.is_empty()),
+        difficulties,
+    })
+}
+
+/// Check that every difficulty in a mapset folder shares its metadata and audio file.
+#[tauri::command]
+async fn check_metadata_consistency(folder: String) -> Result<MetadataConsistencyPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || check_mapset_metadata(Path::new(&folder)))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+const DENSITY_DEFAULT_BUCKET_MS: i32 = 250;
+const DENSITY_DEFAULT_LEVELS: usize = 6;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DensityLevel {
+    bucket_ms: i32,
+    max: u32,
+    buckets: Vec<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DensityHistogramPayload {
+    file_path: String,
+    length_ms: i32,
+    /// Finest first; each level halves the previous one's resolution.
+    levels: Vec<DensityLevel>,
+}
+
+/// Object density histograms for the timeline minimap, at `bucket_ms` and successively coarser
+/// resolutions so zooming only picks a level instead of re-binning.
+#[tauri::command]
+async fn get_density_histogram(
+    file_path: String,
+    bucket_ms: Option<i32>,
+    levels: Option<usize>,
+) -> Result<DensityHistogramPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let parsed = load_parsed_osu(&file_path)?;
+        let bucket_ms = bucket_ms.filter(|ms| *ms > 0).unwrap_or(DENSITY_DEFAULT_BUCKET_MS);
+        let length_ms = parsed.hit_ends.iter().max().copied().unwrap_or(0).max(0);
+        let bucket_count = (length_ms / bucket_ms + 1) as usize;
+
+        let mut buckets = build_density_histogram(&parsed.hit_starts, bucket_ms, bucket_count);
+        let mut level_bucket_ms = bucket_ms;
+        let mut histograms = Vec::new();
+        for _ in 0..levels.unwrap_or(DENSITY_DEFAULT_LEVELS).max(1) {
+            let coarser: Vec<u32> = buckets.chunks(2).map(|pair| pair.iter().sum()).collect();
+            let done = buckets.len() <= 1;
+            histograms.push(DensityLevel {
+                bucket_ms: level_bucket_ms,
+                max: buckets.iter().copied().max().unwrap_or(0),
+                buckets,
+            });
+            if done {
+                break;
+            }
+            buckets = coarser;
+            level_bucket_ms *= 2;
+        }
+
+        Ok(DensityHistogramPayload { file_path, length_ms, levels: histograms })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Same resolution the timeline highlights use, so both agree on what counts as mapped.
+const PROGRESS_BINS: usize = 120;
+const PROGRESS_TREND_MAX_SNAPSHOTS: usize = 30;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressSection {
+    /// `intro`, `kiai`, `section` or `outro`.
+    kind: &'static str,
+    start_ms: i32,
+    end_ms: i32,
+    progress: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressTrendPoint {
+    created_at_ms: u64,
+    progress: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MappingProgressPayload {
+    file_path: String,
+    progress: f64,
+    mapped_ms: i32,
+    mappable_ms: i32,
+    sections: Vec<ProgressSection>,
+    /// Oldest first, one point per snapshot followed by the file as it is now.
+    trend: Vec<ProgressTrendPoint>,
+}
+
+/// Which of `PROGRESS_BINS` equal slices of the audio hold an object (or a break, when breaks count).
+fn progress_coverage(parsed: &ParsedOsu, duration_ms: f64, count_breaks: bool) -> Vec<bool> {
+    let mut covered = vec![false; PROGRESS_BINS];
+    let bin_of = |time: i32| ((f64::from(time) / duration_ms * PROGRESS_BINS as f64) as usize).min(PROGRESS_BINS - 1);
+    for (index, &start) in parsed.hit_starts.iter().enumerate() {
+        if start < 0 || f64::from(start) > duration_ms {
+            continue;
+        }
+        let end = parsed.hit_ends.get(index).copied().unwrap_or(start).max(start);
+        covered[bin_of(start)..=bin_of(end)].fill(true);
+    }
+    if count_breaks {
+        for range in &parsed.break_periods {
+            let start = range.start.max(0);
+            if f64::from(start) > duration_ms || range.end <= start {
+                continue;
+            }
+            covered[bin_of(start)..=bin_of(range.end)].fill(true);
+        }
+    }
+    covered
+}
+
+/// Mapped fraction of the audio. With `ignore_start_and_breaks`, the lead-in before the first object
+/// is left out and breaks count as mapped, like the renderer's `computeProgress`.
+fn mapped_fraction(parsed: &ParsedOsu, duration_ms: f64, ignore_start_and_breaks: bool) -> (f64, Vec<bool>) {
+    let covered = progress_coverage(parsed, duration_ms, ignore_start_and_breaks);
+    let mapped_bins = covered.iter().filter(|bin| **bin).count();
+    let first_bin = if ignore_start_and_breaks {
+        covered.iter().position(|bin| *bin).unwrap_or(0)
+    } else {
+        0
+    };
+    let fraction = mapped_bins as f64 / (PROGRESS_BINS - first_bin).max(1) as f64;
+    (fraction.clamp(0.0, 1.0), covered)
+}
+
+/// Section boundaries come from bookmarks when the map has any, otherwise from kiai toggles.
+fn progress_sections(parsed: &ParsedOsu, duration_ms: i32, covered: &[bool]) -> Vec<ProgressSection> {
+    let mut kiai_toggles: Vec<(i32, bool)> = Vec::new();
+    for point in &parsed.timing_points {
+        if kiai_toggles.last().is_some_and(|(_, kiai)| *kiai) != point.kiai {
+            kiai_toggles.push((point.time as i32, point.kiai));
+        }
+    }
+    let kiai_at = |time: i32| {
+        kiai_toggles
+            .iter()
+            .take_while(|(toggle_time, _)| *toggle_time <= time)
+            .last()
+            .is_some_and(|(_, kiai)| *kiai)
+    };
+
+    let mut boundaries: Vec<i32> = if parsed.bookmarks.is_empty() {
+        kiai_toggles.iter().map(|(time, _)| *time).collect()
+    } else {
+        parsed.bookmarks.clone()
+    };
+    boundaries.retain(|time| *time > 0 && *time < duration_ms);
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries.insert(0, 0);
+    boundaries.push(duration_ms);
+
+    let section_count = boundaries.len() - 1;
+    boundaries
+        .windows(2)
+        .enumerate()
+        .map(|(index, window)| {
+            let (start_ms, end_ms) = (window[0], window[1]);
+            let bin_center_ms = |bin: usize| (bin as f64 + 0.5) * f64::from(duration_ms) / PROGRESS_BINS as f64;
+            let bins: Vec<bool> = (0..PROGRESS_BINS)
+                .filter(|bin| (f64::from(start_ms)..f64::from(end_ms)).contains(&bin_center_ms(*bin)))
+                .map(|bin| covered[bin])
+                .collect();
+            let progress = if bins.is_empty() {
+                0.0
+            } else {
+                bins.iter().filter(|bin| **bin).count() as f64 / bins.len() as f64
+            };
+            let kind = if kiai_at(start_ms) {
+                "kiai"
+            } else if index == 0 && section_count > 1 {
+                "intro"
+            } else if index + 1 == section_count && section_count > 1 {
+                "outro"
+            } else {
+                "section"
+            };
+            ProgressSection { kind, start_ms, end_ms, progress }
+        })
+        .collect()
+}
+
+/// Mapped percentage of a map against its audio length, with per-section completion and a trend
+/// rebuilt from its snapshots.
+#[tauri::command]
+async fn compute_mapping_progress(
+    app_handle: tauri::AppHandle,
+    file_path: String,
+    audio_duration: f64,
+) -> Result<MappingProgressPayload, String> {
+    if !audio_duration.is_finite() || audio_duration <= 0.0 {
+        return Err("audio duration must be positive".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let ignore_start_and_breaks = read_native_setting(&app_handle, "ignoreStartAndBreaks")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+
+        let parsed = load_parsed_osu(&file_path)?;
+        let (progress, covered) = mapped_fraction(&parsed, audio_duration, ignore_start_and_breaks);
+        let duration_ms = audio_duration.round() as i32;
+        let first_bin = covered.iter().position(|bin| *bin).unwrap_or(0);
+        let mappable_ms = if ignore_start_and_breaks {
+            (audio_duration * (PROGRESS_BINS - first_bin) as f64 / PROGRESS_BINS as f64).round() as i32
+        } else {
+            duration_ms
+        };
+        let sections = progress_sections(&parsed, duration_ms, &covered);
+
+        let snapshot_dir = beatmap_snapshot_dir(&file_path)?;
+        let mut trend: Vec<ProgressTrendPoint> = read_beatmap_snapshots(&snapshot_dir, &file_path)
+            .into_iter()
+            .take(PROGRESS_TREND_MAX_SNAPSHOTS)
+            .filter_map(|snapshot| {
+                let bytes = read_beatmap_snapshot(&snapshot_dir, &snapshot.id).ok()?;
+                let parsed = parse_osu_content(&decode_osu_bytes(&bytes).0);
+                let (progress, _) = mapped_fraction(&parsed, audio_duration, ignore_start_and_breaks);
+                Some(ProgressTrendPoint { created_at_ms: snapshot.created_at_ms, progress })
+            })
+            .collect();
+        trend.reverse();
+        trend.push(ProgressTrendPoint {
+            created_at_ms: get_mtime_ms(Path::new(&file_path))
+                .map(|ms| ms as u64)
+                .unwrap_or_else(|_| unix_time_ms() as u64),
+            progress,
+        });
+
+        Ok(MappingProgressPayload {
+            file_path,
+            progress,
+            mapped_ms: (progress * f64::from(mappable_ms)).round() as i32,
+            mappable_ms,
+            sections,
+            trend,
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MapWorkStatsPayload {
+    file_path: String,
+    total_active_ms: f64,
+    total_saves: u32,
+    objects_added: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_edit_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_edit_ms: Option<f64>,
+    /// Oldest first.
+    sessions: Vec<WorkSession>,
+}
+
+/// Time spent on a map, from the edit sessions recorded whenever a rescan sees its mtime change.
+#[tauri::command]
+fn get_map_work_stats(path: String) -> Result<MapWorkStatsPayload, String> {
+    let stats = work_stats().lock().map_err(|err| err.to_string())?;
+    let sessions = stats.maps.get(&path).cloned().unwrap_or_default();
+    Ok(MapWorkStatsPayload {
+        total_active_ms: sessions.iter().map(|session| session.active_ms).sum(),
+        total_saves: sessions.iter().map(|session| session.saves).sum(),
+        objects_added: sessions.iter().map(|session| session.objects_added).sum(),
+        first_edit_ms: sessions.first().map(|session| session.started_at_ms),
+        last_edit_ms: sessions.last().map(|session| session.ended_at_ms),
+        file_path: path,
+        sessions,
+    })
+}
+
+#[tauri::command]
+async fn compare_maps(
+    path_a: String,
+    path_b: String,
+    bucket_ms: Option<i32>,
+) -> Result<MapComparisonPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        build_map_comparison(path_a, path_b, bucket_ms.unwrap_or(1000))
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Structural diff of two versions of a map: changed fields, timing points and hit objects.
+#[tauri::command]
+async fn compare_beatmaps(path_a: String, path_b: String) -> Result<BeatmapDiffPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || build_beatmap_diff(path_a, path_b))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+fn stat_file(file_path: String) -> Option<FileStatPayload> {
+    let mtime_ms = get_mtime_ms(Path::new(&file_path)).ok()?;
+    Some(FileStatPayload { mtime_ms })
+}
+
+#[tauri::command]
+fn prepare_lazer_map_session(file_path: String, data_root: String) -> Result<LazerPreparedSession, String> {
+    let data_root_path = resolve_lazer_data_root(&data_root)
+        .ok_or_else(|| "osu!lazer data folder not found".to_string())?;
+    let beatmap_hash = beatmap_hash_from_lazer_path(&file_path)
+        .ok_or_else(|| "failed to derive lazer beatmap hash".to_string())?;
+    let manifest = get_lazer_manifest(&data_root_path, &beatmap_hash)?;
+
+    if !manifest.h.eq_ignore_ascii_case(&beatmap_hash) {
+        return Err("realm-resolver returned a mismatched beatmap manifest".to_string());
+    }
+
+    if manifest.f.is_empty() {
+        return Err("no files available to unpack for this beatmap set".to_string());
+    }
+
+    let session_dir = create_lazer_session_dir(&beatmap_hash)?;
+    let unpacked_dir = session_dir.join("unpacked");
+    fs::create_dir_all(&unpacked_dir).map_err(|err| err.to_string())?;
+
+    let mut files = Vec::with_capacity(manifest.f.len());
+    let target_osu_name = manifest.o.as_deref().map(|name| name.replace('\\', "/").to_ascii_lowercase());
+    let mut unpacked_osu_relative_path: Option<String> = None;
+
+    for entry in manifest.f {
+        let Some(relative_path) = normalize_relative_session_path(&entry.n) else {
+            continue;
+        };
+
+        let destination = unpacked_dir.join(&relative_path);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+
+        fs::copy(&entry.p, &destination).map_err(|err| {
+            format!("failed to unpack {}: {err}", relative_path.to_string_lossy())
+        })?;
+
+        let relative_string = relative_path.to_string_lossy().replace('\\', "/");
+        if unpacked_osu_relative_path.is_none() {
+            let is_target = target_osu_name
+                .as_deref()
+                .is_some_and(|target| target == relative_string.to_ascii_lowercase());
+            let is_osu_file = relative_string.to_ascii_lowercase().ends_with(".osu");
+            if is_target || is_osu_file {
+                unpacked_osu_relative_path = Some(relative_string.clone());
+            }
+        }
+
+        files.push(LazerSessionFileEntry {
+            relative_path: relative_string,
+            source_path: entry.p,
+        });
+    }
+
+    if files.is_empty() {
+        return Err("no unpackable files were found for this beatmap set".to_string());
+    }
+
+    let state = LazerSessionState {
+        source_file_path: file_path,
+        unpacked_osu_relative_path: unpacked_osu_relative_path.clone(),
+        files,
+    };
+    write_lazer_session_state(&session_dir, &state)?;
+
+    let unpacked_osu_path = unpacked_osu_relative_path
+        .as_ref()
+        .map(|relative| unpacked_dir.join(relative).to_string_lossy().to_string());
+
+    Ok(LazerPreparedSession {
+        session_dir: session_dir.to_string_lossy().to_string(),
+        unpacked_dir: unpacked_dir.to_string_lossy().to_string(),
+        unpacked_osu_path,
+    })
+}
+
+#[tauri::command]
+fn commit_lazer_map_session(session_dir: String) -> Result<(), String> {
+    let session_dir_path = PathBuf::from(&session_dir);
+    let unpacked_dir = session_dir_path.join("unpacked");
+    let state = read_lazer_session_state(&session_dir_path)?;
+
+    for file in &state.files {
+        let relative_path = Path::new(&file.relative_path);
+        let unpacked_path = unpacked_dir.join(relative_path);
+        if !unpacked_path.is_file() {
+            continue;
+        }
+
+        if let Some(parent) = Path::new(&file.source_path).parent() {
+            fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+
+        fs::copy(&unpacked_path, &file.source_path).map_err(|err| {
+            format!("failed to repack {}: {err}", file.relative_path)
+        })?;
+    }
+
+    let _ = fs::remove_dir_all(&session_dir_path);
+    Ok(())
+}
+
+/// Rewrite the given [Metadata] fields, backing the file up first. With `apply_to_set` every
+/// difficulty in the folder is updated, except `Version` which only applies to `file_path` itself.
+#[tauri::command]
+fn write_osu_metadata(
+    file_path: String,
+    changes: OsuMetadataChanges,
+    apply_to_set: Option<bool>,
+) -> Result<Vec<OsuFileWriteResult>, String> {
+    let source_path = PathBuf::from(&file_path);
+    let targets = if apply_to_set.unwrap_or(false) {
+        list_set_osu_files(&source_path)?
+    } else {
+        vec![source_path.clone()]
+    };
+
+    let shared_fields = [
+        ("Title", &changes.title),
+        ("Artist", &changes.artist),
+        ("Creator", &changes.creator),
+        ("Tags", &changes.tags),
+        ("Source", &changes.source),
+    ];
+
+    Ok(targets
+        .iter()
+        .map(|target| {
+            let is_source = path_cache_key(target) == path_cache_key(&source_path);
+            let result = rewrite_osu_file(target, true, |content| {
+                let mut updated = content.to_string();
+                for (key, value) in shared_fields {
+                    if let Some(value) = value {
+                        updated = upsert_osu_section_value(&updated, "Metadata", key, value.trim())?;
+                    }
+                }
+                if let (true, Some(version)) = (is_source, changes.version.as_ref()) {
+                    updated = upsert_osu_section_value(&updated, "Metadata", "Version", version.trim())?;
+                }
+                Ok(updated)
+            });
+            OsuFileWriteResult::from_result(target, result)
+        })
+        .collect())
+}
+
+/// Commit a preview point to `[General] PreviewTime`. `-1` clears it back to the osu! default.
+#[tauri::command]
+fn set_preview_time(
+    file_path: String,
+    time_ms: i32,
+    apply_to_set: Option<bool>,
+) -> Result<Vec<OsuFileWriteResult>, String> {
+    if time_ms < -1 {
+        return Err("Preview time must be -1 or a non-negative time in milliseconds".to_string());
+    }
+    let source_path = PathBuf::from(&file_path);
+    let targets = if apply_to_set.unwrap_or(false) {
+        list_set_osu_files(&source_path)?
+    } else {
+        vec![source_path]
+    };
+
+    let value = time_ms.to_string();
+    Ok(targets
+        .iter()
+        .map(|target| {
+            let result = rewrite_osu_file(target, false, |content| {
+                upsert_osu_section_value(content, "General", "PreviewTime", &value)
+            });
+            OsuFileWriteResult::from_result(target, result)
+        })
+        .collect())
+}
+
+const SET_CONSISTENT_METADATA_FIELDS: [&str; 7] = [
+    "Title",
+    "TitleUnicode",
+    "Artist",
+    "ArtistUnicode",
+    "Creator",
+    "Source",
+    "Tags",
+];
+
+/// Largest .osu read out of a guest difficulty archive; real difficulties are a few MB at most.
+const MAX_MERGED_OSU_BYTES: u64 = 64 * 1024 * 1024;
+
+fn merge_guest_diff_into(
+    source: &Path,
+    target_folder: &Path,
+    overwrite: bool,
+) -> Result<GuestDiffMergePayload, String> {
+    if !target_folder.is_dir() {
+        return Err("target folder not found".to_string());
+    }
+
+    let reference_content = list_osu_files_in_folder(target_folder)?
+        .first()
+        .and_then(|path| fs::read(path).ok())
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string());
+    let set_audio = reference_content
+        .as_deref()
+        .and_then(|content| read_osu_section_value(content, "General", "AudioFilename"))
+        .filter(|audio| !audio.is_empty());
+
+    let is_archive = source
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("osz") || ext.eq_ignore_ascii_case("zip"));
+    let mut archive = if is_archive {
+        let file = fs::File::open(source).map_err(|err| err.to_string())?;
+        Some(zip::ZipArchive::new(BufReader::new(file)).map_err(|err| err.to_string())?)
+    } else {
+        None
+    };
+
+    let mut incoming: Vec<(String, Vec<u8>)> = Vec::new();
+    match archive.as_mut() {
+        Some(archive) => {
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index).map_err(|err| err.to_string())?;
+                let Some(name) = entry.enclosed_name() else {
+                    continue;
+                };
+                let is_osu = name
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("osu"));
+                if entry.is_dir() || !is_osu {
+                    continue;
+                }
+                let Some(file_name) = name.file_name().map(|name| name.to_string_lossy().to_string()) else {
+                    continue;
+                };
+                let mut bytes = Vec::new();
+                entry.by_ref().take(MAX_MERGED_OSU_BYTES + 1).read_to_end(&mut bytes).map_err(|err| err.to_string())?;
+                if bytes.len() as u64 > MAX_MERGED_OSU_BYTES {
+                    return Err(format!("{file_name} is too large to be a beatmap"));
+                }
+                incoming.push((file_name, bytes));
+            }
+        }
+        None => {
+            let file_name = source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .ok_or_else(|| "invalid beatmap path".to_string())?;
+            incoming.push((file_name, fs::read(source).map_err(|err| err.to_string())?));
+        }
+    }
+
+    if incoming.is_empty() {
+        return Err("no .osu files were found to merge".to_string());
+    }
+
+    let mut diffs = Vec::with_capacity(incoming.len());
+    let mut copied_assets = Vec::new();
+
+    for (source_name, bytes) in incoming {
+        let mut entry = GuestDiffMergeEntry {
+            source_name: source_name.clone(),
+            destination_path: None,
+            audio_filename_fixed_from: None,
+            audio_missing: false,
+            background_missing: false,
+            metadata_mismatches: Vec::new(),
+            error: None,
+        };
+
+        let destination = target_folder.join(&source_name);
+        if destination.exists() && !overwrite {
+            entry.error = Some("a difficulty with this file name already exists".to_string());
+            diffs.push(entry);
+            continue;
+        }
+
+        let Ok(mut content) = String::from_utf8(bytes) else {
+            entry.error = Some("beatmap is not valid UTF-8".to_string());
+            diffs.push(entry);
+            continue;
+        };
+
+        let guest_audio = read_osu_section_value(&content, "General", "AudioFilename").unwrap_or_default();
+        if let Some(set_audio) = set_audio.as_deref() {
+            if !guest_audio.eq_ignore_ascii_case(set_audio) {
+                match upsert_osu_section_value(&content, "General", "AudioFilename", set_audio) {
+                    Ok(updated) => {
+                        content = updated;
+                        entry.audio_filename_fixed_from = Some(guest_audio.clone());
+                    }
+                    Err(err) => entry.error = Some(err),
+                }
+            }
+        }
+        let effective_audio = set_audio.as_deref().unwrap_or(&guest_audio);
+        entry.audio_missing = effective_audio.is_empty() || !target_folder.join(effective_audio).is_file();
+
+        let background = parse_osu_content(&content).metadata.background;
+        if !background.is_empty() && !target_folder.join(&background).is_file() {
+            let normalized = background.replace('\\', "/").to_ascii_lowercase();
+            let extracted = archive.as_mut().and_then(|archive| {
+                let index = (0..archive.len()).find(|&index| {
+                    archive.by_index(index).ok().and_then(|file| file.enclosed_name()).is_some_and(|name| {
+                        name.to_string_lossy().replace('\\', "/").to_ascii_lowercase() == normalized
+                    })
+                })?;
+                let mut file = archive.by_index(index).ok()?;
+                let target = target_folder.join(&background);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent).ok()?;
+                }
+                let mut output = fs::File::create(&target).ok()?;
+                std::io::copy(&mut file, &mut output).ok()?;
+                Some(target)
+            });
+            match extracted {
+                Some(path) => copied_assets.push(path.to_string_lossy().to_string()),
+                None => entry.background_missing = true,
+            }
+        }
+
+        if let Some(reference) = reference_content.as_deref() {
+            for field in SET_CONSISTENT_METADATA_FIELDS {
+                let guest_value = read_osu_section_value(&content, "Metadata", field).unwrap_or_default();
+                let set_value = read_osu_section_value(reference, "Metadata", field).unwrap_or_default();
+                if guest_value != set_value {
+                    entry.metadata_mismatches.push(MetadataMismatch {
+                        field: field.to_string(),
+                        guest_value,
+                        set_value,
+                    });
+                }
+            }
+        }
+
+        if entry.error.is_none() {
+            match write_file_atomically(&destination, content.as_bytes()) {
+                Ok(()) => entry.destination_path = Some(destination.to_string_lossy().to_string()),
+                Err(err) => entry.error = Some(err),
+            }
+        }
+        diffs.push(entry);
+    }
+
+    Ok(GuestDiffMergePayload {
+        target_folder: target_folder.to_string_lossy().to_string(),
+        diffs,
+        copied_assets,
+    })
+}
+
+/// Copy a guest difficulty (.osu, or every .osu inside an .osz) into an existing set folder,
+/// aligning its AudioFilename with the set and reporting assets/metadata that need attention.
+#[tauri::command]
+async fn merge_guest_diff(
+    osu_or_osz: String,
+    target_folder: String,
+    overwrite: Option<bool>,
+) -> Result<GuestDiffMergePayload, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        merge_guest_diff_into(
+            Path::new(&osu_or_osz),
+            Path::new(&target_folder),
+            overwrite.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Replace (or insert) the `[Editor] Bookmarks` line so markers placed in mosu round-trip into osu!.
+/// An empty list removes the line, matching what the osu! editor writes.
+#[tauri::command]
+fn write_osu_bookmarks(
+    file_path: String,
+    bookmarks: Vec<i32>,
+    backup: Option<bool>,
+) -> OsuFileWriteResult {
+    let path = PathBuf::from(&file_path);
+    let mut bookmarks: Vec<i32> = bookmarks.into_iter().filter(|time| *time >= 0).collect();
+    bookmarks.sort_unstable();
+    bookmarks.dedup();
+
+    let result = rewrite_osu_file(&path, backup.unwrap_or(false), |content| {
+        if bookmarks.is_empty() {
+            return Ok(remove_osu_section_value(content, "Editor", "Bookmarks"));
+        }
+        let value = bookmarks
+            .iter()
+            .map(|time| time.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        upsert_osu_section_value(content, "Editor", "Bookmarks", &value)
+    });
+    OsuFileWriteResult::from_result(&path, result)
+}
+
+/// Shift the whole map by `offset_ms` (positive = later) and write it back.
+#[tauri::command]
+fn apply_offset(file_path: String, offset_ms: i32, backup: bool) -> OsuFileWriteResult {
+    let path = PathBuf::from(&file_path);
+    let result = if offset_ms == 0 {
+        Ok(None)
+    } else {
+        rewrite_osu_file(&path, backup, |content| Ok(shift_osu_content(content, offset_ms)))
+    };
+    OsuFileWriteResult::from_result(&path, result)
+}
+
+#[tauri::command]
+fn parse_stable_collections(path: String) -> Result<Vec<OsuCollectionPayload>, String> {
+    let db = read_stable_collections_file(Path::new(&path))?;
+    Ok(db.collections)
+}
+
+#[tauri::command]
+fn add_to_stable_collection(
+    collection_db_path: String,
+    collection_name: String,
+    beatmap_hash: String,
+) -> CollectionMutationPayload {
+    let path = PathBuf::from(&collection_db_path);
+    let normalized_name = collection_name.trim();
+    let normalized_hash = beatmap_hash.trim().to_ascii_lowercase();
+
+    if normalized_name.is_empty() || normalized_hash.is_empty() {
+        return CollectionMutationPayload {
+            success: false,
+            error: Some("invalid_input".to_string()),
+        };
+    }
+
+    let mut db = match read_stable_collections_file(&path) {
+        Ok(db) => db,
+        Err(error) => {
+            let locked = fs::File::open(&path)
+                .err()
+                .is_some_and(|err| is_locked_io_error(&err));
+            return CollectionMutationPayload {
+                success: false,
+                error: Some(if locked { "file_locked".to_string() } else { error }),
+            };
+        }
+    };
+
+    let Some(collection) = db.collections.iter_mut().find(|collection| {
+        collection.name.eq_ignore_ascii_case(normalized_name)
+    }) else {
+        return CollectionMutationPayload {
+            success: false,
+            error: Some("collection not found".to_string()),
+        };
+    };
+
+    if collection
+        .beatmap_hashes
+        .iter()
+        .any(|hash| hash.eq_ignore_ascii_case(&normalized_hash))
+    {
+        return CollectionMutationPayload {
+            success: true,
+            error: None,
+        };
+    }
+
+    collection.beatmap_hashes.push(normalized_hash);
     let bytes = match write_stable_collections_bytes(&db) {
         Ok(bytes) => bytes,
         Err(error) => {
             return CollectionMutationPayload {
                 success: false,
-                error: Some(error),
+                error: Some(error),
+            };
+        }
+    };
+
+    match fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(&path)
+    {
+        Ok(mut file) => {
+            if let Err(error) = file.write_all(&bytes) {
+                return CollectionMutationPayload {
+                    success: false,
+                    error: Some(if is_locked_io_error(&error) {
+                        "file_locked".to_string()
+                    } else {
+                        error.to_string()
+                    }),
+                };
+            }
+        }
+        Err(error) => {
+            return CollectionMutationPayload {
+                success: false,
+                error: Some(if is_locked_io_error(&error) {
+                    "file_locked".to_string()
+                } else {
+                    error.to_string()
+                }),
+            };
+        }
+    }
+
+    CollectionMutationPayload {
+        success: true,
+        error: None,
+    }
+}
+
+/// collection.db version written when exporting to a file that doesn't exist yet.
+const NEW_COLLECTION_DB_VERSION: i32 = 20150203;
+
+/// A named list of maps kept by mosu itself, separate from osu!'s collections.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Playlist {
+    name: String,
+    /// .osu paths in the order they were added.
+    file_paths: Vec<String>,
+    created_at_ms: u64,
+    updated_at_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PlaylistExportPayload {
+    collection_name: String,
+    /// Maps newly added to the collection.
+    added: usize,
+    /// Playlist entries whose file could no longer be read.
+    missing: Vec<String>,
+}
+
+fn playlists_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|err| err.to_string())?;
+    Ok(data_dir.join("playlists.json"))
+}
+
+/// A missing file means no playlists yet; anything else is an error so a damaged file is never
+/// overwritten by the next save.
+fn load_playlists(path: &Path) -> Result<Vec<Playlist>, String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.to_string()),
+    };
+    serde_json::from_slice(&bytes).map_err(|err| format!("playlists.json is unreadable: {err}"))
+}
+
+fn save_playlists(path: &Path, playlists: &[Playlist]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let bytes = serde_json::to_vec_pretty(playlists).map_err(|err| err.to_string())?;
+    write_file_atomically(path, &bytes)
+}
+
+/// Playlist names are matched case-insensitively, like osu! collections.
+fn find_playlist<'a>(playlists: &'a mut [Playlist], name: &str) -> Result<&'a mut Playlist, String> {
+    playlists
+        .iter_mut()
+        .find(|playlist| playlist.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| format!("playlist not found: {name}"))
+}
+
+#[tauri::command]
+fn list_playlists(app_handle: tauri::AppHandle) -> Result<Vec<Playlist>, String> {
+    load_playlists(&playlists_path(&app_handle)?)
+}
+
+#[tauri::command]
+fn create_playlist(app_handle: tauri::AppHandle, name: String) -> Result<Vec<Playlist>, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("playlist name is empty".to_string());
+    }
+    let path = playlists_path(&app_handle)?;
+    let mut playlists = load_playlists(&path)?;
+    if playlists.iter().any(|playlist| playlist.name.eq_ignore_ascii_case(&name)) {
+        return Err(format!("a playlist named {name} already exists"));
+    }
+    let now = unix_time_ms() as u64;
+    playlists.push(Playlist { name, file_paths: Vec::new(), created_at_ms: now, updated_at_ms: now });
+    save_playlists(&path, &playlists)?;
+    Ok(playlists)
+}
+
+#[tauri::command]
+fn delete_playlist(app_handle: tauri::AppHandle, name: String) -> Result<Vec<Playlist>, String> {
+    let path = playlists_path(&app_handle)?;
+    let mut playlists = load_playlists(&path)?;
+    playlists.retain(|playlist| !playlist.name.eq_ignore_ascii_case(name.trim()));
+    save_playlists(&path, &playlists)?;
+    Ok(playlists)
+}
+
+/// Append maps to a playlist; ones already in it are skipped.
+#[tauri::command]
+fn add_to_playlist(app_handle: tauri::AppHandle, name: String, file_paths: Vec<String>) -> Result<Playlist, String> {
+    let path = playlists_path(&app_handle)?;
+    let mut playlists = load_playlists(&path)?;
+    let playlist = find_playlist(&mut playlists, &name)?;
+    for file_path in file_paths {
+        let key = path_cache_key(Path::new(&file_path));
+        if !playlist.file_paths.iter().any(|existing| path_cache_key(Path::new(existing)) == key) {
+            playlist.file_paths.push(file_path);
+        }
+    }
+    playlist.updated_at_ms = unix_time_ms() as u64;
+    let playlist = playlist.clone();
+    save_playlists(&path, &playlists)?;
+    Ok(playlist)
+}
+
+#[tauri::command]
+fn remove_from_playlist(
+    app_handle: tauri::AppHandle,
+    name: String,
+    file_paths: Vec<String>,
+) -> Result<Playlist, String> {
+    let path = playlists_path(&app_handle)?;
+    let mut playlists = load_playlists(&path)?;
+    let playlist = find_playlist(&mut playlists, &name)?;
+    let keys: Vec<String> = file_paths.iter().map(|file_path| path_cache_key(Path::new(file_path))).collect();
+    playlist.file_paths.retain(|existing| !keys.contains(&path_cache_key(Path::new(existing))));
+    playlist.updated_at_ms = unix_time_ms() as u64;
+    let playlist = playlist.clone();
+    save_playlists(&path, &playlists)?;
+    Ok(playlist)
+}
+
+/// Copy a playlist into a stable collection.db as a collection of the same name (or
+/// `collection_name`), merging with an existing collection. Maps are matched by their current
+/// MD5, so osu! picks up edits made since they were added.
+#[tauri::command]
+async fn export_playlist_as_collection(
+    app_handle: tauri::AppHandle,
+    name: String,
+    collection_db_path: String,
+    collection_name: Option<String>,
+) -> Result<PlaylistExportPayload, String> {
+    let mut playlists = load_playlists(&playlists_path(&app_handle)?)?;
+    let playlist = find_playlist(&mut playlists, &name)?.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let db_path = PathBuf::from(&collection_db_path);
+        let mut db = if db_path.exists() {
+            read_stable_collections_file(&db_path)?
+        } else {
+            StableCollectionsDb { version: NEW_COLLECTION_DB_VERSION, collections: Vec::new() }
+        };
+
+        let collection_name = collection_name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| playlist.name.clone());
+        let existing = db.collections.iter().position(|collection| collection.name.eq_ignore_ascii_case(&collection_name));
+        let index = existing.unwrap_or_else(|| {
+            db.collections.push(OsuCollectionPayload { name: collection_name.clone(), beatmap_hashes: Vec::new() });
+            db.collections.len() - 1
+        });
+        let collection = &mut db.collections[index];
+
+        let mut added = 0;
+        let mut missing = Vec::new();
+        for file_path in playlist.file_paths {
+            let Ok(bytes) = fs::read(&file_path) else {
+                missing.push(file_path);
+                continue;
+            };
+            let hash = compute_osu_md5_hex(&bytes);
+            if !collection.beatmap_hashes.iter().any(|existing| existing.eq_ignore_ascii_case(&hash)) {
+                collection.beatmap_hashes.push(hash);
+                added += 1;
+            }
+        }
+
+        let bytes = write_stable_collections_bytes(&db)?;
+        write_file_atomically(&db_path, &bytes)?;
+        Ok(PlaylistExportPayload { collection_name, added, missing })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+fn get_lazer_collections(data_root: Option<String>) -> Result<Vec<OsuCollectionPayload>, String> {
+    let exe = find_realm_resolver_exe()
+        .ok_or_else(|| "realm-resolver sidecar not found".to_string())?;
+
+    let mut command = Command::new(&exe);
+    if let Some(data_root) = data_root.as_ref().filter(|value| !value.trim().is_empty()) {
+        command.arg(data_root);
+    }
+    let output = command
+        .arg("list-collections")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .output()
+        .map_err(|err| format!("failed to run realm-resolver: {err}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("realm-resolver failed: {stderr}"));
+    }
+
+    serde_json::from_slice::<Vec<OsuCollectionPayload>>(&output.stdout)
+        .map_err(|err| format!("failed to parse lazer collections: {err}"))
+}
+
+#[tauri::command]
+fn add_to_lazer_collection(
+    data_root: Option<String>,
+    collection_name: String,
+    beatmap_hash: String,
+) -> CollectionMutationPayload {
+    let exe = match find_realm_resolver_exe() {
+        Some(exe) => exe,
+        None => {
+            return CollectionMutationPayload {
+                success: false,
+                error: Some("realm-resolver sidecar not found".to_string()),
+            };
+        }
+    };
+
+    let mut command = Command::new(&exe);
+    if let Some(data_root) = data_root.as_ref().filter(|value| !value.trim().is_empty()) {
+        command.arg(data_root);
+    }
+    let output = match command
+        .arg("add-to-collection")
+        .arg("--collection-name")
+        .arg(collection_name)
+        .arg("--beatmap-hash")
+        .arg(beatmap_hash)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .output()
+    {
+        Ok(output) => output,
+        Err(error) => {
+            return CollectionMutationPayload {
+                success: false,
+                error: Some(error.to_string()),
+            };
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if let Ok(payload) = serde_json::from_str::<CollectionMutationPayload>(&stdout) {
+        return payload;
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return CollectionMutationPayload {
+            success: false,
+            error: Some(if stderr.trim().is_empty() {
+                "realm-resolver failed".to_string()
+            } else {
+                stderr.trim().to_string()
+            }),
+        };
+    }
+
+    CollectionMutationPayload {
+        success: false,
+        error: Some("invalid_sidecar_response".to_string()),
+    }
+}
+
+#[tauri::command]
+fn show_item_in_folder(file_path: String) -> Result<(), String> {
+    let path = PathBuf::from(&file_path);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        
+        // Canonicalize to get an absolute path with backslashes.
+        // Explorer /select requires backslashes — forward slashes cause it
+        // to silently fall back to opening the Documents folder.
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+        let canonical_str = canonical.to_string_lossy();
+        // Strip the \\?\ prefix that canonicalize adds on Windows
+        let clean_path = canonical_str.strip_prefix("\\\\?\\").unwrap_or(&canonical_str);
+        
+        // IMPORTANT: Use raw_arg instead of arg — Rust's .arg() auto-quotes 
+        // arguments containing spaces, but explorer.exe /select, does NOT 
+        // support quoted arguments and silently falls back to Documents.
+        Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", clean_path))
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .status()
+            .map_err(|err| err.to_string())?;
+        return Ok(());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let target = path.parent().unwrap_or(Path::new(&file_path));
+        open::that(target).map_err(|err| err.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct OsuInstallPathsPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stable_install: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stable_songs: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lazer_data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lazer_files: Option<String>,
+}
+
+/// Executable registered for `osu://` links. Either client may have claimed it.
+#[cfg(target_os = "windows")]
+fn osu_protocol_handler_exe() -> Option<PathBuf> {
+    use std::os::windows::process::CommandExt;
+
+    let output = Command::new("reg")
+        .args(["query", r"HKEY_CLASSES_ROOT\osu\shell\open\command", "/ve"])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // `    (Default)    REG_SZ    "C:\...\osu!.exe" "%1"`
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = stdout.lines().find_map(|line| line.split_once("REG_SZ").map(|(_, value)| value.trim()))?;
+    let exe = match value.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next()?,
+        None => value.split(" \"").next()?,
+    };
+    Some(PathBuf::from(exe))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn osu_protocol_handler_exe() -> Option<PathBuf> {
+    None
+}
+
+/// Songs folder of a stable install, honouring a `BeatmapDirectory` override in any `osu!.<user>.cfg`.
+fn stable_songs_folder(install_dir: &Path) -> Option<PathBuf> {
+    let configured = fs::read_dir(install_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("osu!.") && name.ends_with(".cfg"))
+        })
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .find_map(|config| {
+            config.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "BeatmapDirectory")
+                    .then(|| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            })
+        });
+
+    // Relative values are relative to the install directory; join() keeps absolute ones as-is.
+    let songs = install_dir.join(configured.as_deref().unwrap_or("Songs"));
+    songs.is_dir().then_some(songs)
+}
+
+/// A lazer data directory, following the `storage.ini` redirect lazer writes when the user moves it.
+fn follow_lazer_storage_redirect(default_dir: &Path) -> PathBuf {
+    let redirected = fs::read_to_string(default_dir.join("storage.ini")).ok().and_then(|ini| {
+        ini.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "FullPath").then(|| PathBuf::from(value.trim()))
+        })
+    });
+    redirected
+        .filter(|path| path.join("client.realm").is_file())
+        .unwrap_or_else(|| default_dir.to_path_buf())
+}
+
+fn detect_osu_install_paths(app_handle: &tauri::AppHandle) -> OsuInstallPathsPayload {
+    let paths = app_handle.path();
+    let home = paths.home_dir().ok();
+    let data_dir = paths.data_dir().ok();
+    let local_data_dir = paths.local_data_dir().ok();
+
+    let handler = osu_protocol_handler_exe().and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let handler_is_lazer = handler.as_ref().is_some_and(|dir| {
+        dir.ancestors()
+            .any(|dir| dir.file_name().is_some_and(|name| name.eq_ignore_ascii_case("osulazer")))
+    });
+
+    let mut stable_candidates: Vec<PathBuf> = Vec::new();
+    if !handler_is_lazer {
+        stable_candidates.extend(handler);
+    }
+    stable_candidates.extend(local_data_dir.as_ref().map(|dir| dir.join("osu!")));
+    if let Some(home) = &home {
+        // Wine prefixes used by the common Linux and macOS installers.
+        stable_candidates.push(home.join(".local/share/osu-wine/osu!"));
+        stable_candidates.push(home.join(".wine/drive_c/osu!"));
+        stable_candidates.push(home.join("Applications/osu!.app/Contents/Resources/drive_c/osu!"));
+        if let Some(user) = home.file_name() {
+            stable_candidates.push(
+                home.join(".wine/drive_c/users")
+                    .join(user)
+                    .join("AppData/Local/osu!"),
+            );
+        }
+    }
+    let stable = stable_candidates
+        .into_iter()
+        .find_map(|dir| stable_songs_folder(&dir).map(|songs| (dir, songs)));
+
+    // osu!lazer stores its data under the platform data dir: %APPDATA%\osu, ~/.local/share/osu,
+    // ~/Library/Application Support/osu.
+    let lazer_data = data_dir
+        .map(|dir| follow_lazer_storage_redirect(&dir.join("osu")))
+        .and_then(|dir| resolve_lazer_data_root(&dir.to_string_lossy()));
+
+    OsuInstallPathsPayload {
+        stable_install: stable.as_ref().map(|(dir, _)| dir.to_string_lossy().to_string()),
+        stable_songs: stable.as_ref().map(|(_, songs)| songs.to_string_lossy().to_string()),
+        lazer_files: lazer_data
+            .as_ref()
+            .map(|dir| dir.join("files"))
+            .filter(|files| files.is_dir())
+            .map(|files| files.to_string_lossy().to_string()),
+        lazer_data: lazer_data.map(|dir| dir.to_string_lossy().to_string()),
+    }
+}
+
+/// Locate osu!stable's Songs folder and osu!lazer's data/files folders, where installed.
+#[tauri::command]
+async fn detect_osu_songs_folder(app_handle: tauri::AppHandle) -> Result<OsuInstallPathsPayload, String> {
+    tauri::async_runtime::spawn_blocking(move || detect_osu_install_paths(&app_handle))
+        .await
+        .map_err(|err| err.to_string())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EditorOpenPayload {
+    launched: bool,
+    /// `01:23:456 (1,2,3) - ` style editor timestamp, for pasting into the editor or a mod post.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    edit_url: Option<String>,
+}
+
+/// Editor timestamp for `time_ms`, listing the objects at that time by combo number, or by
+/// `time|column` for mania, the same way the osu! editor copies a selection.
+fn editor_timestamp(content: &str, parsed: &ParsedOsu, time_ms: i32) -> String {
+    let objects = parse_playfield_objects(content, parsed);
+
+    let selection: Vec<String> = if parsed.metadata.mode == 3 {
+        let key_count = parsed.metadata.difficulty.cs.round().max(1.0);
+        objects
+            .iter()
+            .filter(|object| object.time == time_ms)
+            .map(|object| format!("{}|{}", object.time, (object.x * key_count / OSU_PLAYFIELD_WIDTH).floor() as i32))
+            .collect()
+    } else {
+        objects
+            .iter()
+            .zip(combo_numbers(&objects))
+            .filter(|(object, _)| object.time == time_ms && object.object_type & 8 == 0)
+            .map(|(_, combo)| combo.to_string())
+            .collect()
+    };
+    format_editor_timestamp_text(time_ms, &selection)
+}
+
+/// Launch osu! with the map and, for `time_ms`, copy the editor timestamp and send an
+/// `osu://edit/` link so a running editor seeks there. Stable is started directly; lazer only
+/// receives the link.
+#[tauri::command]
+async fn open_in_osu_editor(
+    app_handle: tauri::AppHandle,
+    file_path: String,
+    time_ms: Option<i32>,
+    client_type: Option<String>,
+) -> Result<EditorOpenPayload, String> {
+    let client = OsuClient::from_option(client_type);
+    *TRACKED_EDITOR_MAP.lock().map_err(|err| err.to_string())? = Some(file_path.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let timestamp = match time_ms {
+            Some(time_ms) => {
+                let (content, parsed) = load_osu_with_content(&file_path)?;
+                Some(editor_timestamp(&content, &parsed, time_ms))
+            }
+            None => None,
+        };
+
+        let mut launched = false;
+        if client == OsuClient::Stable {
+            let exe = detect_osu_install_paths(&app_handle)
+                .stable_install
+                .map(|dir| Path::new(&dir).join("osu!.exe"))
+                .filter(|exe| exe.is_file());
+            if let Some(exe) = exe {
+                Command::new(exe).arg(&file_path).spawn().map_err(|err| err.to_string())?;
+                launched = true;
+            }
+        }
+
+        let edit_url = timestamp.as_deref().map(|timestamp| editor_edit_url(timestamp.trim_end_matches(" - ")));
+        if let Some(timestamp) = &timestamp {
+            use tauri_plugin_clipboard_manager::ClipboardExt;
+            let _ = app_handle.clipboard().write_text(timestamp.clone());
+        }
+        if let Some(url) = &edit_url {
+            open::that(url).map_err(|err| err.to_string())?;
+        }
+
+        Ok(EditorOpenPayload { launched, timestamp, edit_url })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// One `mm:ss:mmm (objects)` timestamp found in text.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct EditorTimestamp {
+    time_ms: i32,
+    /// Combo numbers, or `time|column` pairs for mania; empty for a bare timestamp.
+    objects: Vec<String>,
+    text: String,
+}
+
+fn format_editor_timestamp_text(time_ms: i32, objects: &[String]) -> String {
+    let time_ms = time_ms.max(0);
+    let clock = format!("{:02}:{:02}:{:03}", time_ms / 60_000, time_ms / 1000 % 60, time_ms % 1000);
+    if objects.is_empty() {
+        format!("{clock} - ")
+    } else {
+        format!("{clock} ({}) - ", objects.join(","))
+    }
+}
+
+fn editor_edit_url(timestamp: &str) -> String {
+    format!("osu://edit/{}", timestamp.replace(' ', "%20"))
+}
+
+/// Timestamp starting at `start`, returning it with the byte offset just past it.
+fn parse_editor_timestamp_at(text: &str, start: usize) -> Option<(EditorTimestamp, usize)> {
+    let bytes = text.as_bytes();
+    let digits_from = |from: usize| bytes[from..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+
+    let minutes_len = digits_from(start);
+    let seconds_at = start + minutes_len + 1;
+    if minutes_len == 0 || bytes.get(seconds_at - 1) != Some(&b':') || digits_from(seconds_at) != 2 {
+        return None;
+    }
+    let millis_at = seconds_at + 3;
+    if bytes.get(millis_at - 1) != Some(&b':') || digits_from(millis_at) != 3 {
+        return None;
+    }
+    let minutes: i32 = text[start..start + minutes_len].parse().ok()?;
+    let seconds: i32 = text[seconds_at..seconds_at + 2].parse().ok()?;
+    let millis: i32 = text[millis_at..millis_at + 3].parse().ok()?;
+    // Minutes have no upper bound, so the total can overflow even when every part parses.
+    let time_ms = minutes.checked_mul(60_000)?.checked_add(seconds * 1000 + millis)?;
+    let mut end = millis_at + 3;
+
+    let mut objects = Vec::new();
+    let rest = &text[end..];
+    if let Some(inner) = rest.strip_prefix(" (").or_else(|| rest.strip_prefix('(')) {
+        if let Some(close) = inner.find(')') {
+            let list = &inner[..close];
+            let valid = list.chars().all(|c| c.is_ascii_digit() || matches!(c, ',' | '|' | ' '));
+            if valid && !list.trim().is_empty() {
+                objects = list.split(',').map(|object| object.trim().to_string()).filter(|object| !object.is_empty()).collect();
+                end += rest.len() - inner.len() + close + 1;
+            }
+        }
+    }
+
+    Some((
+        EditorTimestamp { time_ms, objects, text: text[start..end].to_string() },
+        end,
+    ))
+}
+
+/// Every editor timestamp in `text`, in order, e.g. from a pasted mod post.
+fn parse_editor_timestamps(text: &str) -> Vec<EditorTimestamp> {
+    let bytes = text.as_bytes();
+    let mut timestamps = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        let starts_number = bytes[index].is_ascii_digit() && (index == 0 || !bytes[index - 1].is_ascii_digit());
+        match starts_number.then(|| parse_editor_timestamp_at(text, index)).flatten() {
+            Some((timestamp, end)) => {
+                timestamps.push(timestamp);
+                index = end;
+            }
+            None => index += 1,
+        }
+    }
+    timestamps
+}
+
+/// Timestamps in `text`, or in the clipboard when no text is given.
+#[tauri::command]
+fn parse_editor_timestamp(app_handle: tauri::AppHandle, text: Option<String>) -> Result<Vec<EditorTimestamp>, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let text = match text {
+        Some(text) => text,
+        None => app_handle.clipboard().read_text().map_err(|err| err.to_string())?,
+    };
+    Ok(parse_editor_timestamps(&text))
+}
+
+/// `mm:ss:mmm (objects) - ` for the timeline, also copied to the clipboard when `copy` is set.
+#[tauri::command]
+fn format_editor_timestamp(
+    app_handle: tauri::AppHandle,
+    time_ms: i32,
+    object_indices: Option<Vec<String>>,
+    copy: Option<bool>,
+) -> Result<String, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let timestamp = format_editor_timestamp_text(time_ms, &object_indices.unwrap_or_default());
+    if copy.unwrap_or(false) {
+        app_handle.clipboard().write_text(timestamp.clone()).map_err(|err| err.to_string())?;
+    }
+    Ok(timestamp)
+}
+
+/// Map most recently sent to the osu! editor from mosu; captured timestamps go to it when the
+/// osu! window title doesn't name a library map.
+static TRACKED_EDITOR_MAP: Mutex<Option<String>> = Mutex::new(None);
+
+/// Shortcut currently registered for timestamp capture, so settings changes only re-register
+/// when it actually changes.
+static TIMESTAMP_SHORTCUT: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct EditorTimestampCapturedEvent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<EditorTimestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Title of the focused window. Only Windows exposes it without extra permissions; elsewhere
+/// the shortcut is trusted to have been pressed in osu!.
+#[cfg(target_os = "windows")]
+fn foreground_window_title() -> Option<String> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowTextW};
+
+    let mut buffer = [0u16; 512];
+    let length = unsafe { GetWindowTextW(GetForegroundWindow(), buffer.as_mut_ptr(), buffer.len() as i32) };
+    (length > 0).then(|| String::from_utf16_lossy(&buffer[..length as usize]))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn foreground_window_title() -> Option<String> {
+    None
+}
+
+/// Map part of an osu! stable window title: `Artist - Title [Diff]` from `osu!  - Artist - Title [Diff]`
+/// while playing, with `(Mapper)` and `.osu` added in the editor. Menus show only the client name.
+fn osu_window_map_text(title: &str) -> Option<&str> {
+    let (_, playing) = title.strip_prefix("osu!")?.split_once(" - ")?;
+    Some(playing.trim()).filter(|playing| !playing.is_empty())
+}
+
+/// Library map named by an osu! window title such as `osu!  - Artist - Title (Mapper) [Diff].osu`.
+/// The tracked map wins when it matches too, since several copies of a set can be in the library.
+fn map_for_osu_window_title(title: &str, tracked: Option<&str>) -> Option<String> {
+    let playing = osu_window_map_text(title)?;
+    let cache = library_cache().lock().ok()?;
+    let names = |entry: &LibraryCacheEntry| {
+        let metadata = &entry.metadata;
+        playing.contains(&format!("{} - {}", metadata.artist, metadata.title))
+            && playing.contains(&format!("[{}]", metadata.version))
+    };
+    if let Some(entry) = tracked.and_then(|file_path| cache.entries.get(file_path)).filter(|entry| names(entry)) {
+        return Some(entry.file_path.clone());
+    }
+    cache.entries.values().find(|entry| names(entry)).map(|entry| entry.file_path.clone())
+}
+
+/// Handle the capture shortcut: when osu! has focus, take the editor timestamp from the
+/// clipboard and hand it to the renderer as a note for the map being edited.
+fn capture_editor_timestamp(app_handle: &tauri::AppHandle) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let window_title = foreground_window_title();
+    if cfg!(target_os = "windows") && !window_title.as_deref().is_some_and(|title| title.starts_with("osu!")) {
+        return;
+    }
+
+    let tracked = TRACKED_EDITOR_MAP.lock().ok().and_then(|tracked| tracked.clone());
+    let file_path = window_title
+        .as_deref()
+        .and_then(|title| map_for_osu_window_title(title, tracked.as_deref()))
+        .or(tracked);
+    let timestamp =
+        app_handle.clipboard().read_text().ok().and_then(|text| parse_editor_timestamps(&text).into_iter().next());
+
+    let error = if timestamp.is_none() {
+        Some("The clipboard holds no editor timestamp. Copy a selection in the osu! editor first.".to_string())
+    } else if file_path.is_none() {
+        Some("No map is being edited. Open one in the osu! editor from mosu first.".to_string())
+    } else {
+        None
+    };
+    let _ = app_handle.emit("editor-timestamp-captured", EditorTimestampCapturedEvent { file_path, timestamp, error });
+}
+
+/// Register the `timestampShortcut` setting (an accelerator like `CommandOrControl+Alt+N`) as
+/// the global capture shortcut. It is opt-in: unset or empty, no shortcut is taken.
+fn apply_shortcut_settings(app_handle: &tauri::AppHandle, settings: &SettingsMap) {
+    use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+    let shortcut = settings
+        .get("timestampShortcut")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    let Ok(mut registered) = TIMESTAMP_SHORTCUT.lock() else {
+        return;
+    };
+    if registered.as_deref() == Some(shortcut.as_str()) {
+        return;
+    }
+
+    if let Some(previous) = registered.take() {
+        let _ = app_handle.global_shortcut().unregister(previous.as_str());
+    }
+    if shortcut.is_empty() {
+        return;
+    }
+    match app_handle.global_shortcut().register(shortcut.as_str()) {
+        Ok(()) => *registered = Some(shortcut),
+        Err(err) => eprintln!("failed to register the {shortcut} shortcut: {err}"),
+    }
+}
+
+/// How often the osu! window title is checked for the map being played or edited.
+const NOW_PLAYING_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct NowPlayingEvent {
+    running: bool,
+    /// Map part of the window title, e.g. `Artist - Title [Diff]`; empty in menus.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    title: String,
+    /// Library map the title names, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_path: Option<String>,
+    /// The title is the editor's, which ends in `.osu`.
+    editing: bool,
+}
+
+/// Last state sent as `osu-now-playing`.
+static NOW_PLAYING: Mutex<Option<NowPlayingEvent>> = Mutex::new(None);
+
+/// Window title of a running osu! (`osu!.exe`) process. Lazer's process has the same name but
+/// its title never names the map.
+#[cfg(target_os = "windows")]
+fn osu_process_window_title() -> Option<String> {
+    use windows_sys::Win32::Foundation::{CloseHandle, BOOL, HWND, LPARAM};
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
+    };
+
+    unsafe extern "system" fn visit(window: HWND, found: LPARAM) -> BOOL {
+        let found = &mut *(found as *mut Option<String>);
+        if IsWindowVisible(window) == 0 {
+            return 1;
+        }
+        let mut process_id = 0;
+        GetWindowThreadProcessId(window, &mut process_id);
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, process_id);
+        if process.is_null() {
+            return 1;
+        }
+        let mut image_name = [0u16; 1024];
+        let mut image_name_length = image_name.len() as u32;
+        let queried = QueryFullProcessImageNameW(process, 0, image_name.as_mut_ptr(), &mut image_name_length);
+        CloseHandle(process);
+        if queried == 0 {
+            return 1;
+        }
+        let exe = String::from_utf16_lossy(&image_name[..image_name_length as usize]);
+        if !exe.to_ascii_lowercase().ends_with("\\osu!.exe") {
+            return 1;
+        }
+
+        let mut title = [0u16; 512];
+        let title_length = GetWindowTextW(window, title.as_mut_ptr(), title.len() as i32);
+        if title_length <= 0 {
+            return 1;
+        }
+        *found = Some(String::from_utf16_lossy(&title[..title_length as usize]));
+        0
+    }
+
+    let mut found: Option<String> = None;
+    unsafe { EnumWindows(Some(visit), &mut found as *mut Option<String> as LPARAM) };
+    found
+}
+
+#[cfg(not(target_os = "windows"))]
+fn osu_process_window_title() -> Option<String> {
+    None
+}
+
+/// Read osu!'s state, reusing `previous`'s map while the title is unchanged since resolving it
+/// walks the whole library cache.
+fn detect_now_playing(previous: Option<&NowPlayingEvent>) -> NowPlayingEvent {
+    let Some(window_title) = osu_process_window_title() else {
+        return NowPlayingEvent::default();
+    };
+    let title = osu_window_map_text(&window_title).unwrap_or_default().to_string();
+    let file_path = match previous.filter(|previous| previous.running && previous.title == title) {
+        Some(previous) => previous.file_path.clone(),
+        None => {
+            let tracked = TRACKED_EDITOR_MAP.lock().ok().and_then(|tracked| tracked.clone());
+            map_for_osu_window_title(&window_title, tracked.as_deref())
+        }
+    };
+    NowPlayingEvent {
+        running: true,
+        file_path,
+        editing: title.ends_with(".osu"),
+        title,
+    }
+}
+
+/// Follow osu! stable's window title and emit `osu-now-playing` whenever the running state or
+/// the open map changes, so the renderer can focus the same map. Only Windows exposes other
+/// processes' windows, so elsewhere nothing is started.
+fn start_now_playing_watcher(app_handle: tauri::AppHandle) {
+    if !cfg!(target_os = "windows") {
+        return;
+    }
+    std::thread::spawn(move || loop {
+        let previous = NOW_PLAYING.lock().ok().and_then(|last| last.clone());
+        let current = detect_now_playing(previous.as_ref());
+        let changed = {
+            let mut last = NOW_PLAYING.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            let changed = last.as_ref() != Some(&current);
+            *last = Some(current.clone());
+            changed
+        };
+        if changed {
+            let _ = app_handle.emit("osu-now-playing", current);
+        }
+        std::thread::sleep(NOW_PLAYING_POLL_INTERVAL);
+    });
+}
+
+/// What osu! currently has open, as last reported by `osu-now-playing`.
+#[tauri::command]
+fn get_now_playing() -> Result<NowPlayingEvent, String> {
+    Ok(NOW_PLAYING.lock().map_err(|err| err.to_string())?.clone().unwrap_or_default())
+}
+
+/// A lint result or a note the user flagged on the timeline.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ModPostIssue {
+    message: String,
+    #[serde(default)]
+    time: Option<i32>,
+    /// Combo numbers (or mania `time|column`) to put in the timestamp.
+    #[serde(default)]
+    objects: Vec<String>,
+    #[serde(default)]
+    severity: Option<LintSeverity>,
+    /// Difficulty name to group under; issues without one go to "General".
+    #[serde(default)]
+    difficulty: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ModPostFormat {
+    /// osu! forum BBCode.
+    Bbcode,
+    /// Beatmap discussion (modding v2) Markdown.
+    Markdown,
+}
+
+fn mod_post_severity_label(severity: Option<LintSeverity>) -> Option<&'static str> {
+    match severity? {
+        LintSeverity::Error => Some("problem"),
+        LintSeverity::Warning => Some("suggestion"),
+        LintSeverity::Info => None,
+    }
+}
+
+fn render_mod_post(issues: &[ModPostIssue], format: ModPostFormat) -> String {
+    let mut groups: BTreeMap<&str, Vec<&ModPostIssue>> = BTreeMap::new();
+    for issue in issues {
+        groups.entry(issue.difficulty.as_deref().unwrap_or("General")).or_default().push(issue);
+    }
+    // "General" leads, the difficulties follow in name order.
+    let mut ordered: Vec<(&str, Vec<&ModPostIssue>)> = groups.into_iter().collect();
+    ordered.sort_by_key(|(difficulty, _)| *difficulty != "General");
+
+    let mut post = String::new();
+    for (difficulty, mut group) in ordered {
+        group.sort_by_key(|issue| issue.time.unwrap_or(-1));
+        match format {
+            ModPostFormat::Bbcode => post.push_str(&format!("[b]{difficulty}[/b]\n[list]\n")),
+            ModPostFormat::Markdown => post.push_str(&format!("## {difficulty}\n\n")),
+        }
+        for issue in group {
+            let label = mod_post_severity_label(issue.severity);
+            let timestamp = issue.time.map(|time| format_editor_timestamp_text(time, &issue.objects));
+            let link = timestamp.as_deref().map(|timestamp| {
+                let timestamp = timestamp.trim_end_matches(" - ");
+                match format {
+                    ModPostFormat::Bbcode => format!("[url={}]{timestamp}[/url] - ", editor_edit_url(timestamp)),
+                    ModPostFormat::Markdown => format!("[{timestamp}]({}) - ", editor_edit_url(timestamp)),
+                }
+            });
+            let line = match format {
+                ModPostFormat::Bbcode => format!(
+                    "[*]{}{}{}\n",
+                    label.map(|label| format!("[b]{label}[/b] ")).unwrap_or_default(),
+                    link.unwrap_or_default(),
+                    issue.message
+                ),
+                ModPostFormat::Markdown => format!(
+                    "- {}{}{}\n",
+                    label.map(|label| format!("**{label}** ")).unwrap_or_default(),
+                    link.unwrap_or_default(),
+                    issue.message
+                ),
+            };
+            post.push_str(&line);
+        }
+        match format {
+            ModPostFormat::Bbcode => post.push_str("[/list]\n\n"),
+            ModPostFormat::Markdown => post.push('\n'),
+        }
+    }
+    post.trim_end().to_string()
+}
+
+/// Render lint results or timeline notes as a mod post with clickable editor timestamps.
+#[tauri::command]
+fn export_mod_post(issues: Vec<ModPostIssue>, format: ModPostFormat) -> String {
+    render_mod_post(&issues, format)
+}
+
+fn read_osu_file_payload(path: &Path) -> Result<OsuFilePayload, String> {
+    let bytes = fs::read(path).map_err(|err| err.to_string())?;
+    let mtime_ms = get_mtime_ms(path)?;
+    let (content, decode_warnings) = decode_osu_bytes(&bytes);
+    Ok(OsuFilePayload {
+        file_path: path.to_string_lossy().to_string(),
+        content: content.into_owned(),
+        stat: FileStatPayload { mtime_ms },
+        beatmap_hash: Some(compute_osu_md5_hex(&bytes)),
+        decode_warnings: (!decode_warnings.is_empty()).then_some(decode_warnings),
+    })
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+/// `parent/name`, or `parent/name (2)`, `parent/name (3)`, ... when that already exists.
+fn unused_folder_path(parent: &Path, name: &str) -> PathBuf {
+    let mut folder = parent.join(name);
+    let mut suffix = 2;
+    while folder.exists() {
+        folder = parent.join(format!("{name} ({suffix})"));
+        suffix += 1;
+    }
+    folder
+}
+
+/// Extract an .osz next to itself, into a folder named after the archive like osu! does, and
+/// read the difficulties it contained. An existing folder of that name is left alone and the
+/// archive goes into a numbered one instead. Entries are streamed to disk, so the sizes claimed
+/// in the zip headers are never trusted for allocations.
+fn extract_osz_archive(archive_path: &Path) -> Result<OszImportPayload, String> {
+    let stem = archive_path
+        .file_stem()
+        .map(|stem| sanitize_file_name(&stem.to_string_lossy()))
+        .filter(|stem| !stem.is_empty())
+        .ok_or_else(|| "invalid .osz path".to_string())?;
+    let parent = archive_path.parent().ok_or_else(|| "invalid .osz path".to_string())?;
+    extract_osz_archive_into(archive_path, parent, &stem)
+}
+
+/// Extract into a new folder named `folder_name` (or `folder_name (2)`, ...) under `parent`.
+fn extract_osz_archive_into(archive_path: &Path, parent: &Path, folder_name: &str) -> Result<OszImportPayload, String> {
+    let folder = unused_folder_path(parent, folder_name);
+
+    let file = fs::File::open(archive_path).map_err(|err| err.to_string())?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|err| err.to_string())?;
+    let mut osu_paths = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|err| err.to_string())?;
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let target = folder.join(&name);
+        if entry.is_dir() {
+            fs::create_dir_all(&target).map_err(|err| err.to_string())?;
+            continue;
+        }
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        let mut output = fs::File::create(&target).map_err(|err| err.to_string())?;
+        std::io::copy(&mut entry, &mut output).map_err(|err| err.to_string())?;
+        if has_extension(&target, "osu") {
+            osu_paths.push(target);
+        }
+    }
+
+    if osu_paths.is_empty() {
+        return Err("the archive does not contain any .osu files".to_string());
+    }
+    osu_paths.sort();
+    let files = osu_paths
+        .iter()
+        .map(|path| read_osu_file_payload(path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(OszImportPayload {
+        archive_path: archive_path.to_string_lossy().to_string(),
+        folder_path: folder.to_string_lossy().to_string(),
+        files,
+    })
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8, String> {
+    let mut buf = [0_u8; 1];
+    reader.read_exact(&mut buf).map_err(|err| err.to_string())?;
+    Ok(buf[0])
+}
+
+fn read_u16_le<R: Read>(reader: &mut R) -> Result<u16, String> {
+    let mut buf = [0_u8; 2];
+    reader.read_exact(&mut buf).map_err(|err| err.to_string())?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_i64_le<R: Read>(reader: &mut R) -> Result<i64, String> {
+    let mut buf = [0_u8; 8];
+    reader.read_exact(&mut buf).map_err(|err| err.to_string())?;
+    Ok(i64::from_le_bytes(buf))
+}
+
+/// .NET `DateTime` ticks at the Unix epoch.
+const DOTNET_EPOCH_TICKS: i64 = 621_355_968_000_000_000;
+
+fn dotnet_ticks_to_unix_ms(ticks: i64) -> i64 {
+    (ticks - DOTNET_EPOCH_TICKS) / 10_000
+}
+
+fn score_accuracy(mode: u8, counts: &OsuScoreCounts) -> f64 {
+    let [n300, n100, n50, geki, katu, miss] = [
+        counts.count_300,
+        counts.count_100,
+        counts.count_50,
+        counts.count_geki,
+        counts.count_katu,
+        counts.count_miss,
+    ]
+    .map(f64::from);
+    let (earned, possible) = match mode {
+        1 => (n300 + 0.5 * n100, n300 + n100 + miss),
+        2 => (n300 + n100 + n50, n300 + n100 + n50 + katu + miss),
+        3 => (
+            300.0 * (n300 + geki) + 200.0 * katu + 100.0 * n100 + 50.0 * n50,
+            300.0 * (n300 + geki + katu + n100 + n50 + miss),
+        ),
+        _ => (300.0 * n300 + 100.0 * n100 + 50.0 * n50, 300.0 * (n300 + n100 + n50 + miss)),
+    };
+    if possible > 0.0 {
+        earned / possible
+    } else {
+        0.0
+    }
+}
+
+/// Read the score record shared by .osr headers and `scores.db` entries, up to and including
+/// the mods. Callers read the format-specific tail.
+fn read_osu_score_record<R: Read>(reader: &mut R) -> Result<ReplayInfoPayload, String> {
+    let mode = read_u8(reader)?;
+    let game_version = read_i32_le(reader)?;
+    let beatmap_hash = read_osu_string(reader)?.unwrap_or_default();
+    let player_name = read_osu_string(reader)?.unwrap_or_default();
+    let replay_hash = read_osu_string(reader)?.unwrap_or_default();
+    let counts = OsuScoreCounts {
+        count_300: read_u16_le(reader)?,
+        count_100: read_u16_le(reader)?,
+        count_50: read_u16_le(reader)?,
+        count_geki: read_u16_le(reader)?,
+        count_katu: read_u16_le(reader)?,
+        count_miss: read_u16_le(reader)?,
+    };
+    let score = read_i32_le(reader)?;
+    let max_combo = read_u16_le(reader)?;
+    let perfect = read_u8(reader)? != 0;
+    let mods = read_i32_le(reader)? as u32;
+
+    Ok(ReplayInfoPayload {
+        file_path: String::new(),
+        mode,
+        game_version,
+        beatmap_hash,
+        player_name,
+        replay_hash,
+        accuracy: score_accuracy(mode, &counts),
+        counts,
+        score,
+        max_combo,
+        perfect,
+        mods,
+        mods_acronym: mods_acronym(mods),
+        played_at_ms: 0,
+    })
+}
+
+/// Parse the header of an .osr replay; the compressed frame data is not read.
+fn parse_replay_header(bytes: &[u8]) -> Result<ReplayInfoPayload, String> {
+    let mut reader = std::io::Cursor::new(bytes);
+    let mut replay = read_osu_score_record(&mut reader)?;
+    let _life_bar = read_osu_string(&mut reader)?;
+    replay.played_at_ms = dotnet_ticks_to_unix_ms(read_i64_le(&mut reader)?);
+    Ok(replay)
+}
+
+/// Legacy mod bit for Target Practice; its scores carry an extra accuracy value.
+const MOD_TARGET_PRACTICE: u32 = 1 << 23;
+
+struct LocalScoresCache {
+    path: PathBuf,
+    mtime_ms: f64,
+    scores: HashMap<String, Vec<ReplayInfoPayload>>,
+}
+
+static LOCAL_SCORES_CACHE: Mutex<Option<LocalScoresCache>> = Mutex::new(None);
+
+/// Parse osu!stable's `scores.db` into plays keyed by beatmap MD5, best score first.
+fn parse_scores_db(bytes: &[u8]) -> Result<HashMap<String, Vec<ReplayInfoPayload>>, String> {
+    let mut reader = std::io::Cursor::new(bytes);
+    let _version = read_i32_le(&mut reader)?;
+    let beatmap_count = read_i32_le(&mut reader)?.max(0);
+    // Counts come from the file, so capacity is capped at what the remaining bytes can hold:
+    // a beatmap entry takes at least 5 bytes and a score far more than 32.
+    let remaining = |reader: &std::io::Cursor<&[u8]>| bytes.len().saturating_sub(reader.position() as usize);
+    let mut scores = HashMap::with_capacity((beatmap_count as usize).min(remaining(&reader) / 5));
+    for _ in 0..beatmap_count {
+        let beatmap_hash = read_osu_string(&mut reader)?.unwrap_or_default();
+        let score_count = read_i32_le(&mut reader)?.max(0);
+        let mut plays = Vec::with_capacity((score_count as usize).min(remaining(&reader) / 32));
+        for _ in 0..score_count {
+            let mut play = read_osu_score_record(&mut reader)?;
+            let _life_bar = read_osu_string(&mut reader)?;
+            play.played_at_ms = dotnet_ticks_to_unix_ms(read_i64_le(&mut reader)?);
+            let _marker = read_i32_le(&mut reader)?;
+            let _online_score_id = read_i64_le(&mut reader)?;
+            if play.mods & MOD_TARGET_PRACTICE != 0 {
+                let mut accuracy = [0_u8; 8];
+                reader.read_exact(&mut accuracy).map_err(|err| err.to_string())?;
+            }
+            plays.push(play);
+        }
+        plays.sort_by_key(|play| std::cmp::Reverse(play.score));
+        scores.insert(beatmap_hash.to_ascii_lowercase(), plays);
+    }
+    Ok(scores)
+}
+
+/// `scores.db` next to the configured Songs folder, or in the detected stable install.
+fn find_scores_db(app_handle: &tauri::AppHandle) -> Option<PathBuf> {
+    let configured = read_native_setting(app_handle, "stableSongsDir")
+        .and_then(|dir| dir.as_str().map(PathBuf::from))
+        .and_then(|songs| songs.parent().map(|install| install.join("scores.db")));
+    configured
+        .into_iter()
+        .chain(detect_osu_install_paths(app_handle).stable_install.map(|dir| Path::new(&dir).join("scores.db")))
+        .find(|path| path.is_file())
+}
+
+/// The user's local osu!stable plays of one difficulty, best score first. Re-reads
+/// `scores.db` only when it has changed since the last call.
+#[tauri::command]
+async fn get_local_scores(app_handle: tauri::AppHandle, beatmap_md5: String) -> Result<Vec<ReplayInfoPayload>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(path) = find_scores_db(&app_handle) else {
+            return Ok(Vec::new());
+        };
+        let mtime_ms = get_mtime_ms(&path)?;
+        let mut cache = LOCAL_SCORES_CACHE.lock().map_err(|err| err.to_string())?;
+        let fresh = cache
+            .as_ref()
+            .is_some_and(|cached| cached.path == path && cached.mtime_ms == mtime_ms);
+        if !fresh {
+            let bytes = fs::read(&path).map_err(|err| err.to_string())?;
+            *cache = Some(LocalScoresCache {
+                scores: parse_scores_db(&bytes)?,
+                path,
+                mtime_ms,
+            });
+        }
+        Ok(cache
+            .as_ref()
+            .and_then(|cached| cached.scores.get(&beatmap_md5.to_ascii_lowercase()))
+            .cloned()
+            .unwrap_or_default())
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Ingest dropped paths the same way the file dialogs do: .osu files are read, .osz archives
+/// extracted and .osr replays parsed. Anything else is ignored.
+fn ingest_dropped_files(paths: &[PathBuf]) -> FilesDroppedEvent {
+    let mut event = FilesDroppedEvent::default();
+    let mut osu_files = Vec::new();
+    for path in paths {
+        let result = if has_extension(path, "osu") {
+            read_osu_file_payload(path).map(|file| osu_files.push(file))
+        } else if has_extension(path, "osz") {
+            extract_osz_archive(path).map(|import| event.imports.push(import))
+        } else if has_extension(path, "osr") {
+            fs::read(path)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| parse_replay_header(&bytes))
+                .map(|replay| {
+                    event.replays.push(ReplayInfoPayload {
+                        file_path: path.to_string_lossy().to_string(),
+                        ..replay
+                    })
+                })
+        } else {
+            continue;
+        };
+        if let Err(error) = result {
+            event.errors.push(DroppedFileError {
+                file_path: path.to_string_lossy().to_string(),
+                error,
+            });
+        }
+    }
+    if !osu_files.is_empty() {
+        event.osu = Some(OpenOsuFilePayload { files: osu_files });
+    }
+    event
+}
+
+#[tauri::command]
+fn import_osz() -> Result<Vec<OszImportPayload>, String> {
+    let Some(archives) = rfd::FileDialog::new()
+        .add_filter("osu! beatmap archive", &["osz"])
+        .set_title("Select beatmap archives (.osz)")
+        .pick_files()
+    else {
+        return Ok(Vec::new());
+    };
+
+    archives.iter().map(|path| extract_osz_archive(path)).collect()
+}
+
+const BEATMAP_MIRROR_URL: &str = "https://catboy.best";
+
+/// URLs mosu was launched with, handed to the renderer once it is ready to listen.
+static LAUNCH_DEEP_LINKS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq)]
+enum DeepLinkTarget {
+    Path(PathBuf),
+    BeatmapSet(u64),
+    Beatmap(u64),
+}
+
+/// Understands `mosu://open?path=…`, `mosu://set/<id>`, the osu!direct-style `mosu://s/<id>`,
+/// `mosu://dl/<id>`, `mosu://b/<id>` (and the same under `osu://`), plus osu! website beatmap URLs.
+fn parse_deep_link(url: &str) -> Option<DeepLinkTarget> {
+    let url = reqwest::Url::parse(url.trim()).ok()?;
+    let mut segments: Vec<String> = url
+        .host_str()
+        .into_iter()
+        .map(str::to_string)
+        .chain(url.path_segments().into_iter().flatten().map(str::to_string))
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    match url.scheme() {
+        "mosu" | "osu" => {}
+        "http" | "https" if url.host_str().is_some_and(|host| host.ends_with("osu.ppy.sh")) => {
+            // https://osu.ppy.sh/beatmapsets/<set>#osu/<map>, /b/<map>, /beatmaps/<map>
+            segments.remove(0);
+            if segments.first().map(String::as_str) == Some("beatmapsets") {
+                let fragment_map = url
+                    .fragment()
+                    .and_then(|fragment| fragment.rsplit('/').next())
+                    .and_then(|id| id.parse().ok());
+                if let Some(map_id) = fragment_map {
+                    return Some(DeepLinkTarget::Beatmap(map_id));
+                }
+            }
+        }
+        _ => return None,
+    }
+
+    let id = segments.get(1).and_then(|id| id.parse::<u64>().ok());
+    match segments.first()?.to_ascii_lowercase().as_str() {
+        "open" => url
+            .query_pairs()
+            .find(|(key, _)| key == "path")
+            .map(|(_, path)| DeepLinkTarget::Path(PathBuf::from(path.as_ref()))),
+        "set" | "s" | "dl" | "d" | "beatmapsets" => id.map(DeepLinkTarget::BeatmapSet),
+        "b" | "beatmap" | "beatmaps" => id.map(DeepLinkTarget::Beatmap),
+        _ => None,
+    }
+}
+
+/// Where downloaded sets go: the configured stable Songs folder, the detected one, or a
+/// downloads folder in app data when osu!stable isn't around.
+fn deep_link_download_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let configured = read_native_setting(app_handle, "stableSongsDir")
+        .and_then(|dir| dir.as_str().map(PathBuf::from))
+        .filter(|dir| dir.is_dir());
+    if let Some(dir) = configured {
+        return Ok(dir);
+    }
+    if let Some(songs) = detect_osu_install_paths(app_handle).stable_songs {
+        return Ok(PathBuf::from(songs));
+    }
+    let downloads = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|err| err.to_string())?
+        .join("downloads");
+    fs::create_dir_all(&downloads).map_err(|err| err.to_string())?;
+    Ok(downloads)
+}
+
+/// An already extracted set folder, named `<id>` or `<id> Artist - Title` like osu! does.
+fn find_beatmap_set_folder(songs_dir: &Path, set_id: u64) -> Option<PathBuf> {
+    let exact = set_id.to_string();
+    let prefix = format!("{set_id} ");
+    fs::read_dir(songs_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .find(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            entry.path().is_dir() && (name == exact || name.starts_with(&prefix))
+        })
+        .map(|entry| entry.path())
+}
+
+async fn resolve_beatmap_set_id(client: &reqwest::Client, beatmap_id: u64) -> Result<u64, String> {
+    let request = client
+        .get(format!("{BEATMAP_MIRROR_URL}/api/v2/b/{beatmap_id}"))
+        .header("User-Agent", "mosu-app");
+    let response = send_with_retry(request).await?;
+    if !response.status().is_success() {
+        return Err(format!("Beatmap {beatmap_id} was not found ({})", response.status()));
+    }
+    let json: Value = response.json().await.map_err(|err| err.to_string())?;
+    json.get("beatmapset_id")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| format!("Beatmap {beatmap_id} has no beatmap set"))
+}
+
+/// Largest .osz a link may download; ranked sets with video stay well below this.
+const BEATMAP_SET_MAX_DOWNLOAD_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Distinguishes downloads of the same set started in the same millisecond.
+static NEXT_BEATMAP_SET_DOWNLOAD: AtomicUsize = AtomicUsize::new(0);
+
+/// Download set `set_id` to a temporary .osz under the app data folder and extract it into
+/// `songs_dir`.
+async fn download_beatmap_set(client: &reqwest::Client, songs_dir: &Path, set_id: u64) -> Result<OszImportPayload, String> {
+    let request = client
+        .get(format!("{BEATMAP_MIRROR_URL}/d/{set_id}"))
+        .header("User-Agent", "mosu-app");
+    let mut response = send_with_retry(request).await?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to download beatmap set {set_id} ({})", response.status()));
+    }
+    let too_large = || {
+        let limit_mb = BEATMAP_SET_MAX_DOWNLOAD_BYTES / 1024 / 1024;
+        format!("Beatmap set {set_id} is larger than {limit_mb} MB")
+    };
+    if response.content_length().is_some_and(|length| length > BEATMAP_SET_MAX_DOWNLOAD_BYTES) {
+        return Err(too_large());
+    }
+    // Prefer the mirror's "<id> Artist - Title.osz" so the folder matches what osu! would create.
+    let folder_name = response
+        .headers()
+        .get(reqwest::header::CONTENT_DISPOSITION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split("filename=").nth(1))
+        .map(|name| sanitize_file_name(name.split(';').next().unwrap_or_default().trim().trim_matches('"')))
+        .filter(|name| name.to_ascii_lowercase().ends_with(".osz"))
+        .map(|name| name[..name.len() - ".osz".len()].to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| set_id.to_string());
+
+    // Not `downloads`, which may itself be the Songs folder the set is extracted into.
+    let download_dir = LIBRARY_CACHE_DIR
+        .get()
+        .ok_or_else(|| "app data folder unavailable".to_string())?
+        .join("partial-downloads");
+    fs::create_dir_all(&download_dir).map_err(|err| err.to_string())?;
+    let sequence = NEXT_BEATMAP_SET_DOWNLOAD.fetch_add(1, Ordering::Relaxed);
+    let archive_path = download_dir.join(format!("{set_id}-{}-{sequence}.osz", unix_time_ms()));
+
+    let mut file = fs::File::create(&archive_path).map_err(|err| err.to_string())?;
+    let mut downloaded_bytes = 0u64;
+    let downloaded = loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break Ok(()),
+            Err(err) => break Err(err.to_string()),
+        };
+        downloaded_bytes += chunk.len() as u64;
+        if downloaded_bytes > BEATMAP_SET_MAX_DOWNLOAD_BYTES {
+            break Err(too_large());
+        }
+        if let Err(err) = file.write_all(&chunk) {
+            break Err(err.to_string());
+        }
+    };
+    drop(file);
+    if let Err(err) = downloaded {
+        let _ = fs::remove_file(&archive_path);
+        return Err(err);
+    }
+
+    let songs_dir = songs_dir.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        let imported = extract_osz_archive_into(&archive_path, &songs_dir, &folder_name);
+        let _ = fs::remove_file(&archive_path);
+        imported
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// Local paths a link may open: absolute, not a network share or device path, and inside one of
+/// the registered library roots once symlinks and `..` are resolved.
+fn validate_deep_link_path(app_handle: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    let raw = path.to_string_lossy();
+    if raw.starts_with(r"\\") || raw.starts_with("//") || !path.is_absolute() {
+        return Err(format!("Links can only open local library paths, not {raw}"));
+    }
+    let canonical = fs::canonicalize(path).map_err(|err| err.to_string())?;
+    let inside_library = load_library_roots(&library_roots_path(app_handle)?)
+        .iter()
+        .filter_map(|root| fs::canonicalize(&root.path).ok())
+        .any(|root| canonical.starts_with(root));
+    if !inside_library {
+        return Err(format!("{raw} is not inside a library folder"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum DeepLinkActionKind {
+    /// Reads maps already on disk.
+    Open,
+    /// Unpacks a local .osz into the Songs folder.
+    Extract,
+    /// Fetches a set from the mirror unless it is already in the Songs folder.
+    Download,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeepLinkAction {
+    kind: DeepLinkActionKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beatmap_set_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beatmap_id: Option<u64>,
+}
+
+fn deep_link_action(app_handle: &tauri::AppHandle, url: &str) -> Result<(DeepLinkTarget, DeepLinkAction), String> {
+    let target = parse_deep_link(url).ok_or_else(|| format!("Unsupported link: {url}"))?;
+    let action = match &target {
+        DeepLinkTarget::Path(path) => {
+            validate_deep_link_path(app_handle, path)?;
+            let kind = if has_extension(path, "osz") { DeepLinkActionKind::Extract } else { DeepLinkActionKind::Open };
+            DeepLinkAction { kind, path: Some(path.to_string_lossy().to_string()), beatmap_set_id: None, beatmap_id: None }
+        }
+        DeepLinkTarget::BeatmapSet(set_id) => DeepLinkAction {
+            kind: DeepLinkActionKind::Download,
+            path: None,
+            beatmap_set_id: Some(*set_id),
+            beatmap_id: None,
+        },
+        DeepLinkTarget::Beatmap(beatmap_id) => DeepLinkAction {
+            kind: DeepLinkActionKind::Download,
+            path: None,
+            beatmap_set_id: None,
+            beatmap_id: Some(*beatmap_id),
+        },
+    };
+    Ok((target, action))
+}
+
+/// What opening `url` would do, so the renderer can ask before anything is downloaded or
+/// extracted. Fails for unsupported links and paths outside the library.
+#[tauri::command]
+async fn describe_deep_link(app_handle: tauri::AppHandle, url: String) -> Result<DeepLinkAction, String> {
+    tauri::async_runtime::spawn_blocking(move || deep_link_action(&app_handle, &url).map(|(_, action)| action))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+/// Resolve a deep link into the same shape as a file drop. Set/beatmap links download the set
+/// first unless it is already in the Songs folder. Links that download or extract anything are
+/// refused unless the user `confirmed` them.
+#[tauri::command]
+async fn open_deep_link(
+    app_handle: tauri::AppHandle,
+    url: String,
+    confirmed: Option<bool>,
+) -> Result<FilesDroppedEvent, String> {
+    let (target, action) = {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn_blocking(move || deep_link_action(&app_handle, &url))
+            .await
+            .map_err(|err| err.to_string())??
+    };
+    if action.kind != DeepLinkActionKind::Open && !confirmed.unwrap_or(false) {
+        return Err("This link needs to be confirmed before mosu downloads or extracts anything".to_string());
+    }
+
+    let set_id = match target {
+        DeepLinkTarget::Path(path) => {
+            return tauri::async_runtime::spawn_blocking(move || {
+                let paths = if path.is_dir() {
+                    list_osu_files_in_folder(&path)?
+                } else {
+                    vec![path]
+                };
+                Ok(ingest_dropped_files(&paths))
+            })
+            .await
+            .map_err(|err| err.to_string())?;
+        }
+        DeepLinkTarget::BeatmapSet(set_id) => set_id,
+        DeepLinkTarget::Beatmap(beatmap_id) => {
+            let client = http_client(Duration::from_secs(15))?;
+            resolve_beatmap_set_id(&client, beatmap_id).await?
+        }
+    };
+
+    let songs_dir = {
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn_blocking(move || deep_link_download_dir(&app_handle))
+            .await
+            .map_err(|err| err.to_string())??
+    };
+    if let Some(folder) = find_beatmap_set_folder(&songs_dir, set_id) {
+        return tauri::async_runtime::spawn_blocking(move || {
+            Ok(ingest_dropped_files(&list_osu_files_in_folder(&folder)?))
+        })
+        .await
+        .map_err(|err| err.to_string())?;
+    }
+
+    let client = http_client(Duration::from_secs(300))?;
+    let imported = download_beatmap_set(&client, &songs_dir, set_id).await?;
+    Ok(FilesDroppedEvent {
+        imports: vec![imported],
+        ..FilesDroppedEvent::default()
+    })
+}
+
+/// Links mosu was started with. Links arriving later are emitted as `deep-link` events.
+#[tauri::command]
+fn take_launch_deep_links() -> Vec<String> {
+    LAUNCH_DEEP_LINKS
+        .lock()
+        .map(|mut links| std::mem::take(&mut *links))
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn open_osu_file() -> Option<OpenOsuFilePayload> {
+    let files = rfd::FileDialog::new()
+        .add_filter("osu! beatmap", &["osu"])
+        .set_title("Select a beatmap (.osu) file")
+        .pick_files()?;
+
+    let results: Vec<OsuFilePayload> = files
+        .iter()
+        .filter_map(|path| read_osu_file_payload(path).ok())
+        .collect();
+
+    if results.is_empty() {
+        None
+    } else {
+        Some(OpenOsuFilePayload { files: results })
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn scan_directory_osu_files(
+    window: tauri::Window,
+    webview: tauri::Webview,
+    dir_path: String,
+    mapper_name: Option<String>,
+    known_files: Option<HashMap<String, f64>>,
+    client_type: Option<String>,
+    options: Option<ScanOptions>,
+    on_batch: Option<tauri::ipc::JavaScriptChannelId>,
+) -> ScanDirectoryPayload {
+    let dir_clone = dir_path.clone();
+    let fallback_dir = dir_path.clone();
+    let client = OsuClient::from_option(client_type);
+    let mut options = options.unwrap_or_default();
+    options.batch_channel = on_batch.map(|id| ScanBatchChannel::new(id.channel_on(webview)));
+    let mapper_name = expand_mapper_filter(mapper_name, &options).await;
+    // Use streaming: send batches over onBatch (or scan-batch events), return empty payload
+    // The renderer listens for scan-complete to know the scan is done
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_directory_streaming(&dir_clone, mapper_name, known_files, client, &options, &window);
+    })
+    .await
+    .ok();
+    ScanDirectoryPayload {
+        files: vec![],
+        directory: fallback_dir,
+    }
+}
+
+#[tauri::command]
+async fn scan_file_list(
+    window: tauri::Window,
+    paths: Vec<String>,
+    mapper_name: Option<String>,
+    known_files: Option<HashMap<String, f64>>,
+    options: Option<ScanOptions>,
+) -> ScanDirectoryPayload {
+    let options = options.unwrap_or_default();
+    let mapper_name = expand_mapper_filter(mapper_name, &options).await;
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_file_list_streaming(paths, mapper_name, known_files, &options, &window);
+    })
+    .await
+    .ok();
+    ScanDirectoryPayload {
+        files: vec![],
+        directory: String::new(),
+    }
+}
+
+/// Incremental rescan against the persistent library cache; see `rescan_changed_streaming`.
+#[tauri::command]
+async fn rescan_changed(
+    window: tauri::Window,
+    webview: tauri::Webview,
+    dir_path: String,
+    mapper_name: Option<String>,
+    client_type: Option<String>,
+    options: Option<ScanOptions>,
+    on_batch: Option<tauri::ipc::JavaScriptChannelId>,
+) -> ScanDirectoryPayload {
+    let client = OsuClient::from_option(client_type);
+    let mut options = options.unwrap_or_default();
+    options.batch_channel = on_batch.map(|id| ScanBatchChannel::new(id.channel_on(webview)));
+    let mapper_name = expand_mapper_filter(mapper_name, &options).await;
+    tauri::async_runtime::spawn_blocking(move || {
+        rescan_changed_streaming(&dir_path, mapper_name, client, &options, &window);
+    })
+    .await
+    .ok();
+    ScanDirectoryPayload {
+        files: vec![],
+        directory: String::new(),
+    }
+}
+
+/// Mark one more batch on a scan's `onBatch` channel as handled, letting the scan send on.
+#[tauri::command]
+fn ack_scan_batch(channel_id: u32) -> Result<(), String> {
+    if let Some(acked) = SCAN_CHANNEL_ACKS.lock().map_err(|err| err.to_string())?.get_mut(&channel_id) {
+        *acked += 1;
+        SCAN_CHANNEL_ACKED.notify_all();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn list_directory_osu_files(
+    window: tauri::Window,
+    dir_path: String,
+    mapper_name: Option<String>,
+    client_type: Option<String>,
+) -> ScanDirectoryPayload {
+    let dir_clone = dir_path.clone();
+    let fallback_dir = dir_path.clone();
+    let client = OsuClient::from_option(client_type);
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_directory_streaming(&dir_clone, mapper_name, Some(HashMap::new()), client, &ScanOptions::default(), &window);
+    })
+    .await
+    .ok();
+    ScanDirectoryPayload {
+        files: vec![],
+        directory: fallback_dir,
+    }
+}
+
+#[tauri::command]
+async fn open_mapper_osu_files(
+    window: tauri::Window,
+    mapper_name: String,
+    client_type: Option<String>,
+) -> Option<ScanDirectoryPayload> {
+    let client = OsuClient::from_option(client_type);
+    let dir = rfd::FileDialog::new()
+        .set_title(format!(
+            "{} to search for maps by \"{}\"",
+            if client == OsuClient::Lazer {
+                "Select the osu!lazer data folder"
+            } else {
+                "Select the Songs folder"
+            },
+            mapper_name,
+        ))
+        .pick_folder()?;
+
+    let dir_path = dir.to_string_lossy().to_string();
+    let fallback_dir = dir_path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_directory_streaming(
+            &dir_path,
+            Some(mapper_name),
+            Some(HashMap::new()),
+            client,
+            &ScanOptions::default(),
+            &window,
+        );
+    })
+    .await
+    .ok();
+    Some(ScanDirectoryPayload {
+        files: vec![],
+        directory: fallback_dir,
+    })
+}
+
+#[tauri::command]
+async fn open_folder_osu_files(
+    window: tauri::Window,
+    client_type: Option<String>,
+) -> Option<ScanDirectoryPayload> {
+    let client = OsuClient::from_option(client_type);
+    let dir = rfd::FileDialog::new()
+        .set_title(if client == OsuClient::Lazer {
+            "Select an osu!lazer data folder to scan for maps"
+        } else {
+            "Select a songs folder to scan for .osu files"
+        })
+        .pick_folder()?;
+
+    let dir_path = dir.to_string_lossy().to_string();
+    let fallback_dir = dir_path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        scan_directory_streaming(&dir_path, None, Some(HashMap::new()), client, &ScanOptions::default(), &window);
+    })
+    .await
+    .ok();
+    Some(ScanDirectoryPayload {
+        files: vec![],
+        directory: fallback_dir,
+    })
+}
+
+#[tauri::command]
+fn select_directory(title: Option<String>) -> Option<String> {
+    let dialog = rfd::FileDialog::new();
+    let dialog = if let Some(title) = title {
+        dialog.set_title(title)
+    } else {
+        dialog.set_title("Select Folder")
+    };
+
+    dialog
+        .pick_folder()
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn analysis_state(_is_analyzing: bool) {}
+
+#[tauri::command]
+fn window_minimize(window: tauri::Window) -> Result<(), String> {
+    let to_tray = read_native_setting(window.app_handle(), "minimizeToTray").and_then(|value| value.as_bool());
+    if to_tray == Some(true) {
+        window.hide().map_err(|err| err.to_string())
+    } else {
+        window.minimize().map_err(|err| err.to_string())
+    }
+}
+
+#[tauri::command]
+fn window_maximize(window: tauri::Window) -> Result<(), String> {
+    if window.is_maximized().map_err(|err| err.to_string())? {
+        window.unmaximize().map_err(|err| err.to_string())
+    } else {
+        window.maximize().map_err(|err| err.to_string())
+    }
+}
+
+#[tauri::command]
+fn window_close(window: tauri::Window) -> Result<(), String> {
+    window.close().map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+async fn embed_sync(url: String, api_key: String, data: Value) -> EmbedSyncPayload {
+    let client = http_client(Duration::from_secs(30));
+
+    let client = match client {
+        Ok(client) => client,
+        Err(err) => {
+            return EmbedSyncPayload {
+                success: false,
+                status: None,
+                data: None,
+                error: Some(err.to_string()),
+            }
+        }
+    };
+
+    let request = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {api_key}"))
+        .json(&data);
+    let response = send_with_retry(request).await;
+
+    match response {
+        Ok(resp) => {
+            let status = resp.status().as_u16();
+            let body = resp.json::<Value>().await.ok();
+            EmbedSyncPayload {
+                success: (200..300).contains(&status),
+                status: Some(status),
+                data: body,
+                error: None,
+            }
+        }
+        Err(err) => EmbedSyncPayload {
+            success: false,
+            status: None,
+            data: None,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+const EMBED_GRAPH_WIDTH: u32 = 600;
+const EMBED_GRAPH_HEIGHT: u32 = 120;
+const EMBED_GRAPH_BARS: usize = 120;
+const EMBED_GRAPH_FILE_NAME: &str = "density.png";
+
+/// The map an embed is about. Everything except the narrative text is read from the file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEmbedMap {
+    file_path: String,
+    /// Mapping progress, 0–100.
+    progress: f64,
+    #[serde(default)]
+    deadline: Option<String>,
+    /// Free text from the user, shown as the embed description.
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct ProgressEmbedStats {
+    total_maps: usize,
+    todo_count: usize,
+    completed_count: usize,
+    overall_progress: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct DiscordEmbedField {
+    name: String,
+    value: String,
+    inline: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DiscordEmbedImage {
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DiscordEmbedFooter {
+    text: String,
+}
+
+/// A Discord embed object, serialized with Discord's own field names.
+#[derive(Debug, Serialize)]
+struct DiscordEmbed {
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    color: u32,
+    fields: Vec<DiscordEmbedField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<DiscordEmbedImage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    footer: Option<DiscordEmbedFooter>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EmbedAttachment {
+    file_name: String,
+    content_type: String,
+    /// Base64-encoded file contents.
+    data: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEmbedPayload {
+    embed: DiscordEmbed,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attachment: Option<EmbedAttachment>,
+}
+
+/// Red → orange → yellow → green as the map nears completion.
+fn progress_embed_colour(progress: f64) -> u32 {
+    match progress {
+        p if p >= 100.0 => 0x2ECC71,
+        p if p >= 67.0 => 0xF1C40F,
+        p if p >= 34.0 => 0xE67E22,
+        _ => 0xE74C3C,
+    }
+}
+
+fn format_duration_ms(ms: i32) -> String {
+    let seconds = ms.max(0) / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Object density as a bar graph, with the part covered by `progress` highlighted.
+fn render_density_graph_png(hit_starts: &[i32], length_ms: i32, progress: f64) -> Result<Vec<u8>, String> {
+    use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+    let mut pixmap = Pixmap::new(EMBED_GRAPH_WIDTH, EMBED_GRAPH_HEIGHT).ok_or("Invalid graph size")?;
+    pixmap.fill(Color::from_rgba8(32, 34, 37, 255));
+
+    let bucket_ms = (length_ms / EMBED_GRAPH_BARS as i32).max(1);
+    let buckets = build_density_histogram(hit_starts, bucket_ms, EMBED_GRAPH_BARS);
+    let peak = buckets.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let bar_width = EMBED_GRAPH_WIDTH as f32 / EMBED_GRAPH_BARS as f32;
+    let done_bars = (progress.clamp(0.0, 100.0) / 100.0 * EMBED_GRAPH_BARS as f64).round() as usize;
+
+    for (index, count) in buckets.iter().enumerate() {
+        let height = *count as f32 / peak * (EMBED_GRAPH_HEIGHT as f32 - 8.0);
+        let Some(rect) = Rect::from_xywh(
+            index as f32 * bar_width + 1.0,
+            EMBED_GRAPH_HEIGHT as f32 - height,
+            (bar_width - 2.0).max(1.0),
+            height.max(1.0),
+        ) else {
+            continue;
+        };
+        let mut paint = Paint::default();
+        if index < done_bars {
+            paint.set_color_rgba8(255, 102, 170, 255);
+        } else {
+            paint.set_color_rgba8(114, 118, 125, 255);
+        }
+        pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+    }
+
+    pixmap.encode_png().map_err(|err| err.to_string())
+}
+
+/// 5x7 bitmap glyphs for image captions, one row per byte (low 5 bits, leftmost pixel first).
+/// Lowercase letters use the uppercase glyphs; anything else missing is drawn as `?`.
+const CAPTION_FONT: [(char, [u8; 7]); 59] = [
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('A', [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08]),
+    ('\'', [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00]),
+    ('"', [0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('!', [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04]),
+    ('?', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('[', [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E]),
+    (']', [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('&', [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D]),
+    ('+', [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F]),
+    ('*', [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00]),
+    ('%', [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03]),
+    ('#', [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A]),
+    ('~', [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00]),
+    ('<', [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02]),
+    ('>', [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08]),
+];
+
+/// Draw `text` in the caption font with its top-left corner at (`x`, `y`), cut off with `...`
+/// once it would pass `max_width`. Each font pixel becomes a `scale`-sized square.
+fn draw_caption(pixmap: &mut tiny_skia::Pixmap, text: &str, x: f32, y: f32, scale: f32, max_width: f32) {
+    use tiny_skia::{Paint, Rect, Transform};
+
+    let advance = 6.0 * scale;
+    let max_chars = (max_width / advance).floor().max(0.0) as usize;
+    let mut chars: Vec<char> = text.chars().map(|c| c.to_ascii_uppercase()).collect();
+    if chars.len() > max_chars {
+        chars.truncate(max_chars.saturating_sub(3));
+        chars.extend(['.', '.', '.']);
+    }
+
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(255, 255, 255, 255);
+    let fallback = CAPTION_FONT
+        .iter()
+        .find(|(glyph, _)| *glyph == '?')
+        .map(|(_, rows)| rows);
+    for (index, c) in chars.iter().enumerate() {
+        let Some(rows) = CAPTION_FONT
+            .iter()
+            .find(|(glyph, _)| glyph == c)
+            .map(|(_, rows)| rows)
+            .or(fallback)
+        else {
+            continue;
+        };
+        let left = x + index as f32 * advance;
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..5 {
+                if bits & (0x10 >> column) == 0 {
+                    continue;
+                }
+                if let Some(rect) = Rect::from_xywh(left + column as f32 * scale, y + row as f32 * scale, scale, scale)
+                {
+                    pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressImagePayload {
+    /// Where the PNG was written, when an output path was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// PNG as a data URL otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    width: u32,
+    height: u32,
+    progress: f64,
+}
+
+/// The whole song as a strip: object density on top of kiai and break bands, bookmark lines,
+/// a mapped/unmapped bar underneath and the map's title and completion above.
+fn render_progress_png(
+    file_path: &str,
+    parsed: &ParsedOsu,
+    width: u32,
+    height: u32,
+) -> Result<(Vec<u8>, f64), String> {
+    use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+    const KIAI: (u8, u8, u8, u8) = (255, 170, 60, 48);
+    const BREAK: (u8, u8, u8, u8) = (90, 140, 255, 48);
+    const BOOKMARK: (u8, u8, u8, u8) = (80, 160, 255, 200);
+    const MAPPED: (u8, u8, u8, u8) = (255, 102, 170, 255);
+    const UNMAPPED: (u8, u8, u8, u8) = (114, 118, 125, 255);
+
+    let duration_ms = click_track_duration_ms(file_path, parsed).max(1.0);
+    let (fraction, covered) = mapped_fraction(parsed, duration_ms, false);
+    let progress = fraction * 100.0;
+
+    let mut pixmap = Pixmap::new(width, height).ok_or("Invalid image size")?;
+    pixmap.fill(Color::from_rgba8(32, 34, 37, 255));
+    let (width_px, height_px) = (width as f32, height as f32);
+    let scale = (height_px / 60.0).floor().clamp(1.0, 4.0);
+    let coverage_top = height_px - (height_px * 0.06).max(4.0);
+    let graph_top = 7.0 * scale + 12.0;
+    let graph_bottom = coverage_top - 4.0;
+    let x_of = |time: f64| (time / duration_ms).clamp(0.0, 1.0) as f32 * width_px;
+    let fill = |pixmap: &mut Pixmap, left: f32, top: f32, right: f32, bottom: f32, (r, g, b, a): (u8, u8, u8, u8)| {
+        if let Some(rect) = Rect::from_ltrb(left, top, right.max(left + 1.0), bottom) {
+            let mut paint = Paint::default();
+            paint.set_color_rgba8(r, g, b, a);
+            pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+        }
+    };
+    let band = |pixmap: &mut Pixmap, start_ms: f64, end_ms: f64, colour| {
+        fill(pixmap, x_of(start_ms), graph_top, x_of(end_ms), graph_bottom, colour);
+    };
+
+    let mut kiai_start: Option<f64> = None;
+    for point in &parsed.timing_points {
+        match (kiai_start, point.kiai) {
+            (None, true) => kiai_start = Some(point.time),
+            (Some(start), false) => {
+                band(&mut pixmap, start, point.time, KIAI);
+                kiai_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = kiai_start {
+        band(&mut pixmap, start, duration_ms, KIAI);
+    }
+    for range in &parsed.break_periods {
+        band(&mut pixmap, f64::from(range.start), f64::from(range.end), BREAK);
+    }
+
+    let bars = (width / 4).max(1) as usize;
+    let bucket_ms = (duration_ms / bars as f64).ceil().max(1.0) as i32;
+    let buckets = build_density_histogram(&parsed.hit_starts, bucket_ms, bars);
+    let peak = buckets.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let bar_width = width_px / bars as f32;
+    for (index, count) in buckets.iter().enumerate().filter(|(_, count)| **count > 0) {
+        let left = index as f32 * bar_width;
+        let top = graph_bottom - *count as f32 / peak * (graph_bottom - graph_top - 4.0);
+        fill(&mut pixmap, left + 0.5, top, left + bar_width - 0.5, graph_bottom, MAPPED);
+    }
+
+    for &bookmark in &parsed.bookmarks {
+        band(&mut pixmap, f64::from(bookmark), f64::from(bookmark), BOOKMARK);
+    }
+
+    let bin_width = width_px / covered.len() as f32;
+    for (index, mapped) in covered.iter().enumerate() {
+        let left = index as f32 * bin_width;
+        let colour = if *mapped { MAPPED } else { UNMAPPED };
+        fill(&mut pixmap, left, coverage_top, left + bin_width, height_px, colour);
+    }
+
+    let metadata = &parsed.metadata;
+    let percent = format!("{progress:.0}%");
+    let percent_width = percent.len() as f32 * 6.0 * scale;
+    let title = format!("{} - {} [{}]", metadata.artist, metadata.title, metadata.version);
+    draw_caption(&mut pixmap, &percent, width_px - percent_width - 6.0, 6.0, scale, percent_width);
+    draw_caption(&mut pixmap, &title, 6.0, 6.0, scale, width_px - percent_width - 24.0);
+
+    let png = pixmap.encode_png().map_err(|err| err.to_string())?;
+    Ok((png, progress))
+}
+
+/// Shareable PNG of a map's progress for embeds. Written to `output_path` when given, otherwise
+/// returned as a data URL.
+#[tauri::command]
+async fn render_progress_image(
+    file_path: String,
+    width: Option<u32>,
+    height: Option<u32>,
+    output_path: Option<String>,
+) -> Result<ProgressImagePayload, String> {
+    let width = width.unwrap_or(EMBED_GRAPH_WIDTH).clamp(200, 1920);
+    let height = height.unwrap_or(EMBED_GRAPH_HEIGHT).clamp(60, 1080);
+    tauri::async_runtime::spawn_blocking(move || {
+        let parsed = load_parsed_osu(&file_path)?;
+        let (png, progress) = render_progress_png(&file_path, &parsed, width, height)?;
+        let (path, image) = match output_path {
+            Some(path) => {
+                fs::write(&path, &png).map_err(|err| err.to_string())?;
+                (Some(path), None)
+            }
+            None => (
+                None,
+                Some(format!(
+                    "data:image/png;base64,{}",
+                    base64::engine::general_purpose::STANDARD.encode(png)
+                )),
+            ),
+        };
+        Ok(ProgressImagePayload {
+            path,
+            image,
+            width,
+            height,
+            progress,
+        })
+    })
+    .await
+    .map_err(|err| err.to_string())?
+}
+
+/// The embed and, when it rendered, the density graph PNG its image points at.
+fn build_progress_embed_parts(
+    map: ProgressEmbedMap,
+    stats: Option<ProgressEmbedStats>,
+) -> Result<(DiscordEmbed, Option<Vec<u8>>), String> {
+    let (content, parsed) = load_osu_with_content(&map.file_path)?;
+    let metadata = &parsed.metadata;
+    let last = parsed.hit_ends.iter().max().copied().unwrap_or(0);
+    let bpm = compute_bpm_summary(&parsed.timing_points, last);
+    let star_rating = fs::read(&map.file_path).ok().and_then(|bytes| cached_star_rating(&bytes, 0));
+    let progress = map.progress.clamp(0.0, 100.0);
+
+    let mut fields = vec![
+        DiscordEmbedField { name: "Progress".to_string(), value: format!("{progress:.0}%"), inline: true },
+        DiscordEmbedField {
+            name: "Stars".to_string(),
+            value: star_rating.map_or_else(|| "?".to_string(), |stars| format!("{stars:.2}★")),
+            inline: true,
+        },
+        DiscordEmbedField {
+            name: "BPM".to_string(),
+            value: if bpm.variable {
+                format!("{:.0}–{:.0} ({:.0})", bpm.min, bpm.max, bpm.dominant)
+            } else {
+                format!("{:.0}", bpm.dominant)
+            },
+            inline: true,
+        },
+        DiscordEmbedField {
+            name: "Drain".to_string(),
+            value: format_duration_ms(compute_drain_time_ms(&parsed)),
+            inline: true,
+        },
+        DiscordEmbedField { name: "Objects".to_string(), value: parsed.hit_starts.len().to_string(), inline: true },
+        DiscordEmbedField {
+            name: "Mode".to_string(),
+            value: mode_display_name(metadata.mode).to_string(),
+            inline: true,
+        },
+    ];
+    if let Some(deadline) = map.deadline.filter(|deadline| !deadline.trim().is_empty()) {
+        fields.push(DiscordEmbedField { name: "Deadline".to_string(), value: deadline, inline: true });
+    }
+
+    let graph = match render_density_graph_png(&parsed.hit_starts, last, progress) {
+        Ok(png) => Some(png),
+        Err(err) => {
+            eprintln!("failed to render density graph: {err}");
+            None
+        }
+    };
+
+    let (beatmap_id, beatmap_set_id) = parse_osu_beatmap_ids(&content);
+    let url = match (beatmap_set_id, beatmap_id) {
+        (Some(set_id), Some(map_id)) => Some(format!(
+            "https://osu.ppy.sh/beatmapsets/{set_id}#{}/{map_id}",
+            mode_display_name(metadata.mode)
+        )),
+        (Some(set_id), None) => Some(format!("https://osu.ppy.sh/beatmapsets/{set_id}")),
+        _ => None,
+    };
+    let footer = stats.map(|stats| DiscordEmbedFooter {
+        text: format!(
+            "{} maps · {} to do · {} done · {:.1}% overall",
+            stats.total_maps, stats.todo_count, stats.completed_count, stats.overall_progress
+        ),
+    });
+
+    let embed = DiscordEmbed {
+        title: format!("{} - {} [{}]", metadata.artist, metadata.title, metadata.version),
+        description: map.description.filter(|text| !text.trim().is_empty()),
+        url,
+        color: progress_embed_colour(progress),
+        fields,
+        image: graph.as_ref().map(|_| DiscordEmbedImage { url: format!("attachment://{EMBED_GRAPH_FILE_NAME}") }),
+        footer,
+    };
+    Ok((embed, graph))
+}
+
+/// Discord embed describing a map's progress, built from the map itself. Only the description
+/// comes from the user; the density graph is returned as an attachment the embed references, so
+/// it must be uploaded alongside it (as `post_progress_embed` does).
+#[tauri::command]
+async fn build_progress_embed(
+    map: ProgressEmbedMap,
+    stats: Option<ProgressEmbedStats>,
+) -> Result<ProgressEmbedPayload, String> {
+    let (embed, graph) = tauri::async_runtime::spawn_blocking(move || build_progress_embed_parts(map, stats))
+        .await
+        .map_err(|err| err.to_string())??;
+    Ok(ProgressEmbedPayload {
+        embed,
+        attachment: graph.map(|png| EmbedAttachment {
+            file_name: EMBED_GRAPH_FILE_NAME.to_string(),
+            content_type: "image/png".to_string(),
+            data: base64::engine::general_purpose::STANDARD.encode(png),
+        }),
+    })
+}
+
+fn push_multipart_part(body: &mut Vec<u8>, boundary: &str, disposition: &str, content_type: &str, data: &[u8]) {
+    body.extend_from_slice(format!("--{boundary}\r\nContent-Disposition: form-data; {disposition}\r\n").as_bytes());
+    body.extend_from_slice(format!("Content-Type: {content_type}\r\n\r\n").as_bytes());
+    body.extend_from_slice(data);
+    body.extend_from_slice(b"\r\n");
+}
+
+/// `multipart/form-data` body for a Discord webhook: the message JSON plus the embed's image file.
+fn progress_embed_multipart(boundary: &str, embed: &DiscordEmbed, graph: Option<&[u8]>) -> Result<Vec<u8>, String> {
+    let attachments: Vec<Value> =
+        graph.iter().map(|_| serde_json::json!({ "id": 0, "filename": EMBED_GRAPH_FILE_NAME })).collect();
+    let message = serde_json::json!({ "embeds": [embed], "attachments": attachments });
+    let message = serde_json::to_vec(&message).map_err(|err| err.to_string())?;
+
+    let mut body = Vec::new();
+    push_multipart_part(&mut body, boundary, "name=\"payload_json\"", "application/json", &message);
+    if let Some(png) = graph {
+        let disposition = format!("name=\"files[0]\"; filename=\"{EMBED_GRAPH_FILE_NAME}\"");
+        push_multipart_part(&mut body, boundary, &disposition, "image/png", png);
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    Ok(body)
+}
+
+/// Post the progress embed to a Discord webhook, uploading the density graph it references.
+#[tauri::command]
+async fn post_progress_embed(
+    webhook_url: String,
+    map: ProgressEmbedMap,
+    stats: Option<ProgressEmbedStats>,
+) -> Result<(), String> {
+    if !webhook_url.starts_with("https://") {
+        return Err("webhook URL must use https".to_string());
+    }
+    let (embed, graph) = tauri::async_runtime::spawn_blocking(move || build_progress_embed_parts(map, stats))
+        .await
+        .map_err(|err| err.to_string())??;
+
+    let boundary = format!("mosu-{}", unix_time_ms());
+    let body = progress_embed_multipart(&boundary, &embed, graph.as_deref())?;
+    let request = http_client(Duration::from_secs(30))?
+        .post(webhook_url)
+        .header(reqwest::header::CONTENT_TYPE, format!("multipart/form-data; boundary={boundary}"))
+        .body(body);
+    let response = send_with_retry(request).await?;
+    if !response.status().is_success() {
+        return Err(format!("webhook returned HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+const AUTO_SYNC_MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A to-do map whose file is re-read before every automatic sync.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct WatchedMap {
+    id: String,
+    file_path: String,
+}
+
+#[derive(Clone)]
+struct AutoSyncConfig {
+    url: String,
+    api_key: String,
+    payload: Value,
+    watched: Vec<WatchedMap>,
+}
+
+struct AutoSyncState {
+    config: Option<AutoSyncConfig>,
+    /// Bumped on start/stop so a superseded scheduler thread exits.
+    generation: u64,
+    last_signature: Option<String>,
+}
+
+static AUTO_SYNC: Mutex<AutoSyncState> = Mutex::new(AutoSyncState { config: None, generation: 0, last_signature: None });
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct SyncResultEvent {
+    success: bool,
+    /// Nothing changed since the last successful sync, so nothing was sent.
+    skipped: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    synced_at_ms: u64,
+}
+
+/// `YYYY-MM-DDTHH:MM:SS.mmmZ` for a unix timestamp in ms.
+fn unix_ms_to_iso8601(ms: u64) -> String {
+    let days = (ms / 86_400_000) as i64;
+    let ms_of_day = ms % 86_400_000;
+    // Civil-from-days, after Howard Hinnant's date algorithms.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    )
+}
+
+/// Refresh each watched map's object count, drain time and last edit in the payload's to-do items.
+fn refresh_watched_maps(payload: &mut Value, watched: &[WatchedMap]) {
+    let Some(items) = payload.get_mut("todoItems").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for map in watched {
+        let Some(item) = items
+            .iter_mut()
+            .find(|item| item.get("id").and_then(Value::as_str) == Some(map.id.as_str()))
+            .and_then(Value::as_object_mut)
+        else {
+            continue;
+        };
+        let Ok(parsed) = load_parsed_osu(&map.file_path) else {
+            continue;
+        };
+        item.insert("objectCount".to_string(), Value::from(parsed.hit_starts.len()));
+        item.insert("drainTimeMs".to_string(), Value::from(compute_drain_time_ms(&parsed)));
+        if let Ok(mtime_ms) = get_mtime_ms(Path::new(&map.file_path)) {
+            item.insert("lastEditedMs".to_string(), Value::from(mtime_ms));
+        }
+    }
+}
+
+async fn run_auto_sync(app_handle: &tauri::AppHandle, mut config: AutoSyncConfig) {
+    refresh_watched_maps(&mut config.payload, &config.watched);
+    let signature = config.payload.to_string();
+    let synced_at_ms = unix_time_ms() as u64;
+    if AUTO_SYNC.lock().unwrap().last_signature.as_deref() == Some(signature.as_str()) {
+        let _ = app_handle.emit("sync-result", SyncResultEvent {
+            success: true,
+            skipped: true,
+            status: None,
+            error: None,
+            synced_at_ms,
+        });
+        return;
+    }
+
+    if let Some(payload) = config.payload.as_object_mut() {
+        payload.insert("lastUpdated".to_string(), Value::from(unix_ms_to_iso8601(synced_at_ms)));
+    }
+    let result = embed_sync(config.url, config.api_key, config.payload).await;
+    let success = result.success
+        && result
+            .data
+            .as_ref()
+            .is_some_and(|data| data.get("success").and_then(Value::as_bool).unwrap_or(*data == Value::Bool(true)));
+    if success {
+        AUTO_SYNC.lock().unwrap().last_signature = Some(signature);
+    }
+    let _ = app_handle.emit("sync-result", SyncResultEvent {
+        success,
+        skipped: false,
+        status: result.status,
+        error: result.error.or_else(|| {
+            (!success).then(|| {
+                result
+                    .data
+                    .as_ref()
+                    .and_then(|data| data.get("error"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("Sync Failed")
+                    .to_string()
+            })
+        }),
+        synced_at_ms,
+    });
+}
+
+/// Sync the embed every `interval_secs` while the app runs. `payload` is the embed payload the
+/// renderer builds; `watched` maps are re-read from disk before each sync, and unchanged payloads
+/// are not re-sent. Results arrive as `sync-result` events.
+#[tauri::command]
+fn start_auto_sync(
+    app_handle: tauri::AppHandle,
+    url: String,
+    api_key: String,
+    interval_secs: u64,
+    payload: Value,
+    watched: Option<Vec<WatchedMap>>,
+) -> Result<(), String> {
+    let interval = Duration::from_secs(interval_secs).max(AUTO_SYNC_MIN_INTERVAL);
+    let generation = {
+        let mut state = AUTO_SYNC.lock().map_err(|err| err.to_string())?;
+        state.generation += 1;
+        state.config = Some(AutoSyncConfig { url, api_key, payload, watched: watched.unwrap_or_default() });
+        state.generation
+    };
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        let config = {
+            let Ok(state) = AUTO_SYNC.lock() else {
+                return;
             };
+            if state.generation != generation {
+                return;
+            }
+            state.config.clone()
+        };
+        let Some(config) = config else {
+            return;
+        };
+        tauri::async_runtime::block_on(run_auto_sync(&app_handle, config));
+    });
+    Ok(())
+}
+
+/// Replace the payload (and optionally the watched maps) used by the next automatic sync.
+#[tauri::command]
+fn update_auto_sync_payload(payload: Value, watched: Option<Vec<WatchedMap>>) -> Result<(), String> {
+    let mut state = AUTO_SYNC.lock().map_err(|err| err.to_string())?;
+    if let Some(config) = state.config.as_mut() {
+        config.payload = payload;
+        if let Some(watched) = watched {
+            config.watched = watched;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_auto_sync() -> Result<(), String> {
+    let mut state = AUTO_SYNC.lock().map_err(|err| err.to_string())?;
+    state.generation += 1;
+    state.config = None;
+    Ok(())
+}
+
+/// Every osu! API v1 `get_beatmaps` entry matching `params`.
+async fn query_beatmaps(client: &reqwest::Client, api_key: &str, params: &[(&str, &str)]) -> Result<Vec<Value>, String> {
+    let request = client
+        .get("https://osu.ppy.sh/api/get_beatmaps")
+        .query(&[("k", api_key)])
+        .query(params);
+    let response = send_with_retry(request).await?;
+
+    if !response.status().is_success() {
+        return Err(format!("osu! API returned status {}", response.status()));
+    }
+
+    match response.json().await.map_err(|err| err.to_string())? {
+        Value::Array(entries) => Ok(entries),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// The osu! API v1 `get_beatmaps` entry for a .osu MD5, if the beatmap exists online.
+async fn fetch_beatmap_by_hash(
+    client: &reqwest::Client,
+    api_key: &str,
+    beatmap_hash: &str,
+) -> Result<Option<Value>, String> {
+    let entries = query_beatmaps(client, api_key, &[("h", beatmap_hash), ("limit", "1")]).await?;
+    Ok(entries.into_iter().next())
+}
+
+/// The osu! API v1 `get_beatmaps` entry for a beatmap id.
+async fn fetch_beatmap_by_id(client: &reqwest::Client, api_key: &str, beatmap_id: i64) -> Result<Option<Value>, String> {
+    let beatmap_id = beatmap_id.to_string();
+    let entries = query_beatmaps(client, api_key, &[("b", &beatmap_id), ("limit", "1")]).await?;
+    Ok(entries.into_iter().next())
+}
+
+/// Look up a beatmap's ids through the osu! API v1 by its .osu MD5.
+async fn lookup_beatmap_ids_by_hash(
+    client: &reqwest::Client,
+    api_key: &str,
+    beatmap_hash: &str,
+) -> Result<Option<(i64, i64)>, String> {
+    let entry = fetch_beatmap_by_hash(client, api_key, beatmap_hash).await?;
+    Ok(entry.as_ref().and_then(beatmap_entry_ids))
+}
+
+/// Look up a beatmap's ids through the osu! API v1 by creator, artist, title and difficulty name,
+/// for files whose MD5 matches nothing online (edited after submission, or saved by a newer client).
+async fn lookup_beatmap_ids_by_metadata(
+    client: &reqwest::Client,
+    api_key: &str,
+    content: &str,
+) -> Result<Option<(i64, i64)>, String> {
+    let field = |section: &str, key: &str| read_osu_section_value(content, section, key).unwrap_or_default();
+    let (creator, artist, title, version) = (
+        field("Metadata", "Creator"),
+        field("Metadata", "Artist"),
+        field("Metadata", "Title"),
+        field("Metadata", "Version"),
+    );
+    if creator.is_empty() || title.is_empty() || version.is_empty() {
+        return Ok(None);
+    }
+    let mode = field("General", "Mode").parse::<i32>().unwrap_or(0).to_string();
+
+    // Without `a=1` only maps made for `mode` come back, not converts.
+    let entries = query_beatmaps(
+        client,
+        api_key,
+        &[("u", &creator), ("type", "string"), ("m", &mode), ("limit", "500")],
+    )
+    .await?;
+    let field_matches = |entry: &Value, key: &str, expected: &str| {
+        entry
+            .get(key)
+            .and_then(Value::as_str)
+            .is_some_and(|value| value.trim().eq_ignore_ascii_case(expected))
+    };
+    Ok(entries
+        .iter()
+        .find(|entry| {
+            field_matches(entry, "title", &title)
+                && field_matches(entry, "artist", &artist)
+                && field_matches(entry, "version", &version)
+        })
+        .and_then(beatmap_entry_ids))
+}
+
+/// `(beatmap_id, beatmapset_id)` of a `get_beatmaps` entry; the API sends both as strings.
+fn beatmap_entry_ids(entry: &Value) -> Option<(i64, i64)> {
+    let read_id = |key: &str| {
+        entry.get(key).and_then(|value| {
+            value
+                .as_i64()
+                .or_else(|| value.as_str().and_then(|raw| raw.parse::<i64>().ok()))
+        })
+    };
+    read_id("beatmap_id").zip(read_id("beatmapset_id"))
+}
+
+/// Fill in missing BeatmapID/BeatmapSetID values by matching each file's MD5 against the osu! API,
+/// falling back to its creator and metadata, and optionally write the ids back into the .osu
+/// (a timestamped backup is made first).
+#[tauri::command]
+async fn backfill_beatmap_ids(
+    window: tauri::Window,
+    file_paths: Vec<String>,
+    api_key: String,
+    write_back: Option<bool>,
+) -> Result<BeatmapIdBackfillPayload, String> {
+    let api_key = api_key.trim().to_string();
+    if api_key.is_empty() {
+        return Err("an osu! API key is required".to_string());
+    }
+
+    let client = http_client(Duration::from_secs(15))?;
+    let write_back = write_back.unwrap_or(false);
+    let total = file_paths.len();
+    let mut entries = Vec::new();
+    let mut matched = 0_usize;
+    let mut skipped = 0_usize;
+
+    for (index, file_path) in file_paths.into_iter().enumerate() {
+        let _ = window.emit("beatmap-id-backfill-progress", BeatmapIdBackfillProgressEvent {
+            file_path: file_path.clone(),
+            current: index + 1,
+            total,
+        });
+
+        let bytes = match fs::read(&file_path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                entries.push(BeatmapIdBackfillEntry {
+                    file_path,
+                    beatmap_hash: String::new(),
+                    beatmap_id: None,
+                    beatmap_set_id: None,
+                    written: false,
+                    backup_path: None,
+                    error: Some(err.to_string()),
+                });
+                continue;
+            }
+        };
+
+        let content = decode_osu_bytes(&bytes).0;
+        if let (Some(_), Some(_)) = parse_osu_beatmap_ids(&content) {
+            skipped += 1;
+            continue;
         }
-    };
 
-    match fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(&path)
-    {
-        Ok(mut file) => {
-            if let Err(error) = file.write_all(&bytes) {
-                return CollectionMutationPayload {
-                    success: false,
-                    error: Some(if is_locked_io_error(&error) {
-                        "file_locked".to_string()
-                    } else {
-                        error.to_string()
-                    }),
-                };
+        let beatmap_hash = compute_osu_md5_hex(&bytes);
+        let mut entry = BeatmapIdBackfillEntry {
+            file_path: file_path.clone(),
+            beatmap_hash: beatmap_hash.clone(),
+            beatmap_id: None,
+            beatmap_set_id: None,
+            written: false,
+            backup_path: None,
+            error: None,
+        };
+
+        let lookup = match lookup_beatmap_ids_by_hash(&client, &api_key, &beatmap_hash).await {
+            Ok(None) => lookup_beatmap_ids_by_metadata(&client, &api_key, &content).await,
+            result => result,
+        };
+        match lookup {
+            Ok(Some((beatmap_id, beatmap_set_id))) => {
+                matched += 1;
+                entry.beatmap_id = Some(beatmap_id);
+                entry.beatmap_set_id = Some(beatmap_set_id);
             }
+            Ok(None) => entry.error = Some("no online beatmap matches this file".to_string()),
+            Err(err) => entry.error = Some(err),
         }
-        Err(error) => {
-            return CollectionMutationPayload {
-                success: false,
-                error: Some(if is_locked_io_error(&error) {
-                    "file_locked".to_string()
-                } else {
-                    error.to_string()
-                }),
+
+        if let (true, Some(beatmap_id), Some(beatmap_set_id)) =
+            (write_back, entry.beatmap_id, entry.beatmap_set_id)
+        {
+            let result = rewrite_osu_file(Path::new(&file_path), true, |content| {
+                let updated = upsert_osu_section_value(content, "Metadata", "BeatmapID", &beatmap_id.to_string())?;
+                upsert_osu_section_value(&updated, "Metadata", "BeatmapSetID", &beatmap_set_id.to_string())
+            });
+            match result {
+                Ok(backup) => {
+                    entry.written = backup.is_some();
+                    entry.backup_path = backup.map(|backup| backup.to_string_lossy().to_string());
+                }
+                Err(err) => entry.error = Some(err),
+            }
+        }
+
+        entries.push(entry);
+    }
+
+    Ok(BeatmapIdBackfillPayload {
+        entries,
+        matched,
+        skipped,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BeatmapClassificationPayload {
+    language: String,
+    genre: String,
+    /// `api` when the osu! API knew the map's language or genre, otherwise `heuristic`.
+    source: &'static str,
+}
+
+/// Language and genre for a map: the metadata guess, replaced by what the osu! API lists for
+/// submitted maps when an API key is given. The result is kept in the library cache, and an
+/// API answer found there is returned without asking again.
+#[tauri::command]
+async fn classify_beatmap(file_path: String, api_key: Option<String>) -> Result<BeatmapClassificationPayload, String> {
+    let from_api = library_cache()
+        .lock()
+        .map_err(|err| err.to_string())?
+        .entries
+        .get(&file_path)
+        .filter(|entry| entry.classification_source.as_deref() == Some("api"))
+        .map(|entry| BeatmapClassificationPayload {
+            language: entry.metadata.language.clone(),
+            genre: entry.metadata.genre.clone(),
+            source: "api",
+        });
+    if let Some(payload) = from_api {
+        return Ok(payload);
+    }
+
+    let lookup_path = file_path.clone();
+    let (metadata, beatmap_hash) = tauri::async_runtime::spawn_blocking(move || {
+        let bytes = fs::read(&lookup_path).map_err(|err| err.to_string())?;
+        let metadata = parse_osu_content(&decode_osu_bytes(&bytes).0).metadata;
+        Ok::<_, String>((metadata, compute_osu_md5_hex(&bytes)))
+    })
+    .await
+    .map_err(|err| err.to_string())??;
+
+    let mut payload = BeatmapClassificationPayload {
+        language: metadata.language,
+        genre: metadata.genre,
+        source: "heuristic",
+    };
+    if let Some(api_key) = api_key.map(|key| key.trim().to_string()).filter(|key| !key.is_empty()) {
+        let client = http_client(Duration::from_secs(15))?;
+        if let Some(entry) = fetch_beatmap_by_hash(&client, &api_key, &beatmap_hash).await? {
+            let name = |key: &str, names: &[&'static str]| {
+                entry
+                    .get(key)
+                    .and_then(|value| value.as_u64().or_else(|| value.as_str()?.parse().ok()))
+                    .and_then(|id| names.get(id as usize).copied())
+                    .filter(|name| !name.is_empty())
             };
+            if let Some(language) = name("language_id", &OSU_LANGUAGES) {
+                payload.language = language.to_string();
+                payload.source = "api";
+            }
+            if let Some(genre) = name("genre_id", &OSU_GENRES) {
+                payload.genre = genre.to_string();
+                payload.source = "api";
+            }
         }
     }
 
-    CollectionMutationPayload {
-        success: true,
-        error: None,
+    library_cache()
+        .lock()
+        .map_err(|err| err.to_string())?
+        .set_classification(&file_path, &payload);
+    Ok(payload)
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecentPlayPayload {
+    beatmap_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    beatmap_hash: Option<String>,
+    /// "Artist - Title [Version]" from the API.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    display_name: Option<String>,
+    /// Library file with the same MD5, when the map is downloaded locally.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_file_path: Option<String>,
+    score: i64,
+    max_combo: u32,
+    #[serde(flatten)]
+    counts: OsuScoreCounts,
+    accuracy: f64,
+    perfect: bool,
+    mods: u32,
+    mods_acronym: String,
+    rank: String,
+    /// UTC timestamp as the API returns it, e.g. "2024-05-01 18:30:00".
+    played_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    map_max_combo: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pp: Option<f64>,
+    /// pp with every miss turned into a 300 and a full combo.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pp_if_fc: Option<f64>,
+}
+
+fn api_field<T: std::str::FromStr>(entry: &Value, key: &str) -> Option<T> {
+    entry.get(key)?.as_str()?.parse().ok()
+}
+
+/// First library file whose cached MD5 matches, if it is still on disk.
+fn find_library_file_by_hash(beatmap_hash: &str) -> Option<String> {
+    let cache = library_cache().lock().ok()?;
+    cache
+        .entries
+        .values()
+        .find(|entry| {
+            entry
+                .beatmap_hash
+                .as_deref()
+                .is_some_and(|hash| hash.eq_ignore_ascii_case(beatmap_hash))
+        })
+        .map(|entry| entry.file_path.clone())
+        .filter(|path| Path::new(path).is_file())
+}
+
+/// Actual pp and pp if the play had been a full combo, computed locally with rosu-pp in the
+/// mode the play was set in, so plays on converts are rated against the converted map.
+fn recent_play_pp(
+    file_path: &str,
+    play: &RecentPlayPayload,
+    mode: rosu_pp::model::mode::GameMode,
+) -> Option<(u32, f64, f64)> {
+    use rosu_pp::model::mode::ConvertStatus;
+    use rosu_pp::Performance;
+
+    let mut map = Beatmap::from_bytes(&fs::read(file_path).ok()?).ok()?;
+    if map.convert_in_place(mode) == ConvertStatus::Incompatible {
+        return None;
     }
+    let attributes = Difficulty::new().mods(play.mods).calculate(&map);
+    let counts = &play.counts;
+    let pp = Performance::new(attributes.clone())
+        .mods(play.mods)
+        .combo(play.max_combo)
+        .n300(u32::from(counts.count_300))
+        .n100(u32::from(counts.count_100))
+        .n50(u32::from(counts.count_50))
+        .misses(u32::from(counts.count_miss))
+        .calculate()
+        .pp();
+    let pp_if_fc = Performance::new(attributes.clone())
+        .mods(play.mods)
+        .n300(u32::from(counts.count_300) + u32::from(counts.count_miss))
+        .n100(u32::from(counts.count_100))
+        .n50(u32::from(counts.count_50))
+        .misses(0)
+        .calculate()
+        .pp();
+    Some((attributes.max_combo(), pp, pp_if_fc))
 }
 
+/// A user's recent plays from the osu! API v1, matched to library files by MD5, with pp and
+/// pp-if-FC calculated for every map that is available locally.
 #[tauri::command]
-fn get_lazer_collections(data_root: Option<String>) -> Result<Vec<OsuCollectionPayload>, String> {
-    let exe = find_realm_resolver_exe()
-        .ok_or_else(|| "realm-resolver sidecar not found".to_string())?;
+async fn get_recent_plays(
+    user_id: String,
+    api_key: String,
+    mode: Option<i32>,
+    limit: Option<u32>,
+) -> Result<Vec<RecentPlayPayload>, String> {
+    use rosu_pp::model::mode::GameMode;
+
+    let api_key = api_key.trim().to_string();
+    if api_key.is_empty() {
+        return Err("an osu! API key is required".to_string());
+    }
+    let user_id = user_id.trim().to_string();
+    let user_type = if user_id.chars().all(|c| c.is_ascii_digit()) { "id" } else { "string" };
+    let mode = mode.unwrap_or(0).clamp(0, 3).to_string();
+    let limit = limit.unwrap_or(20).clamp(1, 50).to_string();
+
+    let client = http_client(Duration::from_secs(15))?;
+    let request = client
+        .get("https://osu.ppy.sh/api/get_user_recent")
+        .query(&[("k", api_key.as_str()), ("u", &user_id), ("m", &mode), ("limit", &limit), ("type", user_type)]);
+    let response = send_with_retry(request).await?;
+    if !response.status().is_success() {
+        return Err(format!("osu! API returned status {}", response.status()));
+    }
+    let entries: Vec<Value> = response.json().await.map_err(|err| err.to_string())?;
+
+    let mode_byte = mode.parse::<u8>().unwrap_or(0);
+    let mut beatmaps: HashMap<i64, Option<Value>> = HashMap::new();
+    let mut plays = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let beatmap_id = api_field::<i64>(entry, "beatmap_id").unwrap_or(0);
+        if let std::collections::hash_map::Entry::Vacant(slot) = beatmaps.entry(beatmap_id) {
+            slot.insert(fetch_beatmap_by_id(&client, &api_key, beatmap_id).await.ok().flatten());
+        }
+        let beatmap = beatmaps.get(&beatmap_id).and_then(Option::as_ref);
+        let text = |key: &str| beatmap.and_then(|beatmap| beatmap.get(key)?.as_str()).unwrap_or_default();
+
+        let counts = OsuScoreCounts {
+            count_300: api_field(entry, "count300").unwrap_or(0),
+            count_100: api_field(entry, "count100").unwrap_or(0),
+            count_50: api_field(entry, "count50").unwrap_or(0),
+            count_geki: api_field(entry, "countgeki").unwrap_or(0),
+            count_katu: api_field(entry, "countkatu").unwrap_or(0),
+            count_miss: api_field(entry, "countmiss").unwrap_or(0),
+        };
+        let mods = api_field::<u32>(entry, "enabled_mods").unwrap_or(0);
+        let beatmap_hash = Some(text("file_md5").to_string()).filter(|hash| !hash.is_empty());
+        plays.push(RecentPlayPayload {
+            beatmap_id,
+            local_file_path: beatmap_hash.as_deref().and_then(find_library_file_by_hash),
+            beatmap_hash,
+            display_name: beatmap.map(|_| format!("{} - {} [{}]", text("artist"), text("title"), text("version"))),
+            score: api_field(entry, "score").unwrap_or(0),
+            max_combo: api_field(entry, "maxcombo").unwrap_or(0),
+            accuracy: score_accuracy(mode_byte, &counts),
+            counts,
+            perfect: api_field::<u8>(entry, "perfect").unwrap_or(0) != 0,
+            mods,
+            mods_acronym: mods_acronym(mods),
+            rank: entry.get("rank").and_then(Value::as_str).unwrap_or_default().to_string(),
+            played_at: entry.get("date").and_then(Value::as_str).unwrap_or_default().to_string(),
+            map_max_combo: None,
+            pp: None,
+            pp_if_fc: None,
+        });
+    }
 
-    let mut command = Command::new(&exe);
-    if let Some(data_root) = data_root.as_ref().filter(|value| !value.trim().is_empty()) {
-        command.arg(data_root);
+    let game_mode = match mode_byte {
+        1 => GameMode::Taiko,
+        2 => GameMode::Catch,
+        3 => GameMode::Mania,
+        _ => GameMode::Osu,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        for play in &mut plays {
+            let Some(file_path) = play.local_file_path.clone() else {
+                continue;
+            };
+            if let Some((map_max_combo, pp, pp_if_fc)) = recent_play_pp(&file_path, play, game_mode) {
+                play.map_max_combo = Some(map_max_combo);
+                play.pp = Some(pp);
+                play.pp_if_fc = Some(pp_if_fc);
+            }
+        }
+        plays
+    })
+    .await
+    .map_err(|err| err.to_string())
+}
+
+/// Width of the BPM ranges used for `most_used_bpm_range`.
+const MAPPER_STATS_BPM_BUCKET: f64 = 20.0;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BpmRangeCount {
+    min: f64,
+    max: f64,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MapperStatsPayload {
+    mapper: String,
+    directory: String,
+    total_difficulties: usize,
+    total_sets: usize,
+    total_drain_ms: i64,
+    /// Index `i` counts difficulties in `[i, i + 1)` stars; the last bucket is 10★ and above.
+    star_distribution: Vec<usize>,
+    mode_counts: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    most_used_bpm_range: Option<BpmRangeCount>,
+    /// Ranked sets per year; only present when an API key was given for the online lookup.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maps_per_year: Option<BTreeMap<String, usize>>,
+}
+
+fn mode_display_name(mode: i32) -> &'static str {
+    match mode {
+        1 => "taiko",
+        2 => "fruits",
+        3 => "mania",
+        _ => "osu",
     }
-    let output = command
-        .arg("list-collections")
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .output()
-        .map_err(|err| format!("failed to run realm-resolver: {err}"))?;
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("realm-resolver failed: {stderr}"));
+/// Year a beatmap was ranked/approved/loved according to the osu! API v1, if it ever was.
+async fn lookup_ranked_year(client: &reqwest::Client, api_key: &str, beatmap_hash: &str) -> Option<String> {
+    let entry = fetch_beatmap_by_hash(client, api_key, beatmap_hash).await.ok()??;
+    let approved_date = entry.get("approved_date")?.as_str()?;
+    approved_date.get(..4).map(str::to_string)
+}
+
+/// Scan `directory` for `mapper`'s difficulties and summarise them for profile-style embeds.
+/// Star ratings come from the library cache where possible and are calculated otherwise.
+#[tauri::command]
+async fn aggregate_mapper_stats(
+    directory: String,
+    mapper: String,
+    client_type: Option<String>,
+    api_key: Option<String>,
+) -> Result<MapperStatsPayload, String> {
+    let client = OsuClient::from_option(client_type);
+    let scan_dir = directory.clone();
+    let scan_mapper = mapper.clone();
+    let (files, star_ratings) = tauri::async_runtime::spawn_blocking(move || {
+        let files = scan_directory_internal(&scan_dir, Some(scan_mapper), None, client).files;
+        let star_ratings: Vec<Option<f64>> = files
+            .iter()
+            .map(|file| {
+                let cached = file.metadata.as_ref().map_or(-1.0, |metadata| metadata.star_rating);
+                if cached >= 0.0 {
+                    return Some(cached);
+                }
+                let cached = library_cache()
+                    .lock()
+                    .ok()
+                    .and_then(|cache| cache.entries.get(&file.file_path).map(|entry| entry.metadata.star_rating))
+                    .filter(|stars| *stars >= 0.0);
+                cached.or_else(|| {
+                    let star_rating = cached_star_rating(&fs::read(&file.file_path).ok()?, 0)?;
+                    if let Ok(mut cache) = library_cache().lock() {
+                        cache.set_star_rating(&file.file_path, star_rating);
+                    }
+                    Some(star_rating)
+                })
+            })
+            .collect();
+        flush_library_cache();
+        (files, star_ratings)
+    })
+    .await
+    .map_err(|err| err.to_string())?;
+
+    let mut star_distribution = vec![0_usize; 11];
+    for stars in star_ratings.into_iter().flatten() {
+        star_distribution[(stars.floor() as usize).min(10)] += 1;
+    }
+
+    let mut mode_counts = BTreeMap::new();
+    let mut bpm_buckets: BTreeMap<i64, usize> = BTreeMap::new();
+    // One representative hash per set, so the online lookup is one request per set rather than per diff.
+    let mut sets: BTreeMap<String, Option<String>> = BTreeMap::new();
+    let mut total_drain_ms = 0_i64;
+    for file in &files {
+        let Some(metadata) = &file.metadata else {
+            continue;
+        };
+        *mode_counts.entry(mode_display_name(metadata.mode).to_string()).or_insert(0) += 1;
+        total_drain_ms += i64::from(drain_time_ms(
+            file.hit_starts.as_deref().unwrap_or_default(),
+            file.hit_ends.as_deref().unwrap_or_default(),
+            file.break_periods.as_deref().unwrap_or_default(),
+        ));
+        if let Some(bpm) = file.bpm.as_ref().filter(|bpm| bpm.dominant > 0.0) {
+            *bpm_buckets.entry((bpm.dominant / MAPPER_STATS_BPM_BUCKET).floor() as i64).or_insert(0) += 1;
+        }
+        // Stable groups a set by folder; lazer stores files by hash, so fall back to the set id.
+        let set_key = match client {
+            OsuClient::Stable => Path::new(&file.file_path)
+                .parent()
+                .map(|folder| folder.to_string_lossy().to_string()),
+            OsuClient::Lazer => Some(metadata.beatmap_set_id.clone()).filter(|id| !id.is_empty()),
+        }
+        .unwrap_or_else(|| file.file_path.clone());
+        let hash = sets.entry(set_key).or_insert(None);
+        if hash.is_none() {
+            *hash = file.beatmap_hash.clone();
+        }
+    }
+
+    let most_used_bpm_range = bpm_buckets
+        .into_iter()
+        .max_by_key(|(bucket, count)| (*count, std::cmp::Reverse(*bucket)))
+        .map(|(bucket, count)| BpmRangeCount {
+            min: bucket as f64 * MAPPER_STATS_BPM_BUCKET,
+            max: (bucket + 1) as f64 * MAPPER_STATS_BPM_BUCKET,
+            count,
+        });
+
+    let api_key = api_key.map(|key| key.trim().to_string()).filter(|key| !key.is_empty());
+    let maps_per_year = match api_key {
+        Some(api_key) => {
+            let http = http_client(Duration::from_secs(15))?;
+            let mut years = BTreeMap::new();
+            for hash in sets.values().flatten() {
+                if let Some(year) = lookup_ranked_year(&http, &api_key, hash).await {
+                    *years.entry(year).or_insert(0) += 1;
+                }
+            }
+            Some(years)
+        }
+        None => None,
+    };
+
+    Ok(MapperStatsPayload {
+        mapper,
+        directory,
+        total_difficulties: files.len(),
+        total_sets: sets.len(),
+        total_drain_ms,
+        star_distribution,
+        mode_counts,
+        most_used_bpm_range,
+        maps_per_year,
+    })
+}
+
+/// User id or username from a profile URL, or the input itself.
+fn osu_user_query(url_or_id: &str) -> String {
+    let url_or_id = url_or_id.trim();
+    if url_or_id.starts_with("http") {
+        url_or_id
+            .split('/')
+            .last()
+            .unwrap_or("")
+            .split('?')
+            .next()
+            .unwrap_or("")
+            .to_string()
+    } else {
+        url_or_id.to_string()
+    }
+}
+
+async fn fetch_osu_user_profile(id_str: &str) -> Result<OsuUserData, String> {
+    println!("[mosu] Fetching osu! user data for: {}", id_str);
+
+    let client = http_client_builder(Duration::from_secs(15))?
+        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let url = format!("https://osu.ppy.sh/users/{}", id_str);
+    let response = send_with_retry(client.get(&url)).await.map_err(|e| {
+        println!("[mosu] Request failed: {}", e);
+        e.to_string()
+    })?;
+    
+    if !response.status().is_success() {
+        println!("[mosu] Profile fetch returned status: {}", response.status());
+        return Err(format!("Failed to fetch profile: {}", response.status()));
+    }
+
+    let html = response.text().await.map_err(|e| e.to_string())?;
+    let document = scraper::Html::parse_document(&html);
+    
+    // Modern osu! profiles store data in a JSON blob within a .js-react element
+    let react_selector = scraper::Selector::parse(".js-react").map_err(|_| "Selector error")?;
+    let element = document.select(&react_selector)
+        .find(|e| e.value().attr("data-initial-data").is_some())
+        .ok_or_else(|| {
+            println!("[mosu] Could not find .js-react element with data-initial-data");
+            "Could not find profile data on page".to_string()
+        })?;
+    
+    let json_str = element.value().attr("data-initial-data").unwrap();
+    let data: Value = serde_json::from_str(json_str).map_err(|e| {
+        println!("[mosu] JSON parse error: {}", e);
+        format!("Failed to parse profile JSON: {}", e)
+    })?;
+    
+    // The structure is usually { "user": { ... } }
+    let user = data.get("user").ok_or_else(|| {
+        println!("[mosu] 'user' key not found in JSON data");
+        "User data not found in profile".to_string()
+    })?;
+    
+    let actual_id = user.get("id")
+        .and_then(|v| {
+            if let Some(i) = v.as_i64() { Some(i.to_string()) }
+            else if let Some(s) = v.as_str() { Some(s.to_string()) }
+            else { None }
+        })
+        .ok_or_else(|| "User ID not found in JSON".to_string())?;
+        
+    let username = user.get("username")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Username not found in JSON".to_string())?;
+    
+    let mut names = vec![username.to_string()];
+    println!("[mosu] Found primary username: {}", username);
+    
+    if let Some(previous) = user.get("previous_usernames").and_then(|v| v.as_array()) {
+        for name_val in previous {
+            if let Some(name) = name_val.as_str() {
+                if !name.is_empty() {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+
+    // Remove duplicates while preserving order (primary name stays first)
+    let mut seen = std::collections::HashSet::new();
+    names.retain(|n| seen.insert(n.to_lowercase()));
+    println!("[mosu] Total unique names found (order preserved): {:?}", names);
+
+    let text = |value: Option<&Value>| value.and_then(Value::as_str).map(str::to_string);
+    let count = |key: &str| user.get(key).and_then(Value::as_u64).map(|count| count as u32);
+    let country = user.get("country");
+    Ok(OsuUserData {
+        id: actual_id,
+        names,
+        avatar_url: text(user.get("avatar_url")),
+        country_code: text(country.and_then(|country| country.get("code"))),
+        country_name: text(country.and_then(|country| country.get("name"))),
+        ranked_count: count("ranked_beatmapset_count"),
+        loved_count: count("loved_beatmapset_count"),
+        pending_count: count("pending_beatmapset_count"),
+        graveyard_count: count("graveyard_beatmapset_count"),
+        guest_count: count("guest_beatmapset_count"),
+    })
+}
+
+const OSU_USER_CACHE_FILE: &str = "osu-user-cache.json";
+const OSU_USER_CACHE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Minimum spacing between profile fetches, so tagging a whole library doesn't hammer the site.
+const OSU_WEBSITE_REQUEST_INTERVAL: Duration = Duration::from_millis(1000);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct CachedOsuUser {
+    fetched_at_ms: u64,
+    user: OsuUserData,
+}
+
+/// Resolved profiles by user id, with every known username pointing back at its id.
+#[derive(Default)]
+struct OsuUserCache {
+    path: Option<PathBuf>,
+    users: HashMap<String, CachedOsuUser>,
+    aliases: HashMap<String, String>,
+}
+
+impl OsuUserCache {
+    fn load(dir: Option<&Path>) -> Self {
+        let path = dir.map(|dir| dir.join(OSU_USER_CACHE_FILE));
+        let mut cache = Self { path, ..Self::default() };
+        let users = cache
+            .path
+            .as_deref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice::<Vec<CachedOsuUser>>(&bytes).ok())
+            .unwrap_or_default();
+        for cached in users {
+            cache.index(cached);
+        }
+        cache
+    }
+
+    fn index(&mut self, cached: CachedOsuUser) {
+        for name in &cached.user.names {
+            self.aliases.insert(name.to_lowercase(), cached.user.id.clone());
+        }
+        self.users.insert(cached.user.id.clone(), cached);
+    }
+
+    fn lookup(&self, query: &str) -> Option<&CachedOsuUser> {
+        self.users
+            .get(query)
+            .or_else(|| self.aliases.get(&query.to_lowercase()).and_then(|id| self.users.get(id)))
+    }
+
+    fn insert(&mut self, user: OsuUserData) {
+        self.index(CachedOsuUser { fetched_at_ms: unix_time_ms() as u64, user });
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let users: Vec<&CachedOsuUser> = self.users.values().collect();
+        let written = serde_json::to_vec(&users)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| write_file_atomically(&path, &bytes));
+        if let Err(err) = written {
+            eprintln!("failed to write osu! user cache: {err}");
+        }
     }
+}
 
-    serde_json::from_slice::<Vec<OsuCollectionPayload>>(&output.stdout)
-        .map_err(|err| format!("failed to parse lazer collections: {err}"))
+static OSU_USER_CACHE: OnceLock<Mutex<OsuUserCache>> = OnceLock::new();
+
+fn osu_user_cache() -> &'static Mutex<OsuUserCache> {
+    OSU_USER_CACHE.get_or_init(|| Mutex::new(OsuUserCache::load(LIBRARY_CACHE_DIR.get().map(PathBuf::as_path))))
 }
 
-#[tauri::command]
-fn add_to_lazer_collection(
-    data_root: Option<String>,
-    collection_name: String,
-    beatmap_hash: String,
-) -> CollectionMutationPayload {
-    let exe = match find_realm_resolver_exe() {
-        Some(exe) => exe,
-        None => {
-            return CollectionMutationPayload {
-                success: false,
-                error: Some("realm-resolver sidecar not found".to_string()),
-            };
+/// Profile for a user id or username, from the cache while it is fresh. A stale entry is still
+/// used if the profile can't be fetched.
+async fn resolve_osu_user(query: &str) -> Result<OsuUserData, String> {
+    let cached = osu_user_cache().lock().unwrap().lookup(query).cloned();
+    if let Some(cached) = &cached {
+        let age_ms = (unix_time_ms() as u64).saturating_sub(cached.fetched_at_ms);
+        if u128::from(age_ms) < OSU_USER_CACHE_TTL.as_millis() {
+            return Ok(cached.user.clone());
         }
-    };
-
-    let mut command = Command::new(&exe);
-    if let Some(data_root) = data_root.as_ref().filter(|value| !value.trim().is_empty()) {
-        command.arg(data_root);
     }
-    let output = match command
-        .arg("add-to-collection")
-        .arg("--collection-name")
-        .arg(collection_name)
-        .arg("--beatmap-hash")
-        .arg(beatmap_hash)
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .output()
-    {
-        Ok(output) => output,
-        Err(error) => {
-            return CollectionMutationPayload {
-                success: false,
-                error: Some(error.to_string()),
-            };
+
+    match fetch_osu_user_profile(query).await {
+        Ok(user) => {
+            osu_user_cache().lock().unwrap().insert(user.clone());
+            Ok(user)
         }
-    };
+        Err(err) => cached.map(|cached| cached.user).ok_or(err),
+    }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    if let Ok(payload) = serde_json::from_str::<CollectionMutationPayload>(&stdout) {
-        return payload;
+#[tauri::command]
+async fn get_osu_user_data(url_or_id: String) -> Result<OsuUserData, String> {
+    let query = osu_user_query(&url_or_id);
+    if query.is_empty() {
+        return Err("Invalid osu! user URL or ID".to_string());
     }
+    resolve_osu_user(&query).await
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return CollectionMutationPayload {
-            success: false,
-            error: Some(if stderr.trim().is_empty() {
-                "realm-resolver failed".to_string()
-            } else {
-                stderr.trim().to_string()
-            }),
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OsuUserResolution {
+    query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user: Option<OsuUserData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Resolve many user ids/usernames at once; uncached ones are fetched one at a time.
+#[tauri::command]
+async fn resolve_osu_users(queries: Vec<String>) -> Vec<OsuUserResolution> {
+    let mut resolutions = Vec::with_capacity(queries.len());
+    for query in queries {
+        let normalized = osu_user_query(&query);
+        let result = if normalized.is_empty() {
+            Err("Invalid osu! user URL or ID".to_string())
+        } else {
+            resolve_osu_user(&normalized).await
         };
+        let (user, error) = match result {
+            Ok(user) => (Some(user), None),
+            Err(err) => (None, Some(err)),
+        };
+        resolutions.push(OsuUserResolution { query, user, error });
     }
+    resolutions
+}
 
-    CollectionMutationPayload {
-        success: false,
-        error: Some("invalid_sidecar_response".to_string()),
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OsuUserProfilePayload {
+    user: OsuUserData,
+    /// Avatar as a data URL, served from disk once it has been fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avatar: Option<String>,
+}
+
+fn sniff_image_mime_type(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG") {
+        "image/png"
+    } else if bytes.starts_with(b"GIF8") {
+        "image/gif"
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        "image/jpeg"
     }
 }
 
-#[tauri::command]
-fn show_item_in_folder(file_path: String) -> Result<(), String> {
-    let path = PathBuf::from(&file_path);
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        
-        // Canonicalize to get an absolute path with backslashes.
-        // Explorer /select requires backslashes — forward slashes cause it
-        // to silently fall back to opening the Documents folder.
-        let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
-        let canonical_str = canonical.to_string_lossy();
-        // Strip the \\?\ prefix that canonicalize adds on Windows
-        let clean_path = canonical_str.strip_prefix("\\\\?\\").unwrap_or(&canonical_str);
-        
-        // IMPORTANT: Use raw_arg instead of arg — Rust's .arg() auto-quotes 
-        // arguments containing spaces, but explorer.exe /select, does NOT 
-        // support quoted arguments and silently falls back to Documents.
-        Command::new("explorer")
-            .raw_arg(format!("/select,\"{}\"", clean_path))
-            .creation_flags(0x08000000) // CREATE_NO_WINDOW
-            .status()
-            .map_err(|err| err.to_string())?;
-        return Ok(());
+/// Avatar bytes cached under `<app data>/avatars/<user id>`, fetched on first use.
+async fn cached_osu_avatar(user: &OsuUserData) -> Option<Vec<u8>> {
+    let path = LIBRARY_CACHE_DIR.get().map(|dir| dir.join("avatars").join(&user.id));
+    if let Some(bytes) = path.as_deref().and_then(|path| fs::read(path).ok()) {
+        return Some(bytes);
     }
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        let target = path.parent().unwrap_or(Path::new(&file_path));
-        open::that(target).map_err(|err| err.to_string())
+    let url = user.avatar_url.as_deref()?;
+    let request = http_client(Duration::from_secs(15)).ok()?.get(url);
+    let response = send_with_retry(request)
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?;
+    let bytes = response.bytes().await.ok()?.to_vec();
+    if let Some(path) = &path {
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Err(err) = write_file_atomically(path, &bytes) {
+            eprintln!("failed to cache avatar for osu! user {}: {err}", user.id);
+        }
     }
+    Some(bytes)
 }
 
+/// Profile card data for a user: names, country, mapping counts and avatar. Works offline once
+/// the profile and avatar have been fetched.
 #[tauri::command]
-fn open_osu_file() -> Option<OpenOsuFilePayload> {
-    let files = rfd::FileDialog::new()
-        .add_filter("osu! beatmap", &["osu"])
-        .set_title("Select a beatmap (.osu) file")
-        .pick_files()?;
+async fn get_osu_user_profile(id: String) -> Result<OsuUserProfilePayload, String> {
+    let query = osu_user_query(&id);
+    if query.is_empty() {
+        return Err("Invalid osu! user URL or ID".to_string());
+    }
+    let user = resolve_osu_user(&query).await?;
+    let avatar = cached_osu_avatar(&user).await.map(|bytes| {
+        let mime = sniff_image_mime_type(&bytes);
+        format!("data:{mime};base64,{}", base64::engine::general_purpose::STANDARD.encode(bytes))
+    });
+    Ok(OsuUserProfilePayload { user, avatar })
+}
 
-    let mut results: Vec<OsuFilePayload> = Vec::new();
-    for path in files {
-        let file_path = path.to_string_lossy().to_string();
-        if let Ok(bytes) = fs::read(&path) {
-            if let Ok(mtime_ms) = get_mtime_ms(&path) {
-                let content = String::from_utf8_lossy(&bytes).to_string();
-                results.push(OsuFilePayload {
-                    file_path,
-                    content,
-                    stat: FileStatPayload { mtime_ms },
-                    beatmap_hash: Some(compute_osu_md5_hex(&bytes)),
-                });
-            }
+/// All usernames an osu! user has had.
+async fn resolve_osu_usernames(user_id: &str) -> Result<Vec<String>, String> {
+    resolve_osu_user(user_id).await.map(|user| user.names)
+}
+
+/// Add every known username of `options.mapper_user_id` to the comma-separated mapper filter.
+async fn expand_mapper_filter(mapper_name: Option<String>, options: &ScanOptions) -> Option<String> {
+    let Some(user_id) = options.mapper_user_id() else {
+        return mapper_name;
+    };
+    match resolve_osu_usernames(user_id).await {
+        Ok(names) => {
+            let filter: Vec<String> = mapper_name.into_iter().chain(names).collect();
+            Some(filter.join(","))
+        }
+        Err(err) => {
+            eprintln!("failed to resolve usernames for osu! user {user_id}: {err}");
+            mapper_name
         }
     }
+}
 
-    if results.is_empty() {
-        None
-    } else {
-        Some(OpenOsuFilePayload { files: results })
-    }
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct TrayActionEvent {
+    action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    paused: Option<bool>,
 }
 
-#[tauri::command]
-async fn scan_directory_osu_files(
-    window: tauri::Window,
-    dir_path: String,
-    mapper_name: Option<String>,
-    known_files: Option<HashMap<String, f64>>,
-    client_type: Option<String>,
-) -> ScanDirectoryPayload {
-    let dir_clone = dir_path.clone();
-    let fallback_dir = dir_path.clone();
-    let client = OsuClient::from_option(client_type);
-    // Use streaming: emit batches via events, return empty payload
-    // The renderer listens for scan-batch and scan-complete events
-    tauri::async_runtime::spawn_blocking(move || {
-        scan_directory_streaming(&dir_clone, mapper_name, known_files, client, &window);
-    })
-    .await
-    .ok();
-    ScanDirectoryPayload {
-        files: vec![],
-        directory: fallback_dir,
+fn focus_main_window(app_handle: &tauri::AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
     }
 }
 
-#[tauri::command]
-async fn list_directory_osu_files(
-    window: tauri::Window,
-    dir_path: String,
-    mapper_name: Option<String>,
-    client_type: Option<String>,
-) -> ScanDirectoryPayload {
-    let dir_clone = dir_path.clone();
-    let fallback_dir = dir_path.clone();
-    let client = OsuClient::from_option(client_type);
-    tauri::async_runtime::spawn_blocking(move || {
-        scan_directory_streaming(&dir_clone, mapper_name, Some(HashMap::new()), client, &window);
-    })
-    .await
-    .ok();
-    ScanDirectoryPayload {
-        files: vec![],
-        directory: fallback_dir,
-    }
+/// Size and position of a window, kept per label in `window-state.json`. Values are physical
+/// pixels, as reported by the window itself.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+struct WindowState {
+    width: u32,
+    height: u32,
+    x: i32,
+    y: i32,
+    #[serde(default)]
+    maximized: bool,
 }
 
-#[tauri::command]
-async fn open_mapper_osu_files(
-    window: tauri::Window,
-    mapper_name: String,
-    client_type: Option<String>,
-) -> Option<ScanDirectoryPayload> {
-    let client = OsuClient::from_option(client_type);
-    let dir = rfd::FileDialog::new()
-        .set_title(format!(
-            "{} to search for maps by \"{}\"",
-            if client == OsuClient::Lazer {
-                "Select the osu!lazer data folder"
-            } else {
-                "Select the Songs folder"
-            },
-            mapper_name,
-        ))
-        .pick_folder()?;
+static WINDOW_STATE_LOCK: Mutex<()> = Mutex::new(());
 
-    let dir_path = dir.to_string_lossy().to_string();
-    let fallback_dir = dir_path.clone();
-    tauri::async_runtime::spawn_blocking(move || {
-        scan_directory_streaming(&dir_path, Some(mapper_name), Some(HashMap::new()), client, &window);
-    })
-    .await
-    .ok();
-    Some(ScanDirectoryPayload {
-        files: vec![],
-        directory: fallback_dir,
-    })
+fn window_state_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|err| err.to_string())?;
+    Ok(data_dir.join("window-state.json"))
 }
 
-#[tauri::command]
-async fn open_folder_osu_files(
-    window: tauri::Window,
-    client_type: Option<String>,
-) -> Option<ScanDirectoryPayload> {
-    let client = OsuClient::from_option(client_type);
-    let dir = rfd::FileDialog::new()
-        .set_title(if client == OsuClient::Lazer {
-            "Select an osu!lazer data folder to scan for maps"
-        } else {
-            "Select a songs folder to scan for .osu files"
-        })
-        .pick_folder()?;
+fn load_window_states(path: &Path) -> BTreeMap<String, WindowState> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
 
-    let dir_path = dir.to_string_lossy().to_string();
-    let fallback_dir = dir_path.clone();
-    tauri::async_runtime::spawn_blocking(move || {
-        scan_directory_streaming(&dir_path, None, Some(HashMap::new()), client, &window);
-    })
-    .await
-    .ok();
-    Some(ScanDirectoryPayload {
-        files: vec![],
-        directory: fallback_dir,
-    })
+fn save_window_states(path: &Path, states: &BTreeMap<String, WindowState>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    let bytes = serde_json::to_vec_pretty(states).map_err(|err| err.to_string())?;
+    write_file_atomically(path, &bytes)
 }
 
-#[tauri::command]
-fn select_directory(title: Option<String>) -> Option<String> {
-    let dialog = rfd::FileDialog::new();
-    let dialog = if let Some(title) = title {
-        dialog.set_title(title)
-    } else {
-        dialog.set_title("Select Folder")
+/// Record the window's geometry under its label. Minimized windows are skipped and maximized
+/// ones keep their last normal size, so a restore never opens at the maximized bounds.
+fn remember_window_state(window: &tauri::WebviewWindow) -> Result<(), String> {
+    if window.is_minimized().unwrap_or(false) {
+        return Ok(());
+    }
+    let maximized = window.is_maximized().unwrap_or(false);
+    let path = window_state_path(window.app_handle())?;
+    let _guard = WINDOW_STATE_LOCK.lock().unwrap();
+    let mut states = load_window_states(&path);
+    let state = match states.get(window.label()) {
+        Some(previous) if maximized => WindowState { maximized, ..*previous },
+        _ => {
+            let position = window.outer_position().map_err(|err| err.to_string())?;
+            let size = window.inner_size().map_err(|err| err.to_string())?;
+            WindowState { width: size.width, height: size.height, x: position.x, y: position.y, maximized }
+        }
     };
+    states.insert(window.label().to_string(), state);
+    save_window_states(&path, &states)
+}
 
-    dialog
-        .pick_folder()
-        .map(|path| path.to_string_lossy().to_string())
+/// Put a window back where it was last closed. The position is only applied while its top-left
+/// corner still lies on a connected monitor.
+fn restore_window_state(window: &tauri::WebviewWindow) {
+    let Ok(path) = window_state_path(window.app_handle()) else {
+        return;
+    };
+    let Some(state) = load_window_states(&path).remove(window.label()) else {
+        return;
+    };
+    let _ = window.set_size(tauri::PhysicalSize::new(state.width, state.height));
+    let on_screen = window.available_monitors().unwrap_or_default().iter().any(|monitor| {
+        let (origin, size) = (monitor.position(), monitor.size());
+        (origin.x..origin.x + size.width as i32).contains(&state.x)
+            && (origin.y..origin.y + size.height as i32).contains(&state.y)
+    });
+    if on_screen {
+        let _ = window.set_position(tauri::PhysicalPosition::new(state.x, state.y));
+    }
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+fn remember_all_window_states(app_handle: &tauri::AppHandle) {
+    for window in app_handle.webview_windows().values() {
+        if let Err(err) = remember_window_state(window) {
+            eprintln!("failed to save the state of window {}: {err}", window.label());
+        }
+    }
+}
+
+/// Beatmap each comparison window was opened for, by window label.
+static MAP_WINDOWS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+const MAP_WINDOW_LABEL_PREFIX: &str = "map-";
+
+/// Name of `event` as sent to one window. The main window keeps the plain names; comparison
+/// windows get their own namespace, since events reach every webview that listens for them.
+fn window_event_name(label: &str, event: &str) -> String {
+    if label.starts_with(MAP_WINDOW_LABEL_PREFIX) {
+        format!("map-window:{label}:{event}")
+    } else {
+        event.to_string()
+    }
 }
 
-#[tauri::command]
-fn analysis_state(_is_analyzing: bool) {}
-
-#[tauri::command]
-fn window_minimize(window: tauri::Window) -> Result<(), String> {
-    window.minimize().map_err(|err| err.to_string())
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct MapWindowContext {
+    label: String,
+    /// Prepended to the events meant for this window only, e.g. `map-window:map-1a2b3c4d5e6f:`.
+    event_prefix: String,
+    osu: OsuFilePayload,
 }
 
+/// Open `file_path` in a window of its own so two difficulties can sit side by side. Each map
+/// gets a stable label, so opening it again focuses the existing window and its size and
+/// position are remembered between sessions. Returns the window label.
 #[tauri::command]
-fn window_maximize(window: tauri::Window) -> Result<(), String> {
-    if window.is_maximized().map_err(|err| err.to_string())? {
-        window.unmaximize().map_err(|err| err.to_string())
-    } else {
-        window.maximize().map_err(|err| err.to_string())
+async fn open_map_window(app_handle: tauri::AppHandle, file_path: String) -> Result<String, String> {
+    let path = PathBuf::from(&file_path);
+    let label = format!(
+        "{MAP_WINDOW_LABEL_PREFIX}{}",
+        &compute_osu_md5_hex(path_cache_key(&path).as_bytes())[..12]
+    );
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return Ok(label);
     }
+
+    let title = tauri::async_runtime::spawn_blocking(move || {
+        let content = fs::read(&path).map_err(|err| err.to_string())?;
+        let metadata = parse_osu_content_at(&decode_osu_bytes(&content).0, ScanDetailLevel::Metadata).metadata;
+        Ok::<_, String>(format!("{} - {} [{}] - mosu!", metadata.artist, metadata.title, metadata.version))
+    })
+    .await
+    .map_err(|err| err.to_string())??;
+
+    let url = tauri::WebviewUrl::App(format!("index.html?mapWindow={label}").into());
+    let window = tauri::WebviewWindowBuilder::new(&app_handle, &label, url)
+        .title(title)
+        .inner_size(850.0, 600.0)
+        .min_inner_size(850.0, 400.0)
+        .build()
+        .map_err(|err| err.to_string())?;
+    // Only a window that really opened may claim the label, or a later one reusing it would
+    // be served this map.
+    MAP_WINDOWS.lock().map_err(|err| err.to_string())?.insert(label.clone(), file_path);
+    restore_window_state(&window);
+    Ok(label)
 }
 
+/// The beatmap and event namespace of the calling window, or `None` for the main window.
 #[tauri::command]
-fn window_close(window: tauri::Window) -> Result<(), String> {
-    window.close().map_err(|err| err.to_string())
+async fn get_map_window_context(window: tauri::Window) -> Result<Option<MapWindowContext>, String> {
+    let label = window.label().to_string();
+    let Some(file_path) = MAP_WINDOWS.lock().map_err(|err| err.to_string())?.get(&label).cloned() else {
+        return Ok(None);
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        Ok(Some(MapWindowContext {
+            event_prefix: window_event_name(&label, ""),
+            osu: read_osu_file_payload(Path::new(&file_path))?,
+            label,
+        }))
+    })
+    .await
+    .map_err(|err| err.to_string())?
 }
 
-#[tauri::command]
-async fn embed_sync(url: String, api_key: String, data: Value) -> EmbedSyncPayload {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build();
+/// Read one key from the native settings store, for backend behaviour the user toggles.
+fn read_native_setting(app_handle: &tauri::AppHandle, key: &str) -> Option<Value> {
+    let path = settings_file_path(app_handle).ok()?;
+    let _guard = SETTINGS_LOCK.lock().ok()?;
+    read_settings_file(&path).ok().flatten()?.remove(key)
+}
 
-    let client = match client {
-        Ok(client) => client,
-        Err(err) => {
-            return EmbedSyncPayload {
-                success: false,
-                status: None,
-                data: None,
-                error: Some(err.to_string()),
+/// Tray icon with quick actions. Everything except Quit is forwarded to the renderer as a
+/// `tray-action` event, since the library and watcher live there.
+fn build_tray(app: &tauri::App) -> tauri::Result<()> {
+    use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+    use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+
+    let show = MenuItem::with_id(app, "show", "Show mosu!", true, None::<&str>)?;
+    let rescan = MenuItem::with_id(app, "rescan-library", "Rescan library", true, None::<&str>)?;
+    let open_last = MenuItem::with_id(app, "open-last-map", "Open last map", true, None::<&str>)?;
+    let pause = CheckMenuItem::with_id(app, "pause-watcher", "Pause watcher", true, false, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(app, &[&show, &rescan, &open_last, &pause, &separator, &quit])?;
+
+    let mut tray = TrayIconBuilder::with_id("main")
+        .tooltip("mosu!")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(move |app_handle, event| {
+            let action = event.id().as_ref();
+            let paused = match action {
+                "quit" => {
+                    remember_all_window_states(app_handle);
+                    return app_handle.exit(0);
+                }
+                "show" => return focus_main_window(app_handle),
+                "pause-watcher" => Some(pause.is_checked().unwrap_or(false)),
+                _ => None,
+            };
+            if action == "open-last-map" {
+                focus_main_window(app_handle);
             }
-        }
-    };
+            let _ = app_handle.emit("tray-action", TrayActionEvent {
+                action: action.to_string(),
+                paused,
+            });
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                focus_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
 
-    let response = client
-        .post(url)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {api_key}"))
-        .json(&data)
-        .send()
-        .await;
+const CLI_USAGE: &str = "\
+usage:
+  mosu scan <dir> [--json[=<out.json>]] [--mapper <names>] [--client stable|lazer]
+  mosu stars <file.osu>... [--mods <DT|HDHR|...>] [--json[=<out.json>]]
 
-    match response {
-        Ok(resp) => {
-            let status = resp.status().as_u16();
-            let body = resp.json::<Value>().await.ok();
-            EmbedSyncPayload {
-                success: (200..300).contains(&status),
-                status: Some(status),
-                data: body,
-                error: None,
+Without a subcommand mosu starts the desktop app.";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CliStarRating {
+    file_path: String,
+    mods: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stars: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Parse a run of mod acronyms such as "HDDT", "hd,hr" or "NM".
+fn parse_mods_acronym(text: &str) -> Result<u32, String> {
+    let letters: String = text
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if letters.len() % 2 != 0 {
+        return Err(format!("invalid mods: {text}"));
+    }
+    let mut mods = 0;
+    for index in (0..letters.len()).step_by(2) {
+        mods |= match &letters[index..index + 2] {
+            "NM" => 0,
+            "NC" => MOD_NIGHTCORE | MOD_DOUBLE_TIME,
+            acronym => MOD_ACRONYMS
+                .iter()
+                .find(|(_, known)| *known == acronym)
+                .map(|(bit, _)| *bit)
+                .ok_or_else(|| format!("unknown mod: {acronym}"))?,
+        };
+    }
+    Ok(mods)
+}
+
+/// Options that need a value, given as `--flag value` or `--flag=value`.
+const CLI_VALUE_FLAGS: &[&str] = &["client", "mapper", "mods"];
+/// Options whose value is optional, so it can only be attached as `--flag=value`.
+const CLI_OPTIONAL_VALUE_FLAGS: &[&str] = &["json"];
+/// Stands in for an omitted optional value; `--json` alone prints to stdout.
+const CLI_STDOUT: &str = "-";
+
+/// Split CLI arguments into positionals and `--flag` options.
+fn parse_cli_args(args: &[String]) -> Result<(Vec<String>, HashMap<String, String>), String> {
+    let mut positionals = Vec::new();
+    let mut flags = HashMap::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            positionals.push(arg.clone());
+            continue;
+        };
+        let (name, attached) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (flag, None),
+        };
+        let value = if CLI_VALUE_FLAGS.contains(&name) {
+            match attached {
+                Some(value) => value,
+                None => iter.next().cloned().ok_or_else(|| format!("--{name} needs a value"))?,
             }
+        } else if CLI_OPTIONAL_VALUE_FLAGS.contains(&name) {
+            attached.unwrap_or_else(|| CLI_STDOUT.to_string())
+        } else {
+            return Err(format!("unknown option --{name}"));
+        };
+        flags.insert(name.to_string(), value);
+    }
+    Ok((positionals, flags))
+}
+
+fn write_cli_json<T: Serialize>(value: &T, destination: Option<&String>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|err| err.to_string())?;
+    match destination {
+        Some(path) if path != CLI_STDOUT => write_file_atomically(Path::new(path), json.as_bytes()),
+        _ => {
+            println!("{json}");
+            Ok(())
         }
-        Err(err) => EmbedSyncPayload {
-            success: false,
-            status: None,
-            data: None,
-            error: Some(err.to_string()),
-        },
     }
 }
 
-#[tauri::command]
-async fn get_osu_user_data(url_or_id: String) -> Result<OsuUserData, String> {
-    println!("[mosu] Fetching osu! user data for: {}", url_or_id);
-    let id_str = if url_or_id.starts_with("http") {
-        url_or_id
-            .split('/')
-            .last()
-            .unwrap_or("")
-            .split('?')
-            .next()
-            .unwrap_or("")
-            .to_string()
-    } else {
-        url_or_id
+fn run_cli_scan(args: &[String]) -> Result<bool, String> {
+    let (positionals, flags) = parse_cli_args(args)?;
+    let [dir] = positionals.as_slice() else {
+        return Err("scan takes exactly one directory".to_string());
     };
+    let client = OsuClient::from_option(flags.get("client").cloned());
+    if !resolve_scan_root(dir, client).is_dir() {
+        return Err(format!("{dir} is not a directory"));
+    }
+    let result = scan_directory_internal(dir, flags.get("mapper").cloned(), None, client);
+    eprintln!("scanned {} beatmaps in {}", result.files.len(), result.directory);
+    write_cli_json(&result, flags.get("json"))?;
+    Ok(true)
+}
 
-    if id_str.is_empty() {
-        return Err("Invalid osu! user URL or ID".to_string());
+fn run_cli_stars(args: &[String]) -> Result<bool, String> {
+    let (files, flags) = parse_cli_args(args)?;
+    if files.is_empty() {
+        return Err("stars needs at least one .osu file".to_string());
     }
-    
-    println!("[mosu] Normalized user ID: {}", id_str);
+    let mods = flags.get("mods").map_or(Ok(0), |mods| parse_mods_acronym(mods))?;
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(15))
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-        .build()
-        .map_err(|e| e.to_string())?;
+    let ratings: Vec<CliStarRating> = files
+        .into_iter()
+        .map(|file_path| {
+            let stars = fs::read(&file_path)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| Beatmap::from_bytes(&bytes).map_err(|err| err.to_string()))
+                .map(|map| Difficulty::new().mods(mods).calculate(&map).stars())
+                .and_then(|stars| {
+                    stars
+                        .is_finite()
+                        .then_some(stars)
+                        .ok_or_else(|| "star rating is not finite".to_string())
+                });
+            CliStarRating {
+                file_path,
+                mods: mods_acronym(mods),
+                stars: stars.as_ref().ok().copied(),
+                error: stars.err(),
+            }
+        })
+        .collect();
 
-    let url = format!("https://osu.ppy.sh/users/{}", id_str);
-    let response = client.get(&url).send().await.map_err(|e| {
-        println!("[mosu] Request failed: {}", e);
-        e.to_string()
-    })?;
-    
-    if !response.status().is_success() {
-        println!("[mosu] Profile fetch returned status: {}", response.status());
-        return Err(format!("Failed to fetch profile: {}", response.status()));
+    let all_ok = ratings.iter().all(|rating| rating.error.is_none());
+    if flags.contains_key("json") {
+        write_cli_json(&ratings, flags.get("json"))?;
+    } else {
+        for rating in &ratings {
+            match (rating.stars, &rating.error) {
+                (Some(stars), _) => println!("{stars:.2}\t{}\t{}", rating.mods, rating.file_path),
+                (None, Some(error)) => eprintln!("{}: {error}", rating.file_path),
+                (None, None) => {}
+            }
+        }
     }
+    Ok(all_ok)
+}
 
-    let html = response.text().await.map_err(|e| e.to_string())?;
-    let document = scraper::Html::parse_document(&html);
-    
-    // Modern osu! profiles store data in a JSON blob within a .js-react element
-    let react_selector = scraper::Selector::parse(".js-react").map_err(|_| "Selector error")?;
-    let element = document.select(&react_selector)
-        .find(|e| e.value().attr("data-initial-data").is_some())
-        .ok_or_else(|| {
-            println!("[mosu] Could not find .js-react element with data-initial-data");
-            "Could not find profile data on page".to_string()
-        })?;
-    
-    let json_str = element.value().attr("data-initial-data").unwrap();
-    let data: Value = serde_json::from_str(json_str).map_err(|e| {
-        println!("[mosu] JSON parse error: {}", e);
-        format!("Failed to parse profile JSON: {}", e)
-    })?;
-    
-    // The structure is usually { "user": { ... } }
-    let user = data.get("user").ok_or_else(|| {
-        println!("[mosu] 'user' key not found in JSON data");
-        "User data not found in profile".to_string()
-    })?;
-    
-    let actual_id = user.get("id")
-        .and_then(|v| {
-            if let Some(i) = v.as_i64() { Some(i.to_string()) }
-            else if let Some(s) = v.as_str() { Some(s.to_string()) }
-            else { None }
+/// Run a headless subcommand if one was given. Returns the process exit code, or `None` to
+/// start the app normally (including when launched with a `mosu://` link).
+fn run_cli(args: &[std::ffi::OsString]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    let run: fn(&[String]) -> Result<bool, String> = match command.to_str()? {
+        "scan" => run_cli_scan,
+        "stars" => run_cli_stars,
+        "help" | "--help" | "-h" => {
+            attach_parent_console();
+            println!("{CLI_USAGE}");
+            return Some(0);
+        }
+        _ => return None,
+    };
+    attach_parent_console();
+    let rest: Result<Vec<String>, String> = rest
+        .iter()
+        .map(|arg| {
+            arg.to_str().map(str::to_string).ok_or_else(|| format!("{} is not valid UTF-8", arg.to_string_lossy()))
         })
-        .ok_or_else(|| "User ID not found in JSON".to_string())?;
-        
-    let username = user.get("username")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| "Username not found in JSON".to_string())?;
-    
-    let mut names = vec![username.to_string()];
-    println!("[mosu] Found primary username: {}", username);
-    
-    if let Some(previous) = user.get("previous_usernames").and_then(|v| v.as_array()) {
-        for name_val in previous {
-            if let Some(name) = name_val.as_str() {
-                if !name.is_empty() {
-                    names.push(name.to_string());
-                }
-            }
+        .collect();
+    match rest.and_then(|rest| run(&rest)) {
+        Ok(true) => Some(0),
+        Ok(false) => Some(1),
+        Err(err) => {
+            eprintln!("mosu: {err}\n\n{CLI_USAGE}");
+            Some(2)
         }
     }
+}
 
-    // Remove duplicates while preserving order (primary name stays first)
-    let mut seen = std::collections::HashSet::new();
-    names.retain(|n| seen.insert(n.to_lowercase()));
-    println!("[mosu] Total unique names found (order preserved): {:?}", names);
+/// Release builds use the Windows GUI subsystem, so stdout only reaches the terminal after
+/// attaching to the parent's console.
+fn attach_parent_console() {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
 
-    Ok(OsuUserData { id: actual_id, names })
+        // SAFETY: AttachConsole has no preconditions; failure just leaves output detached.
+        unsafe {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
 }
 
 fn main() {
+    // `args` panics on arguments that aren't valid UTF-8, which the app itself may still be handed.
+    let args: Vec<std::ffi::OsString> = std::env::args_os().skip(1).collect();
+    if let Some(code) = run_cli(&args) {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            // Deep links from a second instance are forwarded to `on_open_url` by the plugin.
+            focus_main_window(app);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(|app_handle, _shortcut, event| {
+                    if event.state() == tauri_plugin_global_shortcut::ShortcutState::Pressed {
+                        let app_handle = app_handle.clone();
+                        tauri::async_runtime::spawn_blocking(move || capture_editor_timestamp(&app_handle));
+                    }
+                })
+                .build(),
+        )
+        .register_uri_scheme_protocol(TIMELINE_PROTOCOL, |_ctx, request| {
+            timeline_protocol_response(request.uri().path())
+        })
+        .manage(LibraryState::default())
+        .setup(|app| {
+            use tauri_plugin_deep_link::DeepLinkExt;
+
+            if let Ok(data_dir) = app.path().app_data_dir() {
+                let _ = LIBRARY_CACHE_DIR.set(data_dir);
+            }
+            let _ = NETWORK_EVENTS.set(app.handle().clone());
+            install_crash_reporter(app.handle());
+            let settings = settings_file_path(app.handle()).and_then(|path| read_settings_file(&path));
+            if let Ok(Some(settings)) = &settings {
+                apply_network_settings(settings);
+                apply_backup_settings(settings);
+            }
+            apply_shortcut_settings(app.handle(), &settings.ok().flatten().unwrap_or_default());
+            start_now_playing_watcher(app.handle().clone());
+            if let Err(err) = build_tray(app) {
+                eprintln!("failed to create the tray icon: {err}");
+            }
+            if let Some(window) = app.get_webview_window("main") {
+                restore_window_state(&window);
+            }
+
+            #[cfg(any(target_os = "windows", target_os = "linux"))]
+            if let Err(err) = app.deep_link().register_all() {
+                eprintln!("failed to register the mosu:// scheme: {err}");
+            }
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                if let Ok(mut links) = LAUNCH_DEEP_LINKS.lock() {
+                    links.extend(urls.iter().map(|url| url.to_string()));
+                }
+            }
+            let app_handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    let _ = app_handle.emit("deep-link", url.to_string());
+                }
+            });
+            Ok(())
+        })
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+                let window = window.clone();
+                let paths = paths.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    let dropped = ingest_dropped_files(&paths);
+                    let is_empty = dropped.osu.is_none()
+                        && dropped.imports.is_empty()
+                        && dropped.replays.is_empty()
+                        && dropped.errors.is_empty();
+                    if !is_empty {
+                        let _ = window.emit(&window_event_name(window.label(), "files-dropped"), dropped);
+                    }
+                });
+            }
+            tauri::WindowEvent::CloseRequested { .. } => {
+                if let Some(webview_window) = window.get_webview_window(window.label()) {
+                    if let Err(err) = remember_window_state(&webview_window) {
+                        eprintln!("failed to save the state of window {}: {err}", window.label());
+                    }
+                }
+            }
+            tauri::WindowEvent::Destroyed => {
+                MAP_WINDOWS.lock().unwrap().remove(window.label());
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
             get_app_version,
             open_external_url,
             check_for_updates,
+            download_update,
+            apply_update,
             read_image_file,
             read_binary_file,
             read_audio_file,
@@ -2550,7 +16734,13 @@ fn main() {
             show_item_in_folder,
             open_in_text_editor,
             open_osu_file,
+            import_osz,
+            open_deep_link,
+            take_launch_deep_links,
+            describe_deep_link,
             scan_directory_osu_files,
+            scan_file_list,
+            rescan_changed,
             list_directory_osu_files,
             open_mapper_osu_files,
             open_folder_osu_files,
@@ -2561,9 +16751,304 @@ fn main() {
             window_close,
             embed_sync,
             get_audio_duration,
+            probe_audio,
+            probe_video,
+            render_playfield_snapshot,
+            get_slider_paths,
+            analyze_mania,
+            analyze_taiko,
+            analyze_catch,
+            simulate_score,
+            get_local_scores,
+            get_recent_plays,
+            get_library_page,
+            sort_library,
+            get_library_stats,
+            search_library,
+            get_cached_star_rating,
+            enqueue_job,
+            cancel_job,
+            apply_mods_to_difficulty,
+            open_in_osu_editor,
+            parse_editor_timestamp,
+            format_editor_timestamp,
+            export_mod_post,
+            suggest_breaks,
+            analyze_spread,
+            get_density_histogram,
+            resolve_osu_users,
+            get_osu_user_profile,
+            build_progress_embed,
+            post_progress_embed,
+            start_auto_sync,
+            update_auto_sync_payload,
+            stop_auto_sync,
+            compute_mapping_progress,
+            get_map_work_stats,
+            list_backups,
+            restore_backup,
+            trash_files,
+            undo_trash,
+            export_osz,
+            analyze_audio_quality,
+            check_onset_alignment,
+            render_click_track,
+            export_audio_clip,
+            render_rate_preview,
+            ack_scan_batch,
+            check_metadata_consistency,
+            get_stacked_objects,
+            check_readability,
+            analyze_distance_snap,
+            calculate_difficulty_attributes,
+            calculate_star_rating_progression,
+            render_progress_image,
+            classify_beatmap,
+            list_playlists,
+            create_playlist,
+            delete_playlist,
+            add_to_playlist,
+            remove_from_playlist,
+            export_playlist_as_collection,
+            get_stale_ratings,
+            recalculate_stale_ratings,
+            open_map_window,
+            get_map_window_context,
+            get_now_playing,
             calculate_star_rating,
+            check_snapping,
+            backfill_beatmap_ids,
+            lint_beatmap,
+            write_osu_metadata,
+            compare_maps,
+            merge_guest_diff,
+            write_osu_bookmarks,
+            apply_offset,
+            set_preview_time,
+            create_support_bundle,
+            get_difficulty_settings,
+            query_library,
+            compare_beatmaps,
+            snapshot_beatmap,
+            list_snapshots,
+            restore_snapshot,
+            export_scan_results,
+            aggregate_mapper_stats,
+            detect_osu_songs_folder,
+            list_library_roots,
+            add_library_root,
+            remove_library_root,
+            scan_library_roots,
+            export_mappool,
+            get_settings,
+            set_settings,
+            store_secret,
+            get_secret,
             get_osu_user_data,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editor_timestamp_rejects_overflowing_minutes() {
+        assert_eq!(parse_editor_timestamps("35791:23:647 -")[0].time_ms, i32::MAX);
+        assert!(parse_editor_timestamps("35791:23:648 -").is_empty());
+        assert!(parse_editor_timestamps("100000:00:000 (1) -").is_empty());
+    }
+
+    #[test]
+    fn editor_timestamp_without_objects() {
+        let timestamps = parse_editor_timestamps("00:12:345 - ");
+        assert_eq!(timestamps.len(), 1);
+        assert_eq!(timestamps[0].time_ms, 12_345);
+        assert!(timestamps[0].objects.is_empty());
+        assert_eq!(timestamps[0].text, "00:12:345");
+    }
+
+    #[test]
+    fn editor_timestamp_with_combo_numbers() {
+        let timestamps = parse_editor_timestamps("01:02:003 (1,2,3) - ");
+        assert_eq!(timestamps[0].time_ms, 62_003);
+        assert_eq!(timestamps[0].objects, ["1", "2", "3"]);
+        assert_eq!(timestamps[0].text, "01:02:003 (1,2,3)");
+    }
+
+    #[test]
+    fn editor_timestamp_with_mania_columns() {
+        let timestamps = parse_editor_timestamps("00:01:500 (1500|0,1750|2) - ");
+        assert_eq!(timestamps[0].time_ms, 1_500);
+        assert_eq!(timestamps[0].objects, ["1500|0", "1750|2"]);
+    }
+
+    #[test]
+    fn editor_timestamps_inside_mod_post() {
+        let post = "[Insane]\n- 00:10:000 (4) - too far from 3\n- 1:00:000 - kiai could start here\nmaybe 5:0:00?";
+        let timestamps = parse_editor_timestamps(post);
+        let found: Vec<(i32, &str)> = timestamps.iter().map(|found| (found.time_ms, found.text.as_str())).collect();
+        assert_eq!(found, [(10_000, "00:10:000 (4)"), (60_000, "1:00:000")]);
+    }
+
+    #[test]
+    fn deep_link_sets_and_beatmaps() {
+        assert_eq!(parse_deep_link("mosu://set/1234"), Some(DeepLinkTarget::BeatmapSet(1234)));
+        assert_eq!(parse_deep_link("osu://s/1234"), Some(DeepLinkTarget::BeatmapSet(1234)));
+        assert_eq!(parse_deep_link("mosu://dl/1234/"), Some(DeepLinkTarget::BeatmapSet(1234)));
+        assert_eq!(parse_deep_link("  mosu://b/55  "), Some(DeepLinkTarget::Beatmap(55)));
+    }
+
+    #[test]
+    fn deep_link_website_urls() {
+        assert_eq!(
+            parse_deep_link("https://osu.ppy.sh/beatmapsets/1234#osu/55"),
+            Some(DeepLinkTarget::Beatmap(55))
+        );
+        assert_eq!(parse_deep_link("https://osu.ppy.sh/beatmapsets/1234"), Some(DeepLinkTarget::BeatmapSet(1234)));
+        assert_eq!(parse_deep_link("https://osu.ppy.sh/b/55"), Some(DeepLinkTarget::Beatmap(55)));
+        assert_eq!(parse_deep_link("https://example.com/beatmapsets/1234"), None);
+    }
+
+    #[test]
+    fn deep_link_open_path() {
+        assert_eq!(
+            parse_deep_link("mosu://open?path=%2Fhome%2Fme%2FSongs%2Fa.osu"),
+            Some(DeepLinkTarget::Path(PathBuf::from("/home/me/Songs/a.osu")))
+        );
+        assert_eq!(parse_deep_link("mosu://open"), None);
+    }
+
+    #[test]
+    fn deep_link_rejects_malformed_input() {
+        assert_eq!(parse_deep_link(""), None);
+        assert_eq!(parse_deep_link("not a url"), None);
+        assert_eq!(parse_deep_link("file:///etc/passwd"), None);
+        assert_eq!(parse_deep_link("mosu://set/-1"), None);
+        assert_eq!(parse_deep_link("mosu://set/99999999999999999999999"), None);
+        assert_eq!(parse_deep_link("mosu://unknown/1"), None);
+    }
+
+    /// One `scores.db` score record, as osu!stable writes it.
+    fn scores_db_record(beatmap_hash: &str, score: i32, mods: u32) -> Vec<u8> {
+        let mut record = vec![0_u8];
+        record.extend(20_240_101_i32.to_le_bytes());
+        write_osu_string(&mut record, Some(beatmap_hash)).unwrap();
+        write_osu_string(&mut record, Some("player")).unwrap();
+        write_osu_string(&mut record, Some("0123456789abcdef0123456789abcdef")).unwrap();
+        for count in [300_u16, 20, 5, 40, 10, 2] {
+            record.extend(count.to_le_bytes());
+        }
+        record.extend(score.to_le_bytes());
+        record.extend(412_u16.to_le_bytes());
+        record.push(0);
+        record.extend(mods.to_le_bytes());
+        write_osu_string(&mut record, None).unwrap();
+        record.extend((DOTNET_EPOCH_TICKS + 1_700_000_000_000 * 10_000).to_le_bytes());
+        record.extend((-1_i32).to_le_bytes());
+        record.extend(0_i64.to_le_bytes());
+        if mods & MOD_TARGET_PRACTICE != 0 {
+            record.extend(98.5_f64.to_le_bytes());
+        }
+        record
+    }
+
+    fn scores_db(beatmaps: &[(&str, Vec<Vec<u8>>)]) -> Vec<u8> {
+        let mut bytes = 20_240_101_i32.to_le_bytes().to_vec();
+        bytes.extend((beatmaps.len() as i32).to_le_bytes());
+        for (beatmap_hash, records) in beatmaps {
+            write_osu_string(&mut bytes, Some(beatmap_hash)).unwrap();
+            bytes.extend((records.len() as i32).to_le_bytes());
+            bytes.extend(records.concat());
+        }
+        bytes
+    }
+
+    #[test]
+    fn scores_db_plays_keyed_by_hash_best_first() {
+        let hash = "ABCDEF0123456789ABCDEF0123456789";
+        let bytes = scores_db(&[(
+            hash,
+            vec![
+                scores_db_record(hash, 500_000, 0),
+                scores_db_record(hash, 900_000, 8 | 16),
+                scores_db_record(hash, 700_000, MOD_TARGET_PRACTICE),
+            ],
+        )]);
+        let scores = parse_scores_db(&bytes).unwrap();
+        let plays = &scores[&hash.to_ascii_lowercase()];
+        // The Target Practice play carries an extra accuracy value the parser has to step over.
+        let found: Vec<(i32, u32)> = plays.iter().map(|play| (play.score, play.mods)).collect();
+        assert_eq!(found, [(900_000, 8 | 16), (700_000, MOD_TARGET_PRACTICE), (500_000, 0)]);
+        assert_eq!(plays[0].mods_acronym, "HDHR");
+        assert_eq!(plays[0].played_at_ms, 1_700_000_000_000);
+        assert_eq!(plays[0].max_combo, 412);
+    }
+
+    #[test]
+    fn scores_db_rejects_truncated_files() {
+        let hash = "abcdef0123456789abcdef0123456789";
+        let bytes = scores_db(&[(hash, vec![scores_db_record(hash, 1, 0), scores_db_record(hash, 2, 0)])]);
+        for length in 0..bytes.len() {
+            assert!(parse_scores_db(&bytes[..length]).is_err(), "accepted {length} of {} bytes", bytes.len());
+        }
+    }
+
+    #[test]
+    fn scores_db_survives_hostile_counts_and_lengths() {
+        let mut negative = 20_240_101_i32.to_le_bytes().to_vec();
+        negative.extend((-5_i32).to_le_bytes());
+        assert!(parse_scores_db(&negative).unwrap().is_empty());
+
+        let mut huge_count = 20_240_101_i32.to_le_bytes().to_vec();
+        huge_count.extend(i32::MAX.to_le_bytes());
+        assert!(parse_scores_db(&huge_count).is_err());
+
+        // A beatmap hash claiming to be 32 GB long.
+        let mut huge_string = 20_240_101_i32.to_le_bytes().to_vec();
+        huge_string.extend(1_i32.to_le_bytes());
+        huge_string.extend([0x0b, 0xff, 0xff, 0xff, 0xff, 0x7f]);
+        assert!(parse_scores_db(&huge_string).is_err());
+    }
+
+    #[test]
+    fn mods_acronym_parses_runs_and_separators() {
+        assert_eq!(parse_mods_acronym("HDDT"), Ok(MOD_HIDDEN | MOD_DOUBLE_TIME));
+        assert_eq!(parse_mods_acronym("hd,hr"), Ok(MOD_HIDDEN | MOD_HARD_ROCK));
+        assert_eq!(parse_mods_acronym(" Hd + Fl "), Ok(MOD_HIDDEN | MOD_FLASHLIGHT));
+        assert_eq!(parse_mods_acronym("NM"), Ok(0));
+        assert_eq!(parse_mods_acronym(""), Ok(0));
+    }
+
+    #[test]
+    fn mods_acronym_nightcore_implies_double_time() {
+        let mods = parse_mods_acronym("NC").unwrap();
+        assert_eq!(mods, MOD_NIGHTCORE | MOD_DOUBLE_TIME);
+        assert_eq!(mods_acronym(mods), "NC");
+    }
+
+    #[test]
+    fn mods_acronym_rejects_unknown_and_partial_mods() {
+        assert!(parse_mods_acronym("HDX").is_err());
+        assert!(parse_mods_acronym("ZZ").is_err());
+        assert!(parse_mods_acronym("HDH").is_err());
+        // Pairs are read from the start, so a misaligned run is not silently reinterpreted.
+        assert!(parse_mods_acronym("XHDT").is_err());
+    }
+
+    #[test]
+    fn iso8601_from_unix_ms() {
+        assert_eq!(unix_ms_to_iso8601(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(unix_ms_to_iso8601(86_399_999), "1970-01-01T23:59:59.999Z");
+        assert_eq!(unix_ms_to_iso8601(951_782_400_000), "2000-02-29T00:00:00.000Z");
+        assert_eq!(unix_ms_to_iso8601(1_700_000_000_123), "2023-11-14T22:13:20.123Z");
+        assert_eq!(unix_ms_to_iso8601(253_402_300_799_999), "9999-12-31T23:59:59.999Z");
+    }
+
+    #[test]
+    fn iso8601_handles_the_largest_timestamp() {
+        assert!(unix_ms_to_iso8601(u64::MAX).ends_with("T14:25:51.615Z"));
+    }
+}